@@ -148,3 +148,24 @@ StackCell *int_not_equal(StackCell *stack) {
 
   return push_bool(rest, a != b);
 }
+
+/**
+ * Boolean negation: ( bool -- bool )
+ * Returns true if the input is false
+ */
+StackCell *not(StackCell *stack) {
+  if (!stack) {
+    runtime_error("not: stack underflow");
+  }
+
+  if (stack->tag != TAG_BOOL) {
+    runtime_error("not: type error (expected Bool)");
+  }
+
+  bool value = stack->value.b;
+
+  StackCell *rest = stack->next;
+  free_cell(stack);
+
+  return push_bool(rest, !value);
+}
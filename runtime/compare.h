@@ -33,4 +33,11 @@ StackCell *int_equal(StackCell *stack);
 // != : ( Int Int -- Bool )
 StackCell *int_not_equal(StackCell *stack);
 
+/**
+ * Boolean operations
+ */
+
+// not : ( Bool -- Bool )
+StackCell *not(StackCell *stack);
+
 #endif // CEM_COMPARE_H
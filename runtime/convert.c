@@ -4,6 +4,7 @@
  * Implements conversions between types:
  * - int-to-string: Convert integer to string representation
  * - bool-to-string: Convert boolean to "true" or "false"
+ * - bool_to_int / int_to_bool: Convert between booleans and 0/1 integers
  * - string-to-int: Parse string to integer (TODO)
  */
 
@@ -62,3 +63,45 @@ StackCell *bool_to_string(StackCell *stack) {
   // Push the string "true" or "false"
   return push_string(rest, value ? "true" : "false");
 }
+
+/**
+ * bool_to_int: ( Bool -- Int )
+ * false becomes 0 and true becomes 1
+ */
+StackCell *bool_to_int(StackCell *stack) {
+  if (!stack) {
+    runtime_error("bool_to_int: stack underflow");
+  }
+
+  if (stack->tag != TAG_BOOL) {
+    runtime_error("bool_to_int: type error (expected Bool)");
+  }
+
+  bool value = stack->value.b;
+
+  StackCell *rest = stack->next;
+  free_cell(stack);
+
+  return push_int(rest, value ? 1 : 0);
+}
+
+/**
+ * int_to_bool: ( Int -- Bool )
+ * Any nonzero integer becomes true; 0 becomes false
+ */
+StackCell *int_to_bool(StackCell *stack) {
+  if (!stack) {
+    runtime_error("int_to_bool: stack underflow");
+  }
+
+  if (stack->tag != TAG_INT) {
+    runtime_error("int_to_bool: type error (expected Int)");
+  }
+
+  int64_t value = stack->value.i;
+
+  StackCell *rest = stack->next;
+  free_cell(stack);
+
+  return push_bool(rest, value != 0);
+}
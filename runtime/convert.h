@@ -17,4 +17,10 @@ StackCell *int_to_string(StackCell *stack);
 // bool-to-string : ( Bool -- String )
 StackCell *bool_to_string(StackCell *stack);
 
+// bool_to_int : ( Bool -- Int ), false -> 0 and true -> 1
+StackCell *bool_to_int(StackCell *stack);
+
+// int_to_bool : ( Int -- Bool ), nonzero -> true
+StackCell *int_to_bool(StackCell *stack);
+
 #endif // CEM_CONVERT_H
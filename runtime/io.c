@@ -43,35 +43,44 @@ static void ensure_nonblocking_io(void) {
   io_initialized = true;
 }
 
+// Report an I/O error from the word `name`
+static void io_error(const char *name, const char *what) {
+  char message[128];
+  snprintf(message, sizeof(message), "%s: %s", name, what);
+  runtime_error(message);
+}
+
 /**
- * Write a string to stdout with newline
+ * Pop a string and write it to stdout, with a trailing newline if requested
  * Stack effect: ( str -- )
  *
  * Phase 2b: Uses cleanup handlers to ensure buffer is freed even if
  * strand is terminated while blocked.
  */
-StackCell *write_line(StackCell *stack) {
+static StackCell *write_string_cell(StackCell *stack, const char *name, bool newline) {
   ensure_nonblocking_io();
 
   // Pop string from stack
   if (!stack || stack->tag != TAG_STRING) {
-    runtime_error("write_line: expected string on stack");
+    io_error(name, "expected string on stack");
   }
 
   const char *str = stack->value.s;
   StackCell *rest = stack->next;
 
-  // Calculate total length (string + newline)
+  // Calculate total length (string + optional newline)
   size_t str_len = strlen(str);
-  size_t total_len = str_len + 1; // +1 for newline
+  size_t total_len = str_len + (newline ? 1 : 0);
 
-  // Allocate buffer for string + newline
-  char *buffer = malloc(total_len);
+  // Allocate buffer for string + newline (at least one byte, even for "")
+  char *buffer = malloc(str_len + 1);
   if (!buffer) {
-    runtime_error("write_line: out of memory");
+    io_error(name, "out of memory");
   }
   memcpy(buffer, str, str_len);
-  buffer[str_len] = '\n';
+  if (newline) {
+    buffer[str_len] = '\n';
+  }
 
   // Register cleanup handler to free buffer if strand terminates while blocked
   strand_push_cleanup(free, buffer);
@@ -95,13 +104,13 @@ StackCell *write_line(StackCell *stack) {
         // Real error
         strand_pop_cleanup(); // Remove cleanup handler before freeing manually
         free(buffer);
-        runtime_error("write_line: write() failed");
+        io_error(name, "write() failed");
       }
     } else {
       // n == 0, shouldn't happen for stdout
       strand_pop_cleanup(); // Remove cleanup handler before freeing manually
       free(buffer);
-      runtime_error("write_line: unexpected write() return 0");
+      io_error(name, "unexpected write() return 0");
     }
   }
 
@@ -111,6 +120,22 @@ StackCell *write_line(StackCell *stack) {
   return rest;
 }
 
+/**
+ * Write a string to stdout with newline
+ * Stack effect: ( str -- )
+ */
+StackCell *write_line(StackCell *stack) {
+  return write_string_cell(stack, "write_line", true);
+}
+
+/**
+ * Write a string to stdout without a newline
+ * Stack effect: ( str -- )
+ */
+StackCell *write_string(StackCell *stack) {
+  return write_string_cell(stack, "write", false);
+}
+
 /**
  * Read a line from stdin
  * Stack effect: ( -- str )
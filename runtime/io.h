@@ -7,6 +7,7 @@
  *
  * All I/O operations are non-blocking and async:
  * - write_line() - Write a line to stdout (yields on EWOULDBLOCK)
+ * - write_string() - Write a string to stdout with no newline (backs `write`)
  * - read_line() - Read a line from stdin (yields on EWOULDBLOCK)
  */
 
@@ -32,6 +33,16 @@
  */
 StackCell *write_line(StackCell *stack);
 
+/**
+ * Write a string to stdout, without a trailing newline
+ *
+ * Backs the `write` word (named write_string so it doesn't clash with POSIX
+ * write()). Blocks and errors exactly like write_line.
+ *
+ * Stack effect: ( str -- )
+ */
+StackCell *write_string(StackCell *stack);
+
 /**
  * Read a line from stdin
  *
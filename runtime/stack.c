@@ -39,10 +39,19 @@ void free_cell(StackCell *cell) {
     free(cell->value.s);
   }
 
-  // Free variant data (which is a heap-allocated StackCell for single-field
-  // variants)
+  // Free variant data (a NULL-terminated chain of field cells)
   if (cell->tag == TAG_VARIANT && cell->value.variant.data) {
-    free_cell((StackCell *)cell->value.variant.data);
+    free_stack((StackCell *)cell->value.variant.data);
+  }
+
+  // Free the rest of a composed quotation, or a curried one's captured value
+  // and inner quotation
+  if (cell->tag == TAG_QUOTATION && cell->value.quotation.then) {
+    if (cell->value.quotation.func) {
+      free_cell(cell->value.quotation.then);
+    } else {
+      free_stack(cell->value.quotation.then);
+    }
   }
 
   free(cell);
@@ -56,6 +65,73 @@ void free_stack(StackCell *stack) {
   }
 }
 
+// A curried quotation keeps its captured value and inner quotation in `then`
+static bool is_curried(const StackCell *quotation) {
+  return quotation->value.quotation.func == NULL;
+}
+
+// Copy a quotation value, including the chain of quotations composed after it
+static void copy_quotation(StackCell *dst, const StackCell *src) {
+  dst->value.quotation.func = src->value.quotation.func;
+  dst->value.quotation.then = NULL;
+  if (is_curried(src)) {
+    // stack_dup deep-copies the captured value of any type
+    StackCell *value = stack_dup(src->value.quotation.then);
+    StackCell *inner = alloc_cell();
+    inner->tag = TAG_QUOTATION;
+    copy_quotation(inner, src->value.quotation.then->next);
+    inner->next = NULL;
+    value->next = inner;
+    dst->value.quotation.then = value;
+  } else if (src->value.quotation.then) {
+    StackCell *then = alloc_cell();
+    then->tag = TAG_QUOTATION;
+    copy_quotation(then, src->value.quotation.then);
+    dst->value.quotation.then = then;
+  }
+}
+
+static bool quotations_equal(const StackCell *a, const StackCell *b);
+
+// Whether two values captured by curry are equal (variants never compare equal)
+static bool captured_equal(const StackCell *a, const StackCell *b) {
+  if (a->tag != b->tag) {
+    return false;
+  }
+  switch (a->tag) {
+  case TAG_INT:
+    return a->value.i == b->value.i;
+  case TAG_BOOL:
+    return a->value.b == b->value.b;
+  case TAG_STRING:
+    return strcmp(a->value.s, b->value.s) == 0;
+  case TAG_QUOTATION:
+    return quotations_equal(a, b);
+  case TAG_VARIANT:
+    return false;
+  }
+  return false;
+}
+
+// Two quotations are equal when they run the same chain of functions, with
+// equal values captured at the same points
+static bool quotations_equal(const StackCell *a, const StackCell *b) {
+  while (a && b) {
+    if (a->value.quotation.func != b->value.quotation.func) {
+      return false;
+    }
+    if (is_curried(a)) {
+      const StackCell *a_value = a->value.quotation.then;
+      const StackCell *b_value = b->value.quotation.then;
+      return captured_equal(a_value, b_value) &&
+             quotations_equal(a_value->next, b_value->next);
+    }
+    a = a->value.quotation.then;
+    b = b->value.quotation.then;
+  }
+  return a == b;
+}
+
 // Runtime error handler
 // Note: Currently uses exit(1) for simplicity. In a production runtime,
 // this could be replaced with setjmp/longjmp or return error codes up the
@@ -80,32 +156,64 @@ void runtime_error(const char *message) {
   exit(1);
 }
 
+// Print a single cell's value (no trailing separator)
+static void print_cell(const StackCell *cell) {
+  switch (cell->tag) {
+  case TAG_INT:
+    printf("%lld", (long long)cell->value.i);
+    break;
+  case TAG_BOOL:
+    printf("%s", cell->value.b ? "true" : "false");
+    break;
+  case TAG_STRING:
+    printf("\"%s\"", cell->value.s);
+    break;
+  case TAG_QUOTATION:
+    printf("<quotation>");
+    break;
+  case TAG_VARIANT:
+    printf("<variant:%u>", cell->value.variant.tag);
+    break;
+  }
+}
+
 void print_stack(StackCell *stack) {
   printf("Stack (top to bottom): ");
   StackCell *current = stack;
   while (current) {
-    switch (current->tag) {
-    case TAG_INT:
-      printf("%lld ", (long long)current->value.i);
-      break;
-    case TAG_BOOL:
-      printf("%s ", current->value.b ? "true" : "false");
-      break;
-    case TAG_STRING:
-      printf("\"%s\" ", current->value.s);
-      break;
-    case TAG_QUOTATION:
-      printf("<quotation> ");
-      break;
-    case TAG_VARIANT:
-      printf("<variant:%u> ", current->value.variant.tag);
-      break;
-    }
+    print_cell(current);
+    printf(" ");
     current = current->next;
   }
   printf("\n");
 }
 
+void check_stack_depth(StackCell *stack, int64_t expected) {
+  int64_t depth = 0;
+  for (StackCell *current = stack; current; current = current->next) {
+    depth++;
+  }
+  if (depth != expected) {
+    char message[128];
+    snprintf(message, sizeof message,
+             "program ended with %lld value(s) on the stack, but its entry "
+             "word declares %lld",
+             (long long)depth, (long long)expected);
+    runtime_error(message);
+  }
+}
+
+StackCell *print_top(StackCell *stack) {
+  if (!stack) {
+    runtime_error("inspect: stack underflow");
+  }
+  print_cell(stack);
+  printf("\n");
+  // Flush so debug output interleaves correctly with write_line's direct writes
+  fflush(stdout);
+  return stack;
+}
+
 // ============================================================================
 // Stack Operations
 // ============================================================================
@@ -138,58 +246,29 @@ StackCell *stack_dup(StackCell *stack) {
     }
     break;
   case TAG_QUOTATION:
-    new_cell->value.quotation = stack->value.quotation;
+    copy_quotation(new_cell, stack);
     break;
   case TAG_VARIANT:
     // Copy variant tag
     new_cell->value.variant.tag = stack->value.variant.tag;
 
-    // Deep copy variant data (which is a StackCell* for single-field variants)
+    // Deep copy variant data (a NULL-terminated chain of field cells)
     if (stack->value.variant.data) {
-      // Recursively duplicate the data cell
-      StackCell *original_data = (StackCell *)stack->value.variant.data;
-      StackCell *copied_data = alloc_cell();
-      copied_data->tag = original_data->tag;
-
-      // Copy the value based on its tag
-      switch (original_data->tag) {
-      case TAG_INT:
-        copied_data->value.i = original_data->value.i;
-        break;
-      case TAG_BOOL:
-        copied_data->value.b = original_data->value.b;
-        break;
-      case TAG_STRING:
-        if (original_data->value.s) {
-          copied_data->value.s = strdup(original_data->value.s);
-          if (!copied_data->value.s) {
-            // strdup failed - clean up allocated cells
-            free_cell(copied_data);
-            // Null out the data pointer to avoid double-free when freeing
-            // new_cell
-            new_cell->value.variant.data = NULL;
-            free_cell(new_cell);
-            runtime_error("dup: out of memory copying variant data string");
-          }
-        } else {
-          copied_data->value.s = NULL;
-        }
-        break;
-      case TAG_QUOTATION:
-        copied_data->value.quotation = original_data->value.quotation;
-        break;
-      case TAG_VARIANT:
-        // Nested variants not yet supported
-        free_cell(copied_data);
-        // Null out the data pointer to avoid double-free when freeing new_cell
-        new_cell->value.variant.data = NULL;
-        free_cell(new_cell);
-        runtime_error("dup: nested variant copying not yet supported");
-        break;
+      StackCell *copied_head = NULL;
+      StackCell **copied_tail = &copied_head;
+
+      // Each field is copied with stack_dup, so fields that are themselves
+      // variants (the tail of a list) are copied recursively, the same way
+      // free_cell frees them
+      for (StackCell *original_data = (StackCell *)stack->value.variant.data;
+           original_data; original_data = original_data->next) {
+        StackCell *copied_data = stack_dup(original_data);
+        copied_data->next = NULL;
+        *copied_tail = copied_data;
+        copied_tail = &copied_data->next;
       }
 
-      copied_data->next = NULL;
-      new_cell->value.variant.data = copied_data;
+      new_cell->value.variant.data = copied_head;
     } else {
       // Unit variant (no data)
       new_cell->value.variant.data = NULL;
@@ -255,7 +334,7 @@ StackCell *over(StackCell *stack) {
     }
     break;
   case TAG_QUOTATION:
-    new_cell->value.quotation = second->value.quotation;
+    copy_quotation(new_cell, second);
     break;
   case TAG_VARIANT:
     free_cell(new_cell);
@@ -281,11 +360,11 @@ StackCell *rot(StackCell *stack) {
   StackCell *third = second->next; // A (third element)
   StackCell *rest = third->next;
 
-  // Relink: A -> B -> C -> rest
+  // Relink from the top: A -> C -> B -> rest
   // Result: B C A (A moves to top)
-  second->next = first;
-  first->next = rest;
-  third->next = second;
+  third->next = first;
+  first->next = second;
+  second->next = rest;
 
   return third; // A is now on top
 }
@@ -340,7 +419,7 @@ StackCell *tuck(StackCell *stack) {
     }
     break;
   case TAG_QUOTATION:
-    copy->value.quotation = first->value.quotation;
+    copy_quotation(copy, first);
     break;
   case TAG_VARIANT:
     free_cell(copy);
@@ -356,6 +435,23 @@ StackCell *tuck(StackCell *stack) {
   return first;
 }
 
+StackCell *two_dup(StackCell *stack) {
+  if (!stack || !stack->next) {
+    runtime_error("2dup: stack underflow");
+  }
+
+  // A B -> A B A -> A B A B
+  return over(over(stack));
+}
+
+StackCell *two_drop(StackCell *stack) {
+  if (!stack || !stack->next) {
+    runtime_error("2drop: stack underflow");
+  }
+
+  return drop(drop(stack));
+}
+
 // ============================================================================
 // Arithmetic Operations
 // ============================================================================
@@ -400,6 +496,72 @@ StackCell *subtract(StackCell *stack) {
   return push_int(rest, a - b);
 }
 
+StackCell *negate(StackCell *stack) {
+  if (!stack) {
+    runtime_error("negate: stack underflow");
+  }
+  if (stack->tag != TAG_INT) {
+    runtime_error("negate: type error (expected Int)");
+  }
+
+  // Negate through uint64_t so INT64_MIN wraps instead of overflowing
+  stack->value.i = (int64_t)(0 - (uint64_t)stack->value.i);
+  return stack;
+}
+
+StackCell *int_abs(StackCell *stack) {
+  if (!stack) {
+    runtime_error("abs: stack underflow");
+  }
+  if (stack->tag != TAG_INT) {
+    runtime_error("abs: type error (expected Int)");
+  }
+
+  if (stack->value.i < 0) {
+    stack->value.i = (int64_t)(0 - (uint64_t)stack->value.i);
+  }
+  return stack;
+}
+
+StackCell *signum(StackCell *stack) {
+  if (!stack) {
+    runtime_error("signum: stack underflow");
+  }
+  if (stack->tag != TAG_INT) {
+    runtime_error("signum: type error (expected Int)");
+  }
+
+  int64_t a = stack->value.i;
+  stack->value.i = (a > 0) - (a < 0);
+  return stack;
+}
+
+StackCell *gcd(StackCell *stack) {
+  if (!stack || !stack->next) {
+    runtime_error("gcd: stack underflow");
+  }
+  if (stack->tag != TAG_INT || stack->next->tag != TAG_INT) {
+    runtime_error("gcd: type error (expected Int Int)");
+  }
+
+  // Work on magnitudes as uint64_t, where |INT64_MIN| fits
+  int64_t b = stack->value.i;
+  int64_t a = stack->next->value.i;
+  uint64_t x = a < 0 ? 0 - (uint64_t)a : (uint64_t)a;
+  uint64_t y = b < 0 ? 0 - (uint64_t)b : (uint64_t)b;
+  while (y != 0) {
+    uint64_t r = x % y;
+    x = y;
+    y = r;
+  }
+
+  StackCell *rest = stack->next->next;
+  free_cell(stack->next);
+  free_cell(stack);
+
+  return push_int(rest, (int64_t)x);
+}
+
 StackCell *multiply(StackCell *stack) {
   if (!stack || !stack->next) {
     runtime_error("multiply: stack underflow");
@@ -437,7 +599,31 @@ StackCell *divide_op(StackCell *stack) {
   free_cell(stack->next);
   free_cell(stack);
 
-  return push_int(rest, a / b);
+  // INT64_MIN / -1 overflows in C; like the other operations it wraps, to INT64_MIN
+  return push_int(rest, b == -1 ? (int64_t)(0 - (uint64_t)a) : a / b);
+}
+
+StackCell *mod_op(StackCell *stack) {
+  if (!stack || !stack->next) {
+    runtime_error("mod: stack underflow");
+  }
+  if (stack->tag != TAG_INT || stack->next->tag != TAG_INT) {
+    runtime_error("mod: type error (expected Int Int)");
+  }
+
+  int64_t b = stack->value.i;
+  int64_t a = stack->next->value.i;
+
+  if (b == 0) {
+    runtime_error("mod: division by zero");
+  }
+
+  StackCell *rest = stack->next->next;
+  free_cell(stack->next);
+  free_cell(stack);
+
+  // INT64_MIN % -1 overflows in C even though the remainder is 0
+  return push_int(rest, b == -1 ? 0 : a % b);
 }
 
 // ============================================================================
@@ -500,7 +686,7 @@ StackCell *equal(StackCell *stack) {
       result = (strcmp(stack->value.s, stack->next->value.s) == 0);
       break;
     case TAG_QUOTATION:
-      result = (stack->value.quotation == stack->next->value.quotation);
+      result = quotations_equal(stack, stack->next);
       break;
     case TAG_VARIANT:
       // TODO: Implement variant equality
@@ -550,7 +736,8 @@ StackCell *push_string(StackCell *stack, const char *value) {
 StackCell *push_quotation(StackCell *stack, void *func_ptr) {
   StackCell *cell = alloc_cell();
   cell->tag = TAG_QUOTATION;
-  cell->value.quotation = func_ptr;
+  cell->value.quotation.func = func_ptr;
+  cell->value.quotation.then = NULL;
   cell->next = stack;
   return cell;
 }
@@ -564,6 +751,101 @@ StackCell *push_variant(StackCell *stack, uint32_t tag, void *data) {
   return cell;
 }
 
+StackCell *make_variant(StackCell *stack, uint32_t tag, uint32_t field_count) {
+  if (field_count == 0) {
+    return push_variant(stack, tag, NULL);
+  }
+
+  // Detach the top field_count cells as the variant's data
+  StackCell *fields = stack;
+  StackCell *last = stack;
+  for (uint32_t i = 1; i < field_count; i++) {
+    if (!last) {
+      runtime_error("make_variant: stack underflow");
+    }
+    last = last->next;
+  }
+  if (!last) {
+    runtime_error("make_variant: stack underflow");
+  }
+
+  StackCell *rest = last->next;
+  last->next = NULL;
+  return push_variant(rest, tag, fields);
+}
+
+uint32_t variant_tag(StackCell *stack) {
+  if (!stack) {
+    runtime_error("variant_tag: stack underflow");
+  }
+  if (stack->tag != TAG_VARIANT) {
+    runtime_error("variant_tag: expected variant on top of stack");
+  }
+  return stack->value.variant.tag;
+}
+
+StackCell *unwrap_variant(StackCell *stack, uint32_t field_count) {
+  if (!stack) {
+    runtime_error("unwrap_variant: stack underflow");
+  }
+  if (stack->tag != TAG_VARIANT) {
+    runtime_error("unwrap_variant: expected variant on top of stack");
+  }
+
+  StackCell *rest = stack->next;
+  StackCell *fields = (StackCell *)stack->value.variant.data;
+  free(stack); // The fields move to the stack, so free only the variant cell
+
+  if (field_count == 0) {
+    if (fields) {
+      free_stack(fields);
+    }
+    return rest;
+  }
+
+  // Walk to the deepest field and link it to the rest of the stack
+  StackCell *last = fields;
+  for (uint32_t i = 1; i < field_count && last; i++) {
+    last = last->next;
+  }
+  if (!last || last->next) {
+    runtime_error("unwrap_variant: field count does not match variant data");
+  }
+  last->next = rest;
+  return fields;
+}
+
+// ============================================================================
+// Cell Accessors
+// ============================================================================
+
+bool cell_bool(StackCell *stack) {
+  if (!stack) {
+    runtime_error("cell_bool: stack underflow");
+  }
+  if (stack->tag != TAG_BOOL) {
+    runtime_error("cell_bool: expected bool on top of stack");
+  }
+  return stack->value.b;
+}
+
+StackCell *cell_rest(StackCell *stack) {
+  if (!stack) {
+    runtime_error("cell_rest: stack underflow");
+  }
+  return stack->next;
+}
+
+int64_t cell_int(StackCell *stack) {
+  if (!stack) {
+    runtime_error("cell_int: stack underflow");
+  }
+  if (stack->tag != TAG_INT) {
+    runtime_error("cell_int: expected int on top of stack");
+  }
+  return stack->value.i;
+}
+
 // ============================================================================
 // String Operations
 // ============================================================================
@@ -665,6 +947,69 @@ StackCell *string_equal(StackCell *stack) {
   return push_bool(rest, result);
 }
 
+// Build Cons(head, tail) as a detached cell: the fields are stored top first,
+// so the tail cell comes before the head
+static StackCell *list_cons(const char *head, size_t len, StackCell *tail) {
+  char *copy = malloc(len + 1);
+  if (!copy) {
+    runtime_error("string_split: out of memory");
+  }
+  memcpy(copy, head, len);
+  copy[len] = '\0';
+
+  tail->next = push_string(NULL, copy);
+  free(copy);
+  return push_variant(NULL, LIST_CONS_TAG, tail);
+}
+
+StackCell *string_split(StackCell *stack) {
+  if (!stack || !stack->next) {
+    runtime_error("string_split: stack underflow");
+  }
+  if (stack->tag != TAG_STRING || stack->next->tag != TAG_STRING) {
+    runtime_error("string_split: expected two strings on stack");
+  }
+
+  const char *delim = stack->value.s;
+  const char *str = stack->next->value.s;
+  if (!str || !delim) {
+    runtime_error("string_split: NULL string pointer");
+  }
+  size_t delim_len = strlen(delim);
+  if (delim_len == 0) {
+    runtime_error("string_split: empty delimiter");
+  }
+
+  // Record where each piece starts, then build the list from the last piece back
+  size_t pieces = 1;
+  for (const char *p = strstr(str, delim); p; p = strstr(p + delim_len, delim)) {
+    pieces++;
+  }
+  const char **starts = malloc(pieces * sizeof(*starts));
+  if (!starts) {
+    runtime_error("string_split: out of memory");
+  }
+  starts[0] = str;
+  size_t n = 1;
+  for (const char *p = strstr(str, delim); p; p = strstr(p + delim_len, delim)) {
+    starts[n++] = p + delim_len;
+  }
+
+  const char *end = str + strlen(str);
+  StackCell *list = push_variant(NULL, LIST_NIL_TAG, NULL);
+  for (size_t i = pieces; i-- > 0;) {
+    list = list_cons(starts[i], (size_t)(end - starts[i]), list);
+    end = starts[i] - delim_len;
+  }
+  free(starts);
+
+  StackCell *rest = stack->next->next;
+  free_cell(stack->next);
+  free_cell(stack);
+  list->next = rest;
+  return list;
+}
+
 // ============================================================================
 // Control Flow Operations (Placeholders)
 // ============================================================================
@@ -678,15 +1023,99 @@ StackCell *call_quotation(StackCell *stack) {
   }
 
   // Pop the quotation
-  void *func_ptr = stack->value.quotation;
+  void *func_ptr = stack->value.quotation.func;
+  StackCell *then = stack->value.quotation.then;
   StackCell *rest = stack->next;
   free(stack);
 
+  // A curried quotation pushes its captured value, then calls its inner quotation
+  if (!func_ptr) {
+    StackCell *value = then;
+    StackCell *inner = value->next;
+    value->next = rest;
+    inner->next = value;
+    return call_quotation(inner);
+  }
+
   // Call the function pointer with the rest of the stack
   // The function has signature: StackCell* (*)(StackCell*)
   typedef StackCell *(*QuotationFunc)(StackCell *);
   QuotationFunc func = (QuotationFunc)func_ptr;
-  return func(rest);
+  StackCell *result = func(rest);
+
+  // A composed quotation continues with the quotation composed after it
+  if (then) {
+    then->next = result;
+    return call_quotation(then);
+  }
+  return result;
+}
+
+StackCell *compose_quotations(StackCell *stack) {
+  if (!stack || !stack->next) {
+    runtime_error("compose: stack underflow");
+  }
+  if (stack->tag != TAG_QUOTATION || stack->next->tag != TAG_QUOTATION) {
+    runtime_error("compose: expected two quotations on top of stack");
+  }
+
+  StackCell *second = stack;
+  StackCell *first = stack->next;
+  StackCell *rest = first->next;
+
+  // Append the top quotation to the end of the other's composed chain; a
+  // curried quotation's chain continues in its inner quotation
+  StackCell *last = first;
+  while (last->value.quotation.then) {
+    last = is_curried(last) ? last->value.quotation.then->next
+                            : last->value.quotation.then;
+  }
+  last->value.quotation.then = second;
+  second->next = NULL;
+
+  first->next = rest;
+  return first;
+}
+
+StackCell *seq(StackCell *stack) {
+  if (!stack || !stack->next) {
+    runtime_error("seq: stack underflow");
+  }
+  if (stack->tag != TAG_QUOTATION || stack->next->tag != TAG_QUOTATION) {
+    runtime_error("seq: expected two quotations on top of stack");
+  }
+
+  StackCell *second = stack;
+  StackCell *first = stack->next;
+
+  // Run the first quotation with the second set aside, then the second
+  StackCell *result = call_quotation(first);
+  second->next = result;
+  return call_quotation(second);
+}
+
+StackCell *curry(StackCell *stack) {
+  if (!stack || !stack->next) {
+    runtime_error("curry: stack underflow");
+  }
+  if (stack->tag != TAG_QUOTATION) {
+    runtime_error("curry: expected a quotation on top of stack");
+  }
+
+  StackCell *quotation = stack;
+  StackCell *value = stack->next;
+  StackCell *rest = value->next;
+
+  // Both cells move into the curried quotation's captured list
+  value->next = quotation;
+  quotation->next = NULL;
+
+  StackCell *curried = alloc_cell();
+  curried->tag = TAG_QUOTATION;
+  curried->value.quotation.func = NULL;
+  curried->value.quotation.then = value;
+  curried->next = rest;
+  return curried;
 }
 
 StackCell *if_then_else(StackCell *stack) {
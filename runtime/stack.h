@@ -12,6 +12,7 @@
 #define CEM_RUNTIME_STACK_H
 
 #include <stdbool.h>
+#include <stddef.h>
 #include <stdint.h>
 
 /**
@@ -41,17 +42,23 @@ typedef enum {
  *   - int64_t i: 8 bytes
  *   - bool b: 1 byte (C99 bool from stdbool.h, typically uint8_t)
  *   - char* s: 8 bytes
- *   - void* quotation: 8 bytes
+ *   - quotation struct: 16 bytes (function pointer + pointer to composed tail)
  *   - variant struct: 16 bytes (4-byte tag + 4-byte padding + 8-byte pointer)
  * - next: 8 bytes (pointer) at offset 24
  * TOTAL: 32 bytes
  *
  * IMPORTANT ABI ASSUMPTIONS for LLVM codegen:
- * 1. bool is represented as i8 (uint8_t/unsigned char)
+ * 1. bool is represented as i8 (uint8_t/unsigned char) holding 0 or 1
  * 2. bool value is stored at the first byte of the union (offset 8 from struct
  * start)
  * 3. The union is 16 bytes due to the variant struct being the largest member
  *
+ * Generated code never touches that byte directly: it passes booleans to
+ * push_bool and reads them back with cell_bool as `i1 zeroext`, which is how
+ * clang lowers a C bool parameter or return value. The attribute makes the i1
+ * travel zero-extended in both directions, so a push always stores exactly 0
+ * or 1 and a load is a plain truncation back to i1.
+ *
  * If the C bool type changes (e.g., becomes i32 on some platform), the LLVM
  * code generation in src/codegen/mod.rs must be updated accordingly.
  */
@@ -62,7 +69,13 @@ typedef struct StackCell {
     int64_t i;       // Integer value
     bool b;          // Boolean value (ABI: typically uint8_t)
     char *s;         // String value (owned)
-    void *quotation; // Quotation (function pointer)
+    // A curried quotation (from curry) has a NULL func, and its `then` is a
+    // two-cell list linked by `next`: the captured value, then the quotation
+    // to call once that value is pushed
+    struct {
+      void *func;             // Quotation function pointer
+      struct StackCell *then; // Quotation to run afterwards (from compose), or NULL
+    } quotation;
     struct {
       uint32_t tag; // Variant tag
       void *data;   // Variant data
@@ -72,6 +85,10 @@ typedef struct StackCell {
   struct StackCell *next; // Pointer to rest of stack
 } StackCell;
 
+// Codegen can relink cells itself (Target::cell_next_offset in src/codegen/target.rs)
+_Static_assert(offsetof(StackCell, next) == 8 + 2 * sizeof(void *),
+               "StackCell.next moved; update Target::cell_next_offset");
+
 // ============================================================================
 // Stack Operations
 // ============================================================================
@@ -118,6 +135,18 @@ StackCell *nip(StackCell *stack);
  */
 StackCell *tuck(StackCell *stack);
 
+/**
+ * 2dup ( A B -- A B A B )
+ * Duplicate the top two elements (backs the `2dup` word)
+ */
+StackCell *two_dup(StackCell *stack);
+
+/**
+ * 2drop ( A B -- )
+ * Remove the top two elements (backs the `2drop` word)
+ */
+StackCell *two_drop(StackCell *stack);
+
 // ============================================================================
 // Arithmetic Operations
 // ============================================================================
@@ -134,6 +163,32 @@ StackCell *add(StackCell *stack);
  */
 StackCell *subtract(StackCell *stack);
 
+/**
+ * negate ( Int -- Int )
+ * Negate an integer (INT64_MIN wraps to itself)
+ */
+StackCell *negate(StackCell *stack);
+
+/**
+ * abs ( Int -- Int )
+ * Absolute value of an integer (INT64_MIN wraps to itself; backs the `abs` word)
+ */
+StackCell *int_abs(StackCell *stack);
+
+/**
+ * signum ( Int -- Int )
+ * -1, 0, or 1 as the integer is negative, zero, or positive
+ */
+StackCell *signum(StackCell *stack);
+
+/**
+ * gcd ( Int Int -- Int )
+ * Greatest common divisor of the two integers' absolute values. gcd of 0 and n is
+ * |n|, so both zero gives 0; a result of 2^63 (only from INT64_MIN and 0 or
+ * INT64_MIN) wraps to INT64_MIN.
+ */
+StackCell *gcd(StackCell *stack);
+
 /**
  * multiply ( Int Int -- Int )
  * Multiply two integers
@@ -142,10 +197,19 @@ StackCell *multiply(StackCell *stack);
 
 /**
  * divide ( Int Int -- Int )
- * Divide two integers (second / first)
+ * Divide two integers (second / first), truncating toward zero (also `quot`).
+ * Aborts with a runtime error when the divisor is zero. INT64_MIN / -1 wraps to
+ * INT64_MIN.
  */
 StackCell *divide_op(StackCell *stack);
 
+/**
+ * mod ( Int Int -- Int )
+ * Remainder of second / first, with the sign of the dividend (also `rem`).
+ * Aborts with a runtime error when the divisor is zero.
+ */
+StackCell *mod_op(StackCell *stack);
+
 // ============================================================================
 // Comparison Operations
 // ============================================================================
@@ -205,6 +269,55 @@ StackCell *push_quotation(StackCell *stack, void *func_ptr);
  */
 StackCell *push_variant(StackCell *stack, uint32_t tag, void *data);
 
+/**
+ * make_variant ( fields... -- Variant )
+ * Pop field_count cells and push a variant that owns them as its data.
+ * The fields are kept as a chain (top field first, next-terminated by NULL)
+ * so match can relink the chain onto the rest of the stack to unwrap them.
+ * @param stack Current stack
+ * @param tag Variant tag (constructor index)
+ * @param field_count Number of fields to take from the stack
+ */
+StackCell *make_variant(StackCell *stack, uint32_t tag, uint32_t field_count);
+
+/**
+ * variant_tag ( Variant -- Variant )
+ * Read the constructor tag of the variant on top of the stack (non-consuming).
+ * Lets codegen switch on tags without knowing the StackCell layout.
+ */
+uint32_t variant_tag(StackCell *stack);
+
+/**
+ * unwrap_variant ( Variant -- fields... )
+ * Pop the variant on top of the stack and relink its field_count fields onto
+ * the rest of the stack, in the order they were constructed.
+ * @param stack Current stack (variant on top)
+ * @param field_count Number of fields the matched constructor declares
+ */
+StackCell *unwrap_variant(StackCell *stack, uint32_t field_count);
+
+// ============================================================================
+// Cell Accessors (used by generated code instead of raw struct offsets)
+// ============================================================================
+
+/**
+ * cell_bool ( Bool -- Bool )
+ * Read the boolean on top of the stack (non-consuming)
+ */
+bool cell_bool(StackCell *stack);
+
+/**
+ * cell_rest
+ * Return the cell below the top of the stack (the top cell's next pointer)
+ */
+StackCell *cell_rest(StackCell *stack);
+
+/**
+ * cell_int ( Int -- Int )
+ * Read the integer on top of the stack (non-consuming)
+ */
+int64_t cell_int(StackCell *stack);
+
 // ============================================================================
 // String Operations
 // ============================================================================
@@ -227,6 +340,23 @@ StackCell *string_concat(StackCell *stack);
  */
 StackCell *string_equal(StackCell *stack);
 
+/**
+ * Variant tags of the builtin List type's constructors
+ *
+ * These are codegen's variant_tag_for("List", "Cons") and ("List", "Nil"), so lists
+ * built here match against a program's `Cons` and `Nil` like lists it built itself.
+ */
+#define LIST_CONS_TAG 521182884u
+#define LIST_NIL_TAG 2061549104u
+
+/**
+ * string_split ( String String -- List(String) )
+ * Split the second string at each occurrence of the delimiter on top, in order.
+ * An empty input yields a one-element list holding the empty string; an empty
+ * delimiter is a runtime error.
+ */
+StackCell *string_split(StackCell *stack);
+
 // ============================================================================
 // Control Flow Operations
 // ============================================================================
@@ -237,6 +367,28 @@ StackCell *string_equal(StackCell *stack);
  */
 StackCell *call_quotation(StackCell *stack);
 
+/**
+ * compose_quotations ( Quotation Quotation -- Quotation )
+ * Concatenate two quotations: the result runs the second-from-top quotation,
+ * then the top one. Backs the `compose` word.
+ */
+StackCell *compose_quotations(StackCell *stack);
+
+/**
+ * seq ( Quotation Quotation -- ... )
+ * Call the second-from-top quotation, then the top one, on the stack below
+ * them. Backs the `seq` word, whose quotations both leave the stack as they
+ * found it.
+ */
+StackCell *seq(StackCell *stack);
+
+/**
+ * curry ( A Quotation -- Quotation )
+ * Capture the value under the quotation: the result pushes that value, then
+ * runs the quotation. The value moves into the new quotation.
+ */
+StackCell *curry(StackCell *stack);
+
 /**
  * if_then_else ( Bool Quotation Quotation -- ... )
  * Conditional execution: if true call first quotation, else call second
@@ -274,6 +426,19 @@ void free_stack(StackCell *stack);
  */
 void print_stack(StackCell *stack);
 
+/**
+ * Fail with a runtime error unless the stack holds exactly `expected` values.
+ * Emitted after the entry word returns under `--strict-stack`.
+ */
+void check_stack_depth(StackCell *stack, int64_t expected);
+
+/**
+ * print_top ( A -- A )
+ * Print the top stack value followed by a newline, leaving it in place.
+ * Backs the `inspect` debugging word.
+ */
+StackCell *print_top(StackCell *stack);
+
 /**
  * Runtime error handling
  */
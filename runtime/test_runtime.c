@@ -41,6 +41,50 @@ void test_arithmetic() {
   assert(stack->value.i == 7);
   free_stack(stack);
 
+  // Test: 5 negate => -5
+  stack = NULL;
+  stack = push_int(stack, 5);
+  stack = negate(stack);
+  assert(stack->value.i == -5);
+  free_stack(stack);
+
+  // Test: -5 abs => 5
+  stack = NULL;
+  stack = push_int(stack, -5);
+  stack = int_abs(stack);
+  assert(stack->value.i == 5);
+  free_stack(stack);
+
+  // Test: -3 signum => -1, 0 signum => 0
+  stack = NULL;
+  stack = push_int(stack, -3);
+  stack = signum(stack);
+  assert(stack->value.i == -1);
+  stack->value.i = 0;
+  stack = signum(stack);
+  assert(stack->value.i == 0);
+  free_stack(stack);
+
+  // Test: 12 -18 gcd => 6, 0 0 gcd => 0, INT64_MIN 0 gcd wraps to INT64_MIN
+  stack = NULL;
+  stack = push_int(stack, 12);
+  stack = push_int(stack, -18);
+  stack = gcd(stack);
+  assert(stack->value.i == 6);
+  free_stack(stack);
+  stack = NULL;
+  stack = push_int(stack, 0);
+  stack = push_int(stack, 0);
+  stack = gcd(stack);
+  assert(stack->value.i == 0);
+  free_stack(stack);
+  stack = NULL;
+  stack = push_int(stack, INT64_MIN);
+  stack = push_int(stack, 0);
+  stack = gcd(stack);
+  assert(stack->value.i == INT64_MIN);
+  free_stack(stack);
+
   // Test: 6 7 * => 42
   stack = NULL;
   stack = push_int(stack, 6);
@@ -57,7 +101,23 @@ void test_arithmetic() {
   assert(stack->value.i == 5);
   free_stack(stack);
 
-  printf("  ✓ add, subtract, multiply, divide work\n");
+  // Test: INT64_MIN -1 / wraps to INT64_MIN instead of overflowing
+  stack = NULL;
+  stack = push_int(stack, INT64_MIN);
+  stack = push_int(stack, -1);
+  stack = divide_op(stack);
+  assert(stack->value.i == INT64_MIN);
+  free_stack(stack);
+
+  // Test: -7 2 mod => -1 (sign of the dividend)
+  stack = NULL;
+  stack = push_int(stack, -7);
+  stack = push_int(stack, 2);
+  stack = mod_op(stack);
+  assert(stack->value.i == -1);
+  free_stack(stack);
+
+  printf("  ✓ add, subtract, negate, abs, signum, gcd, multiply, divide, mod work\n");
 }
 
 void test_stack_ops() {
@@ -90,7 +150,18 @@ void test_stack_ops() {
   assert(stack->next->next->value.i == 1);
   free_stack(stack);
 
-  printf("  ✓ dup, swap, over work\n");
+  // Test rot: 1 2 3 rot => 2 3 1
+  stack = NULL;
+  stack = push_int(stack, 1);
+  stack = push_int(stack, 2);
+  stack = push_int(stack, 3);
+  stack = rot(stack);
+  assert(stack->value.i == 1);
+  assert(stack->next->value.i == 3);
+  assert(stack->next->next->value.i == 2);
+  free_stack(stack);
+
+  printf("  ✓ dup, swap, over, rot work\n");
 }
 
 void test_comparisons() {
@@ -147,6 +218,75 @@ void test_strings() {
   printf("  ✓ push_string and string equality work\n");
 }
 
+// Collect the strings of a List(String) built by string_split, head first
+static size_t list_strings(StackCell *list, const char **out, size_t max) {
+  size_t count = 0;
+  while (list->value.variant.tag == LIST_CONS_TAG) {
+    StackCell *tail = (StackCell *)list->value.variant.data;
+    assert(count < max);
+    out[count++] = tail->next->value.s;
+    list = tail;
+  }
+  assert(list->value.variant.tag == LIST_NIL_TAG);
+  return count;
+}
+
+void test_string_split() {
+  printf("Testing string_split...\n");
+
+  const char *parts[8];
+  StackCell *stack = push_string(NULL, "a,b,c");
+  stack = push_string(stack, ",");
+  stack = string_split(stack);
+  assert(stack->tag == TAG_VARIANT && stack->next == NULL);
+  assert(list_strings(stack, parts, 8) == 3);
+  assert(strcmp(parts[0], "a") == 0 && strcmp(parts[1], "b") == 0 &&
+         strcmp(parts[2], "c") == 0);
+  free_stack(stack);
+
+  // Adjacent and trailing delimiters produce empty pieces
+  stack = push_string(NULL, "x::y::");
+  stack = push_string(stack, "::");
+  stack = string_split(stack);
+  assert(list_strings(stack, parts, 8) == 3);
+  assert(strcmp(parts[0], "x") == 0 && strcmp(parts[1], "y") == 0 &&
+         strcmp(parts[2], "") == 0);
+  free_stack(stack);
+
+  // An empty input is a single empty piece
+  stack = push_string(NULL, "");
+  stack = push_string(stack, ",");
+  stack = string_split(stack);
+  assert(list_strings(stack, parts, 8) == 1);
+  assert(strcmp(parts[0], "") == 0);
+  free_stack(stack);
+
+  printf("  ✓ string_split works\n");
+}
+
+void test_dup_list() {
+  printf("Testing dup of a list...\n");
+
+  // A two-element list nests its tail variant inside the head's fields
+  const char *parts[8];
+  StackCell *stack = push_string(NULL, "a,b");
+  stack = push_string(stack, ",");
+  stack = string_split(stack);
+  stack = stack_dup(stack);
+  assert(stack->next && stack->next->next == NULL);
+
+  assert(list_strings(stack, parts, 8) == 2);
+  assert(strcmp(parts[0], "a") == 0 && strcmp(parts[1], "b") == 0);
+  assert(list_strings(stack->next, parts, 8) == 2);
+  assert(strcmp(parts[0], "a") == 0 && strcmp(parts[1], "b") == 0);
+
+  // The copy owns its own cells, so both lists can be freed
+  assert(stack->value.variant.data != stack->next->value.variant.data);
+  free_stack(stack);
+
+  printf("  ✓ dup copies nested variants\n");
+}
+
 void test_example_program() {
   printf("Testing example: (5 + 3) * 2...\n");
 
@@ -164,6 +304,51 @@ void test_example_program() {
   printf("  ✓ Example program: result = 16\n");
 }
 
+void test_curry() {
+  printf("Testing curry...\n");
+
+  // 41 1 [ + ] curry : a quotation that adds 1
+  StackCell *stack = NULL;
+  stack = push_int(stack, 41);
+  stack = push_int(stack, 1);
+  stack = push_quotation(stack, (void *)add);
+  stack = curry(stack);
+  assert(stack->tag == TAG_QUOTATION);
+  assert(stack->next->value.i == 41);
+
+  // Copies capture their own value and compare equal to the original
+  stack = stack_dup(stack);
+  stack = equal(stack_dup(stack_dup(stack)));
+  assert(stack->value.b);
+  stack = drop(stack);
+
+  // Call one copy on 41, then the other on 1
+  stack = call_quotation(swap(rot(stack)));
+  assert(stack->value.i == 42);
+  assert(stack->next->tag == TAG_QUOTATION);
+  stack = call_quotation(swap(push_int(swap(stack), 1)));
+  assert(stack->value.i == 2);
+  assert(stack->next->value.i == 42);
+  free_stack(stack);
+
+  printf("  ✓ curry captures a value for later calls\n");
+}
+
+void test_seq() {
+  printf("Testing seq...\n");
+
+  // 3 [ dup ] [ * ] seq : dup must run before *
+  StackCell *stack = push_int(NULL, 3);
+  stack = push_quotation(stack, (void *)stack_dup);
+  stack = push_quotation(stack, (void *)multiply);
+  stack = seq(stack);
+  assert(stack->value.i == 9);
+  assert(stack->next == NULL);
+  free_stack(stack);
+
+  printf("  ✓ seq runs its quotations in order\n");
+}
+
 int main() {
   printf("=== Cem Runtime Tests ===\n\n");
 
@@ -172,6 +357,10 @@ int main() {
   test_stack_ops();
   test_comparisons();
   test_strings();
+  test_string_split();
+  test_dup_list();
+  test_curry();
+  test_seq();
   test_example_program();
 
   printf("\n✅ All runtime tests passed!\n");
@@ -4,6 +4,7 @@ Abstract Syntax Tree definitions for Cem
 This module defines the core AST types representing Cem programs.
 */
 pub mod types;
+pub mod visit;
 
 use std::fmt;
 use std::sync::Arc;
@@ -61,12 +62,59 @@ pub struct Program {
     pub word_defs: Vec<WordDef>,
 }
 
+impl Program {
+    /// Iterate over the words enabled under the given set of active features
+    pub fn enabled_words<'a>(
+        &'a self,
+        features: &'a [String],
+    ) -> impl Iterator<Item = &'a WordDef> + 'a {
+        self.word_defs.iter().filter(|w| w.is_enabled(features))
+    }
+
+    /// Combine programs parsed from separate files into one
+    ///
+    /// Fails on a type defined more than once, or a word defined more than once under
+    /// the same feature gate (alternatives under different `when(feature)` gates are fine).
+    pub fn merge(programs: impl IntoIterator<Item = Program>) -> Result<Program, String> {
+        let mut merged = Program {
+            type_defs: Vec::new(),
+            word_defs: Vec::new(),
+        };
+
+        for program in programs {
+            for typedef in program.type_defs {
+                if merged.type_defs.iter().any(|t| t.name == typedef.name) {
+                    return Err(format!("Type '{}' is defined more than once", typedef.name));
+                }
+                merged.type_defs.push(typedef);
+            }
+            for word in program.word_defs {
+                if let Some(previous) = merged
+                    .word_defs
+                    .iter()
+                    .find(|w| w.name == word.name && w.feature == word.feature)
+                {
+                    return Err(format!(
+                        "Word '{}' at {} is already defined at {}",
+                        word.name, word.loc, previous.loc
+                    ));
+                }
+                merged.word_defs.push(word);
+            }
+        }
+
+        Ok(merged)
+    }
+}
+
 /// Type definition (Algebraic Data Type / Sum Type)
 #[derive(Debug, Clone, PartialEq)]
 pub struct TypeDef {
     pub name: String,
     pub type_params: Vec<String>,
     pub variants: Vec<Variant>,
+    /// Documentation from the `##` comment lines directly above the definition
+    pub doc: Option<String>,
 }
 
 /// A variant of a sum type
@@ -83,6 +131,44 @@ pub struct WordDef {
     pub effect: types::Effect,
     pub body: Vec<Expr>,
     pub loc: SourceLoc, // Location of the word definition (: word_name line)
+    /// Feature gate from a `when(feature)` prefix; the word only exists when it is active
+    pub feature: Option<String>,
+    /// Inlining hint from an `inline` or `noinline` prefix
+    pub inline: Option<InlineHint>,
+    /// Documentation names given to effect slots, e.g. `( n:Int -- sum:Int )`
+    pub slot_names: SlotNames,
+    /// Documentation from the `##` comment lines directly above the definition
+    pub doc: Option<String>,
+}
+
+/// How the optimizer should treat calls to a word
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InlineHint {
+    /// `inline`: always inline the word into its callers
+    Always,
+    /// `noinline`: never inline it
+    Never,
+}
+
+/// Optional names for the slots of a word's effect signature
+///
+/// Each list runs bottom to top, parallel to the concrete types of the effect, with
+/// `None` for unnamed slots. Names are for documentation and diagnostics only and
+/// never affect typing.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct SlotNames {
+    pub inputs: Vec<Option<String>>,
+    pub outputs: Vec<Option<String>>,
+}
+
+impl WordDef {
+    /// Check whether this word is included under the given set of active features
+    pub fn is_enabled(&self, features: &[String]) -> bool {
+        match &self.feature {
+            Some(feature) => features.iter().any(|f| f == feature),
+            None => true,
+        }
+    }
 }
 
 /// Expression in the body of a word
@@ -110,11 +196,23 @@ pub enum Expr {
     },
 
     /// If expression (condition is top of stack)
+    ///
+    /// `ifte [ cond ] [ then ] [ else ]` computes its own condition: `condition` is that
+    /// quotation, run first and required to push just a Bool.
     If {
+        condition: Option<Box<Expr>>,
         then_branch: Box<Expr>,
         else_branch: Box<Expr>,
         loc: SourceLoc,
     },
+
+    /// While loop: runs `condition` (which pushes a Bool) and, while it is true,
+    /// `body`; both are quotations
+    While {
+        condition: Box<Expr>,
+        body: Box<Expr>,
+        loc: SourceLoc,
+    },
 }
 
 impl Expr {
@@ -128,6 +226,7 @@ impl Expr {
             Expr::Quotation(_, loc) => loc,
             Expr::Match { loc, .. } => loc,
             Expr::If { loc, .. } => loc,
+            Expr::While { loc, .. } => loc,
         }
     }
 }
@@ -139,14 +238,33 @@ pub struct MatchBranch {
     pub body: Vec<Expr>,
 }
 
-/// Pattern for matching on sum types
+/// Pattern for matching on sum types or integers
 #[derive(Debug, Clone, PartialEq)]
 pub enum Pattern {
-    /// Match a specific variant, binding its fields
-    Variant {
-        name: String,
-        // Field patterns could be added later for nested matching
-    },
+    /// Match a specific variant, pushing its fields
+    ///
+    /// `bindings` name the pushed fields bottom to top, as in `Cons(head, rest)`, for
+    /// documentation; when given there must be one per field. They aren't bound as words,
+    /// so an arm body naming one calls the word of that name. Empty when the pattern is
+    /// the bare variant name.
+    Variant { name: String, bindings: Vec<String> },
+
+    /// Match an Int equal to the literal
+    Int(i64),
+
+    /// Match any Int no earlier arm matched (`_`)
+    Wildcard,
+}
+
+impl fmt::Display for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Pattern::Variant { name, bindings } if bindings.is_empty() => write!(f, "{}", name),
+            Pattern::Variant { name, bindings } => write!(f, "{}({})", name, bindings.join(", ")),
+            Pattern::Int(n) => write!(f, "{}", n),
+            Pattern::Wildcard => write!(f, "_"),
+        }
+    }
 }
 
 impl fmt::Display for Expr {
@@ -170,7 +288,11 @@ impl fmt::Display for Expr {
                 }
                 write!(f, "end")
             }
+            Expr::If {
+                condition: Some(_), ..
+            } => write!(f, "ifte"),
             Expr::If { .. } => write!(f, "if"),
+            Expr::While { .. } => write!(f, "while"),
         }
     }
 }
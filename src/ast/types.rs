@@ -25,6 +25,15 @@ pub enum Type {
 
     /// Quotation type (first-class function)
     Quotation(Box<Effect>),
+
+    /// Quotation that owns a linear value `curry` moved into it (Linear - not Copy)
+    ///
+    /// It unifies with a plain quotation of the same effect; `captured` is the moved
+    /// value's type, kept for error messages.
+    Closure {
+        effect: Box<Effect>,
+        captured: Box<Type>,
+    },
 }
 
 /// Stack effect signature: (inputs -- outputs)
@@ -71,9 +80,29 @@ impl StackType {
 
     /// Create a stack from a vec of types (first = bottom, last = top)
     pub fn from_vec(types: Vec<Type>) -> Self {
-        types
-            .into_iter()
-            .fold(StackType::Empty, |stack, ty| stack.push(ty))
+        Self::from_vec_with_row(types, None)
+    }
+
+    /// Create a stack from types (first = bottom, last = top) over an optional row variable
+    ///
+    /// This is the exact inverse of `to_vec`.
+    pub fn from_vec_with_row(types: Vec<Type>, row: Option<String>) -> Self {
+        let base = row.map_or(StackType::Empty, StackType::RowVar);
+        types.into_iter().fold(base, |stack, ty| stack.push(ty))
+    }
+
+    /// Split the stack into its concrete types (first = bottom, last = top) and the
+    /// row variable at the bottom, if any
+    pub fn to_vec(&self) -> (Vec<Type>, Option<String>) {
+        let mut types: Vec<Type> = self.iter().cloned().collect();
+        types.reverse();
+        (types, self.row_var().map(str::to_string))
+    }
+
+    /// Iterate over the concrete types from the top of the stack down, stopping at
+    /// the bottom (empty stack or row variable)
+    pub fn iter(&self) -> StackIter<'_> {
+        StackIter { current: self }
     }
 
     /// Pop a type from the stack, returning (rest, top) or None if empty
@@ -98,6 +127,63 @@ impl StackType {
     pub fn is_row_var(&self) -> bool {
         matches!(self, StackType::RowVar(_))
     }
+
+    /// Get the row variable at the bottom of this stack, if it has one
+    pub fn row_var(&self) -> Option<&str> {
+        match self {
+            StackType::Empty => None,
+            StackType::Cons { rest, .. } => rest.row_var(),
+            StackType::RowVar(name) => Some(name),
+        }
+    }
+
+    /// Check whether this stack's values sit on top of `other`
+    ///
+    /// Concrete types are compared structurally from the top down, and `other` may hold
+    /// more values below them. The bottom of `self` (empty or a row variable) is not
+    /// compared, so `( Int )` is a prefix of `( String Int )` but not of `( Int String )`.
+    pub fn is_prefix_of(&self, other: &StackType) -> bool {
+        match (self, other) {
+            (StackType::Cons { rest: r1, top: t1 }, StackType::Cons { rest: r2, top: t2 }) => {
+                t1 == t2 && r1.is_prefix_of(r2)
+            }
+            (StackType::Cons { .. }, _) => false,
+            (StackType::Empty | StackType::RowVar(_), _) => true,
+        }
+    }
+
+    /// Count the concrete types above the bottom (empty stack or row variable)
+    pub fn concrete_depth(&self) -> usize {
+        self.iter().count()
+    }
+}
+
+/// Iterator over a stack's concrete types, top first (see `StackType::iter`)
+pub struct StackIter<'a> {
+    current: &'a StackType,
+}
+
+impl<'a> Iterator for StackIter<'a> {
+    type Item = &'a Type;
+
+    fn next(&mut self) -> Option<&'a Type> {
+        match self.current {
+            StackType::Cons { rest, top } => {
+                self.current = rest;
+                Some(top)
+            }
+            StackType::Empty | StackType::RowVar(_) => None,
+        }
+    }
+}
+
+impl<'a> IntoIterator for &'a StackType {
+    type Item = &'a Type;
+    type IntoIter = StackIter<'a>;
+
+    fn into_iter(self) -> StackIter<'a> {
+        self.iter()
+    }
 }
 
 impl Effect {
@@ -140,6 +226,7 @@ impl Type {
             Type::Var(_) => false,       // Conservative: assume not Copy
             Type::Named { .. } => false, // Conservative: requires trait analysis
             Type::Quotation(_) => true,  // Quotations are Copy (just code pointers for now)
+            Type::Closure { .. } => false,
         }
     }
 
@@ -159,18 +246,22 @@ impl fmt::Display for Type {
             Type::Named { name, args } => {
                 write!(f, "{}", name)?;
                 if !args.is_empty() {
-                    write!(f, "<")?;
+                    write!(f, "(")?;
                     for (i, arg) in args.iter().enumerate() {
                         if i > 0 {
-                            write!(f, ", ")?;
+                            write!(f, " ")?;
                         }
                         write!(f, "{}", arg)?;
                     }
-                    write!(f, ">")?;
+                    write!(f, ")")?;
                 }
                 Ok(())
             }
-            Type::Quotation(eff) => write!(f, "[{}]", eff),
+            Type::Quotation(eff) | Type::Closure { effect: eff, .. } => {
+                write!(f, "[")?;
+                eff.fmt_sides(f)?;
+                write!(f, " ]")
+            }
         }
     }
 }
@@ -185,14 +276,31 @@ impl fmt::Display for StackType {
                 }
                 write!(f, "{}", top)
             }
-            StackType::RowVar(name) => write!(f, "{}", name),
+            StackType::RowVar(name) => write!(f, "..{}", name),
         }
     }
 }
 
+/// Renders the Forth-style signature as written in source, e.g. `( Int -- Int )`
 impl fmt::Display for Effect {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "( {} -- {} )", self.inputs, self.outputs)
+        write!(f, "(")?;
+        self.fmt_sides(f)?;
+        write!(f, " )")
+    }
+}
+
+impl Effect {
+    /// Write ` inputs -- outputs`, shared by signatures `( .. )` and quotation types `[ .. ]`
+    fn fmt_sides(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if !matches!(self.inputs, StackType::Empty) {
+            write!(f, " {}", self.inputs)?;
+        }
+        write!(f, " --")?;
+        if !matches!(self.outputs, StackType::Empty) {
+            write!(f, " {}", self.outputs)?;
+        }
+        Ok(())
     }
 }
 
@@ -211,6 +319,40 @@ mod tests {
         assert_eq!(rest.depth(), Some(1));
     }
 
+    #[test]
+    fn test_iter_yields_types_from_the_top() {
+        let stack = StackType::from_vec(vec![
+            Type::Int,
+            Type::String,
+            Type::Var("A".to_string()),
+            Type::Bool,
+        ]);
+        let types: Vec<&Type> = stack.iter().collect();
+        assert_eq!(
+            types,
+            vec![
+                &Type::Bool,
+                &Type::Var("A".to_string()),
+                &Type::String,
+                &Type::Int
+            ]
+        );
+        assert_eq!(StackType::empty().iter().count(), 0);
+    }
+
+    #[test]
+    fn test_iter_stops_at_row_variable() {
+        let stack =
+            StackType::from_vec_with_row(vec![Type::Int, Type::Bool], Some("R".to_string()));
+        let types: Vec<&Type> = (&stack).into_iter().collect();
+        assert_eq!(types, vec![&Type::Bool, &Type::Int]);
+        assert_eq!(StackType::RowVar("R".to_string()).iter().next(), None);
+        assert_eq!(
+            stack.to_vec(),
+            (vec![Type::Int, Type::Bool], Some("R".to_string()))
+        );
+    }
+
     #[test]
     fn test_effect_composition() {
         // dup: (A -- A A)
@@ -235,6 +377,24 @@ mod tests {
         assert_eq!(composed.outputs.depth(), Some(1));
     }
 
+    #[test]
+    fn test_is_prefix_of() {
+        let int = StackType::empty().push(Type::Int);
+        let string_int = StackType::empty().push(Type::String).push(Type::Int);
+        let int_string = StackType::empty().push(Type::Int).push(Type::String);
+
+        assert!(int.is_prefix_of(&string_int));
+        assert!(!int.is_prefix_of(&int_string));
+        assert!(string_int.is_prefix_of(&string_int));
+        assert!(!string_int.is_prefix_of(&int));
+
+        // The bottom of the prefix isn't compared
+        assert!(StackType::empty().is_prefix_of(&int));
+        let row_int = StackType::RowVar("R".to_string()).push(Type::Int);
+        assert!(row_int.is_prefix_of(&string_int));
+        assert!(!string_int.is_prefix_of(&row_int));
+    }
+
     #[test]
     fn test_copy_types() {
         assert!(Type::Int.is_copy());
@@ -242,4 +402,51 @@ mod tests {
         assert!(!Type::String.is_copy());
         assert!(Type::String.is_linear());
     }
+
+    #[test]
+    fn test_to_vec_round_trip() {
+        let stacks = [
+            StackType::empty(),
+            StackType::from_vec(vec![Type::Int, Type::Bool, Type::String]),
+            StackType::RowVar("R".to_string())
+                .push(Type::Int)
+                .push(Type::Var("A".to_string())),
+            StackType::RowVar("R".to_string()),
+        ];
+
+        for stack in stacks {
+            let (types, row) = stack.to_vec();
+            assert_eq!(StackType::from_vec_with_row(types, row), stack);
+        }
+    }
+
+    #[test]
+    fn test_to_vec_order_and_row() {
+        let stack = StackType::RowVar("R".to_string())
+            .push(Type::Int)
+            .push(Type::Bool);
+        assert_eq!(
+            stack.to_vec(),
+            (vec![Type::Int, Type::Bool], Some("R".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_effect_display_matches_source_syntax() {
+        let option = Type::Named {
+            name: "Option".to_string(),
+            args: vec![Type::Int],
+        };
+        let effect = Effect::from_vecs(vec![option, Type::Int], vec![Type::Int]);
+        assert_eq!(effect.to_string(), "( Option(Int) Int -- Int )");
+
+        let effect = Effect::from_vecs(vec![], vec![Type::Bool]);
+        assert_eq!(effect.to_string(), "( -- Bool )");
+        assert_eq!(Effect::from_vecs(vec![], vec![]).to_string(), "( -- )");
+
+        let row = |name: &str| StackType::RowVar(name.to_string());
+        let quotation = Type::Quotation(Box::new(Effect::new(row("A"), row("B"))));
+        let effect = Effect::new(row("A").push(quotation), row("B"));
+        assert_eq!(effect.to_string(), "( ..A [ ..A -- ..B ] -- ..B )");
+    }
 }
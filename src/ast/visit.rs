@@ -0,0 +1,100 @@
+/**
+Traversal of expression trees
+
+Passes that need to look at every expression in a body (analyses, lints, rewrites
+that first collect facts) use these instead of matching on `Quotation`, `If`,
+`While`, and `Match` themselves. Expressions are visited in source order, each
+before the expressions nested inside it.
+*/
+use super::Expr;
+
+/// Fold `f` over `expr` and every expression nested inside it
+pub fn fold_expr<'a, T>(expr: &'a Expr, init: T, f: &mut impl FnMut(T, &'a Expr) -> T) -> T {
+    let acc = f(init, expr);
+    match expr {
+        Expr::Quotation(body, _) => fold_exprs(body, acc, f),
+        Expr::If {
+            condition,
+            then_branch,
+            else_branch,
+            ..
+        } => {
+            let acc = match condition {
+                Some(condition) => fold_expr(condition, acc, f),
+                None => acc,
+            };
+            let acc = fold_expr(then_branch, acc, f);
+            fold_expr(else_branch, acc, f)
+        }
+        Expr::While {
+            condition, body, ..
+        } => {
+            let acc = fold_expr(condition, acc, f);
+            fold_expr(body, acc, f)
+        }
+        Expr::Match { branches, .. } => branches
+            .iter()
+            .fold(acc, |acc, branch| fold_exprs(&branch.body, acc, f)),
+        Expr::IntLit(..) | Expr::BoolLit(..) | Expr::StringLit(..) | Expr::WordCall(..) => acc,
+    }
+}
+
+/// Fold `f` over every expression in `exprs`, at any depth
+pub fn fold_exprs<'a, T>(exprs: &'a [Expr], init: T, f: &mut impl FnMut(T, &'a Expr) -> T) -> T {
+    exprs.iter().fold(init, |acc, expr| fold_expr(expr, acc, f))
+}
+
+/// Call `f` on `expr` and every expression nested inside it
+pub fn visit_expr<'a>(expr: &'a Expr, f: &mut impl FnMut(&'a Expr)) {
+    fold_expr(expr, (), &mut |(), e| f(e));
+}
+
+/// Call `f` on every expression in `exprs`, at any depth
+pub fn visit_exprs<'a>(exprs: &'a [Expr], f: &mut impl FnMut(&'a Expr)) {
+    fold_exprs(exprs, (), &mut |(), e| f(e));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::Parser;
+
+    #[test]
+    fn test_visitor_reaches_every_nested_word_call() {
+        let program = Parser::new(
+            r#"
+            : f ( Option(Int) Bool -- Int )
+              if [ drop 1 ] [ match Some => [ dup + ] None => [ 0 ] end ]
+              while [ false ] [ tick ]
+              [ [ inner ] call_quotation ] call_quotation ;
+            "#,
+        )
+        .parse()
+        .unwrap();
+        let body = &program.word_defs[0].body;
+
+        let mut calls = Vec::new();
+        visit_exprs(body, &mut |expr| {
+            if let Expr::WordCall(name, _) = expr {
+                calls.push(name.as_str());
+            }
+        });
+        assert_eq!(
+            calls,
+            vec![
+                "drop",
+                "dup",
+                "+",
+                "tick",
+                "inner",
+                "call_quotation",
+                "call_quotation"
+            ]
+        );
+
+        let literals = fold_exprs(body, 0, &mut |count, expr| {
+            count + matches!(expr, Expr::IntLit(..) | Expr::BoolLit(..)) as usize
+        });
+        assert_eq!(literals, 3);
+    }
+}
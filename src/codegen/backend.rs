@@ -0,0 +1,74 @@
+/**
+Pluggable code generation backends
+
+A `Backend` turns a parsed `Program` into target code. The text LLVM IR
+generator (`CodeGen`) is the only backend today; the trait exists so tools can
+select a backend at runtime and so new targets can be added alongside it.
+*/
+use super::{CodeGen, CodegenResult};
+use crate::ast::Program;
+
+/// A code generation backend
+pub trait Backend {
+    /// Short human-readable name of the backend (e.g. "llvm-text")
+    fn name(&self) -> &'static str;
+
+    /// Compile a program, optionally generating an entry point that calls `entry_word`
+    ///
+    /// Returns the generated code as text.
+    fn compile_program_with_main(
+        &mut self,
+        program: &Program,
+        entry_word: Option<&str>,
+    ) -> CodegenResult<String>;
+}
+
+impl Backend for CodeGen {
+    fn name(&self) -> &'static str {
+        "llvm-text"
+    }
+
+    fn compile_program_with_main(
+        &mut self,
+        program: &Program,
+        entry_word: Option<&str>,
+    ) -> CodegenResult<String> {
+        CodeGen::compile_program_with_main(self, program, entry_word)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::types::{Effect, StackType, Type};
+    use crate::ast::{Expr, SourceLoc, WordDef};
+
+    #[test]
+    fn test_compile_through_trait_object() {
+        let program = Program {
+            type_defs: vec![],
+            word_defs: vec![WordDef {
+                name: "five".to_string(),
+                effect: Effect {
+                    inputs: StackType::Empty,
+                    outputs: StackType::Empty.push(Type::Int),
+                },
+                body: vec![Expr::IntLit(5, SourceLoc::unknown())],
+                loc: SourceLoc::unknown(),
+                feature: None,
+                inline: None,
+                slot_names: Default::default(),
+                doc: None,
+            }],
+        };
+
+        let mut backend: Box<dyn Backend> = Box::new(CodeGen::new());
+        assert_eq!(backend.name(), "llvm-text");
+
+        let ir = backend
+            .compile_program_with_main(&program, Some("five"))
+            .unwrap();
+        assert!(ir.contains("define ptr @five"));
+        assert!(ir.contains("define i32 @main()"));
+    }
+}
@@ -0,0 +1,154 @@
+/**
+Control-flow graphs of compiled words
+
+Recovers the basic blocks of one word's function, and the edges between them,
+from the IR codegen produced, and renders them as a Graphviz digraph. Reading
+the blocks back from the IR (rather than tracking them while emitting) keeps the
+graph exactly in step with what `compile_expr` actually generated.
+*/
+use super::llvm_global;
+
+/// A basic block of a compiled word
+#[derive(Debug, Clone, PartialEq)]
+pub struct Block {
+    /// The block's label (`entry`, `then_0`, `merge_0`, ...)
+    pub label: String,
+
+    /// Labels of the blocks this one can branch to, in branch order
+    pub successors: Vec<String>,
+
+    /// The terminator when the block leaves the function (`ret` or `unreachable`)
+    pub exit: Option<&'static str>,
+}
+
+/// The basic blocks of one function, in the order they appear in the IR
+#[derive(Debug, Clone, PartialEq)]
+pub struct ControlFlowGraph {
+    pub function: String,
+    pub blocks: Vec<Block>,
+}
+
+impl ControlFlowGraph {
+    /// Extract the graph of `@symbol` from a module's IR, or None if the module
+    /// doesn't define it
+    pub fn from_ir(ir: &str, symbol: &str) -> Option<Self> {
+        let header = format!("define ptr {}(", llvm_global(symbol));
+        let mut lines = ir.lines().skip_while(|line| !line.starts_with(&header));
+        lines.next()?;
+
+        let mut blocks: Vec<Block> = Vec::new();
+        for line in lines.take_while(|line| *line != "}") {
+            if let Some(label) = block_label(line) {
+                blocks.push(Block {
+                    label: label.to_string(),
+                    successors: Vec::new(),
+                    exit: None,
+                });
+                continue;
+            }
+            let Some(block) = blocks.last_mut() else {
+                continue;
+            };
+
+            // Branch targets appear as `label %name`, in `br` and in every `switch` case
+            let mut rest = line;
+            while let Some(pos) = rest.find("label %") {
+                rest = &rest[pos + "label %".len()..];
+                let end = rest
+                    .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '.'))
+                    .unwrap_or(rest.len());
+                let target = &rest[..end];
+                if !block.successors.iter().any(|s| s == target) {
+                    block.successors.push(target.to_string());
+                }
+            }
+
+            let instruction = line.trim_start();
+            if instruction.starts_with("ret ") {
+                block.exit = Some("ret");
+            } else if instruction.starts_with("unreachable") {
+                block.exit = Some("unreachable");
+            }
+        }
+
+        Some(ControlFlowGraph {
+            function: symbol.to_string(),
+            blocks,
+        })
+    }
+
+    /// Render the graph in Graphviz dot syntax
+    pub fn to_dot(&self) -> String {
+        let mut dot = format!("digraph \"{}\" {{\n", self.function);
+        dot.push_str("  node [shape=box];\n");
+        for block in &self.blocks {
+            match block.exit {
+                Some(exit) => dot.push_str(&format!(
+                    "  \"{}\" [label=\"{}\\n{}\"];\n",
+                    block.label, block.label, exit
+                )),
+                None => dot.push_str(&format!("  \"{}\";\n", block.label)),
+            }
+        }
+        for block in &self.blocks {
+            for successor in &block.successors {
+                dot.push_str(&format!("  \"{}\" -> \"{}\";\n", block.label, successor));
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+/// The label a line defines, if it starts a new basic block
+fn block_label(line: &str) -> Option<&str> {
+    let label = line.strip_suffix(':')?;
+    (!label.is_empty()
+        && label
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '_' || c == '.'))
+    .then_some(label)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codegen::CodeGen;
+    use crate::parser::Parser;
+
+    fn graph_of(source: &str, word: &str) -> ControlFlowGraph {
+        let program = Parser::new(source).parse().unwrap();
+        let ir = CodeGen::new().compile_program(&program).unwrap();
+        ControlFlowGraph::from_ir(&ir, word).unwrap()
+    }
+
+    #[test]
+    fn test_if_produces_then_else_and_merge_blocks() {
+        let graph = graph_of(
+            ": pick ( Bool -- Int ) if [ 1 ] [ 2 ] ; : other ( -- ) ;",
+            "pick",
+        );
+        let labels: Vec<_> = graph.blocks.iter().map(|b| b.label.as_str()).collect();
+        assert_eq!(labels[0], "entry");
+        assert!(labels.iter().any(|l| l.starts_with("then_")));
+        assert!(labels.iter().any(|l| l.starts_with("else_")));
+        assert!(labels.iter().any(|l| l.starts_with("merge_")));
+
+        let entry = &graph.blocks[0];
+        assert_eq!(entry.successors.len(), 2);
+        assert!(entry.successors[0].starts_with("then_"));
+        assert!(entry.successors[1].starts_with("else_"));
+
+        let dot = graph.to_dot();
+        assert!(dot.starts_with("digraph \"pick\" {"));
+        assert!(dot.contains(&format!("\"entry\" -> \"{}\";", entry.successors[0])));
+        assert!(dot.contains("\\nret\"]"));
+    }
+
+    #[test]
+    fn test_missing_function_has_no_graph() {
+        let program = Parser::new(": f ( -- ) ;").parse().unwrap();
+        let ir = CodeGen::new().compile_program(&program).unwrap();
+        assert!(ControlFlowGraph::from_ir(&ir, "g").is_none());
+    }
+}
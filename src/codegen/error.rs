@@ -14,6 +14,19 @@ pub enum CodegenError {
 
     /// Linker error
     LinkerError { message: String },
+
+    /// clang ran but rejected the IR or failed to link; `details` holds its diagnostics
+    LlvmError { operation: String, details: String },
+
+    /// Requested entry word is not defined by the program
+    MissingEntryWord { name: String },
+
+    /// Word body leaves a different number of values than its effect declares
+    OutputArityMismatch {
+        word: String,
+        expected: usize,
+        actual: isize,
+    },
 }
 
 impl fmt::Display for CodegenError {
@@ -28,6 +41,21 @@ impl fmt::Display for CodegenError {
             CodegenError::LinkerError { message } => {
                 write!(f, "Linker error: {}", message)
             }
+            CodegenError::LlvmError { operation, details } => {
+                write!(f, "clang {} failed:\n{}", operation, details)
+            }
+            CodegenError::MissingEntryWord { name } => {
+                write!(f, "Entry word '{}' is not defined in the program", name)
+            }
+            CodegenError::OutputArityMismatch {
+                word,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "Word '{}' declares {} output(s) but its body leaves {}",
+                word, expected, actual
+            ),
         }
     }
 }
@@ -6,10 +6,52 @@ This module handles:
 - Invoking clang with appropriate flags
 - Linking with C runtime
 */
-use super::{CodegenError, CodegenResult};
+use super::{CodegenError, CodegenResult, Target};
 use std::fs;
+use std::io;
 use std::path::Path;
-use std::process::Command;
+use std::process::{Command, Output};
+
+/// Map a failure to start clang to a linker error
+///
+/// A missing clang binary gets an actionable message; any other spawn failure is
+/// reported as-is. (A clang that runs but fails is reported separately by exit status.)
+fn clang_spawn_error(program: &str, e: io::Error) -> CodegenError {
+    let message = if e.kind() == io::ErrorKind::NotFound {
+        format!(
+            "{} not found in PATH; install LLVM/clang to link Cem programs",
+            program
+        )
+    } else {
+        format!("Failed to execute {}: {}", program, e)
+    };
+    CodegenError::LinkerError { message }
+}
+
+/// Build the error for a clang run that exited unsuccessfully
+///
+/// Includes clang's own diagnostics (stderr, then stdout) so undefined symbols
+/// or IR syntax errors reach the user verbatim.
+fn clang_failure(operation: &str, result: &Output) -> CodegenError {
+    let mut details = String::from_utf8_lossy(&result.stderr)
+        .trim_end()
+        .to_string();
+    let stdout = String::from_utf8_lossy(&result.stdout);
+    if !stdout.trim().is_empty() {
+        if !details.is_empty() {
+            details.push('\n');
+        }
+        details.push_str(stdout.trim_end());
+    }
+    if details.is_empty() {
+        details = format!("clang exited with status: {}", result.status);
+    }
+
+    CodegenError::LlvmError {
+        operation: operation.to_string(),
+        details,
+    }
+}
 
 /// Validate a file path to prevent command injection
 ///
@@ -35,6 +77,27 @@ fn validate_path(path: &str) -> CodegenResult<()> {
     Ok(())
 }
 
+/// How IR is built into an object, executable, or library
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct LinkOptions {
+    /// Target to compile and link for
+    pub target: Target,
+
+    /// Print each clang command line before it runs
+    pub verbose: bool,
+
+    /// Keep the intermediates of the build next to the output: the assembly
+    /// (OUTPUT.s) and the object assembled from it (OUTPUT.o)
+    pub save_temps: bool,
+}
+
+/// What `link` produces from the compiled object
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Product {
+    Executable,
+    SharedLibrary,
+}
+
 /// Link LLVM IR with C runtime to produce executable
 ///
 /// # Arguments
@@ -50,8 +113,153 @@ fn validate_path(path: &str) -> CodegenResult<()> {
 /// link_program(ir, "runtime/libcem_runtime.a", "program").unwrap();
 /// ```
 pub fn link_program(ir_code: &str, runtime_lib: &str, output: &str) -> CodegenResult<()> {
+    link_program_for_target(ir_code, runtime_lib, output, Target::Native, false)
+}
+
+/// Link LLVM IR with a C runtime built for `target`
+///
+/// The runtime library must have been compiled for the same target
+/// (see `Target::runtime_lib` for the conventional location).
+/// When `verbose` is set, the exact clang command lines are printed before they run.
+pub fn link_program_for_target(
+    ir_code: &str,
+    runtime_lib: &str,
+    output: &str,
+    target: Target,
+    verbose: bool,
+) -> CodegenResult<()> {
+    let options = LinkOptions {
+        target,
+        verbose,
+        ..LinkOptions::default()
+    };
+    link_program_with_libraries(ir_code, runtime_lib, &[], output, &options)
+}
+
+/// Link LLVM IR with precompiled Cem libraries and the C runtime built for `target`
+///
+/// `libraries` are static archives from `cem compile --lib`; they come before the
+/// runtime on the command line since they depend on it.
+pub fn link_program_with_libraries(
+    ir_code: &str,
+    runtime_lib: &str,
+    libraries: &[String],
+    output: &str,
+    options: &LinkOptions,
+) -> CodegenResult<()> {
+    link(
+        "clang",
+        ir_code,
+        &archives(libraries, runtime_lib),
+        output,
+        Product::Executable,
+        options,
+    )?;
+    Ok(())
+}
+
+/// Link LLVM IR compiled without a `main` into a shared library OUTPUT.so
+/// (OUTPUT.dylib on macOS), returning its path
+///
+/// The IR, the Cem `libraries`, and the runtime are linked as position-independent
+/// code, so the runtime archive must itself be built with `-fPIC` (as
+/// `just build-runtime` does). Every word is a `define`d function with external
+/// linkage and default visibility, so each one is exported under its symbol (see
+/// `symbol_for_word`). Only native targets can produce shared libraries.
+pub fn link_shared_library(
+    ir_code: &str,
+    runtime_lib: &str,
+    libraries: &[String],
+    output: &str,
+    options: &LinkOptions,
+) -> CodegenResult<String> {
+    if options.target != Target::Native {
+        return Err(CodegenError::LinkerError {
+            message: format!(
+                "Shared libraries can't be built for target '{}'",
+                options.target
+            ),
+        });
+    }
+    link(
+        "clang",
+        ir_code,
+        &archives(libraries, runtime_lib),
+        output,
+        Product::SharedLibrary,
+        options,
+    )
+}
+
+/// The archives to link, in command-line order: the Cem libraries, then the runtime
+/// they depend on
+fn archives<'a>(libraries: &'a [String], runtime_lib: &'a str) -> Vec<&'a str> {
+    libraries
+        .iter()
+        .map(String::as_str)
+        .chain(std::iter::once(runtime_lib))
+        .collect()
+}
+
+/// Build IR into `product` with `archives` by running `clang` (the compiler program),
+/// returning the path of what was built
+///
+/// The IR is first compiled to OUTPUT.o, which is then linked; with `save_temps` the
+/// object is kept, along with the assembly it was built from.
+fn link(
+    clang: &str,
+    ir_code: &str,
+    archives: &[&str],
+    output: &str,
+    product: Product,
+    options: &LinkOptions,
+) -> CodegenResult<String> {
     // Validate paths to prevent command injection
-    validate_path(runtime_lib)?;
+    validate_path(output)?;
+    for archive in archives {
+        validate_path(archive)?;
+    }
+
+    let pic = product == Product::SharedLibrary;
+    let object = compile(clang, ir_code, output, pic, options)?;
+    let built = match product {
+        Product::Executable => output.to_string(),
+        Product::SharedLibrary => format!("{}.{}", output, std::env::consts::DLL_EXTENSION),
+    };
+
+    let mut command = Command::new(clang);
+    command.args(options.target.clang_args());
+    if product == Product::SharedLibrary {
+        command.args(["-shared", "-fPIC"]);
+    }
+    command.arg(&object).args(archives).arg("-o").arg(&built);
+    let result = run(clang, &mut command, "link", options.verbose);
+    if !options.save_temps {
+        fs::remove_file(&object).ok();
+    }
+    result?;
+
+    // Keep .ll file for inspection but report success
+    if options.verbose {
+        println!("Generated: {}.ll", output);
+        println!("Output: {}", built);
+    }
+
+    Ok(built)
+}
+
+/// Compile IR to the object file OUTPUT.o by running `clang`, returning its path
+///
+/// With `save_temps` the IR is compiled to the assembly OUTPUT.s and the object is
+/// assembled from that, so the kept assembly is exactly what the object contains.
+/// `pic` compiles position-independent code, as a shared library needs.
+fn compile(
+    clang: &str,
+    ir_code: &str,
+    output: &str,
+    pic: bool,
+    options: &LinkOptions,
+) -> CodegenResult<String> {
     validate_path(output)?;
 
     // Write IR to temporary .ll file
@@ -60,32 +268,64 @@ pub fn link_program(ir_code: &str, runtime_lib: &str, output: &str) -> CodegenRe
         message: format!("Failed to write {}: {}", ll_file, e),
     })?;
 
-    // Call clang to compile and link
-    let status = Command::new("clang")
+    let object = format!("{}.o", output);
+    let assembly = format!("{}.s", output);
+    let mut command = Command::new(clang);
+    command.args(options.target.clang_args());
+    if pic {
+        command.arg("-fPIC");
+    }
+    command
+        .arg(if options.save_temps { "-S" } else { "-c" })
         .arg(&ll_file)
-        .arg(runtime_lib)
         .arg("-o")
-        .arg(output)
+        .arg(if options.save_temps {
+            &assembly
+        } else {
+            &object
+        })
         .arg("-O2") // Enable optimizations for musttail
-        .arg("-Wno-override-module") // Suppress target triple override warning
-        .status()
-        .map_err(|e| CodegenError::LinkerError {
-            message: format!("Failed to execute clang: {}", e),
-        })?;
+        .arg("-Wno-override-module"); // Suppress target triple override warning
+    run(clang, &mut command, "compile", options.verbose)?;
 
-    if !status.success() {
-        return Err(CodegenError::LinkerError {
-            message: format!("clang exited with status: {}", status),
-        });
+    if options.save_temps {
+        let mut command = Command::new(clang);
+        command
+            .args(options.target.clang_args())
+            .arg("-c")
+            .arg(&assembly)
+            .arg("-o")
+            .arg(&object);
+        run(clang, &mut command, "assemble", options.verbose)?;
     }
 
-    // Keep .ll file for inspection but report success
-    println!("Generated: {}", ll_file);
-    println!("Executable: {}", output);
+    Ok(object)
+}
 
+/// Run one step of a build, printing its command line first when `verbose` is set
+fn run(program: &str, command: &mut Command, operation: &str, verbose: bool) -> CodegenResult<()> {
+    if verbose {
+        println!("Running: {}", display_command(command));
+    }
+
+    let result = command
+        .output()
+        .map_err(|e| clang_spawn_error(program, e))?;
+    if !result.status.success() {
+        return Err(clang_failure(operation, &result));
+    }
     Ok(())
 }
 
+/// Render a command line the way it would be typed in a shell (for logging)
+fn display_command(command: &Command) -> String {
+    std::iter::once(command.get_program())
+        .chain(command.get_args())
+        .map(|arg| arg.to_string_lossy())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
 /// Link program with default runtime location
 pub fn link_program_default(ir_code: &str, output: &str) -> CodegenResult<()> {
     link_program(ir_code, "runtime/libcem_runtime.a", output)
@@ -95,6 +335,44 @@ pub fn link_program_default(ir_code: &str, output: &str) -> CodegenResult<()> {
 ///
 /// This is useful for testing IR generation without needing a complete program with main()
 pub fn compile_to_object(ir_code: &str, output: &str) -> CodegenResult<()> {
+    compile_to_object_for_target(ir_code, output, Target::Native)
+}
+
+/// Compile LLVM IR to an object file for `target` without linking
+pub fn compile_to_object_for_target(
+    ir_code: &str,
+    output: &str,
+    target: Target,
+) -> CodegenResult<()> {
+    let options = LinkOptions {
+        target,
+        ..LinkOptions::default()
+    };
+    compile_to_object_with_options(ir_code, output, &options)
+}
+
+/// Compile LLVM IR to the object file OUTPUT.o without linking, keeping the assembly
+/// it was built from (OUTPUT.s) when `options.save_temps` is set
+pub fn compile_to_object_with_options(
+    ir_code: &str,
+    output: &str,
+    options: &LinkOptions,
+) -> CodegenResult<()> {
+    compile("clang", ir_code, output, false, options)?;
+    Ok(())
+}
+
+/// Compile LLVM IR to native assembly (`<output>.s`) for inspecting generated code
+pub fn compile_to_assembly(ir_code: &str, output: &str) -> CodegenResult<()> {
+    compile_to_assembly_for_target(ir_code, output, Target::Native)
+}
+
+/// Compile LLVM IR to assembly for `target` without assembling or linking
+pub fn compile_to_assembly_for_target(
+    ir_code: &str,
+    output: &str,
+    target: Target,
+) -> CodegenResult<()> {
     // Validate path to prevent command injection
     validate_path(output)?;
 
@@ -104,28 +382,49 @@ pub fn compile_to_object(ir_code: &str, output: &str) -> CodegenResult<()> {
         message: format!("Failed to write {}: {}", ll_file, e),
     })?;
 
-    // Call clang to compile to object file
-    let status = Command::new("clang")
-        .arg("-c")
+    // Call clang to stop after code generation
+    let result = Command::new("clang")
+        .args(target.clang_args())
+        .arg("-S")
         .arg(&ll_file)
         .arg("-o")
-        .arg(format!("{}.o", output))
-        .arg("-O2") // Enable optimizations
+        .arg(format!("{}.s", output))
+        .arg("-O2") // Match the optimization level of linked builds
         .arg("-Wno-override-module") // Suppress target triple override warning
-        .status()
-        .map_err(|e| CodegenError::LinkerError {
-            message: format!("Failed to execute clang: {}", e),
-        })?;
+        .output()
+        .map_err(|e| clang_spawn_error("clang", e))?;
+
+    if !result.status.success() {
+        return Err(clang_failure("assemble", &result));
+    }
 
-    if !status.success() {
+    Ok(())
+}
+
+/// Bundle object files into a static library with `ar`
+pub fn archive_objects(objects: &[String], archive: &str) -> CodegenResult<()> {
+    validate_path(archive)?;
+    for object in objects {
+        validate_path(object)?;
+    }
+
+    let result = Command::new("ar")
+        .arg("rcs")
+        .arg(archive)
+        .args(objects)
+        .output()
+        .map_err(|e| clang_spawn_error("ar", e))?;
+
+    if !result.status.success() {
         return Err(CodegenError::LinkerError {
-            message: format!("clang exited with status: {}", status),
+            message: format!(
+                "ar failed to create {}: {}",
+                archive,
+                String::from_utf8_lossy(&result.stderr).trim_end()
+            ),
         });
     }
 
-    println!("Generated: {}", ll_file);
-    println!("Object file: {}.o", output);
-
     Ok(())
 }
 
@@ -134,9 +433,7 @@ pub fn check_clang() -> CodegenResult<String> {
     let output = Command::new("clang")
         .arg("--version")
         .output()
-        .map_err(|e| CodegenError::LinkerError {
-            message: format!("clang not found. Please install LLVM/clang: {}", e),
-        })?;
+        .map_err(|e| clang_spawn_error("clang", e))?;
 
     let version = String::from_utf8_lossy(&output.stdout);
     Ok(version.lines().next().unwrap_or("unknown").to_string())
@@ -146,6 +443,71 @@ pub fn check_clang() -> CodegenResult<String> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_display_command() {
+        let mut command = Command::new("clang");
+        command.args(["prog.ll", "-o", "prog"]);
+        assert_eq!(display_command(&command), "clang prog.ll -o prog");
+    }
+
+    #[test]
+    fn test_missing_clang_is_reported_clearly() {
+        // Run the whole link step with a compiler that can't be found
+        let program = "cem-test-no-such-clang";
+        let err = link(
+            program,
+            "define ptr @f(ptr %stack) {\nentry:\n  ret ptr %stack\n}\n",
+            &["runtime/libcem_runtime.a"],
+            "target/test_missing_clang",
+            Product::Executable,
+            &LinkOptions::default(),
+        )
+        .unwrap_err();
+        std::fs::remove_file("target/test_missing_clang.ll").ok();
+
+        assert_eq!(
+            err,
+            CodegenError::LinkerError {
+                message: format!(
+                    "{} not found in PATH; install LLVM/clang to link Cem programs",
+                    program
+                ),
+            }
+        );
+    }
+
+    #[test]
+    fn test_other_spawn_failures_keep_cause() {
+        let err = clang_spawn_error("clang", io::Error::from(io::ErrorKind::PermissionDenied));
+        let CodegenError::LinkerError { message } = err else {
+            panic!("Expected LinkerError");
+        };
+        assert!(message.starts_with("Failed to execute clang:"));
+    }
+
+    #[test]
+    fn test_broken_ir_reports_clang_diagnostics() {
+        let err = link_program(
+            "define ptr @broken(",
+            "runtime/libcem_runtime.a",
+            "target/test_broken_ir",
+        )
+        .unwrap_err();
+        std::fs::remove_file("target/test_broken_ir.ll").ok();
+
+        match err {
+            CodegenError::LlvmError { operation, details } => {
+                assert_eq!(operation, "compile");
+                assert!(
+                    details.contains("error"),
+                    "Expected clang's diagnostic, got: {}",
+                    details
+                );
+            }
+            other => panic!("Expected LlvmError, got {:?}", other),
+        }
+    }
+
     #[test]
     fn test_check_clang() {
         let version = check_clang().unwrap();
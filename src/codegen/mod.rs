@@ -20,23 +20,83 @@ Example output:
 ```llvm
 define ptr @square(ptr %stack) {
 entry:
-  %0 = call ptr @dup(ptr %stack)
+  %0 = call ptr @stack_dup(ptr %stack)
   %1 = call ptr @multiply(ptr %0)
   ret ptr %1
 }
 ```
 */
+pub mod backend;
+pub mod cfg;
 pub mod error;
 pub mod ir;
 pub mod linker;
+pub mod options;
+pub mod peephole;
+pub mod target;
 
+pub use backend::Backend;
+pub use cfg::ControlFlowGraph;
 pub use error::{CodegenError, CodegenResult};
 pub use ir::IRGenerator;
-pub use linker::{compile_to_object, link_program};
+pub use linker::{
+    LinkOptions, archive_objects, compile_to_assembly, compile_to_assembly_for_target,
+    compile_to_object, compile_to_object_for_target, compile_to_object_with_options, link_program,
+    link_program_for_target, link_program_with_libraries, link_shared_library,
+};
+pub use options::{CodeGenOptions, StackSeed};
+pub use target::Target;
+
+/// The runtime tag for a variant, derived from its type and variant names
+///
+/// This is a 32-bit FNV-1a hash of `Type::Variant`, masked to 31 bits so it is also a
+/// non-negative `i32` in the IR. Because it depends only on the names, independently
+/// compiled units assign the same tag to the same variant.
+pub fn variant_tag_for(type_name: &str, variant_name: &str) -> u32 {
+    let mut hash: u32 = 0x811c_9dc5;
+    for byte in type_name
+        .bytes()
+        .chain("::".bytes())
+        .chain(variant_name.bytes())
+    {
+        hash ^= byte as u32;
+        hash = hash.wrapping_mul(0x0100_0193);
+    }
+    hash & 0x7fff_ffff
+}
+
+/// A global's name as written in IR: `@symbol`, quoted as `@"..."` when the symbol
+/// has characters LLVM doesn't allow in a bare name
+///
+/// Words named with backticks (and the library symbols bound to them) can be any text,
+/// so their symbols may need quoting.
+pub fn llvm_global(symbol: &str) -> String {
+    let bare = !symbol.is_empty()
+        && !symbol.starts_with(|c: char| c.is_ascii_digit())
+        && symbol
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '$' | '.' | '_' | '-'));
+    if bare {
+        return format!("@{}", symbol);
+    }
+    let mut quoted = String::from("@\"");
+    for byte in symbol.bytes() {
+        if (byte.is_ascii_graphic() || byte == b' ') && byte != b'"' && byte != b'\\' {
+            quoted.push(byte as char);
+        } else {
+            quoted.push_str(&format!(r"\{:02X}", byte));
+        }
+    }
+    quoted.push('"');
+    quoted
+}
 
 #[cfg(test)]
 use crate::ast::SourceLoc;
-use crate::ast::{Expr, Pattern, Program, WordDef};
+use crate::ast::types::{Effect, Type};
+use crate::ast::{Expr, InlineHint, MatchBranch, Pattern, Program, TypeDef, WordDef};
+use crate::symbol::{runtime_symbol, symbol_for_word};
+use crate::typechecker::environment::Environment;
 use std::fmt::Write as _;
 use std::process::Command;
 
@@ -46,26 +106,43 @@ pub struct CodeGen {
     string_globals: String, // Separate area for string constant declarations
     temp_counter: usize,
     string_counter: usize, // Separate counter for string constants (never reset)
-    current_block: String, // Track the current basic block label we're emitting into
-    metadata_counter: usize, // Counter for debug metadata IDs
+    quotation_functions: String, // Separate area for quotation bodies, emitted as top-level functions
+    quotation_counter: usize,    // Counter for quotation function names (never reset)
+    current_block: String,       // Track the current basic block label we're emitting into
+    metadata_counter: usize,     // Counter for debug metadata IDs
     file_metadata: std::collections::HashMap<String, usize>, // filename -> metadata ID
     compile_unit_id: Option<usize>, // ID of the DICompileUnit metadata node
     word_subprograms: Vec<(String, usize, usize, usize)>, // (word_name, file_id, line, subprogram_id)
     current_subprogram_id: Option<usize>, // ID of the current function's DISubprogram
     debug_locations: std::collections::HashMap<(usize, usize, usize, usize), usize>, // (file_id, line, col, scope) -> DILocation ID
     string_constants: std::collections::HashMap<String, String>, // string content -> global name (@.str.N)
-    variant_tags: std::collections::HashMap<String, u32>, // variant_name -> tag (index in type definition)
+    variant_tags: std::collections::HashMap<String, u32>, // variant_name -> tag (see variant_tag_for)
     variant_field_counts: std::collections::HashMap<String, usize>, // variant_name -> number of fields
+    options: CodeGenOptions, // Target, features, and which checks and metadata to emit
+    word_effects: std::collections::HashMap<String, Effect>, // word_name -> declared effect (for arity checks)
+    external_words: Vec<(String, String, Effect)>, // (name, symbol, effect) of words from precompiled libraries
+    word_abi_globals: std::collections::HashSet<String>, // `@symbol`s declared or defined as `ptr (ptr)`, which can be musttail-called
+    top_is_nonzero_literal: bool, // The last expression pushed a nonzero Int literal (no divide-by-zero check needed)
+    in_loop: bool, // Compiling a while condition or body, where nothing is in tail position
+    top_type: Option<Type>, // Statically known type of the top cell, if any (lets `dup` skip the runtime call)
+    builtins: Environment,  // Builtin word effects (arity checks and top-of-stack types)
 }
 
 impl CodeGen {
-    /// Create a new code generator
+    /// Create a new code generator with the default options
     pub fn new() -> Self {
+        Self::with_options(CodeGenOptions::default())
+    }
+
+    /// Create a code generator configured by `options`
+    pub fn with_options(options: CodeGenOptions) -> Self {
         CodeGen {
             output: String::new(),
             string_globals: String::new(),
             temp_counter: 0,
             string_counter: 0,
+            quotation_functions: String::new(),
+            quotation_counter: 0,
             current_block: "entry".to_string(),
             metadata_counter: 0,
             file_metadata: std::collections::HashMap::new(),
@@ -76,9 +153,57 @@ impl CodeGen {
             string_constants: std::collections::HashMap::new(),
             variant_tags: std::collections::HashMap::new(),
             variant_field_counts: std::collections::HashMap::new(),
+            options,
+            word_effects: std::collections::HashMap::new(),
+            external_words: Vec::new(),
+            word_abi_globals: std::collections::HashSet::new(),
+            top_is_nonzero_literal: false,
+            in_loop: false,
+            top_type: None,
+            builtins: Environment::new(),
         }
     }
 
+    /// Set the active features used to select `when(feature)` words
+    pub fn set_features(&mut self, features: Vec<String>) {
+        self.options.features = features;
+    }
+
+    /// Make a word compiled into a precompiled library callable from this program
+    ///
+    /// The word is declared under `symbol` and its effect is used like that of a word
+    /// defined in the program.
+    pub fn add_external_word(&mut self, name: &str, symbol: &str, effect: Effect) {
+        self.external_words
+            .push((name.to_string(), symbol.to_string(), effect));
+    }
+
+    /// The symbol to call for a word: its library symbol if it is external
+    fn callee_symbol(&self, name: &str) -> String {
+        self.external_words
+            .iter()
+            .find(|(external, _, _)| external == name)
+            .map(|(_, symbol, _)| symbol.clone())
+            .unwrap_or_else(|| runtime_symbol(name))
+    }
+
+    /// Enable or disable the output arity check (disabled by default)
+    ///
+    /// When enabled, each word body's net stack change is computed statically and
+    /// compared against the declared `Effect::outputs`. Bodies whose effect can't be
+    /// determined (e.g. calls to runtime words with no known signature) are not checked.
+    pub fn set_output_arity_check(&mut self, enabled: bool) {
+        self.options.check_output_arity = enabled;
+    }
+
+    /// Create a code generator for a specific compilation target
+    pub fn with_target(target: Target) -> Self {
+        Self::with_options(CodeGenOptions {
+            target,
+            ..CodeGenOptions::default()
+        })
+    }
+
     /// Generate a fresh temporary variable name (without % prefix)
     fn fresh_temp(&mut self) -> String {
         let name = format!("{}", self.temp_counter);
@@ -86,6 +211,35 @@ impl CodeGen {
         name
     }
 
+    /// The global holding a NUL-terminated copy of `s`, emitting it on first use
+    fn string_global(&mut self, s: &str) -> String {
+        if let Some(existing) = self.string_constants.get(s) {
+            return existing.clone();
+        }
+        // Create new global string constant
+        let str_global = format!("@.str.{}", self.string_counter);
+        self.string_counter += 1; // Increment for the string global itself
+
+        let escaped = Self::escape_llvm_string(s);
+        // Length is original byte count - escaping is just text representation.
+        // E.g., "a\"b" is 3 bytes even though we write it as 5 chars in IR text.
+        // UTF-8 chars like "😀" (4 bytes) escape to "\F0\9F\98\80" but still represent 4 bytes.
+        let str_len = s.len() + 1; // +1 for null terminator
+
+        // Emit global to string_globals area, byte-aligned as clang does for C
+        // string literals
+        let global_decl = format!(
+            "{} = private unnamed_addr constant [{} x i8] c\"{}\\00\", align 1\n",
+            str_global, str_len, escaped
+        );
+        self.string_globals.push_str(&global_decl);
+
+        // Remember this string for deduplication in future occurrences
+        self.string_constants
+            .insert(s.to_string(), str_global.clone());
+        str_global
+    }
+
     /// Escape a string for LLVM IR string literals
     /// LLVM IR requires hex escaping for non-printable characters
     fn escape_llvm_string(s: &str) -> String {
@@ -109,30 +263,6 @@ impl CodeGen {
         result
     }
 
-    /// Map operator symbols to valid LLVM function names
-    /// LLVM doesn't allow symbols like +, -, <, > as function names
-    /// Also maps hyphenated Cem names to underscore C names
-    fn map_operator_to_function(name: &str) -> String {
-        match name {
-            // Arithmetic operators
-            "+" => "add".to_string(),
-            "-" => "subtract".to_string(),
-            "*" => "multiply".to_string(),
-            "/" => "divide_op".to_string(),
-            // Comparison operators
-            "<" => "int_less".to_string(),
-            ">" => "int_greater".to_string(),
-            "<=" => "int_less_equal".to_string(),
-            ">=" => "int_greater_equal".to_string(),
-            "=" => "int_equal".to_string(),
-            "!=" => "int_not_equal".to_string(),
-            // Special functions
-            "exit" => "exit_op".to_string(), // Avoid conflict with stdlib exit()
-            // For hyphenated names, replace hyphens with underscores
-            _ => name.replace('-', "_"),
-        }
-    }
-
     /// Compile a complete program to LLVM IR
     pub fn compile_program(&mut self, program: &Program) -> CodegenResult<String> {
         self.compile_program_with_main(program, None)
@@ -144,15 +274,27 @@ impl CodeGen {
     /// * `program` - The AST program to compile
     /// * `entry_word` - Optional name of word to call from main(). If None, no main() is generated.
     ///   If Some("word_name"), generates main() that calls that word and prints result.
+    ///
+    /// An empty program compiled without an entry word produces a valid module with no
+    /// functions (a library). Requesting an entry word that the program does not define,
+    /// including on an empty program, is an error.
     pub fn compile_program_with_main(
         &mut self,
         program: &Program,
         entry_word: Option<&str>,
     ) -> CodegenResult<String> {
-        // Emit module header
-        writeln!(&mut self.output, "; Cem Compiler - Generated LLVM IR")
-            .map_err(|e| CodegenError::InternalError(e.to_string()))?;
-        writeln!(&mut self.output).map_err(|e| CodegenError::InternalError(e.to_string()))?;
+        // Words gated off by inactive features are not part of this compilation
+        let features = self.options.features.clone();
+        let words: Vec<&WordDef> = program.enabled_words(&features).collect();
+
+        // Validate the entry word up front so we never emit a main() calling an undefined symbol
+        if let Some(word_name) = entry_word
+            && !words.iter().any(|w| w.name == word_name)
+        {
+            return Err(CodegenError::MissingEntryWord {
+                name: word_name.to_string(),
+            });
+        }
 
         // Note: We intentionally omit the target triple to let clang use its default.
         // This avoids "overriding the module target triple" warnings that occur when
@@ -161,42 +303,87 @@ impl CodeGen {
         // Declare runtime functions
         self.emit_runtime_declarations()?;
 
-        // Build variant tag map and field count map from type definitions
-        // Each variant gets a u32 tag corresponding to its index in the type's variant list
-        for typedef in &program.type_defs {
-            for (idx, variant) in typedef.variants.iter().enumerate() {
-                self.variant_tags.insert(variant.name.clone(), idx as u32);
-                self.variant_field_counts
-                    .insert(variant.name.clone(), variant.fields.len());
-            }
+        // Declare words provided by precompiled libraries
+        if !self.external_words.is_empty() {
+            writeln!(&mut self.output, "; Library word declarations")
+                .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+        }
+        for (name, symbol, effect) in self.external_words.clone() {
+            self.declare_word_abi(llvm_global(&symbol))?;
+            self.word_effects.insert(name, effect);
         }
 
-        // Collect all unique source files from the program
-        let mut source_files = std::collections::HashSet::new();
-        for word in &program.word_defs {
+        self.register_definitions(program, &words)?;
+
+        // Collect all unique source files from the program, in a stable order so the
+        // same program always produces the same IR
+        let mut source_files = std::collections::BTreeSet::new();
+        for word in &words {
             source_files.insert(word.loc.file.as_ref());
         }
 
         // Emit debug metadata setup
-        self.emit_debug_info_header(&source_files)?;
+        if self.options.debug_info {
+            self.emit_debug_info_header(&source_files)?;
+        }
 
-        // Emit all word definitions
-        for word in &program.word_defs {
-            self.compile_word(word)?;
+        // Emit all word definitions, with no-op sequences like `dup drop` removed
+        for word in &words {
+            if self.options.verbose {
+                println!("Compiling word '{}'", word.name);
+            }
+            self.compile_word(&peephole::simplify_word(word))?;
         }
 
+        // Emit the quotation bodies collected while compiling the words
+        self.output
+            .push_str(&std::mem::take(&mut self.quotation_functions));
+
         // Generate main() if requested
         if let Some(word_name) = entry_word {
             self.emit_main_function(word_name)?;
         }
 
         // Emit debug metadata footer (compile unit and module flags)
-        self.emit_debug_info_footer()?;
+        if self.options.debug_info {
+            self.emit_debug_info_footer()?;
+        }
+
+        // Prepend string constants to output, then the module header describing it all
+        let body = self.string_globals.clone() + &self.output;
+        Ok(Self::module_header(&source_files, &body)? + &body)
+    }
+
+    /// The comment block opening every module: compiler version, source files, and a
+    /// build ID hashed from the version and the module body
+    ///
+    /// Nothing in it depends on when or where the compiler ran, so recompiling the same
+    /// program with the same compiler reproduces the IR byte for byte.
+    fn module_header(
+        source_files: &std::collections::BTreeSet<&str>,
+        body: &str,
+    ) -> CodegenResult<String> {
+        let version = env!("CARGO_PKG_VERSION");
 
-        // Prepend string constants to output
-        let final_output = self.string_globals.clone() + &self.output;
+        // FNV-1a, as for variant tags: stable across Rust versions and platforms
+        let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+        for byte in version.bytes().chain([0]).chain(body.bytes()) {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+        }
 
-        Ok(final_output)
+        let mut header = String::from("; Cem Compiler - Generated LLVM IR\n");
+        writeln!(header, "; Compiler version: {}", version)
+            .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+        if !source_files.is_empty() {
+            let files: Vec<&str> = source_files.iter().copied().collect();
+            writeln!(header, "; Source: {}", files.join(", "))
+                .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+        }
+        writeln!(header, "; Build ID: {:016x}", hash)
+            .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+        header.push('\n');
+        Ok(header)
     }
 
     /// Get the target triple by querying clang
@@ -222,21 +409,48 @@ impl CodeGen {
         }
     }
 
+    /// Declare `global` (an `@symbol`) with the word prototype `ptr (ptr)`, recording
+    /// that it can be musttail-called
+    fn declare_word_abi(&mut self, global: String) -> CodegenResult<()> {
+        writeln!(&mut self.output, "declare ptr {}(ptr)", global)
+            .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+        self.word_abi_globals.insert(global);
+        Ok(())
+    }
+
     /// Emit declarations for all runtime functions
     fn emit_runtime_declarations(&mut self) -> CodegenResult<()> {
         writeln!(&mut self.output, "; Runtime function declarations")
             .map_err(|e| CodegenError::InternalError(e.to_string()))?;
 
         // Stack operations (ptr -> ptr)
-        for func in &["dup", "drop", "swap", "over", "rot", "nip", "tuck"] {
-            writeln!(&mut self.output, "declare ptr @{}(ptr)", func)
-                .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+        for func in &[
+            "stack_dup",
+            "drop",
+            "swap",
+            "over",
+            "rot",
+            "nip",
+            "tuck",
+            "two_dup",
+            "two_drop",
+        ] {
+            self.declare_word_abi(format!("@{}", func))?;
         }
 
         // Arithmetic (ptr -> ptr)
-        for func in &["add", "subtract", "multiply", "divide_op"] {
-            writeln!(&mut self.output, "declare ptr @{}(ptr)", func)
-                .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+        for func in &[
+            "add",
+            "subtract",
+            "negate",
+            "int_abs",
+            "signum",
+            "gcd",
+            "multiply",
+            "divide_op",
+            "mod_op",
+        ] {
+            self.declare_word_abi(format!("@{}", func))?;
         }
 
         // Comparisons (ptr -> ptr)
@@ -248,14 +462,18 @@ impl CodeGen {
             "int_equal",
             "int_not_equal",
         ] {
-            writeln!(&mut self.output, "declare ptr @{}(ptr)", func)
-                .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+            self.declare_word_abi(format!("@{}", func))?;
         }
 
+        // Boolean operations
+        self.declare_word_abi("@not".to_string())?;
+
         // Push operations
         writeln!(&mut self.output, "declare ptr @push_int(ptr, i64)")
             .map_err(|e| CodegenError::InternalError(e.to_string()))?;
-        writeln!(&mut self.output, "declare ptr @push_bool(ptr, i1)")
+        // C `bool` crosses the ABI as a zero-extended i1 (stored as one byte, 0 or 1);
+        // the zeroext attributes must match on declarations and calls
+        writeln!(&mut self.output, "declare ptr @push_bool(ptr, i1 zeroext)")
             .map_err(|e| CodegenError::InternalError(e.to_string()))?;
         writeln!(&mut self.output, "declare ptr @push_string(ptr, ptr)")
             .map_err(|e| CodegenError::InternalError(e.to_string()))?;
@@ -263,38 +481,55 @@ impl CodeGen {
             .map_err(|e| CodegenError::InternalError(e.to_string()))?;
         writeln!(&mut self.output, "declare ptr @push_variant(ptr, i32, ptr)")
             .map_err(|e| CodegenError::InternalError(e.to_string()))?;
-
-        // Control flow operations
-        writeln!(&mut self.output, "declare ptr @call_quotation(ptr)")
+        writeln!(&mut self.output, "declare ptr @make_variant(ptr, i32, i32)")
+            .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+        writeln!(&mut self.output, "declare i32 @variant_tag(ptr)")
+            .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+        writeln!(&mut self.output, "declare ptr @unwrap_variant(ptr, i32)")
             .map_err(|e| CodegenError::InternalError(e.to_string()))?;
 
-        // String operations
-        writeln!(&mut self.output, "declare ptr @string_length(ptr)")
+        // Cell accessors (keep codegen independent of the StackCell layout)
+        writeln!(&mut self.output, "declare zeroext i1 @cell_bool(ptr)")
             .map_err(|e| CodegenError::InternalError(e.to_string()))?;
-        writeln!(&mut self.output, "declare ptr @string_concat(ptr)")
+        self.declare_word_abi("@cell_rest".to_string())?;
+        writeln!(&mut self.output, "declare i64 @cell_int(ptr)")
             .map_err(|e| CodegenError::InternalError(e.to_string()))?;
-        writeln!(&mut self.output, "declare ptr @string_equal(ptr)")
+        writeln!(&mut self.output, "declare void @free_cell(ptr)")
             .map_err(|e| CodegenError::InternalError(e.to_string()))?;
 
+        // Control flow operations
+        self.declare_word_abi("@call_quotation".to_string())?;
+        self.declare_word_abi("@compose_quotations".to_string())?;
+        self.declare_word_abi("@curry".to_string())?;
+        self.declare_word_abi("@seq".to_string())?;
+
+        // String operations
+        self.declare_word_abi("@string_length".to_string())?;
+        self.declare_word_abi("@string_concat".to_string())?;
+        self.declare_word_abi("@string_equal".to_string())?;
+        self.declare_word_abi("@string_split".to_string())?;
+
         // Type conversions
-        writeln!(&mut self.output, "declare ptr @int_to_string(ptr)")
-            .map_err(|e| CodegenError::InternalError(e.to_string()))?;
-        writeln!(&mut self.output, "declare ptr @bool_to_string(ptr)")
-            .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+        self.declare_word_abi("@int_to_string".to_string())?;
+        self.declare_word_abi("@bool_to_string".to_string())?;
+        self.declare_word_abi("@bool_to_int".to_string())?;
+        self.declare_word_abi("@int_to_bool".to_string())?;
 
         // Exit operation
         writeln!(&mut self.output, "declare void @exit_op(ptr)")
             .map_err(|e| CodegenError::InternalError(e.to_string()))?;
 
-        // Scheduler operations (testing)
-        writeln!(&mut self.output, "declare ptr @test_yield(ptr)")
+        // Debugger trap (backs the `breakpoint` word)
+        writeln!(&mut self.output, "declare void @llvm.debugtrap()")
             .map_err(|e| CodegenError::InternalError(e.to_string()))?;
 
+        // Scheduler operations (testing)
+        self.declare_word_abi("@test_yield".to_string())?;
+
         // I/O operations (async)
-        writeln!(&mut self.output, "declare ptr @write_line(ptr)")
-            .map_err(|e| CodegenError::InternalError(e.to_string()))?;
-        writeln!(&mut self.output, "declare ptr @read_line(ptr)")
-            .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+        self.declare_word_abi("@write_line".to_string())?;
+        self.declare_word_abi("@write_string".to_string())?;
+        self.declare_word_abi("@read_line".to_string())?;
 
         // Scheduler operations
         writeln!(&mut self.output, "declare void @scheduler_init()")
@@ -309,6 +544,12 @@ impl CodeGen {
         // Utility functions
         writeln!(&mut self.output, "declare void @print_stack(ptr)")
             .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+        self.declare_word_abi("@print_top".to_string())?;
+        writeln!(
+            &mut self.output,
+            "declare void @check_stack_depth(ptr, i64)"
+        )
+        .map_err(|e| CodegenError::InternalError(e.to_string()))?;
         writeln!(&mut self.output, "declare void @free_stack(ptr)")
             .map_err(|e| CodegenError::InternalError(e.to_string()))?;
         writeln!(&mut self.output, "declare void @runtime_error(ptr)")
@@ -329,7 +570,10 @@ impl CodeGen {
 
     /// Emit a main() function that calls an entry word
     ///
-    /// Generates:
+    /// The entry word starts from an empty stack, or from `options.entry_stack` pushed
+    /// in order. On targets without the scheduler (wasm), main() calls the entry word
+    /// directly.
+    /// Otherwise it generates:
     /// ```llvm
     /// define i32 @main() {
     /// entry:
@@ -340,12 +584,8 @@ impl CodeGen {
     /// }
     /// ```
     fn emit_main_function(&mut self, entry_word: &str) -> CodegenResult<()> {
-        // Avoid name collision - if entry word is "main", it was renamed to "cem_main"
-        let function_name = if entry_word == "main" {
-            "cem_main"
-        } else {
-            entry_word
-        };
+        // The entry word was defined under its mangled symbol (`main` becomes `cem_main`)
+        let function_name = symbol_for_word(entry_word);
 
         writeln!(&mut self.output, "; Main function")
             .map_err(|e| CodegenError::InternalError(e.to_string()))?;
@@ -354,25 +594,70 @@ impl CodeGen {
         writeln!(&mut self.output, "entry:")
             .map_err(|e| CodegenError::InternalError(e.to_string()))?;
 
-        // Initialize scheduler for async I/O
-        writeln!(&mut self.output, "  call void @scheduler_init()")
+        let mut initial_stack = "null".to_string();
+        for (i, seed) in self.options.entry_stack.clone().iter().enumerate() {
+            let (push, value) = match seed {
+                StackSeed::Int(n) => ("push_int", format!("i64 {}", n)),
+                StackSeed::Bool(b) => ("push_bool", format!("i1 zeroext {}", *b as u8)),
+                StackSeed::String(s) => ("push_string", format!("ptr {}", self.string_global(s))),
+            };
+            writeln!(
+                &mut self.output,
+                "  %seed.{} = call ptr @{}(ptr {}, {})",
+                i, push, initial_stack, value
+            )
+            .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+            initial_stack = format!("%seed.{}", i);
+        }
+
+        if self.options.target.uses_scheduler() {
+            // Initialize scheduler for async I/O
+            writeln!(&mut self.output, "  call void @scheduler_init()")
+                .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+
+            // Spawn entry word as a strand
+            writeln!(
+                &mut self.output,
+                "  call i64 @strand_spawn(ptr {}, ptr {})",
+                llvm_global(&function_name),
+                initial_stack
+            )
             .map_err(|e| CodegenError::InternalError(e.to_string()))?;
 
-        // Spawn entry word as a strand
-        writeln!(
-            &mut self.output,
-            "  call i64 @strand_spawn(ptr @{}, ptr null)",
-            function_name
-        )
-        .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+            // Run scheduler (returns final stack from main strand)
+            writeln!(&mut self.output, "  %stack = call ptr @scheduler_run()")
+                .map_err(|e| CodegenError::InternalError(e.to_string()))?;
 
-        // Run scheduler (returns final stack from main strand)
-        writeln!(&mut self.output, "  %stack = call ptr @scheduler_run()")
+            // Shutdown scheduler
+            writeln!(&mut self.output, "  call void @scheduler_shutdown()")
+                .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+        } else {
+            // No scheduler on this target - call the entry word directly
+            writeln!(
+                &mut self.output,
+                "  %stack = call ptr {}(ptr {})",
+                llvm_global(&function_name),
+                initial_stack
+            )
             .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+        }
 
-        // Shutdown scheduler
-        writeln!(&mut self.output, "  call void @scheduler_shutdown()")
+        // The entry word consumes its inputs from the seeded stack and leaves its outputs
+        if self.options.strict_stack {
+            let effect = self.word_effects.get(entry_word).ok_or_else(|| {
+                CodegenError::MissingEntryWord {
+                    name: entry_word.to_string(),
+                }
+            })?;
+            let expected = (self.options.entry_stack.len() + effect.outputs.concrete_depth())
+                .saturating_sub(effect.inputs.concrete_depth());
+            writeln!(
+                &mut self.output,
+                "  call void @check_stack_depth(ptr %stack, i64 {})",
+                expected
+            )
             .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+        }
 
         // Clean up
         writeln!(&mut self.output, "  call void @free_stack(ptr %stack)")
@@ -388,7 +673,7 @@ impl CodeGen {
     /// Emit debug info header: DIFile nodes for each source file
     fn emit_debug_info_header(
         &mut self,
-        source_files: &std::collections::HashSet<&str>,
+        source_files: &std::collections::BTreeSet<&str>,
     ) -> CodegenResult<()> {
         writeln!(&mut self.output, "; Debug Information")
             .map_err(|e| CodegenError::InternalError(e.to_string()))?;
@@ -591,43 +876,56 @@ impl CodeGen {
 
     /// Compile a word definition to LLVM function
     fn compile_word(&mut self, word: &WordDef) -> CodegenResult<()> {
+        if self.options.check_output_arity {
+            self.check_word_output_arity(word)?;
+        }
+
         self.temp_counter = 0; // Reset for each function
         self.current_block = "entry".to_string(); // Reset to entry block
 
-        // Register this word for debug metadata (allocates ID for later emission)
-        let subprogram_id = self.register_word_subprogram(word)?;
-
-        // Set current subprogram for debug location generation
-        self.current_subprogram_id = Some(subprogram_id);
-
-        // Map word name to function name (handles operators and hyphenated names)
-        // Also avoid name collision with C main() - prefix Cem "main" word with "cem_"
-        let function_name = if word.name == "main" {
-            "cem_main".to_string()
+        // Register this word for debug metadata (allocates ID for later emission), and
+        // make it the scope of the debug locations emitted in its body
+        let subprogram_id = if self.options.debug_info {
+            Some(self.register_word_subprogram(word)?)
         } else {
-            Self::map_operator_to_function(&word.name)
+            None
         };
+        self.current_subprogram_id = subprogram_id;
+
+        // Map word name to function name (handles operators, hyphens, and `main`)
+        let function_name = symbol_for_word(&word.name);
 
-        // Emit function definition with debug metadata attachment
+        // Emit the source signature, then the function definition with debug metadata attachment
+        writeln!(&mut self.output, "; {} {}", word.name, word.effect)
+            .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+        let attributes = match word.inline {
+            Some(InlineHint::Always) => " alwaysinline",
+            Some(InlineHint::Never) => " noinline",
+            None => "",
+        };
+        let dbg = subprogram_id.map_or(String::new(), |id| format!(" !dbg !{}", id));
         writeln!(
             &mut self.output,
-            "define ptr @{}(ptr %stack) !dbg !{} {{",
-            function_name, subprogram_id
+            "define ptr {}(ptr %stack){}{} {{",
+            llvm_global(&function_name),
+            attributes,
+            dbg
         )
         .map_err(|e| CodegenError::InternalError(e.to_string()))?;
         writeln!(&mut self.output, "entry:")
             .map_err(|e| CodegenError::InternalError(e.to_string()))?;
 
         // Compile all expressions in the word body
-        let (final_stack, ends_with_musttail) =
-            self.compile_expr_sequence(&word.body, "stack")?;
+        let (final_stack, _ends_with_musttail) = self.compile_expr_sequence(&word.body, "stack")?;
 
         // Check if all paths have already terminated (match/if with all branches returning)
         // This is the OPPOSITE of check_all_paths_returned:
         //   check_all_paths_returned returns true if caller SHOULD emit ret (WordCall case)
         //   We want to know if all paths ALREADY emitted ret (Match/If case)
-        let all_paths_already_terminated = word.body.last()
-            .map_or(false, |e| self.check_all_branches_already_returned(e));
+        let all_paths_already_terminated = word
+            .body
+            .last()
+            .is_some_and(|e| self.check_all_branches_already_returned(e));
 
         // Emit ret unless all paths have already emitted ret
         if !all_paths_already_terminated {
@@ -644,944 +942,3145 @@ impl CodeGen {
         Ok(())
     }
 
-    /// Check if an expression will have all code paths return (needs caller to emit ret)
-    /// Returns true if the expression needs the caller to emit ret (WordCall)
-    /// or if all branches end with expressions that need ret (Match/If with all branches returning)
-    fn check_all_paths_returned(&self, expr: &Expr) -> bool {
-        match expr {
-            // A word call (non-variant) in tail position will be compiled as musttail
-            // The parent context (match branch or word body) will emit the ret statement
-            Expr::WordCall(name, _) => !self.variant_tags.contains_key(name),
-
-            // Match emits ret for each branch if all branches end with musttail
-            Expr::Match { branches, .. } => {
-                branches.iter().all(|b| {
-                    b.body.last().map_or(false, |e| self.check_all_paths_returned(e))
-                })
+    /// Record the variant tags and field counts of the builtin types and the program's
+    /// own, and the program's word effects
+    fn register_definitions(&mut self, program: &Program, words: &[&WordDef]) -> CodegenResult<()> {
+        // Build variant tag map and field count map from type definitions
+        // Tags are derived from the type and variant names (not their position), so
+        // separately compiled units agree on them. The program's types come last, so a
+        // variant it declares replaces a builtin one of the same name.
+        let builtin_types: Vec<TypeDef> = self.builtins.types().cloned().collect();
+        for typedef in builtin_types.iter().chain(&program.type_defs) {
+            let mut seen = std::collections::HashMap::new();
+            for variant in &typedef.variants {
+                let tag = variant_tag_for(&typedef.name, &variant.name);
+                if let Some(other) = seen.insert(tag, &variant.name) {
+                    return Err(CodegenError::InternalError(format!(
+                        "Variants {} and {} of type {} hash to the same tag; rename one",
+                        other, variant.name, typedef.name
+                    )));
+                }
+                self.variant_tags.insert(variant.name.clone(), tag);
+                self.variant_field_counts
+                    .insert(variant.name.clone(), variant.fields.len());
             }
+        }
 
-            // If emits ret for both branches if both end with musttail
-            Expr::If { then_branch, else_branch, .. } => {
-                let then_musttail = if let Expr::Quotation(exprs, _) = &**then_branch {
-                    exprs.last().map_or(false, |e| self.check_all_paths_returned(e))
-                } else {
-                    false
-                };
-                let else_musttail = if let Expr::Quotation(exprs, _) = &**else_branch {
-                    exprs.last().map_or(false, |e| self.check_all_paths_returned(e))
-                } else {
-                    false
-                };
-                then_musttail && else_musttail
-            }
+        // Record declared effects so word bodies can be checked against their signatures
+        for word in words {
+            self.word_effects
+                .insert(word.name.clone(), word.effect.clone());
+            self.word_abi_globals
+                .insert(llvm_global(&symbol_for_word(&word.name)));
+        }
+        Ok(())
+    }
 
-            _ => false,
+    /// Check that every enabled word's body leaves as many values as its effect declares,
+    /// without type checking or generating code
+    ///
+    /// This counts pushes and pops using only declared effects, so it is far cheaper
+    /// than the type checker and reports every unbalanced word instead of stopping at
+    /// the first. Words whose net effect can't be determined statically are skipped.
+    pub fn check_stack_balance<'p>(
+        &mut self,
+        program: &'p Program,
+    ) -> CodegenResult<Vec<(&'p WordDef, CodegenError)>> {
+        let words: Vec<&'p WordDef> = program
+            .word_defs
+            .iter()
+            .filter(|w| w.is_enabled(&self.options.features))
+            .collect();
+        for (name, _, effect) in &self.external_words {
+            self.word_effects.insert(name.clone(), effect.clone());
+        }
+        self.register_definitions(program, &words)?;
+
+        Ok(words
+            .into_iter()
+            .filter_map(|word| Some((word, self.check_word_output_arity(word).err()?)))
+            .collect())
+    }
+
+    /// Verify that a word body leaves exactly the number of outputs its effect declares
+    ///
+    /// `compile_word` returns whatever stack the body produces, so a body that leaves
+    /// too few or too many values would silently break every caller.
+    fn check_word_output_arity(&self, word: &WordDef) -> CodegenResult<()> {
+        let Some(delta) = self.stack_delta(&word.body, &self.builtins) else {
+            return Ok(());
+        };
+
+        let expected = word.effect.outputs.concrete_depth() as isize;
+        let actual = word.effect.inputs.concrete_depth() as isize + delta;
+        if actual != expected {
+            return Err(CodegenError::OutputArityMismatch {
+                word: word.name.clone(),
+                expected: expected as usize,
+                actual,
+            });
         }
+        Ok(())
     }
 
-    /// Check if all branches of a Match/If have already emitted ret
-    /// This is different from check_all_paths_returned:
-    ///   - WordCall: false (needs ret to be emitted)
-    ///   - Match with all branches WordCall: true (all branches already emitted ret)
-    fn check_all_branches_already_returned(&self, expr: &Expr) -> bool {
+    /// Net stack depth change of an expression sequence, if statically known
+    fn stack_delta(&self, exprs: &[Expr], builtins: &Environment) -> Option<isize> {
+        exprs
+            .iter()
+            .map(|e| self.expr_stack_delta(e, builtins))
+            .sum()
+    }
+
+    /// Net stack depth change of a single expression, if statically known
+    ///
+    /// Returns None for calls to words without a known effect, or for branches that
+    /// disagree (the type checker reports those with better context).
+    fn expr_stack_delta(&self, expr: &Expr, builtins: &Environment) -> Option<isize> {
         match expr {
-            // WordCall needs ret to be emitted, hasn't already returned
-            Expr::WordCall(_, _) => false,
+            Expr::IntLit(..) | Expr::BoolLit(..) | Expr::StringLit(..) | Expr::Quotation(..) => {
+                Some(1)
+            }
+
+            Expr::WordCall(name, _) => {
+                if let Some(effect) = self.word_effects.get(name) {
+                    Self::effect_delta(effect)
+                } else if let Some(&fields) = self.variant_field_counts.get(name) {
+                    // Variant constructor: consumes its fields, pushes the variant
+                    Some(1 - fields as isize)
+                } else {
+                    builtins.lookup_word(name).and_then(Self::effect_delta)
+                }
+            }
 
-            // Match has all branches returned if all end with expressions that return
             Expr::Match { branches, .. } => {
-                branches.iter().all(|b| {
-                    b.body.last().map_or(false, |e| self.check_all_paths_returned(e))
-                })
+                // The variant is consumed and its fields are unwrapped onto the stack
+                let mut deltas = branches.iter().map(|branch| {
+                    let fields = match &branch.pattern {
+                        Pattern::Variant { name, .. } => {
+                            *self.variant_field_counts.get(name)? as isize
+                        }
+                        Pattern::Int(_) | Pattern::Wildcard => 0,
+                    };
+                    Some(fields + self.stack_delta(&branch.body, builtins)?)
+                });
+                let first = deltas.next()??;
+                for delta in deltas {
+                    if delta? != first {
+                        return None;
+                    }
+                }
+                Some(first - 1)
             }
 
-            // If has all branches returned if both end with expressions that return
-            Expr::If { then_branch, else_branch, .. } => {
-                let then_returned = if let Expr::Quotation(exprs, _) = &**then_branch {
-                    exprs.last().map_or(false, |e| self.check_all_paths_returned(e))
-                } else {
-                    false
+            Expr::If {
+                condition,
+                then_branch,
+                else_branch,
+                ..
+            } => {
+                // An `ifte` condition pushes the Bool, which is consumed before either
+                // branch runs
+                let pushed = match condition.as_deref() {
+                    Some(Expr::Quotation(condition, _)) => self.stack_delta(condition, builtins)?,
+                    Some(_) => return None,
+                    None => 0,
                 };
-                let else_returned = if let Expr::Quotation(exprs, _) = &**else_branch {
-                    exprs.last().map_or(false, |e| self.check_all_paths_returned(e))
-                } else {
-                    false
+                let (Expr::Quotation(then_body, _), Expr::Quotation(else_body, _)) =
+                    (&**then_branch, &**else_branch)
+                else {
+                    return None;
                 };
-                then_returned && else_returned
+                let then_delta = self.stack_delta(then_body, builtins)?;
+                let else_delta = self.stack_delta(else_body, builtins)?;
+                (then_delta == else_delta).then_some(pushed + then_delta - 1)
             }
 
-            _ => false,
+            Expr::While {
+                condition, body, ..
+            } => {
+                // The condition's Bool is consumed each iteration; the body must be balanced
+                let (Expr::Quotation(condition, _), Expr::Quotation(body, _)) =
+                    (&**condition, &**body)
+                else {
+                    return None;
+                };
+                let balanced = self.stack_delta(condition, builtins)? == 1
+                    && self.stack_delta(body, builtins)? == 0;
+                balanced.then_some(0)
+            }
         }
     }
 
-    /// Compile a branch quotation (quotation inside then/else)
-    /// Returns (result_var, ends_with_musttail)
+    /// Net stack depth change of an effect signature
     ///
-    /// ends_with_musttail is true if the last expression in the quotation
-    /// is a WordCall in tail position (which will be compiled as a musttail call)
-    fn compile_branch_quotation(
-        &mut self,
-        quot: &Expr,
-        initial_stack: &str,
-    ) -> CodegenResult<(String, bool)> {
-        match quot {
-            Expr::Quotation(exprs, _loc) => self.compile_expr_sequence(exprs, initial_stack),
-            _ => Err(CodegenError::InternalError(
-                "If branches must be quotations".to_string(),
-            )),
-        }
+    /// None when the inputs and outputs rest on different row variables, as for
+    /// `call_quotation`: the change then depends on the quotation being called.
+    fn effect_delta(effect: &Effect) -> Option<isize> {
+        (effect.inputs.row_var() == effect.outputs.row_var()).then(|| {
+            effect.outputs.concrete_depth() as isize - effect.inputs.concrete_depth() as isize
+        })
     }
 
-    /// Compile a sequence of expressions (used for quotations and match branches)
-    /// Returns (final_stack_var, ends_with_musttail)
+    /// Whether a word call lowers to a call of a stack function (and so can be tail-called)
     ///
-    /// ends_with_musttail is true if the sequence ends with a WordCall in tail position
-    /// (which will be compiled as a musttail call). The caller should emit `ret ptr %stack`.
-    ///
-    /// If the sequence ends with a Match/If where all branches return, ends_with_musttail
-    /// is false but all code paths have already terminated. The caller should check
-    /// check_all_paths_returned() to determine this case.
-    fn compile_expr_sequence(
-        &mut self,
-        exprs: &[Expr],
-        initial_stack: &str,
-    ) -> CodegenResult<(String, bool)> {
-        let mut stack_var = initial_stack.to_string();
-        let len = exprs.len();
-
-        // Empty sequences don't end with musttail
-        if len == 0 {
-            return Ok((stack_var, false));
-        }
-
-        let mut ends_with_musttail = false;
-
-        for (i, expr) in exprs.iter().enumerate() {
-            let is_tail = i == len - 1; // Track tail position in branch
-            stack_var = self.compile_expr_with_context(expr, &stack_var, is_tail)?;
-
-            // Check if the last expression is a WordCall in tail position
-            if is_tail {
-                if let Expr::WordCall(name, _) = expr {
-                    if !self.variant_tags.contains_key(name) {
-                        ends_with_musttail = true;
-                    }
-                }
-            }
-        }
-        Ok((stack_var, ends_with_musttail))
+    /// Variant constructors, `breakpoint`, `assert`, `some_if`, and `some_unless` are
+    /// emitted inline instead.
+    fn is_function_call(&self, name: &str) -> bool {
+        !matches!(name, "breakpoint" | "assert" | "some_if" | "some_unless")
+            && !self.variant_tags.contains_key(name)
     }
 
-    /// Compile a single expression with tail-call context
-    fn compile_expr_with_context(
+    /// Branch on the Int on top of `stack` to the arms of an integer match, returning
+    /// the rest of the stack below it
+    ///
+    /// The Int's cell is freed once its value and the rest have been read.
+    ///
+    /// Arm `i` starts at `match_case_{match_id}_{i}`; the wildcard arm (or, without one,
+    /// `default_label`) takes every other value. Dense cases become a `switch i64`,
+    /// which LLVM can lower to a jump table; sparse ones a chain of comparisons. A
+    /// repeated literal never matches past its first arm.
+    fn compile_int_dispatch(
         &mut self,
-        expr: &Expr,
+        branches: &[MatchBranch],
         stack: &str,
-        in_tail_position: bool,
+        match_id: usize,
+        default_label: &str,
+    ) -> CodegenResult<String> {
+        let value = self.fresh_temp();
+        writeln!(
+            &mut self.output,
+            "  %{} = call i64 @cell_int(ptr %{})",
+            value, stack
+        )
+        .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+        let rest = self.fresh_temp();
+        writeln!(
+            &mut self.output,
+            "  %{} = call ptr @cell_rest(ptr %{})",
+            rest, stack
+        )
+        .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+        writeln!(&mut self.output, "  call void @free_cell(ptr %{})", stack)
+            .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+
+        let mut cases: Vec<(i64, String)> = Vec::new();
+        let mut fallback = default_label.to_string();
+        for (idx, branch) in branches.iter().enumerate() {
+            let case_label = format!("match_case_{}_{}", match_id, idx);
+            match &branch.pattern {
+                Pattern::Int(n) if !cases.iter().any(|(seen, _)| *seen == *n) => {
+                    cases.push((*n, case_label))
+                }
+                Pattern::Int(_) => {}
+                Pattern::Wildcard => fallback = case_label,
+                Pattern::Variant { name, .. } => {
+                    return Err(CodegenError::InternalError(format!(
+                        "Variant pattern '{}' in an integer match",
+                        name
+                    )));
+                }
+            }
+        }
+
+        let values: Vec<i64> = cases.iter().map(|(n, _)| *n).collect();
+        if Self::is_dense(&values) {
+            write!(
+                &mut self.output,
+                "  switch i64 %{}, label %{} [",
+                value, fallback
+            )
+            .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+            for (n, label) in &cases {
+                write!(&mut self.output, "\n    i64 {}, label %{}", n, label)
+                    .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+            }
+            writeln!(&mut self.output, "\n  ]")
+                .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+        } else {
+            for (i, (n, label)) in cases.iter().enumerate() {
+                let next = if i + 1 < cases.len() {
+                    format!("match_test_{}_{}", match_id, i + 1)
+                } else {
+                    fallback.clone()
+                };
+                let matched = self.fresh_temp();
+                writeln!(
+                    &mut self.output,
+                    "  %{} = icmp eq i64 %{}, {}\n  br i1 %{}, label %{}, label %{}",
+                    matched, value, n, matched, label, next
+                )
+                .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+                if i + 1 < cases.len() {
+                    writeln!(&mut self.output, "{}:", next)
+                        .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+                }
+            }
+            if cases.is_empty() {
+                writeln!(&mut self.output, "  br label %{}", fallback)
+                    .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+            }
+        }
+        Ok(rest)
+    }
+
+    /// Whether integer cases are close enough together for a jump table: at least half
+    /// the values between the smallest and largest case are cases themselves
+    fn is_dense(values: &[i64]) -> bool {
+        let (Some(min), Some(max)) = (values.iter().min(), values.iter().max()) else {
+            return false;
+        };
+        let span = *max as i128 - *min as i128 + 1;
+        span <= 2 * values.len() as i128
+    }
+
+    /// Split the Bool on top of `stack` off the rest of the stack, returning the i1
+    /// condition and the remaining stack
+    ///
+    /// Both are read through runtime accessors, so the StackCell layout lives only in
+    /// runtime/stack.h, and the Bool's cell is freed once they have been read. Fresh
+    /// temps keep nested conditionals from colliding.
+    fn compile_condition(&mut self, stack: &str, dbg: &str) -> CodegenResult<(String, String)> {
+        let cond_var = self.fresh_temp();
+        writeln!(
+            &mut self.output,
+            "  %{} = call zeroext i1 @cell_bool(ptr %{}){}",
+            cond_var, stack, dbg
+        )
+        .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+
+        let rest_var = self.fresh_temp();
+        writeln!(
+            &mut self.output,
+            "  %{} = call ptr @cell_rest(ptr %{}){}",
+            rest_var, stack, dbg
+        )
+        .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+        writeln!(
+            &mut self.output,
+            "  call void @free_cell(ptr %{}){}",
+            stack, dbg
+        )
+        .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+
+        Ok((cond_var, rest_var))
+    }
+
+    /// Lower `some_if` / `some_unless`: pop the Bool, then wrap the value below it in
+    /// `Some` when the Bool is true (`some_if`) or false (`some_unless`), and replace it
+    /// with `None` otherwise
+    ///
+    /// Both paths meet in a merge block whose phi picks the resulting stack, the same
+    /// way the branches of an `if` do.
+    fn compile_conditional_some(
+        &mut self,
+        drop_when_true: bool,
+        stack: &str,
+        loc: &crate::ast::SourceLoc,
+    ) -> CodegenResult<String> {
+        let dbg = self.dbg_annotation(loc);
+        let drop_label = format!("cond_drop_{}", self.temp_counter);
+        let keep_label = format!("cond_keep_{}", self.temp_counter);
+        let merge_label = format!("cond_merge_{}", self.temp_counter);
+        self.temp_counter += 1;
+
+        let (cond_var, rest_var) = self.compile_condition(stack, &dbg)?;
+        let (if_true, if_false) = if drop_when_true {
+            (&drop_label, &keep_label)
+        } else {
+            (&keep_label, &drop_label)
+        };
+        writeln!(
+            &mut self.output,
+            "  br i1 %{}, label %{}, label %{}{}",
+            cond_var, if_true, if_false, dbg
+        )
+        .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+
+        writeln!(&mut self.output, "{}:", drop_label)
+            .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+        let dropped = self.fresh_temp();
+        writeln!(
+            &mut self.output,
+            "  %{} = call ptr @drop(ptr %{}){}",
+            dropped, rest_var, dbg
+        )
+        .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+        let none = self.fresh_temp();
+        writeln!(
+            &mut self.output,
+            "  %{} = call ptr @make_variant(ptr %{}, i32 {}, i32 0){}",
+            none,
+            dropped,
+            variant_tag_for("Option", "None"),
+            dbg
+        )
+        .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+        writeln!(&mut self.output, "  br label %{}", merge_label)
+            .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+
+        writeln!(&mut self.output, "{}:", keep_label)
+            .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+        let some = self.fresh_temp();
+        writeln!(
+            &mut self.output,
+            "  %{} = call ptr @make_variant(ptr %{}, i32 {}, i32 1){}",
+            some,
+            rest_var,
+            variant_tag_for("Option", "Some"),
+            dbg
+        )
+        .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+        writeln!(&mut self.output, "  br label %{}", merge_label)
+            .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+
+        writeln!(&mut self.output, "{}:", merge_label)
+            .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+        let result = self.fresh_temp();
+        writeln!(
+            &mut self.output,
+            "  %{} = phi ptr [ %{}, %{} ], [ %{}, %{} ]",
+            result, none, drop_label, some, keep_label
+        )
+        .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+        self.current_block = merge_label;
+        Ok(result)
+    }
+
+    /// Abort with a "division by zero" runtime error when the Int on top of `stack` is 0
+    fn compile_divisor_check(
+        &mut self,
+        name: &str,
+        stack: &str,
+        loc: &crate::ast::SourceLoc,
+    ) -> CodegenResult<()> {
+        let dbg = self.dbg_annotation(loc);
+        let zero_label = format!("div_zero_{}", self.temp_counter);
+        let ok_label = format!("div_ok_{}", self.temp_counter);
+        self.temp_counter += 1;
+
+        let divisor = self.fresh_temp();
+        writeln!(
+            &mut self.output,
+            "  %{} = call i64 @cell_int(ptr %{}){}",
+            divisor, stack, dbg
+        )
+        .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+        let is_zero = self.fresh_temp();
+        writeln!(
+            &mut self.output,
+            "  %{} = icmp eq i64 %{}, 0{}",
+            is_zero, divisor, dbg
+        )
+        .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+        writeln!(
+            &mut self.output,
+            "  br i1 %{}, label %{}, label %{}{}",
+            is_zero, zero_label, ok_label, dbg
+        )
+        .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+
+        let message = if loc.line == 0 {
+            format!("{}: division by zero", name)
+        } else {
+            format!("{}: division by zero at {}", name, loc)
+        };
+        let message_global = self.string_global(&message);
+        writeln!(&mut self.output, "{}:", zero_label)
+            .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+        writeln!(
+            &mut self.output,
+            "  call void @runtime_error(ptr {}){}",
+            message_global, dbg
+        )
+        .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+        writeln!(&mut self.output, "  unreachable")
+            .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+
+        writeln!(&mut self.output, "{}:", ok_label)
+            .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+        self.current_block = ok_label;
+        Ok(())
+    }
+
+    /// Check if an expression will have all code paths return (needs caller to emit ret)
+    /// Returns true if the expression needs the caller to emit ret (WordCall)
+    /// or if all branches end with expressions that need ret (Match/If with all branches returning)
+    fn check_all_paths_returned(&self, expr: &Expr) -> bool {
+        // Inside a loop nothing returns: every path continues to the next iteration
+        if self.in_loop {
+            return false;
+        }
+
+        match expr {
+            // A word call (non-variant) in tail position will be compiled as musttail
+            // The parent context (match branch or word body) will emit the ret statement
+            Expr::WordCall(name, _) => self.is_function_call(name),
+
+            // Match emits ret for each branch if all branches end with musttail
+            Expr::Match { branches, .. } => branches.iter().all(|b| {
+                b.body
+                    .last()
+                    .is_some_and(|e| self.check_all_paths_returned(e))
+            }),
+
+            // If emits ret for both branches if both end with musttail
+            Expr::If {
+                then_branch,
+                else_branch,
+                ..
+            } => {
+                let then_musttail = if let Expr::Quotation(exprs, _) = &**then_branch {
+                    exprs
+                        .last()
+                        .is_some_and(|e| self.check_all_paths_returned(e))
+                } else {
+                    false
+                };
+                let else_musttail = if let Expr::Quotation(exprs, _) = &**else_branch {
+                    exprs
+                        .last()
+                        .is_some_and(|e| self.check_all_paths_returned(e))
+                } else {
+                    false
+                };
+                then_musttail && else_musttail
+            }
+
+            _ => false,
+        }
+    }
+
+    /// Check if all branches of a Match/If have already emitted ret
+    /// This is different from check_all_paths_returned:
+    ///   - WordCall: false (needs ret to be emitted)
+    ///   - Match with all branches WordCall: true (all branches already emitted ret)
+    fn check_all_branches_already_returned(&self, expr: &Expr) -> bool {
+        match expr {
+            // WordCall needs ret to be emitted, hasn't already returned
+            Expr::WordCall(_, _) => false,
+
+            // Match has all branches returned if all end with expressions that return
+            Expr::Match { branches, .. } => branches.iter().all(|b| {
+                b.body
+                    .last()
+                    .is_some_and(|e| self.check_all_paths_returned(e))
+            }),
+
+            // If has all branches returned if both end with expressions that return
+            Expr::If {
+                then_branch,
+                else_branch,
+                ..
+            } => {
+                let then_returned = if let Expr::Quotation(exprs, _) = &**then_branch {
+                    exprs
+                        .last()
+                        .is_some_and(|e| self.check_all_paths_returned(e))
+                } else {
+                    false
+                };
+                let else_returned = if let Expr::Quotation(exprs, _) = &**else_branch {
+                    exprs
+                        .last()
+                        .is_some_and(|e| self.check_all_paths_returned(e))
+                } else {
+                    false
+                };
+                then_returned && else_returned
+            }
+
+            _ => false,
+        }
+    }
+
+    /// Compile a branch quotation (quotation inside then/else)
+    /// Returns (result_var, ends_with_musttail)
+    ///
+    /// ends_with_musttail is true if the last expression in the quotation
+    /// is a WordCall in tail position (which will be compiled as a musttail call)
+    fn compile_branch_quotation(
+        &mut self,
+        quot: &Expr,
+        initial_stack: &str,
+    ) -> CodegenResult<(String, bool)> {
+        match quot {
+            Expr::Quotation(exprs, _loc) => self.compile_expr_sequence(exprs, initial_stack),
+            _ => Err(CodegenError::InternalError(
+                "If branches must be quotations".to_string(),
+            )),
+        }
+    }
+
+    /// Compile a sequence of expressions (used for quotations and match branches)
+    /// Returns (final_stack_var, ends_with_musttail)
+    ///
+    /// ends_with_musttail is true if the sequence ends with a WordCall in tail position
+    /// (which will be compiled as a musttail call). The caller should emit `ret ptr %stack`.
+    ///
+    /// If the sequence ends with a Match/If where all branches return, ends_with_musttail
+    /// is false but all code paths have already terminated. The caller should check
+    /// check_all_paths_returned() to determine this case.
+    fn compile_expr_sequence(
+        &mut self,
+        exprs: &[Expr],
+        initial_stack: &str,
+    ) -> CodegenResult<(String, bool)> {
+        let mut stack_var = initial_stack.to_string();
+        let len = exprs.len();
+
+        // Nothing is known about the incoming stack (a sibling branch may have left
+        // its facts about the top behind)
+        self.top_is_nonzero_literal = false;
+        self.top_type = None;
+
+        // Empty sequences don't end with musttail
+        if len == 0 {
+            return Ok((stack_var, false));
+        }
+
+        let mut ends_with_musttail = false;
+
+        for (i, expr) in exprs.iter().enumerate() {
+            let is_tail = i == len - 1 && !self.in_loop; // Track tail position in branch
+            stack_var = self.compile_expr_with_context(expr, &stack_var, is_tail)?;
+
+            // Check if the last expression is a WordCall in tail position
+            if is_tail
+                && let Expr::WordCall(name, _) = expr
+                && self.is_function_call(name)
+            {
+                ends_with_musttail = true;
+            }
+        }
+        Ok((stack_var, ends_with_musttail))
+    }
+
+    /// Compile a single expression with tail-call context
+    fn compile_expr_with_context(
+        &mut self,
+        expr: &Expr,
+        stack: &str,
+        in_tail_position: bool,
+    ) -> CodegenResult<String> {
+        // Division checks its divisor first, unless it was just pushed as a nonzero literal
+        let divisor_known_nonzero = std::mem::take(&mut self.top_is_nonzero_literal);
+        if let Expr::WordCall(name, loc) = expr
+            && matches!(name.as_str(), "/" | "quot" | "mod" | "rem")
+            && !divisor_known_nonzero
+        {
+            self.compile_divisor_check(name, stack, loc)?;
+        }
+
+        // A non-tail `dup` of an Int or Bool copies the value instead of calling the runtime
+        let top_type = self.top_type.take();
+        let result = match (expr, &top_type) {
+            (Expr::WordCall(name, loc), Some(ty @ (Type::Int | Type::Bool)))
+                if name == "dup" && self.options.typed_dup && !in_tail_position =>
+            {
+                self.compile_typed_dup(ty, stack, loc)?
+            }
+            (Expr::WordCall(name, loc), _)
+                if matches!(name.as_str(), "swap" | "rot")
+                    && self.options.inline_shuffles
+                    && !in_tail_position =>
+            {
+                self.compile_inline_shuffle(name, stack, loc)?
+            }
+            _ => self.compile_call_or_expr(expr, stack, in_tail_position)?,
+        };
+
+        self.top_is_nonzero_literal = matches!(expr, Expr::IntLit(n, _) if *n != 0);
+        self.top_type = self.static_top_type(expr, top_type);
+        Ok(result)
+    }
+
+    /// The type of the top cell after `expr`, when it is known without the type checker
+    ///
+    /// `before` is the known type of the top cell before `expr` ran.
+    fn static_top_type(&self, expr: &Expr, before: Option<Type>) -> Option<Type> {
+        match expr {
+            Expr::IntLit(..) => Some(Type::Int),
+            Expr::BoolLit(..) => Some(Type::Bool),
+            Expr::StringLit(..) => Some(Type::String),
+            Expr::WordCall(name, _) if name == "dup" => before,
+            Expr::WordCall(name, _) => {
+                let effect = self
+                    .word_effects
+                    .get(name)
+                    .or_else(|| self.builtins.lookup_word(name))?;
+                let (_, top) = effect.outputs.clone().pop()?;
+                matches!(top, Type::Int | Type::Bool | Type::String).then_some(top)
+            }
+            _ => None,
+        }
+    }
+
+    /// Duplicate a top cell of known scalar type by reading its value and pushing a copy
+    ///
+    /// The accessors keep the StackCell layout out of codegen, and unlike `stack_dup`
+    /// they never need to inspect the tag to decide how to copy.
+    fn compile_typed_dup(
+        &mut self,
+        ty: &Type,
+        stack: &str,
+        loc: &crate::ast::SourceLoc,
+    ) -> CodegenResult<String> {
+        let (value_type, accessor, push, arg_type) = match ty {
+            Type::Bool => ("zeroext i1", "cell_bool", "push_bool", "i1 zeroext"),
+            _ => ("i64", "cell_int", "push_int", "i64"),
+        };
+        let dbg = self.dbg_annotation(loc);
+        let value = self.fresh_temp();
+        writeln!(
+            &mut self.output,
+            "  %{} = call {} @{}(ptr %{}){}",
+            value, value_type, accessor, stack, dbg
+        )
+        .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+        let result = self.fresh_temp();
+        writeln!(
+            &mut self.output,
+            "  %{} = call ptr @{}(ptr %{}, {} %{}){}",
+            result, push, stack, arg_type, value, dbg
+        )
+        .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+        Ok(result)
+    }
+
+    /// Compile `swap` or `rot` by relinking the `next` pointers of the top cells
+    ///
+    /// Mirrors the runtime's `swap` and `rot` without their underflow checks; see
+    /// `CodeGenOptions::inline_shuffles`.
+    fn compile_inline_shuffle(
+        &mut self,
+        name: &str,
+        stack: &str,
+        loc: &crate::ast::SourceLoc,
+    ) -> CodegenResult<String> {
+        let depth = if name == "rot" { 3 } else { 2 };
+        let offset = self.options.target.cell_next_offset();
+        let dbg = self.dbg_annotation(loc);
+
+        // cells[0] is the top; cells[depth] is the rest of the stack below them
+        let mut cells = vec![stack.to_string()];
+        let mut next_fields = Vec::new();
+        for _ in 0..depth {
+            let field = self.fresh_temp();
+            let next = self.fresh_temp();
+            writeln!(
+                &mut self.output,
+                "  %{} = getelementptr inbounds i8, ptr %{}, i64 {}\n  %{} = load ptr, ptr %{}{}",
+                field,
+                cells.last().unwrap(),
+                offset,
+                next,
+                field,
+                dbg
+            )
+            .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+            next_fields.push(field);
+            cells.push(next);
+        }
+
+        // swap: a b -- b a, rot: a b c -- b c a; the deepest cell moves to the top
+        let stores = if depth == 3 {
+            [(2, 0), (0, 1), (1, 3)].as_slice()
+        } else {
+            [(1, 0), (0, 2)].as_slice()
+        };
+        for &(cell, next) in stores {
+            writeln!(
+                &mut self.output,
+                "  store ptr %{}, ptr %{}{}",
+                cells[next], next_fields[cell], dbg
+            )
+            .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+        }
+        Ok(cells[depth - 1].clone())
+    }
+
+    /// Compile an expression, tail-calling words in tail position
+    fn compile_call_or_expr(
+        &mut self,
+        expr: &Expr,
+        stack: &str,
+        in_tail_position: bool,
     ) -> CodegenResult<String> {
         match expr {
             // Tail-call optimization: if in tail position and calling a word, use musttail
-            // BUT: variant constructors are not actual functions, so they can't be tail-called
-            Expr::WordCall(name, loc)
-                if in_tail_position && !self.variant_tags.contains_key(name) =>
-            {
+            // BUT: variant constructors and breakpoints are not actual functions, so they
+            // can't be tail-called
+            //
+            // musttail needs the callee's prototype to match the caller's `ptr (ptr)`.
+            // That holds for every word, but not for a runtime function with another
+            // prototype (`exit_op` returns void) or one this module never declares; those
+            // get a plain `tail call`, which LLVM may or may not honor.
+            Expr::WordCall(name, loc) if in_tail_position && self.is_function_call(name) => {
+                let result = self.fresh_temp();
+                let dbg = self.dbg_annotation(loc);
+                let callee = llvm_global(&self.callee_symbol(name));
+                let call = if self.word_abi_globals.contains(&callee) {
+                    "musttail call"
+                } else {
+                    "tail call"
+                };
+                writeln!(
+                    &mut self.output,
+                    "  %{} = {} ptr {}(ptr %{}){}",
+                    result, call, callee, stack, dbg
+                )
+                .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+                Ok(result)
+            }
+            // Otherwise, delegate to normal compile_expr
+            _ => self.compile_expr(expr, stack),
+        }
+    }
+
+    /// Compile a single expression, returning the new stack variable name
+    fn compile_expr(&mut self, expr: &Expr, stack: &str) -> CodegenResult<String> {
+        match expr {
+            Expr::IntLit(n, loc) => {
+                let result = self.fresh_temp();
+                let dbg = self.dbg_annotation(loc);
+                writeln!(
+                    &mut self.output,
+                    "  %{} = call ptr @push_int(ptr %{}, i64 {}){}",
+                    result, stack, n, dbg
+                )
+                .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+                Ok(result)
+            }
+
+            Expr::BoolLit(b, loc) => {
+                let result = self.fresh_temp();
+                let value = if *b { 1 } else { 0 };
+                let dbg = self.dbg_annotation(loc);
+                writeln!(
+                    &mut self.output,
+                    "  %{} = call ptr @push_bool(ptr %{}, i1 zeroext {}){}",
+                    result, stack, value, dbg
+                )
+                .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+                Ok(result)
+            }
+
+            Expr::StringLit(s, loc) => {
+                // String deduplication: Check if we've already emitted this exact string content.
+                // Without this, identical strings like "hello" appearing multiple times in the
+                // source would create separate @.str.N globals for each occurrence, bloating
+                // the binary. By reusing the same global, we reduce IR size and memory usage.
+                let str_global = self.string_global(s);
+
+                let str_len = s.len() + 1; // +1 for null terminator
+
+                // Allocate temps in the order they'll be used in the IR
+                let ptr_temp = self.fresh_temp();
+                let result = self.fresh_temp();
+                let dbg = self.dbg_annotation(loc);
+
+                writeln!(
+                    &mut self.output,
+                    "  %{} = getelementptr inbounds [{} x i8], ptr {}, i64 0, i64 0{}",
+                    ptr_temp, str_len, str_global, dbg
+                )
+                .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+                writeln!(
+                    &mut self.output,
+                    "  %{} = call ptr @push_string(ptr %{}, ptr %{}){}",
+                    result, stack, ptr_temp, dbg
+                )
+                .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+
+                Ok(result)
+            }
+
+            Expr::WordCall(name, loc) => {
+                // Check if this is a variant constructor
+                if let Some(&tag) = self.variant_tags.get(name) {
+                    // This is a variant constructor - make_variant pops the fields and
+                    // pushes the variant, which owns them as its data
+                    let field_count = self.variant_field_counts.get(name).copied().unwrap_or(0);
+                    let dbg = self.dbg_annotation(loc);
+                    let result = self.fresh_temp();
+                    writeln!(
+                        &mut self.output,
+                        "  %{} = call ptr @make_variant(ptr %{}, i32 {}, i32 {}){}",
+                        result, stack, tag, field_count, dbg
+                    )
+                    .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+                    Ok(result)
+                } else if name == "breakpoint" {
+                    // Trap into an attached debugger; the stack is untouched
+                    let dbg = self.dbg_annotation(loc);
+                    writeln!(&mut self.output, "  call void @llvm.debugtrap(){}", dbg)
+                        .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+                    Ok(stack.to_string())
+                } else if name == "some_if" || name == "some_unless" {
+                    self.compile_conditional_some(name == "some_unless", stack, loc)
+                } else if name == "assert" {
+                    // Continue with the rest of the stack when the Bool is true; otherwise
+                    // report the failing assert's location and abort
+                    let dbg = self.dbg_annotation(loc);
+                    let ok_label = format!("assert_ok_{}", self.temp_counter);
+                    let fail_label = format!("assert_fail_{}", self.temp_counter);
+                    self.temp_counter += 1;
+
+                    let (cond_var, rest_var) = self.compile_condition(stack, &dbg)?;
+                    writeln!(
+                        &mut self.output,
+                        "  br i1 %{}, label %{}, label %{}{}",
+                        cond_var, ok_label, fail_label, dbg
+                    )
+                    .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+
+                    let message = if loc.line == 0 {
+                        "assert: assertion failed".to_string()
+                    } else {
+                        format!("assert: assertion failed at {}", loc)
+                    };
+                    let message_global = self.string_global(&message);
+                    writeln!(&mut self.output, "{}:", fail_label)
+                        .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+                    writeln!(
+                        &mut self.output,
+                        "  call void @runtime_error(ptr {}){}",
+                        message_global, dbg
+                    )
+                    .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+                    writeln!(&mut self.output, "  unreachable")
+                        .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+
+                    writeln!(&mut self.output, "{}:", ok_label)
+                        .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+                    self.current_block = ok_label;
+                    Ok(rest_var)
+                } else {
+                    // Regular word call
+                    let result = self.fresh_temp();
+                    let dbg = self.dbg_annotation(loc);
+                    let func_name = self.callee_symbol(name);
+                    writeln!(
+                        &mut self.output,
+                        "  %{} = call ptr {}(ptr %{}){}",
+                        result,
+                        llvm_global(&func_name),
+                        stack,
+                        dbg
+                    )
+                    .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+                    Ok(result)
+                }
+            }
+
+            Expr::Quotation(exprs, _loc) => {
+                // Generate an anonymous function for the quotation
+                let quot_name = format!("quot_{}", self.quotation_counter);
+                self.quotation_counter += 1;
+
+                // The body is its own function: compile it into a fresh buffer with its own
+                // temps and entry block, and no debug scope (it has no DISubprogram)
+                let saved_output = std::mem::take(&mut self.output);
+                let saved_counter = std::mem::replace(&mut self.temp_counter, 0);
+                let saved_block = std::mem::replace(&mut self.current_block, "entry".to_string());
+                let saved_subprogram = self.current_subprogram_id.take();
+                let saved_in_loop = std::mem::replace(&mut self.in_loop, false);
+
+                // Generate the quotation function; internal linkage keeps it out of the
+                // object's symbol table, since every unit numbers its quotations from 0
+                writeln!(
+                    &mut self.output,
+                    "define internal ptr @{}(ptr %stack) {{",
+                    quot_name
+                )
+                .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+                writeln!(&mut self.output, "entry:")
+                    .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+
+                // Compile the quotation body
+                let mut stack_var = "stack".to_string();
+                let len = exprs.len();
+                for (i, expr) in exprs.iter().enumerate() {
+                    let is_tail = i == len - 1;
+                    stack_var = self.compile_expr_with_context(expr, &stack_var, is_tail)?;
+
+                    // If last expression is a musttail call, return its result
+                    if is_tail && let Expr::WordCall(_, _) = expr {
+                        writeln!(&mut self.output, "  ret ptr %{}", stack_var)
+                            .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+                    }
+                }
+
+                // If we didn't return via musttail, return normally
+                if len == 0 || !matches!(exprs.last(), Some(Expr::WordCall(_, _))) {
+                    writeln!(&mut self.output, "  ret ptr %{}", stack_var)
+                        .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+                }
+
+                writeln!(&mut self.output, "}}")
+                    .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+                writeln!(&mut self.output)
+                    .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+
+                // Set the function aside for top-level emission and resume the enclosing one
+                let quot_func = std::mem::replace(&mut self.output, saved_output);
+                self.quotation_functions.push_str(&quot_func);
+                self.temp_counter = saved_counter;
+                self.current_block = saved_block;
+                self.current_subprogram_id = saved_subprogram;
+                self.in_loop = saved_in_loop;
+
+                // Now push the function pointer onto the stack
                 let result = self.fresh_temp();
+                writeln!(
+                    &mut self.output,
+                    "  %{} = call ptr @push_quotation(ptr %{}, ptr @{})",
+                    result, stack, quot_name
+                )
+                .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+                Ok(result)
+            }
+
+            Expr::Match { branches, loc: _ } => {
+                // Pattern matching on variants
+                //
+                // Ownership semantics:
+                // - The variant cell is consumed (popped from stack)
+                // - Its fields are unwrapped onto the stack by the runtime: unit variants
+                //   (None) leave just the rest, Some(T) pushes one value, Pair(A, B) two
+                //
+                // Strategy: extract variant tag, switch on tag, each case executes branch body
+
+                if branches.is_empty() {
+                    return Err(CodegenError::InternalError(
+                        "Empty match expression".to_string(),
+                    ));
+                }
+
+                // Generate labels for each branch and merge point
+                let match_id = self.temp_counter;
+                let merge_label = format!("match_merge_{}", match_id);
+                let default_label = format!("match_default_{}", match_id);
+
+                // An integer match reads the Int and drops its cell up front; every arm
+                // then starts from the rest of the stack
+                let is_int_match = branches
+                    .iter()
+                    .any(|b| !matches!(b.pattern, Pattern::Variant { .. }));
+                let int_rest = if is_int_match {
+                    Some(self.compile_int_dispatch(branches, stack, match_id, &default_label)?)
+                } else {
+                    // Extract variant tag from stack top via the runtime, so codegen doesn't
+                    // depend on how the variant is laid out inside the StackCell union
+                    let variant_tag = self.fresh_temp();
+                    writeln!(
+                        &mut self.output,
+                        "  %{} = call i32 @variant_tag(ptr %{})",
+                        variant_tag, stack
+                    )
+                    .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+
+                    // Generate switch statement
+                    write!(
+                        &mut self.output,
+                        "  switch i32 %{}, label %{} [",
+                        variant_tag, default_label
+                    )
+                    .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+
+                    // Add switch cases for each branch
+                    for (idx, branch) in branches.iter().enumerate() {
+                        let Pattern::Variant { name, .. } = &branch.pattern else {
+                            unreachable!("a match with no integer patterns only has variants");
+                        };
+                        // Look up variant tag from type environment
+                        let tag_value = self.variant_tags.get(name).copied().ok_or_else(|| {
+                            CodegenError::InternalError(format!("Unknown variant: {}", name))
+                        })?;
+                        let case_label = format!("match_case_{}_{}", match_id, idx);
+                        writeln!(
+                            &mut self.output,
+                            "\n    i32 {}, label %{}",
+                            tag_value, case_label
+                        )
+                        .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+                    }
+                    writeln!(&mut self.output, "  ]")
+                        .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+                    None
+                };
+                let needs_default = !branches
+                    .iter()
+                    .any(|b| matches!(b.pattern, Pattern::Wildcard));
+
+                // Generate code for each branch
+                let mut branch_results = Vec::new();
+                let mut branch_predecessors = Vec::new();
+                let mut all_branches_musttail = true;
+
+                for (idx, branch) in branches.iter().enumerate() {
+                    let case_label = format!("match_case_{}_{}", match_id, idx);
+
+                    writeln!(&mut self.output, "{}:", case_label)
+                        .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+                    self.current_block = case_label.clone();
+
+                    // Unwrap the variant: its fields (as many as the TypeDef declares,
+                    // possibly none) are relinked onto the rest of the stack
+                    let initial_stack = match (&branch.pattern, &int_rest) {
+                        (Pattern::Variant { name, .. }, None) => {
+                            let field_count =
+                                self.variant_field_counts.get(name).copied().unwrap_or(0);
+                            let unwrapped = self.fresh_temp();
+                            writeln!(
+                                &mut self.output,
+                                "  %{} = call ptr @unwrap_variant(ptr %{}, i32 {})",
+                                unwrapped, stack, field_count
+                            )
+                            .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+                            unwrapped
+                        }
+                        (_, Some(rest)) => rest.clone(),
+                        (_, None) => {
+                            return Err(CodegenError::InternalError(
+                                "Integer pattern in a variant match".to_string(),
+                            ));
+                        }
+                    };
+
+                    let (branch_stack, ends_with_musttail) =
+                        self.compile_expr_sequence(&branch.body, &initial_stack)?;
+
+                    let predecessor = self.current_block.clone();
+
+                    // Check if this branch terminates (either via musttail or nested match/if)
+                    let branch_last_expr = branch.body.last();
+                    let branch_terminates = ends_with_musttail
+                        || branch_last_expr.is_some_and(|e| self.check_all_paths_returned(e));
+
+                    if branch_terminates {
+                        // Branch terminates - emit ret if needed
+                        if ends_with_musttail {
+                            writeln!(&mut self.output, "  ret ptr %{}", branch_stack)
+                                .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+                        }
+                        // If all paths already returned, no ret needed
+                    } else {
+                        // Branch doesn't terminate - needs merge point
+                        all_branches_musttail = false;
+                        writeln!(&mut self.output, "  br label %{}", merge_label)
+                            .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+                        branch_results.push(branch_stack);
+                        branch_predecessors.push(predecessor);
+                    }
+                }
+
+                // Default case (should never be reached if match is exhaustive); an
+                // integer match's wildcard arm is its default instead
+                if needs_default {
+                    writeln!(&mut self.output, "{}:", default_label)
+                        .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+                    writeln!(
+                        &mut self.output,
+                        "  call void @runtime_error(ptr @.str.match_error)"
+                    )
+                    .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+                    writeln!(&mut self.output, "  unreachable")
+                        .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+                }
+
+                // Add error string to string globals if not already present
+                if needs_default && !self.string_constants.contains_key("match_error") {
+                    let error_msg = "match: non-exhaustive pattern (internal error)";
+                    let escaped = Self::escape_llvm_string(error_msg);
+                    let str_len = error_msg.len() + 1;
+                    let global_decl = format!(
+                        "@.str.match_error = private unnamed_addr constant [{} x i8] c\"{}\\00\", align 1\n",
+                        str_len, escaped
+                    );
+                    self.string_globals.push_str(&global_decl);
+                    // Mark as added to prevent duplicates
+                    self.string_constants
+                        .insert("match_error".to_string(), "@.str.match_error".to_string());
+                }
+
+                // Merge point
+                if !all_branches_musttail {
+                    writeln!(&mut self.output, "{}:", merge_label)
+                        .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+                    self.current_block = merge_label;
+
+                    // Build phi node from branches that didn't return
+                    let result = self.fresh_temp();
+                    write!(&mut self.output, "  %{} = phi ptr", result)
+                        .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+
+                    for (stack_val, pred) in branch_results.iter().zip(branch_predecessors.iter()) {
+                        write!(&mut self.output, " [ %{}, %{} ],", stack_val, pred)
+                            .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+                    }
+                    // Remove trailing comma
+                    self.output.pop();
+                    writeln!(&mut self.output)
+                        .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+
+                    Ok(result)
+                } else {
+                    // All branches ended with musttail and return - no merge point needed
+                    // This is actually unreachable code after the match, so return a dummy value
+                    Ok(stack.to_string()) // Won't be used since all branches returned
+                }
+            }
+
+            Expr::If {
+                condition,
+                then_branch,
+                else_branch,
+                loc,
+            } => {
+                // Stack top must be a Bool
+                // Strategy: extract bool, branch to then/else, both produce same stack effect
+                if condition.is_some() {
+                    return Err(CodegenError::InternalError(
+                        "ifte condition should have been inlined by the peephole pass".to_string(),
+                    ));
+                }
+
+                // Generate unique labels
+                let then_label = format!("then_{}", self.temp_counter);
+                let else_label = format!("else_{}", self.temp_counter);
+                let merge_label = format!("merge_{}", self.temp_counter);
+                let after_label = format!("if_after_{}", self.temp_counter);
+                self.temp_counter += 1;
+
+                // Attribute the condition, branches, and merge to the `if` itself so
+                // debuggers step through the conditional in source order
                 let dbg = self.dbg_annotation(loc);
-                let func_name = Self::map_operator_to_function(name);
+
+                let (cond_var, rest_var) = self.compile_condition(stack, &dbg)?;
+
+                // Branch using the condition variable
                 writeln!(
                     &mut self.output,
-                    "  %{} = musttail call ptr @{}(ptr %{}){}",
-                    result, func_name, stack, dbg
+                    "  br i1 %{}, label %{}, label %{}{}",
+                    cond_var, then_label, else_label, dbg
                 )
                 .map_err(|e| CodegenError::InternalError(e.to_string()))?;
-                Ok(result)
+
+                // Then branch
+                writeln!(&mut self.output, "{}:", then_label)
+                    .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+                self.current_block = then_label.clone();
+                let (then_stack, then_is_musttail) =
+                    self.compile_branch_quotation(then_branch, &rest_var)?;
+
+                // Capture the actual block that will branch to merge (after any nested ifs)
+                let then_predecessor = self.current_block.clone();
+
+                // If then branch ends with musttail, emit return instead of branch
+                if then_is_musttail {
+                    writeln!(&mut self.output, "  ret ptr %{}", then_stack)
+                        .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+                } else {
+                    writeln!(&mut self.output, "  br label %{}{}", merge_label, dbg)
+                        .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+                }
+
+                // Else branch
+                writeln!(&mut self.output, "{}:", else_label)
+                    .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+                self.current_block = else_label.clone();
+                let (else_stack, else_is_musttail) =
+                    self.compile_branch_quotation(else_branch, &rest_var)?;
+
+                // Capture the actual block that will branch to merge (after any nested ifs)
+                let else_predecessor = self.current_block.clone();
+
+                // If else branch ends with musttail, emit return instead of branch
+                if else_is_musttail {
+                    writeln!(&mut self.output, "  ret ptr %{}", else_stack)
+                        .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+                } else {
+                    writeln!(&mut self.output, "  br label %{}{}", merge_label, dbg)
+                        .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+                }
+
+                // Merge point - only if at least one branch doesn't end with musttail
+                if !then_is_musttail || !else_is_musttail {
+                    writeln!(&mut self.output, "{}:", merge_label)
+                        .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+                    self.current_block = merge_label.clone();
+
+                    // Build phi node based on which branches contribute
+                    let result = self.fresh_temp();
+                    if !then_is_musttail && !else_is_musttail {
+                        // Both branches merge - use actual predecessors
+                        writeln!(
+                            &mut self.output,
+                            "  %{} = phi ptr [ %{}, %{} ], [ %{}, %{} ]{}",
+                            result, then_stack, then_predecessor, else_stack, else_predecessor, dbg
+                        )
+                        .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+                    } else if !then_is_musttail {
+                        // Only then branch merges (else returned)
+                        writeln!(
+                            &mut self.output,
+                            "  %{} = phi ptr [ %{}, %{} ]{}",
+                            result, then_stack, then_predecessor, dbg
+                        )
+                        .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+                    } else {
+                        // Only else branch merges (then returned)
+                        writeln!(
+                            &mut self.output,
+                            "  %{} = phi ptr [ %{}, %{} ]{}",
+                            result, else_stack, else_predecessor, dbg
+                        )
+                        .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+                    }
+                    Ok(result)
+                } else {
+                    // Both branches end with musttail and return - no merge point needed.
+                    // Control can't reach past the if; make that explicit with an
+                    // unreachable block, and hand back the if's input stack, which (unlike
+                    // either branch's result) is defined on every path into that block
+                    writeln!(&mut self.output, "{}:", after_label)
+                        .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+                    writeln!(&mut self.output, "  unreachable")
+                        .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+                    self.current_block = after_label;
+                    Ok(stack.to_string())
+                }
             }
-            // Otherwise, delegate to normal compile_expr
-            _ => self.compile_expr(expr, stack),
-        }
-    }
 
-    /// Compile a single expression, returning the new stack variable name
-    fn compile_expr(&mut self, expr: &Expr, stack: &str) -> CodegenResult<String> {
-        match expr {
-            Expr::IntLit(n, loc) => {
-                let result = self.fresh_temp();
+            Expr::While {
+                condition,
+                body,
+                loc,
+            } => {
+                // Strategy: the loop head merges the incoming stack with the stack each
+                // iteration leaves; the condition's Bool picks between body and exit
+                let (Expr::Quotation(condition, _), Expr::Quotation(body, _)) =
+                    (&**condition, &**body)
+                else {
+                    return Err(CodegenError::InternalError(
+                        "While condition and body must be quotations".to_string(),
+                    ));
+                };
+
+                let cond_label = format!("while_cond_{}", self.temp_counter);
+                let body_label = format!("while_body_{}", self.temp_counter);
+                let exit_label = format!("while_exit_{}", self.temp_counter);
+                // Stands in for the back edge until the body's final stack is known
+                let backedge = format!("<while_backedge_{}>", self.temp_counter);
+                self.temp_counter += 1;
                 let dbg = self.dbg_annotation(loc);
+
+                let entry_block = self.current_block.clone();
+                writeln!(&mut self.output, "  br label %{}{}", cond_label, dbg)
+                    .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+                writeln!(&mut self.output, "{}:", cond_label)
+                    .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+                self.current_block = cond_label.clone();
+                let loop_stack = self.fresh_temp();
                 writeln!(
                     &mut self.output,
-                    "  %{} = call ptr @push_int(ptr %{}, i64 {}){}",
-                    result, stack, n, dbg
+                    "  %{} = phi ptr [ %{}, %{} ], {}{}",
+                    loop_stack, stack, entry_block, backedge, dbg
                 )
                 .map_err(|e| CodegenError::InternalError(e.to_string()))?;
-                Ok(result)
+
+                let saved_in_loop = std::mem::replace(&mut self.in_loop, true);
+                let (cond_stack, _) = self.compile_expr_sequence(condition, &loop_stack)?;
+                let (cond_var, rest_var) = self.compile_condition(&cond_stack, &dbg)?;
+                writeln!(
+                    &mut self.output,
+                    "  br i1 %{}, label %{}, label %{}{}",
+                    cond_var, body_label, exit_label, dbg
+                )
+                .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+
+                writeln!(&mut self.output, "{}:", body_label)
+                    .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+                self.current_block = body_label;
+                let (body_stack, _) = self.compile_expr_sequence(body, &rest_var)?;
+                self.in_loop = saved_in_loop;
+                self.output = self.output.replace(
+                    &backedge,
+                    &format!("[ %{}, %{} ]", body_stack, self.current_block),
+                );
+                writeln!(&mut self.output, "  br label %{}{}", cond_label, dbg)
+                    .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+
+                // The exit is only reached from the loop head, where the rest is defined
+                writeln!(&mut self.output, "{}:", exit_label)
+                    .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+                self.current_block = exit_label;
+                Ok(rest_var)
             }
+        }
+    }
+
+    /// Get the generated LLVM IR
+    pub fn emit_ir(&self) -> String {
+        self.output.clone()
+    }
+}
+
+impl Default for CodeGen {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::types::{StackType, Type};
+    use crate::ast::{MatchBranch, TypeDef, Variant};
+
+    #[test]
+    fn test_codegen_simple() {
+        let mut codegen = CodeGen::new();
+
+        // : five ( -- Int ) 5 ;
+        let word = WordDef {
+            name: "five".to_string(),
+            effect: Effect {
+                inputs: StackType::Empty,
+                outputs: StackType::Empty.push(Type::Int),
+            },
+            body: vec![Expr::IntLit(5, SourceLoc::unknown())],
+            loc: SourceLoc::unknown(),
+            feature: None,
+            inline: None,
+            slot_names: Default::default(),
+            doc: None,
+        };
+
+        let program = Program {
+            type_defs: vec![],
+            word_defs: vec![word],
+        };
+
+        let ir = codegen.compile_program(&program).unwrap();
+
+        assert!(ir.contains("define ptr @five"));
+        assert!(ir.contains("call ptr @push_int"));
+        assert!(ir.contains("i64 5"));
+        assert!(ir.contains("ret ptr"));
+    }
+
+    #[test]
+    fn test_codegen_word_call() {
+        let mut codegen = CodeGen::new();
+
+        // : double ( Int -- Int ) dup + ;
+        let word = WordDef {
+            name: "double".to_string(),
+            effect: Effect {
+                inputs: StackType::Empty.push(Type::Int),
+                outputs: StackType::Empty.push(Type::Int),
+            },
+            body: vec![
+                Expr::WordCall("dup".to_string(), SourceLoc::unknown()),
+                Expr::WordCall("add".to_string(), SourceLoc::unknown()),
+            ],
+            loc: SourceLoc::unknown(),
+            feature: None,
+            inline: None,
+            slot_names: Default::default(),
+            doc: None,
+        };
+
+        let program = Program {
+            type_defs: vec![],
+            word_defs: vec![word],
+        };
+
+        let ir = codegen.compile_program(&program).unwrap();
+
+        assert!(ir.contains("@double"));
+        assert!(ir.contains("call ptr @stack_dup"));
+        assert!(ir.contains("call ptr @add"));
+    }
+
+    #[test]
+    fn test_dup_of_known_int_copies_without_runtime_call() {
+        // : twice ( -- Int ) 21 dup + ;
+        let program = Program {
+            type_defs: vec![],
+            word_defs: vec![WordDef {
+                name: "twice".to_string(),
+                effect: Effect {
+                    inputs: StackType::Empty,
+                    outputs: StackType::Empty.push(Type::Int),
+                },
+                body: vec![
+                    Expr::IntLit(21, SourceLoc::unknown()),
+                    Expr::WordCall("dup".to_string(), SourceLoc::unknown()),
+                    Expr::WordCall("+".to_string(), SourceLoc::unknown()),
+                ],
+                loc: SourceLoc::unknown(),
+                feature: None,
+                inline: None,
+                slot_names: Default::default(),
+                doc: None,
+            }],
+        };
+
+        let ir = CodeGen::new().compile_program(&program).unwrap();
+        let body = &ir[ir.find("define ptr @twice").unwrap()..];
+        assert!(body.contains("call ptr @stack_dup(ptr %0)"));
+
+        // Typed mode, for programs that passed the type checker
+        let ir = CodeGen::with_options(CodeGenOptions {
+            typed_dup: true,
+            ..CodeGenOptions::default()
+        })
+        .compile_program(&program)
+        .unwrap();
+        let body = &ir[ir.find("define ptr @twice").unwrap()..];
+        assert!(
+            !body.contains("@stack_dup"),
+            "dup should be specialized:\n{}",
+            body
+        );
+        assert!(body.contains("%1 = call i64 @cell_int(ptr %0)"));
+        assert!(body.contains("%2 = call ptr @push_int(ptr %0, i64 %1)"));
+        assert!(body.contains("musttail call ptr @add(ptr %2)"));
+    }
+
+    #[test]
+    fn test_while_loops_back_through_a_phi() {
+        // : count ( Int -- Int ) while [ dup 10 < ] [ 1 + ] ;
+        let call = |name: &str| Expr::WordCall(name.to_string(), SourceLoc::unknown());
+        let program = Program {
+            type_defs: vec![],
+            word_defs: vec![WordDef {
+                name: "count".to_string(),
+                effect: Effect {
+                    inputs: StackType::from_vec(vec![Type::Int]),
+                    outputs: StackType::from_vec(vec![Type::Int]),
+                },
+                body: vec![Expr::While {
+                    condition: Box::new(Expr::Quotation(
+                        vec![
+                            call("dup"),
+                            Expr::IntLit(10, SourceLoc::unknown()),
+                            call("<"),
+                        ],
+                        SourceLoc::unknown(),
+                    )),
+                    body: Box::new(Expr::Quotation(
+                        vec![Expr::IntLit(1, SourceLoc::unknown()), call("+")],
+                        SourceLoc::unknown(),
+                    )),
+                    loc: SourceLoc::unknown(),
+                }],
+                loc: SourceLoc::unknown(),
+                feature: None,
+                inline: None,
+                slot_names: Default::default(),
+                doc: None,
+            }],
+        };
+
+        let ir = CodeGen::new().compile_program(&program).unwrap();
+        let body = &ir[ir.find("define ptr @count").unwrap()..];
+        let body = &body[..body.find("\n}").unwrap()];
+
+        // Nothing inside the loop may tail-call out of it
+        assert!(
+            !body.contains("musttail"),
+            "Loop body must not tail-call:\n{}",
+            body
+        );
+        assert!(!body.contains("<while_backedge"));
+
+        let phi = body
+            .lines()
+            .find(|l| l.contains("= phi ptr"))
+            .expect("loop head should merge stacks");
+        assert!(phi.contains("[ %stack, %entry ]"), "{}", phi);
+        let add = body
+            .lines()
+            .find(|l| l.contains("@add("))
+            .expect("body should add");
+        let add_result = add.trim_start().split(' ').next().unwrap();
+        assert!(
+            phi.contains(&format!("[ {}, %while_body_", add_result)),
+            "{}",
+            phi
+        );
+
+        // Each iteration frees the condition's Bool after reading it
+        let condition = body
+            .lines()
+            .find(|l| l.contains("@cell_bool("))
+            .expect("loop should read its condition");
+        let cond_stack =
+            &condition[condition.find("(ptr ").unwrap() + 5..condition.find(')').unwrap()];
+        assert!(
+            body.contains(&format!("call void @free_cell(ptr {})", cond_stack)),
+            "{}",
+            body
+        );
+
+        let graph = crate::codegen::ControlFlowGraph::from_ir(&ir, "count").unwrap();
+        let successors = |prefix: &str| {
+            graph
+                .blocks
+                .iter()
+                .find(|b| b.label.starts_with(prefix))
+                .map(|b| b.successors.clone())
+                .unwrap()
+        };
+        assert_eq!(successors("while_cond_").len(), 2);
+        assert!(successors("while_body_")[0].starts_with("while_cond_"));
+        assert_eq!(successors("entry"), successors("while_body_"));
+    }
 
-            Expr::BoolLit(b, loc) => {
-                let result = self.fresh_temp();
-                let value = if *b { 1 } else { 0 };
-                let dbg = self.dbg_annotation(loc);
-                writeln!(
-                    &mut self.output,
-                    "  %{} = call ptr @push_bool(ptr %{}, i1 {}){}",
-                    result, stack, value, dbg
-                )
-                .map_err(|e| CodegenError::InternalError(e.to_string()))?;
-                Ok(result)
-            }
+    #[test]
+    fn test_no_target_triple_in_generated_ir() {
+        let mut codegen = CodeGen::new();
 
-            Expr::StringLit(s, loc) => {
-                // String deduplication: Check if we've already emitted this exact string content.
-                // Without this, identical strings like "hello" appearing multiple times in the
-                // source would create separate @.str.N globals for each occurrence, bloating
-                // the binary. By reusing the same global, we reduce IR size and memory usage.
-                let str_global = if let Some(existing) = self.string_constants.get(s) {
-                    existing.clone()
-                } else {
-                    // Create new global string constant
-                    let str_global = format!("@.str.{}", self.string_counter);
-                    self.string_counter += 1; // Increment for the string global itself
+        let word = WordDef {
+            name: "test".to_string(),
+            effect: Effect {
+                inputs: StackType::Empty,
+                outputs: StackType::Empty,
+            },
+            body: vec![],
+            loc: SourceLoc::unknown(),
+            feature: None,
+            inline: None,
+            slot_names: Default::default(),
+            doc: None,
+        };
 
-                    let escaped = Self::escape_llvm_string(s);
-                    // Length is original byte count - escaping is just text representation.
-                    // E.g., "a\"b" is 3 bytes even though we write it as 5 chars in IR text.
-                    // UTF-8 chars like "😀" (4 bytes) escape to "\F0\9F\98\80" but still represent 4 bytes.
-                    let str_len = s.len() + 1; // +1 for null terminator
+        let program = Program {
+            type_defs: vec![],
+            word_defs: vec![word],
+        };
 
-                    // Emit global to string_globals area
-                    let global_decl = format!(
-                        "{} = private unnamed_addr constant [{} x i8] c\"{}\\00\"\n",
-                        str_global, str_len, escaped
-                    );
-                    self.string_globals.push_str(&global_decl);
+        let ir = codegen.compile_program(&program).unwrap();
 
-                    // Remember this string for deduplication in future occurrences
-                    self.string_constants.insert(s.clone(), str_global.clone());
-                    str_global
-                };
+        // Verify that target triple is NOT present in the IR
+        // We intentionally omit it to let clang use its default and avoid warnings
+        assert!(
+            !ir.contains("target triple"),
+            "IR should not contain target triple declaration"
+        );
+    }
 
-                let str_len = s.len() + 1; // +1 for null terminator
+    #[test]
+    fn test_codegen_quotation() {
+        let mut codegen = CodeGen::new();
 
-                // Allocate temps in the order they'll be used in the IR
-                let ptr_temp = self.fresh_temp();
-                let result = self.fresh_temp();
-                let dbg = self.dbg_annotation(loc);
+        // : test ( -- Int ) [ 5 10 add ] call_quotation ;
+        let word = WordDef {
+            name: "test".to_string(),
+            effect: Effect {
+                inputs: StackType::Empty,
+                outputs: StackType::Empty.push(Type::Int),
+            },
+            body: vec![
+                Expr::Quotation(
+                    vec![
+                        Expr::IntLit(5, SourceLoc::unknown()),
+                        Expr::IntLit(10, SourceLoc::unknown()),
+                        Expr::WordCall("add".to_string(), SourceLoc::unknown()),
+                    ],
+                    SourceLoc::unknown(),
+                ),
+                Expr::WordCall("call_quotation".to_string(), SourceLoc::unknown()),
+            ],
+            loc: SourceLoc::unknown(),
+            feature: None,
+            inline: None,
+            slot_names: Default::default(),
+            doc: None,
+        };
 
-                writeln!(
-                    &mut self.output,
-                    "  %{} = getelementptr inbounds [{} x i8], ptr {}, i32 0, i32 0{}",
-                    ptr_temp, str_len, str_global, dbg
-                )
-                .map_err(|e| CodegenError::InternalError(e.to_string()))?;
-                writeln!(
-                    &mut self.output,
-                    "  %{} = call ptr @push_string(ptr %{}, ptr %{}){}",
-                    result, stack, ptr_temp, dbg
-                )
-                .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+        let program = Program {
+            type_defs: vec![],
+            word_defs: vec![word],
+        };
 
-                Ok(result)
-            }
+        let ir = codegen.compile_program(&program).unwrap();
 
-            Expr::WordCall(name, loc) => {
-                // Check if this is a variant constructor
-                if let Some(&tag) = self.variant_tags.get(name) {
-                    // This is a variant constructor - emit push_variant call
-                    let field_count = self.variant_field_counts.get(name).copied().unwrap_or(0);
-                    let dbg = self.dbg_annotation(loc);
+        // Verify quotation function is generated
+        assert!(
+            ir.contains("define internal ptr @quot_"),
+            "Should generate quotation function"
+        );
+        // Verify quotation is pushed
+        assert!(
+            ir.contains("call ptr @push_quotation"),
+            "Should push quotation"
+        );
+        // Verify quotation contains the body
+        assert!(
+            ir.contains("call ptr @push_int"),
+            "Quotation should push integers"
+        );
+        assert!(ir.contains("call ptr @add"), "Quotation should call add");
+        // Verify call_quotation is called
+        assert!(
+            ir.contains("call ptr @call_quotation"),
+            "Should call call_quotation"
+        );
+    }
 
-                    match field_count {
-                        0 => {
-                            // Unit variant (no fields) - pass NULL as data
-                            let result = self.fresh_temp();
-                            writeln!(
-                                &mut self.output,
-                                "  %{} = call ptr @push_variant(ptr %{}, i32 {}, ptr null){}",
-                                result, stack, tag, dbg
-                            )
-                            .map_err(|e| CodegenError::InternalError(e.to_string()))?;
-                            Ok(result)
-                        }
-                        1 => {
-                            // Single-field variant - we need to allocate a new cell for the field
-                            // and store that as the variant's data (the variant owns this cell)
+    #[test]
+    fn test_empty_program_as_library() {
+        let mut codegen = CodeGen::new();
 
-                            // Allocate a new cell to store the field value
-                            let field_cell = self.fresh_temp();
-                            writeln!(
-                                &mut self.output,
-                                "  %{} = call ptr @alloc_cell(){}",
-                                field_cell, dbg
-                            )
-                            .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+        let program = Program {
+            type_defs: vec![],
+            word_defs: vec![],
+        };
 
-                            // Copy the entire StackCell from top of stack to the new cell
-                            // StackCell is 32 bytes: { i32 tag, [4 x i8] padding, [16 x i8] union, ptr next }
-                            writeln!(
-                                &mut self.output,
-                                "  call void @llvm.memcpy.p0.p0.i64(ptr align 8 %{}, ptr align 8 %{}, i64 32, i1 false)",
-                                field_cell, stack
-                            )
-                            .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+        let ir = codegen.compile_program(&program).unwrap();
 
-                            // Clear the 'next' pointer in the copied cell (it's not part of a stack)
-                            let next_ptr = self.fresh_temp();
-                            writeln!(
-                                &mut self.output,
-                                "  %{} = getelementptr inbounds {{ i32, [4 x i8], [16 x i8], ptr }}, ptr %{}, i32 0, i32 3",
-                                next_ptr, field_cell
-                            )
-                            .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+        // No functions are defined and no main() is generated
+        assert!(
+            !ir.contains("define "),
+            "Empty program should define no functions"
+        );
+        assert!(
+            !ir.contains("@main"),
+            "Library build should not emit main()"
+        );
 
-                            writeln!(&mut self.output, "  store ptr null, ptr %{}", next_ptr)
-                                .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+        // Debug info is still well-formed (placeholder file for the compile unit)
+        assert!(ir.contains("!DICompileUnit"));
+        assert!(ir.contains("!DIFile(filename: \"<empty>\""));
+    }
 
-                            // Get rest of stack (pop the field)
-                            let rest_ptr = self.fresh_temp();
-                            writeln!(
-                                &mut self.output,
-                                "  %{} = getelementptr inbounds {{ i32, [4 x i8], [16 x i8], ptr }}, ptr %{}, i32 0, i32 3",
-                                rest_ptr, stack
-                            )
-                            .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+    #[test]
+    fn test_empty_program_with_entry_is_error() {
+        let mut codegen = CodeGen::new();
 
-                            let rest = self.fresh_temp();
-                            writeln!(
-                                &mut self.output,
-                                "  %{} = load ptr, ptr %{}",
-                                rest, rest_ptr
-                            )
-                            .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+        let program = Program {
+            type_defs: vec![],
+            word_defs: vec![],
+        };
 
-                            // Push variant with the allocated cell as data
-                            let result = self.fresh_temp();
-                            writeln!(
-                                &mut self.output,
-                                "  %{} = call ptr @push_variant(ptr %{}, i32 {}, ptr %{}){}",
-                                result, rest, tag, field_cell, dbg
-                            )
-                            .map_err(|e| CodegenError::InternalError(e.to_string()))?;
-                            Ok(result)
-                        }
-                        _ => {
-                            // Multi-field variants not yet supported
-                            Err(CodegenError::InternalError(format!(
-                                "Variant constructor {} with {} fields not yet supported (only 0 or 1 fields)",
-                                name, field_count
-                            )))
-                        }
-                    }
-                } else {
-                    // Regular word call
-                    let result = self.fresh_temp();
-                    let dbg = self.dbg_annotation(loc);
-                    let func_name = Self::map_operator_to_function(name);
-                    writeln!(
-                        &mut self.output,
-                        "  %{} = call ptr @{}(ptr %{}){}",
-                        result, func_name, stack, dbg
-                    )
-                    .map_err(|e| CodegenError::InternalError(e.to_string()))?;
-                    Ok(result)
-                }
+        let result = codegen.compile_program_with_main(&program, Some("main"));
+        assert_eq!(
+            result.unwrap_err(),
+            CodegenError::MissingEntryWord {
+                name: "main".to_string()
             }
+        );
+    }
 
-            Expr::Quotation(exprs, _loc) => {
-                // Generate an anonymous function for the quotation
-                let quot_name = format!("quot_{}", self.temp_counter);
-                let saved_counter = self.temp_counter;
-                self.temp_counter += 1;
+    #[test]
+    fn test_entry_word_symbol_is_mangled_like_its_definition() {
+        use crate::parser::Parser;
+
+        let program = Parser::new(": run-app ( -- Int ) 1 ;").parse().unwrap();
+        let ir = CodeGen::new()
+            .compile_program_with_main(&program, Some("run-app"))
+            .unwrap();
+
+        assert!(ir.contains("define ptr @run_app(ptr %stack)"));
+        assert!(ir.contains("call i64 @strand_spawn(ptr @run_app, ptr null)"));
+        assert!(!ir.contains("@run-app"));
+
+        let ir = CodeGen::with_target(Target::Wasm32Wasi)
+            .compile_program_with_main(&program, Some("run-app"))
+            .unwrap();
+        assert!(ir.contains("%stack = call ptr @run_app(ptr null)"));
+    }
 
-                // Save current output
-                let saved_output = self.output.clone();
-                self.output.clear();
+    #[test]
+    fn test_options_without_debug_info_emit_no_metadata() {
+        use crate::parser::Parser;
+
+        let program = Parser::new(": square ( Int -- Int ) dup * ;")
+            .parse()
+            .unwrap();
+
+        let ir = CodeGen::new().compile_program(&program).unwrap();
+        assert!(ir.contains("define ptr @square(ptr %stack) !dbg !"));
+        assert!(ir.contains("!DICompileUnit"));
+
+        let ir = CodeGen::with_options(CodeGenOptions {
+            debug_info: false,
+            ..CodeGenOptions::default()
+        })
+        .compile_program(&program)
+        .unwrap();
+        assert!(ir.contains("define ptr @square(ptr %stack) {"));
+        assert!(!ir.contains("!dbg"), "unexpected debug metadata:\n{}", ir);
+        assert!(!ir.contains("!DICompileUnit") && !ir.contains("!llvm.dbg.cu"));
+    }
 
-                // Generate the quotation function
-                writeln!(&mut self.output, "define ptr @{}(ptr %stack) {{", quot_name)
-                    .map_err(|e| CodegenError::InternalError(e.to_string()))?;
-                writeln!(&mut self.output, "entry:")
-                    .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+    #[test]
+    fn test_entry_stack_is_pushed_before_the_entry_word_runs() {
+        use crate::parser::Parser;
+
+        let program = Parser::new(": greet ( Int Bool String -- ) drop drop drop ;")
+            .parse()
+            .unwrap();
+
+        let ir = CodeGen::new()
+            .compile_program_with_main(&program, Some("greet"))
+            .unwrap();
+        assert!(ir.contains("call i64 @strand_spawn(ptr @greet, ptr null)"));
+
+        let ir = CodeGen::with_options(CodeGenOptions {
+            entry_stack: vec![
+                StackSeed::Int(5),
+                StackSeed::Bool(true),
+                StackSeed::String("hi".to_string()),
+            ],
+            ..CodeGenOptions::default()
+        })
+        .compile_program_with_main(&program, Some("greet"))
+        .unwrap();
+        let main = &ir[ir.find("define i32 @main()").unwrap()..];
+        assert!(main.contains(
+            "entry:\n  %seed.0 = call ptr @push_int(ptr null, i64 5)\n  \
+             %seed.1 = call ptr @push_bool(ptr %seed.0, i1 zeroext 1)\n  \
+             %seed.2 = call ptr @push_string(ptr %seed.1, ptr @.str.0)\n"
+        ));
+        assert!(main.contains("call i64 @strand_spawn(ptr @greet, ptr %seed.2)"));
+        assert!(ir.contains("@.str.0 = "));
+    }
 
-                // Compile the quotation body
-                let mut stack_var = "stack".to_string();
-                let len = exprs.len();
-                for (i, expr) in exprs.iter().enumerate() {
-                    let is_tail = i == len - 1;
-                    stack_var = self.compile_expr_with_context(expr, &stack_var, is_tail)?;
+    #[test]
+    fn test_strict_stack_checks_the_final_depth() {
+        use crate::parser::Parser;
+
+        let program = Parser::new(": double ( Int -- Int Int ) dup ;")
+            .parse()
+            .unwrap();
+
+        let ir = CodeGen::new()
+            .compile_program_with_main(&program, Some("double"))
+            .unwrap();
+        assert!(!ir.contains("call void @check_stack_depth"));
+
+        // One seeded value, consumed, and two left in its place
+        let ir = CodeGen::with_options(CodeGenOptions {
+            strict_stack: true,
+            entry_stack: vec![StackSeed::Int(5)],
+            ..CodeGenOptions::default()
+        })
+        .compile_program_with_main(&program, Some("double"))
+        .unwrap();
+        assert!(ir.contains(
+            "  call void @check_stack_depth(ptr %stack, i64 2)\n  call void @free_stack(ptr %stack)"
+        ));
+    }
 
-                    // If last expression is a musttail call, return its result
-                    if is_tail && let Expr::WordCall(_, _) = expr {
-                        writeln!(&mut self.output, "  ret ptr %{}", stack_var)
-                            .map_err(|e| CodegenError::InternalError(e.to_string()))?;
-                    }
-                }
+    #[test]
+    fn test_inline_shuffles_relink_cells_instead_of_calling_swap() {
+        use crate::parser::Parser;
+
+        let program = Parser::new(": f ( Int Int -- Int Int ) swap 1 + ;")
+            .parse()
+            .unwrap();
+
+        let ir = CodeGen::new().compile_program(&program).unwrap();
+        assert!(ir.contains("call ptr @swap(ptr %stack)"));
+
+        let ir = CodeGen::with_options(CodeGenOptions {
+            inline_shuffles: true,
+            debug_info: false,
+            ..CodeGenOptions::default()
+        })
+        .compile_program(&program)
+        .unwrap();
+        assert!(
+            !ir.contains("call ptr @swap"),
+            "swap was not inlined:\n{}",
+            ir
+        );
+        assert!(ir.contains("  %0 = getelementptr inbounds i8, ptr %stack, i64 24\n"));
+        assert!(ir.contains("  %1 = load ptr, ptr %0\n"));
+        assert!(ir.contains("  %3 = load ptr, ptr %2\n"));
+        // The second cell now points at the top one, and the top one at the rest
+        assert!(ir.contains("  store ptr %stack, ptr %2\n  store ptr %3, ptr %0\n"));
+        assert!(ir.contains("call ptr @push_int(ptr %1, i64 1)"));
+    }
 
-                // If we didn't return via musttail, return normally
-                if len == 0 || !matches!(exprs.last(), Some(Expr::WordCall(_, _))) {
-                    writeln!(&mut self.output, "  ret ptr %{}", stack_var)
-                        .map_err(|e| CodegenError::InternalError(e.to_string()))?;
-                }
+    #[test]
+    fn test_module_header_names_version_and_source_without_a_timestamp() {
+        use crate::parser::Parser;
+
+        let compile = || {
+            let program = Parser::new_with_filename(": main ( -- Int ) 42 ;", "answer.cem")
+                .parse()
+                .unwrap();
+            CodeGen::new().compile_program(&program).unwrap()
+        };
+        let ir = compile();
 
-                writeln!(&mut self.output, "}}")
-                    .map_err(|e| CodegenError::InternalError(e.to_string()))?;
-                writeln!(&mut self.output)
-                    .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+        let header: Vec<&str> = ir.lines().take_while(|line| !line.is_empty()).collect();
+        assert_eq!(header[0], "; Cem Compiler - Generated LLVM IR");
+        assert_eq!(
+            header[1],
+            format!("; Compiler version: {}", env!("CARGO_PKG_VERSION"))
+        );
+        assert_eq!(header[2], "; Source: answer.cem");
+        assert!(header[3].starts_with("; Build ID: "));
+        assert_eq!(header.len(), 4);
 
-                // Prepend the quotation function to saved output
-                let quot_func = self.output.clone();
-                self.output = saved_output + &quot_func;
+        // No wall-clock time anywhere: compiling again reproduces the module exactly
+        assert_eq!(ir, compile());
+    }
 
-                // Restore temp counter for current function
-                self.temp_counter = saved_counter + 1;
+    #[test]
+    fn test_dense_int_match_emits_a_switch() {
+        use crate::parser::Parser;
 
-                // Now push the function pointer onto the stack
-                let result = self.fresh_temp();
-                writeln!(
-                    &mut self.output,
-                    "  %{} = call ptr @push_quotation(ptr %{}, ptr @{})",
-                    result, stack, quot_name
-                )
-                .map_err(|e| CodegenError::InternalError(e.to_string()))?;
-                Ok(result)
-            }
+        let compile = |source: &str| {
+            let program = Parser::new(source).parse().unwrap();
+            CodeGen::new().compile_program(&program).unwrap()
+        };
 
-            Expr::Match { branches, loc: _ } => {
-                // Pattern matching on variants
-                //
-                // Ownership semantics:
-                // - The variant cell is consumed (popped from stack)
-                // - For unit variants (None): rest of stack becomes initial stack for branch
-                // - For single-field variants (Some(T)): field data is unwrapped onto stack
-                //   by linking the data cell to rest of stack
-                //
-                // Strategy: extract variant tag, switch on tag, each case executes branch body
+        let ir = compile(": f ( Int -- Int ) match 1 => 10 2 => 20 3 => 30 _ => 0 end ;");
+        assert!(ir.contains("call i64 @cell_int(ptr %stack)"));
+        assert!(ir.contains("call void @free_cell(ptr %stack)"));
+        assert!(ir.contains("switch i64 %0, label %match_case_0_3 ["));
+        assert!(ir.contains("    i64 2, label %match_case_0_1\n"));
+        assert!(!ir.contains("icmp eq i64"));
+        assert!(
+            !ir.contains("@.str.match_error"),
+            "the wildcard is the default"
+        );
 
-                if branches.is_empty() {
-                    return Err(CodegenError::InternalError(
-                        "Empty match expression".to_string(),
-                    ));
-                }
+        // Sparse cases compare one at a time instead
+        let ir = compile(": f ( Int -- Int ) match 1 => 10 1000 => 20 _ => 0 end ;");
+        assert!(!ir.contains("switch i64"));
+        assert!(ir.contains("icmp eq i64 %0, 1000"));
+    }
 
-                // Generate labels for each branch and merge point
-                let match_id = self.temp_counter;
-                let merge_label = format!("match_merge_{}", match_id);
-                let default_label = format!("match_default_{}", match_id);
+    #[test]
+    fn test_stack_balance_reports_every_unbalanced_word() {
+        use crate::parser::Parser;
+
+        let program = Parser::new(
+            ": extra ( Int -- Int ) dup 1 ; : ok ( Int -- Int ) 1 + ; : short ( Int Int -- Int ) drop drop ;",
+        )
+        .parse()
+        .unwrap();
+
+        let unbalanced = CodeGen::new().check_stack_balance(&program).unwrap();
+        let names: Vec<_> = unbalanced.iter().map(|(w, _)| w.name.as_str()).collect();
+        assert_eq!(names, vec!["extra", "short"]);
+        assert_eq!(
+            unbalanced[0].1,
+            CodegenError::OutputArityMismatch {
+                word: "extra".to_string(),
+                expected: 1,
+                actual: 3,
+            }
+        );
+    }
 
-                // Extract variant tag from stack top
-                // StackCell layout: { i32 tag, [4 x i8] padding, [16 x i8] union, ptr next }
-                // Variant is stored in union as: { i32 variant_tag, ptr variant_data }
-                // So variant_tag is at union offset 0 (field 2, index 0-3)
+    #[test]
+    fn test_dup_drop_of_a_literal_is_a_single_push() {
+        use crate::parser::Parser;
+
+        let program = Parser::new(": five ( -- Int ) 5 dup drop ;")
+            .parse()
+            .unwrap();
+        let ir = CodeGen::new().compile_program(&program).unwrap();
+        let body = ir
+            .split("define ptr @five(")
+            .nth(1)
+            .and_then(|rest| rest.split("\n}").next())
+            .unwrap();
+
+        assert_eq!(body.matches("call ptr @push_int").count(), 1, "{}", body);
+        assert!(
+            !body.contains("@stack_dup") && !body.contains("@drop"),
+            "{}",
+            body
+        );
+    }
 
-                // Get pointer to variant tag within the union
-                let variant_tag_ptr = self.fresh_temp();
-                writeln!(
-                    &mut self.output,
-                    "  %{} = getelementptr inbounds {{ i32, [4 x i8], [16 x i8], ptr }}, ptr %{}, i32 0, i32 2, i32 0",
-                    variant_tag_ptr, stack
-                )
-                .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+    #[test]
+    fn test_wasm_target_calls_entry_directly() {
+        let mut codegen = CodeGen::with_target(Target::Wasm32Wasi);
 
-                // Load variant tag as i32 (first 4 bytes of union)
-                let variant_tag = self.fresh_temp();
-                writeln!(
-                    &mut self.output,
-                    "  %{} = load i32, ptr %{}",
-                    variant_tag, variant_tag_ptr
-                )
-                .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+        let program = Program {
+            type_defs: vec![],
+            word_defs: vec![WordDef {
+                name: "main".to_string(),
+                effect: Effect {
+                    inputs: StackType::Empty,
+                    outputs: StackType::Empty.push(Type::Int),
+                },
+                body: vec![Expr::IntLit(42, SourceLoc::unknown())],
+                loc: SourceLoc::unknown(),
+                feature: None,
+                inline: None,
+                slot_names: Default::default(),
+                doc: None,
+            }],
+        };
 
-                // Get rest of stack (next pointer at field index 3)
-                let rest_ptr = self.fresh_temp();
-                writeln!(
-                    &mut self.output,
-                    "  %{} = getelementptr inbounds {{ i32, [4 x i8], [16 x i8], ptr }}, ptr %{}, i32 0, i32 3",
-                    rest_ptr, stack
-                )
-                .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+        let ir = codegen
+            .compile_program_with_main(&program, Some("main"))
+            .unwrap();
 
-                let rest_var = self.fresh_temp();
-                writeln!(
-                    &mut self.output,
-                    "  %{} = load ptr, ptr %{}",
-                    rest_var, rest_ptr
-                )
-                .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+        // wasi's crt calls an exported main(), which runs the entry word without the scheduler
+        assert!(ir.contains("define i32 @main()"));
+        assert!(ir.contains("%stack = call ptr @cem_main(ptr null)"));
+        assert!(!ir.contains("call void @scheduler_init()"));
+        assert!(!ir.contains("@strand_spawn(ptr @cem_main"));
+    }
 
-                // Extract variant data pointer (for single-field variants)
-                // Variant data is at union offset 8 (after the 4-byte tag + 4-byte padding)
-                // We need this to unwrap the variant in branches
-                let variant_data_ptr = self.fresh_temp();
-                writeln!(
-                    &mut self.output,
-                    "  %{} = getelementptr inbounds {{ i32, [4 x i8], [16 x i8], ptr }}, ptr %{}, i32 0, i32 2, i32 8",
-                    variant_data_ptr, stack
-                )
-                .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+    #[test]
+    fn test_inline_attribute_marks_the_definition() {
+        use crate::parser::Parser;
 
-                let variant_data = self.fresh_temp();
-                writeln!(
-                    &mut self.output,
-                    "  %{} = load ptr, ptr %{}",
-                    variant_data, variant_data_ptr
-                )
-                .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+        let program = Parser::new(
+            "inline : fast ( Int -- Int ) 1 + ;\n\
+             noinline : slow ( Int -- Int ) 2 * ;\n\
+             : plain ( Int -- Int ) fast slow ;",
+        )
+        .parse()
+        .unwrap();
+        let ir = CodeGen::with_options(CodeGenOptions {
+            debug_info: false,
+            ..CodeGenOptions::default()
+        })
+        .compile_program(&program)
+        .unwrap();
+
+        assert!(ir.contains("define ptr @fast(ptr %stack) alwaysinline {"));
+        assert!(ir.contains("define ptr @slow(ptr %stack) noinline {"));
+        assert!(ir.contains("define ptr @plain(ptr %stack) {"));
+    }
 
-                // Generate switch statement
-                write!(
-                    &mut self.output,
-                    "  switch i32 %{}, label %{} [",
-                    variant_tag, default_label
-                )
-                .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+    #[test]
+    fn test_gated_off_word_absent_from_ir() {
+        let word = |name: &str, feature: Option<&str>| WordDef {
+            name: name.to_string(),
+            effect: Effect {
+                inputs: StackType::Empty,
+                outputs: StackType::Empty.push(Type::Int),
+            },
+            body: vec![Expr::IntLit(1, SourceLoc::unknown())],
+            loc: SourceLoc::unknown(),
+            feature: feature.map(String::from),
+            inline: None,
+            slot_names: Default::default(),
+            doc: None,
+        };
 
-                // Add switch cases for each branch
-                for (idx, branch) in branches.iter().enumerate() {
-                    let Pattern::Variant { name } = &branch.pattern;
-                    // Look up variant tag from type environment
-                    let tag_value = self.variant_tags.get(name).copied().ok_or_else(|| {
-                        CodegenError::InternalError(format!("Unknown variant: {}", name))
-                    })?;
-                    let case_label = format!("match_case_{}_{}", match_id, idx);
-                    writeln!(
-                        &mut self.output,
-                        "\n    i32 {}, label %{}",
-                        tag_value, case_label
-                    )
-                    .map_err(|e| CodegenError::InternalError(e.to_string()))?;
-                }
-                writeln!(&mut self.output, "  ]")
-                    .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+        let program = Program {
+            type_defs: vec![],
+            word_defs: vec![word("on_linux", Some("linux")), word("always", None)],
+        };
 
-                // Generate code for each branch
-                let mut branch_results = Vec::new();
-                let mut branch_predecessors = Vec::new();
-                let mut all_branches_musttail = true;
+        let ir = CodeGen::new().compile_program(&program).unwrap();
+        assert!(
+            !ir.contains("@on_linux"),
+            "Gated-off word should be skipped"
+        );
+        assert!(ir.contains("define ptr @always"));
 
-                for (idx, branch) in branches.iter().enumerate() {
-                    let case_label = format!("match_case_{}_{}", match_id, idx);
+        let mut codegen = CodeGen::new();
+        codegen.set_features(vec!["linux".to_string()]);
+        let ir = codegen.compile_program(&program).unwrap();
+        assert!(
+            ir.contains("define ptr @on_linux"),
+            "Active feature keeps word"
+        );
+    }
 
-                    writeln!(&mut self.output, "{}:", case_label)
-                        .map_err(|e| CodegenError::InternalError(e.to_string()))?;
-                    self.current_block = case_label.clone();
+    #[test]
+    fn test_row_polymorphic_callee_is_not_counted() {
+        // apply's depth change depends on the quotation it calls
+        let program = crate::parser::Parser::new(
+            ": apply ( ..A [..A -- ..B] -- ..B ) call_quotation ; : one ( -- Int ) [ 1 ] apply ;",
+        )
+        .parse()
+        .unwrap();
 
-                    // Determine the initial stack for this branch
-                    // For variants with data, we need to "unwrap" by linking data cell to rest
-                    let Pattern::Variant { name } = &branch.pattern;
-                    let field_count = self.variant_field_counts.get(name).copied().unwrap_or(0);
+        let mut codegen = CodeGen::new();
+        codegen.set_output_arity_check(true);
+        assert!(codegen.compile_program(&program).is_ok());
+    }
 
-                    let initial_stack = if field_count == 0 {
-                        // Unit variant (e.g., None) - no data, just use rest
-                        rest_var.clone()
-                    } else if field_count == 1 {
-                        // Single-field variant (e.g., Some(T)) - link data cell to rest
-                        // We need to set data->next = rest
-                        let data_next_ptr = self.fresh_temp();
-                        writeln!(
-                            &mut self.output,
-                            "  %{} = getelementptr inbounds {{ i32, [4 x i8], [16 x i8], ptr }}, ptr %{}, i32 0, i32 3",
-                            data_next_ptr, variant_data
-                        )
-                        .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+    #[test]
+    fn test_output_arity_mismatch_is_caught() {
+        // pair: ( -- Int Int ) but the body only pushes one value
+        let program = Program {
+            type_defs: vec![],
+            word_defs: vec![WordDef {
+                name: "pair".to_string(),
+                effect: Effect {
+                    inputs: StackType::Empty,
+                    outputs: StackType::Empty.push(Type::Int).push(Type::Int),
+                },
+                body: vec![Expr::IntLit(1, SourceLoc::unknown())],
+                loc: SourceLoc::unknown(),
+                feature: None,
+                inline: None,
+                slot_names: Default::default(),
+                doc: None,
+            }],
+        };
 
-                        writeln!(
-                            &mut self.output,
-                            "  store ptr %{}, ptr %{}",
-                            rest_var, data_next_ptr
-                        )
-                        .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+        // Off by default
+        assert!(CodeGen::new().compile_program(&program).is_ok());
 
-                        variant_data.clone()
-                    } else {
-                        return Err(CodegenError::InternalError(format!(
-                            "Pattern matching on variant {} with {} fields not yet supported",
-                            name, field_count
-                        )));
-                    };
+        let mut codegen = CodeGen::new();
+        codegen.set_output_arity_check(true);
+        let err = codegen.compile_program(&program).unwrap_err();
+        assert_eq!(
+            err,
+            CodegenError::OutputArityMismatch {
+                word: "pair".to_string(),
+                expected: 2,
+                actual: 1,
+            }
+        );
+    }
 
-                    let (branch_stack, ends_with_musttail) =
-                        self.compile_expr_sequence(&branch.body, &initial_stack)?;
+    #[test]
+    fn test_output_arity_matches_through_calls() {
+        // square: ( Int -- Int ) dup *
+        let program = Program {
+            type_defs: vec![],
+            word_defs: vec![WordDef {
+                name: "square".to_string(),
+                effect: Effect {
+                    inputs: StackType::Empty.push(Type::Int),
+                    outputs: StackType::Empty.push(Type::Int),
+                },
+                body: vec![
+                    Expr::WordCall("dup".to_string(), SourceLoc::unknown()),
+                    Expr::WordCall("*".to_string(), SourceLoc::unknown()),
+                ],
+                loc: SourceLoc::unknown(),
+                feature: None,
+                inline: None,
+                slot_names: Default::default(),
+                doc: None,
+            }],
+        };
 
-                    let predecessor = self.current_block.clone();
+        let mut codegen = CodeGen::new();
+        codegen.set_output_arity_check(true);
+        assert!(codegen.compile_program(&program).is_ok());
+    }
 
-                    // Check if this branch terminates (either via musttail or nested match/if)
-                    let Pattern::Variant { name } = &branch.pattern;
-                    let branch_last_expr = branch.body.last();
-                    let branch_terminates = ends_with_musttail
-                        || branch_last_expr.map_or(false, |e| self.check_all_paths_returned(e));
+    /// `type Option (T) | Some(T) | None`
+    fn option_typedef() -> TypeDef {
+        TypeDef {
+            name: "Option".to_string(),
+            type_params: vec!["T".to_string()],
+            variants: vec![
+                Variant {
+                    name: "Some".to_string(),
+                    fields: vec![Type::Var("T".to_string())],
+                },
+                Variant {
+                    name: "None".to_string(),
+                    fields: vec![],
+                },
+            ],
+            doc: None,
+        }
+    }
 
-                    if branch_terminates {
-                        // Branch terminates - emit ret if needed
-                        if ends_with_musttail {
-                            writeln!(&mut self.output, "  ret ptr %{}", branch_stack)
-                                .map_err(|e| CodegenError::InternalError(e.to_string()))?;
-                        }
-                        // If all paths already returned, no ret needed
-                    } else {
-                        // Branch doesn't terminate - needs merge point
-                        all_branches_musttail = false;
-                        writeln!(&mut self.output, "  br label %{}", merge_label)
-                            .map_err(|e| CodegenError::InternalError(e.to_string()))?;
-                        branch_results.push(branch_stack);
-                        branch_predecessors.push(predecessor);
-                    }
-                }
+    #[test]
+    fn test_variant_constructor_lowers_to_make_variant() {
+        // wrap: ( Int -- Option(Int) ) Some
+        let program = Program {
+            type_defs: vec![option_typedef()],
+            word_defs: vec![WordDef {
+                name: "wrap".to_string(),
+                effect: Effect {
+                    inputs: StackType::Empty.push(Type::Int),
+                    outputs: StackType::Empty.push(Type::Named {
+                        name: "Option".to_string(),
+                        args: vec![Type::Int],
+                    }),
+                },
+                body: vec![Expr::WordCall("Some".to_string(), SourceLoc::unknown())],
+                loc: SourceLoc::unknown(),
+                feature: None,
+                inline: None,
+                slot_names: Default::default(),
+                doc: None,
+            }],
+        };
 
-                // Default case (should never be reached if match is exhaustive)
-                writeln!(&mut self.output, "{}:", default_label)
-                    .map_err(|e| CodegenError::InternalError(e.to_string()))?;
-                writeln!(
-                    &mut self.output,
-                    "  call void @runtime_error(ptr @.str.match_error)"
-                )
-                .map_err(|e| CodegenError::InternalError(e.to_string()))?;
-                writeln!(&mut self.output, "  unreachable")
-                    .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+        let ir = CodeGen::new().compile_program(&program).unwrap();
+        assert!(ir.contains("declare ptr @make_variant(ptr, i32, i32)"));
+        let tag = variant_tag_for("Option", "Some");
+        assert!(
+            ir.contains(&format!(
+                "call ptr @make_variant(ptr %stack, i32 {}, i32 1)",
+                tag
+            )),
+            "Some should construct its tag with one field:\n{}",
+            ir
+        );
+    }
 
-                // Add error string to string globals if not already present
-                if !self.string_constants.contains_key("match_error") {
-                    let error_msg = "match: non-exhaustive pattern (internal error)";
-                    let escaped = Self::escape_llvm_string(error_msg);
-                    let str_len = error_msg.len() + 1;
-                    let global_decl = format!(
-                        "@.str.match_error = private unnamed_addr constant [{} x i8] c\"{}\\00\"\n",
-                        str_len, escaped
-                    );
-                    self.string_globals.push_str(&global_decl);
-                    // Mark as added to prevent duplicates
-                    self.string_constants
-                        .insert("match_error".to_string(), "@.str.match_error".to_string());
-                }
+    #[test]
+    fn test_match_reads_tag_through_runtime() {
+        // unwrap_or_zero: ( -- Int ) 7 Some match Some => [ ] None => [ 0 ] end
+        let branch = |name: &str, body: Vec<Expr>| MatchBranch {
+            pattern: Pattern::Variant {
+                name: name.to_string(),
+                bindings: Vec::new(),
+            },
+            body,
+        };
+        let program = Program {
+            type_defs: vec![option_typedef()],
+            word_defs: vec![WordDef {
+                name: "unwrap_or_zero".to_string(),
+                effect: Effect {
+                    inputs: StackType::Empty,
+                    outputs: StackType::Empty.push(Type::Int),
+                },
+                body: vec![
+                    Expr::IntLit(7, SourceLoc::unknown()),
+                    Expr::WordCall("Some".to_string(), SourceLoc::unknown()),
+                    Expr::Match {
+                        branches: vec![
+                            branch("Some", vec![]),
+                            branch("None", vec![Expr::IntLit(0, SourceLoc::unknown())]),
+                        ],
+                        loc: SourceLoc::unknown(),
+                    },
+                ],
+                loc: SourceLoc::unknown(),
+                feature: None,
+                inline: None,
+                slot_names: Default::default(),
+                doc: None,
+            }],
+        };
 
-                // Merge point
-                if !all_branches_musttail {
-                    writeln!(&mut self.output, "{}:", merge_label)
-                        .map_err(|e| CodegenError::InternalError(e.to_string()))?;
-                    self.current_block = merge_label;
+        let ir = CodeGen::new().compile_program(&program).unwrap();
+        assert!(ir.contains("declare i32 @variant_tag(ptr)"));
+        assert!(ir.contains("call ptr @make_variant("));
+        assert!(ir.contains("call i32 @variant_tag(ptr %"));
+        assert!(
+            !ir.contains("i32 0, i32 2, i32 0"),
+            "Tag should not be read with a raw GEP into the cell union"
+        );
+    }
 
-                    // Build phi node from branches that didn't return
-                    let result = self.fresh_temp();
-                    write!(&mut self.output, "  %{} = phi ptr", result)
-                        .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+    #[test]
+    fn test_if_uses_cell_accessors() {
+        // choose: ( Bool -- Int ) if [ 1 ] [ 2 ]
+        let program = Program {
+            type_defs: vec![],
+            word_defs: vec![WordDef {
+                name: "choose".to_string(),
+                effect: Effect {
+                    inputs: StackType::Empty.push(Type::Bool),
+                    outputs: StackType::Empty.push(Type::Int),
+                },
+                body: vec![Expr::If {
+                    condition: None,
+                    then_branch: Box::new(Expr::Quotation(
+                        vec![Expr::IntLit(1, SourceLoc::unknown())],
+                        SourceLoc::unknown(),
+                    )),
+                    else_branch: Box::new(Expr::Quotation(
+                        vec![Expr::IntLit(2, SourceLoc::unknown())],
+                        SourceLoc::unknown(),
+                    )),
+                    loc: SourceLoc::unknown(),
+                }],
+                loc: SourceLoc::unknown(),
+                feature: None,
+                inline: None,
+                slot_names: Default::default(),
+                doc: None,
+            }],
+        };
 
-                    for (stack_val, pred) in branch_results.iter().zip(branch_predecessors.iter()) {
-                        write!(&mut self.output, " [ %{}, %{} ],", stack_val, pred)
-                            .map_err(|e| CodegenError::InternalError(e.to_string()))?;
-                    }
-                    // Remove trailing comma
-                    self.output.pop();
-                    writeln!(&mut self.output)
-                        .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+        let ir = CodeGen::new().compile_program(&program).unwrap();
+        assert!(ir.contains("call zeroext i1 @cell_bool(ptr %stack)"));
+        assert!(ir.contains("call ptr @cell_rest(ptr %stack)"));
+        // The condition's cell is freed once it has been read
+        assert!(ir.contains("call void @free_cell(ptr %stack)"));
+        assert!(
+            !ir.contains("getelementptr"),
+            "If should not index into the StackCell struct:\n{}",
+            ir
+        );
+    }
 
-                    Ok(result)
-                } else {
-                    // All branches ended with musttail and return - no merge point needed
-                    // This is actually unreachable code after the match, so return a dummy value
-                    Ok(rest_var) // Won't be used since all branches returned
-                }
-            }
+    #[test]
+    fn test_bool_round_trips_as_zero_extended_i1() {
+        // : main ( -- Int ) true if [ 1 ] [ 2 ] ;
+        let program = Program {
+            type_defs: vec![],
+            word_defs: vec![WordDef {
+                name: "main".to_string(),
+                effect: Effect {
+                    inputs: StackType::Empty,
+                    outputs: StackType::Empty.push(Type::Int),
+                },
+                body: vec![
+                    Expr::BoolLit(true, SourceLoc::unknown()),
+                    Expr::If {
+                        condition: None,
+                        then_branch: Box::new(Expr::Quotation(
+                            vec![Expr::IntLit(1, SourceLoc::unknown())],
+                            SourceLoc::unknown(),
+                        )),
+                        else_branch: Box::new(Expr::Quotation(
+                            vec![Expr::IntLit(2, SourceLoc::unknown())],
+                            SourceLoc::unknown(),
+                        )),
+                        loc: SourceLoc::unknown(),
+                    },
+                ],
+                loc: SourceLoc::unknown(),
+                feature: None,
+                inline: None,
+                slot_names: Default::default(),
+                doc: None,
+            }],
+        };
 
-            Expr::If {
-                then_branch,
-                else_branch,
-                loc: _,
-            } => {
-                // Stack top must be a Bool
-                // Strategy: extract bool, branch to then/else, both produce same stack effect
+        let ir = CodeGen::new().compile_program(&program).unwrap();
+        assert!(ir.contains("declare ptr @push_bool(ptr, i1 zeroext)"));
+        assert!(ir.contains("declare zeroext i1 @cell_bool(ptr)"));
+        assert!(ir.contains("call ptr @push_bool(ptr %stack, i1 zeroext 1)"));
+
+        // The loaded condition feeds the branch directly: no i8 loads or casts
+        let cond = ir
+            .lines()
+            .find(|l| l.contains("call zeroext i1 @cell_bool"))
+            .expect("If should read its condition with cell_bool");
+        let cond_var = cond.trim_start().split(' ').next().unwrap();
+        assert!(ir.contains(&format!("br i1 {},", cond_var)));
+        for cast in ["load i8", "trunc i8", "zext i1"] {
+            assert!(!ir.contains(cast), "Unexpected `{}` in:\n{}", cast, ir);
+        }
+    }
 
-                // Generate unique labels
-                let then_label = format!("then_{}", self.temp_counter);
-                let else_label = format!("else_{}", self.temp_counter);
-                let merge_label = format!("merge_{}", self.temp_counter);
-                self.temp_counter += 1;
+    #[test]
+    fn test_if_branch_carries_debug_location() {
+        // pick: ( Bool -- Int ) if [ 1 ] [ 2 ]   with the `if` at line 3, column 5
+        let if_loc = SourceLoc::new(3, 5, "pick.cem");
+        let program = Program {
+            type_defs: vec![],
+            word_defs: vec![WordDef {
+                name: "pick".to_string(),
+                effect: Effect {
+                    inputs: StackType::Empty.push(Type::Bool),
+                    outputs: StackType::Empty.push(Type::Int),
+                },
+                body: vec![Expr::If {
+                    condition: None,
+                    then_branch: Box::new(Expr::Quotation(
+                        vec![Expr::IntLit(1, SourceLoc::new(3, 10, "pick.cem"))],
+                        SourceLoc::new(3, 8, "pick.cem"),
+                    )),
+                    else_branch: Box::new(Expr::Quotation(
+                        vec![Expr::IntLit(2, SourceLoc::new(3, 16, "pick.cem"))],
+                        SourceLoc::new(3, 14, "pick.cem"),
+                    )),
+                    loc: if_loc,
+                }],
+                loc: SourceLoc::new(2, 1, "pick.cem"),
+                feature: None,
+                inline: None,
+                slot_names: Default::default(),
+                doc: None,
+            }],
+        };
 
-                // Extract boolean value from stack top
-                // StackCell C layout (from runtime/stack.h):
-                //   - tag: i32 at offset 0 (4 bytes)
-                //   - padding: 4 bytes (for union alignment)
-                //   - value union at offset 8 (16 bytes total - largest member is variant struct)
-                //   - next: ptr at offset 24 (8 bytes)
-                // LLVM struct: { i32, [4 x i8], [16 x i8], ptr } = 32 bytes
-
-                // Get bool value from union at offset 8 (field index 2)
-                // Bool is stored as i8 in the first byte of the 16-byte union
-                let bool_ptr = self.fresh_temp();
-                writeln!(&mut self.output, "  %{} = getelementptr inbounds {{ i32, [4 x i8], [16 x i8], ptr }}, ptr %{}, i32 0, i32 2, i32 0", bool_ptr, stack)
-                    .map_err(|e| CodegenError::InternalError(e.to_string()))?;
-                let bool_val = self.fresh_temp();
-                writeln!(
-                    &mut self.output,
-                    "  %{} = load i8, ptr %{}",
-                    bool_val, bool_ptr
-                )
-                .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+        let ir = CodeGen::new().compile_program(&program).unwrap();
+        let br = ir
+            .lines()
+            .find(|l| l.trim_start().starts_with("br i1"))
+            .expect("If should emit a conditional branch");
+        assert!(br.contains(", !dbg !"), "Missing debug location: {}", br);
+
+        let phi = ir
+            .lines()
+            .find(|l| l.contains("= phi ptr"))
+            .expect("If should emit a merge phi");
+        assert!(phi.contains(", !dbg !"), "Missing debug location: {}", phi);
+    }
 
-                // Use fresh temp for cond to avoid collisions in nested ifs
-                let cond_var = self.fresh_temp();
-                writeln!(
-                    &mut self.output,
-                    "  %{} = trunc i8 %{} to i1",
-                    cond_var, bool_val
-                )
-                .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+    #[test]
+    fn test_literals_on_one_line_get_their_own_columns() {
+        use crate::parser::Parser;
+
+        let source = ": pair ( -- Int Int )\n  10 20 ;\n";
+        let program = Parser::new_with_filename(source, "pair.cem")
+            .parse()
+            .unwrap();
+        let ir = CodeGen::new().compile_program(&program).unwrap();
+
+        // Follow each push's !dbg reference to its DILocation
+        let location_of = |literal: &str| {
+            let push = ir
+                .lines()
+                .find(|l| l.contains(&format!("i64 {}), !dbg", literal)))
+                .unwrap_or_else(|| panic!("No located push of {}:\n{}", literal, ir));
+            let id = push.rsplit("!dbg ").next().unwrap();
+            ir.lines()
+                .find(|l| l.starts_with(&format!("{} = !DILocation(", id)))
+                .unwrap_or_else(|| panic!("No DILocation {}:\n{}", id, ir))
+                .to_string()
+        };
 
-                // Get rest of stack (next pointer at field index 3)
-                let rest_ptr = self.fresh_temp();
-                writeln!(&mut self.output, "  %{} = getelementptr inbounds {{ i32, [4 x i8], [16 x i8], ptr }}, ptr %{}, i32 0, i32 3", rest_ptr, stack)
-                    .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+        assert!(location_of("10").contains("line: 2, column: 3,"));
+        assert!(location_of("20").contains("line: 2, column: 6,"));
+    }
 
-                // Use fresh temp for rest to avoid collisions in nested ifs
-                let rest_var = self.fresh_temp();
-                writeln!(
-                    &mut self.output,
-                    "  %{} = load ptr, ptr %{}",
-                    rest_var, rest_ptr
-                )
-                .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+    #[test]
+    fn test_match_unwraps_declared_field_count() {
+        // type Shape | Dot | Rect(Int Int)
+        // area: ( Shape -- Int ) match Dot => [ 0 ] Rect => [ * ] end
+        let shape = TypeDef {
+            name: "Shape".to_string(),
+            type_params: vec![],
+            variants: vec![
+                Variant {
+                    name: "Dot".to_string(),
+                    fields: vec![],
+                },
+                Variant {
+                    name: "Rect".to_string(),
+                    fields: vec![Type::Int, Type::Int],
+                },
+            ],
+            doc: None,
+        };
+        let branch = |name: &str, body: Vec<Expr>| MatchBranch {
+            pattern: Pattern::Variant {
+                name: name.to_string(),
+                bindings: Vec::new(),
+            },
+            body,
+        };
+        let program = Program {
+            type_defs: vec![shape],
+            word_defs: vec![WordDef {
+                name: "area".to_string(),
+                effect: Effect {
+                    inputs: StackType::Empty.push(Type::Named {
+                        name: "Shape".to_string(),
+                        args: vec![],
+                    }),
+                    outputs: StackType::Empty.push(Type::Int),
+                },
+                body: vec![Expr::Match {
+                    branches: vec![
+                        branch("Dot", vec![Expr::IntLit(0, SourceLoc::unknown())]),
+                        branch(
+                            "Rect",
+                            vec![Expr::WordCall("*".to_string(), SourceLoc::unknown())],
+                        ),
+                    ],
+                    loc: SourceLoc::unknown(),
+                }],
+                loc: SourceLoc::unknown(),
+                feature: None,
+                inline: None,
+                slot_names: Default::default(),
+                doc: None,
+            }],
+        };
 
-                // Branch using the condition variable
-                writeln!(
-                    &mut self.output,
-                    "  br i1 %{}, label %{}, label %{}",
-                    cond_var, then_label, else_label
-                )
-                .map_err(|e| CodegenError::InternalError(e.to_string()))?;
+        let ir = CodeGen::new().compile_program(&program).unwrap();
+        assert!(
+            ir.contains("call ptr @unwrap_variant(ptr %stack, i32 0)"),
+            "Nullary branch should unwrap no fields:\n{}",
+            ir
+        );
+        assert!(
+            ir.contains("call ptr @unwrap_variant(ptr %stack, i32 2)"),
+            "Two-field branch should unwrap both fields:\n{}",
+            ir
+        );
+    }
 
-                // Then branch
-                writeln!(&mut self.output, "{}:", then_label)
-                    .map_err(|e| CodegenError::InternalError(e.to_string()))?;
-                self.current_block = then_label.clone();
-                let (then_stack, then_is_musttail) =
-                    self.compile_branch_quotation(then_branch, &rest_var)?;
+    #[test]
+    fn test_concatenated_string_literals_fold_into_one_global() {
+        use crate::parser::Parser;
+
+        let program = Parser::new(r#": f ( -- String ) "ab" "cd" ++ ;"#)
+            .parse()
+            .unwrap();
+        let ir = CodeGen::new().compile_program(&program).unwrap();
+
+        let globals: Vec<&str> = ir.lines().filter(|l| l.starts_with("@.str.")).collect();
+        assert_eq!(globals.len(), 1, "expected a single string global:\n{}", ir);
+        assert!(globals[0].contains(r#"[5 x i8] c"abcd\00""#));
+        assert!(!ir.contains("call ptr @string_concat"));
+    }
 
-                // Capture the actual block that will branch to merge (after any nested ifs)
-                let then_predecessor = self.current_block.clone();
+    #[test]
+    fn test_string_globals_and_temps_are_numbered_independently() {
+        // labels: ( -- String Int String Int ) "a" 1 "b" 2
+        let program = Program {
+            type_defs: vec![],
+            word_defs: vec![WordDef {
+                name: "labels".to_string(),
+                effect: Effect {
+                    inputs: StackType::Empty,
+                    outputs: StackType::from_vec(vec![
+                        Type::String,
+                        Type::Int,
+                        Type::String,
+                        Type::Int,
+                    ]),
+                },
+                body: vec![
+                    Expr::StringLit("a".to_string(), SourceLoc::unknown()),
+                    Expr::IntLit(1, SourceLoc::unknown()),
+                    Expr::StringLit("b".to_string(), SourceLoc::unknown()),
+                    Expr::IntLit(2, SourceLoc::unknown()),
+                ],
+                loc: SourceLoc::unknown(),
+                feature: None,
+                inline: None,
+                slot_names: Default::default(),
+                doc: None,
+            }],
+        };
 
-                // If then branch ends with musttail, emit return instead of branch
-                if then_is_musttail {
-                    writeln!(&mut self.output, "  ret ptr %{}", then_stack)
-                        .map_err(|e| CodegenError::InternalError(e.to_string()))?;
-                } else {
-                    writeln!(&mut self.output, "  br label %{}", merge_label)
-                        .map_err(|e| CodegenError::InternalError(e.to_string()))?;
-                }
+        let ir = CodeGen::new().compile_program(&program).unwrap();
 
-                // Else branch
-                writeln!(&mut self.output, "{}:", else_label)
-                    .map_err(|e| CodegenError::InternalError(e.to_string()))?;
-                self.current_block = else_label.clone();
-                let (else_stack, else_is_musttail) =
-                    self.compile_branch_quotation(else_branch, &rest_var)?;
+        // Globals count strings only, regardless of how many temps come before them
+        let globals: Vec<&str> = ir
+            .lines()
+            .filter(|l| l.starts_with("@.str."))
+            .map(|l| l.split(' ').next().unwrap())
+            .collect();
+        assert_eq!(globals, vec!["@.str.0", "@.str.1"]);
 
-                // Capture the actual block that will branch to merge (after any nested ifs)
-                let else_predecessor = self.current_block.clone();
+        // Temps count from zero within the word, each defined exactly once
+        let temps: Vec<&str> = ir
+            .lines()
+            .filter_map(|l| l.trim_start().strip_prefix('%'))
+            .map(|l| l.split(' ').next().unwrap())
+            .collect();
+        assert_eq!(temps, vec!["0", "1", "2", "3", "4", "5"]);
 
-                // If else branch ends with musttail, emit return instead of branch
-                if else_is_musttail {
-                    writeln!(&mut self.output, "  ret ptr %{}", else_stack)
-                        .map_err(|e| CodegenError::InternalError(e.to_string()))?;
-                } else {
-                    writeln!(&mut self.output, "  br label %{}", merge_label)
-                        .map_err(|e| CodegenError::InternalError(e.to_string()))?;
-                }
+        // Numbering is deterministic
+        assert_eq!(ir, CodeGen::new().compile_program(&program).unwrap());
+    }
 
-                // Merge point - only if at least one branch doesn't end with musttail
-                if !then_is_musttail || !else_is_musttail {
-                    writeln!(&mut self.output, "{}:", merge_label)
-                        .map_err(|e| CodegenError::InternalError(e.to_string()))?;
-                    self.current_block = merge_label.clone();
+    #[test]
+    fn test_breakpoint_lowers_to_debugtrap() {
+        // pause: ( Int -- Int ) breakpoint 1 + breakpoint
+        let breakpoint = || Expr::WordCall("breakpoint".to_string(), SourceLoc::unknown());
+        let program = Program {
+            type_defs: vec![],
+            word_defs: vec![WordDef {
+                name: "pause".to_string(),
+                effect: Effect {
+                    inputs: StackType::from_vec(vec![Type::Int]),
+                    outputs: StackType::from_vec(vec![Type::Int]),
+                },
+                body: vec![
+                    breakpoint(),
+                    Expr::IntLit(1, SourceLoc::unknown()),
+                    Expr::WordCall("+".to_string(), SourceLoc::unknown()),
+                    breakpoint(),
+                ],
+                loc: SourceLoc::unknown(),
+                feature: None,
+                inline: None,
+                slot_names: Default::default(),
+                doc: None,
+            }],
+        };
 
-                    // Build phi node based on which branches contribute
-                    let result = self.fresh_temp();
-                    if !then_is_musttail && !else_is_musttail {
-                        // Both branches merge - use actual predecessors
-                        writeln!(
-                            &mut self.output,
-                            "  %{} = phi ptr [ %{}, %{} ], [ %{}, %{} ]",
-                            result, then_stack, then_predecessor, else_stack, else_predecessor
-                        )
-                        .map_err(|e| CodegenError::InternalError(e.to_string()))?;
-                    } else if !then_is_musttail {
-                        // Only then branch merges (else returned)
-                        writeln!(
-                            &mut self.output,
-                            "  %{} = phi ptr [ %{}, %{} ]",
-                            result, then_stack, then_predecessor
-                        )
-                        .map_err(|e| CodegenError::InternalError(e.to_string()))?;
-                    } else {
-                        // Only else branch merges (then returned)
-                        writeln!(
-                            &mut self.output,
-                            "  %{} = phi ptr [ %{}, %{} ]",
-                            result, else_stack, else_predecessor
-                        )
-                        .map_err(|e| CodegenError::InternalError(e.to_string()))?;
-                    }
-                    Ok(result)
-                } else {
-                    // Both branches end with musttail and return - no merge point needed
-                    // This is actually unreachable code after the if, so return a dummy value
-                    Ok(then_stack) // Won't be used since both branches returned
-                }
-            }
-        }
+        let ir = CodeGen::new().compile_program(&program).unwrap();
+
+        assert!(ir.contains("declare void @llvm.debugtrap()"));
+        assert_eq!(ir.matches("call void @llvm.debugtrap()").count(), 2);
+        // A trailing breakpoint is not a tail call; the word returns the stack after it
+        assert!(!ir.contains("@breakpoint"));
+        assert!(ir.contains("ret ptr %"));
     }
 
-    /// Get the generated LLVM IR
-    pub fn emit_ir(&self) -> String {
-        self.output.clone()
+    #[test]
+    fn test_conditional_some_merges_both_paths() {
+        use crate::parser::Parser;
+
+        let program = Parser::new(
+            ": f ( Int Bool -- Option(Int) ) some_unless ; : g ( Int Bool -- Option(Int) ) some_if ;",
+        )
+        .parse()
+        .unwrap();
+        let ir = CodeGen::new().compile_program(&program).unwrap();
+
+        assert!(!ir.contains("@some_if") && !ir.contains("@some_unless"));
+        // some_unless drops on true, some_if on false
+        assert!(ir.contains("label %cond_drop_0, label %cond_keep_0"));
+        assert!(ir.contains(", label %cond_drop_"));
+        // The dropped value becomes None and the kept one Some
+        assert!(ir.contains(&format!(
+            "i32 {}, i32 0)",
+            variant_tag_for("Option", "None")
+        )));
+        assert!(ir.contains(&format!(
+            "i32 {}, i32 1)",
+            variant_tag_for("Option", "Some")
+        )));
+        assert!(ir.contains("phi ptr [ %"));
     }
-}
 
-impl Default for CodeGen {
-    fn default() -> Self {
-        Self::new()
+    #[test]
+    fn test_assert_branches_to_runtime_error() {
+        // : check ( Bool -- ) assert ;   with the assert at line 1, column 20
+        let program = Program {
+            type_defs: vec![],
+            word_defs: vec![WordDef {
+                name: "check".to_string(),
+                effect: Effect {
+                    inputs: StackType::from_vec(vec![Type::Bool]),
+                    outputs: StackType::Empty,
+                },
+                body: vec![Expr::WordCall(
+                    "assert".to_string(),
+                    SourceLoc::new(1, 20, "check.cem"),
+                )],
+                loc: SourceLoc::new(1, 1, "check.cem"),
+                feature: None,
+                inline: None,
+                slot_names: Default::default(),
+                doc: None,
+            }],
+        };
+
+        let ir = CodeGen::new().compile_program(&program).unwrap();
+        assert!(
+            !ir.contains("@assert"),
+            "assert should be lowered inline:\n{}",
+            ir
+        );
+        assert!(ir.contains("call zeroext i1 @cell_bool(ptr %stack)"));
+        assert!(ir.contains("c\"assert: assertion failed at check.cem:1:20\\00\""));
+
+        let fail = ir
+            .lines()
+            .position(|l| l.starts_with("assert_fail_"))
+            .expect("assert should emit a failure block");
+        let lines: Vec<_> = ir.lines().collect();
+        assert!(lines[fail + 1].contains("call void @runtime_error(ptr @.str."));
+        assert_eq!(lines[fail + 2].trim(), "unreachable");
+        assert!(lines[fail + 3].starts_with("assert_ok_"));
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::ast::types::{Effect, StackType, Type};
+    fn divide_by(divisor: i64, op: &str) -> String {
+        // : half ( Int -- Int ) <divisor> <op> ;
+        let program = Program {
+            type_defs: vec![],
+            word_defs: vec![WordDef {
+                name: "half".to_string(),
+                effect: Effect {
+                    inputs: StackType::from_vec(vec![Type::Int]),
+                    outputs: StackType::from_vec(vec![Type::Int]),
+                },
+                body: vec![
+                    Expr::IntLit(divisor, SourceLoc::new(1, 23, "half.cem")),
+                    Expr::WordCall(op.to_string(), SourceLoc::new(1, 25, "half.cem")),
+                ],
+                loc: SourceLoc::new(1, 1, "half.cem"),
+                feature: None,
+                inline: None,
+                slot_names: Default::default(),
+                doc: None,
+            }],
+        };
+        CodeGen::new().compile_program(&program).unwrap()
+    }
 
     #[test]
-    fn test_codegen_simple() {
-        let mut codegen = CodeGen::new();
+    fn test_division_by_zero_literal_is_checked() {
+        let ir = divide_by(0, "/");
+        assert!(ir.contains("call i64 @cell_int(ptr %"));
+        assert!(ir.contains("c\"/: division by zero at half.cem:1:25\\00\""));
+        let zero = ir
+            .lines()
+            .position(|l| l.starts_with("div_zero_"))
+            .expect("division should emit a zero-divisor block");
+        let lines: Vec<_> = ir.lines().collect();
+        assert!(lines[zero + 1].contains("call void @runtime_error(ptr @.str."));
+        assert_eq!(lines[zero + 2].trim(), "unreachable");
+        assert!(ir.contains("musttail call ptr @divide_op(ptr %"));
+    }
 
-        // : five ( -- Int ) 5 ;
-        let word = WordDef {
-            name: "five".to_string(),
-            effect: Effect {
-                inputs: StackType::Empty,
-                outputs: StackType::Empty.push(Type::Int),
-            },
-            body: vec![Expr::IntLit(5, SourceLoc::unknown())],
-            loc: SourceLoc::unknown(),
-        };
+    #[test]
+    fn test_division_by_nonzero_literal_skips_check() {
+        for op in ["/", "quot", "mod", "rem"] {
+            let ir = divide_by(4, op);
+            assert!(
+                !ir.contains("@cell_int(ptr %"),
+                "{} by 4 was checked:\n{}",
+                op,
+                ir
+            );
+            assert!(!ir.contains("division by zero"));
+        }
+        assert!(divide_by(4, "quot").contains("call ptr @divide_op("));
+        assert!(divide_by(4, "rem").contains("call ptr @mod_op("));
+    }
 
+    #[test]
+    fn test_external_words_are_declared_and_called_by_symbol() {
+        // : main ( -- Int ) 3 add-one ;  with add-one from a library
         let program = Program {
             type_defs: vec![],
-            word_defs: vec![word],
+            word_defs: vec![WordDef {
+                name: "main".to_string(),
+                effect: Effect {
+                    inputs: StackType::Empty,
+                    outputs: StackType::from_vec(vec![Type::Int]),
+                },
+                body: vec![
+                    Expr::IntLit(3, SourceLoc::unknown()),
+                    Expr::WordCall("add-one".to_string(), SourceLoc::unknown()),
+                ],
+                loc: SourceLoc::unknown(),
+                feature: None,
+                inline: None,
+                slot_names: Default::default(),
+                doc: None,
+            }],
         };
 
+        let mut codegen = CodeGen::new();
+        codegen.add_external_word(
+            "add-one",
+            "add_one",
+            Effect::from_vecs(vec![Type::Int], vec![Type::Int]),
+        );
         let ir = codegen.compile_program(&program).unwrap();
 
-        assert!(ir.contains("define ptr @five"));
-        assert!(ir.contains("call ptr @push_int"));
-        assert!(ir.contains("i64 5"));
-        assert!(ir.contains("ret ptr"));
+        assert!(ir.contains("declare ptr @add_one(ptr)"));
+        assert!(ir.contains("musttail call ptr @add_one(ptr"));
     }
 
     #[test]
-    fn test_codegen_word_call() {
+    fn test_backticked_external_word_calls_the_exact_symbol() {
+        use crate::parser::Parser;
+
+        let program = Parser::new(
+            ": main ( -- Int ) 3 `some-c-name` 4 `ns::scale` ;\n\
+             : `my word` ( -- ) ;",
+        )
+        .parse()
+        .unwrap();
+
         let mut codegen = CodeGen::new();
+        let int_to_int = Effect::from_vecs(vec![Type::Int], vec![Type::Int]);
+        codegen.add_external_word("some-c-name", "some-c-name", int_to_int.clone());
+        codegen.add_external_word(
+            "ns::scale",
+            "ns::scale",
+            Effect::from_vecs(vec![Type::Int, Type::Int], vec![Type::Int]),
+        );
+        let ir = codegen.compile_program(&program).unwrap();
 
-        // : double ( Int -- Int ) dup + ;
-        let word = WordDef {
-            name: "double".to_string(),
-            effect: Effect {
-                inputs: StackType::Empty.push(Type::Int),
-                outputs: StackType::Empty.push(Type::Int),
-            },
-            body: vec![
-                Expr::WordCall("dup".to_string(), SourceLoc::unknown()),
-                Expr::WordCall("add".to_string(), SourceLoc::unknown()),
-            ],
-            loc: SourceLoc::unknown(),
-        };
+        // Bound symbols are used as given, not mangled like Cem word names
+        assert!(ir.contains("declare ptr @some-c-name(ptr)"), "{}", ir);
+        assert!(ir.contains("call ptr @some-c-name(ptr"));
+        assert!(ir.contains("declare ptr @\"ns::scale\"(ptr)"));
+        assert!(ir.contains("musttail call ptr @\"ns::scale\"(ptr"));
+        assert!(ir.contains("define ptr @\"my word\"(ptr %stack)"));
+    }
+
+    #[test]
+    fn test_tail_call_to_a_non_word_prototype_is_not_musttail() {
+        use crate::parser::Parser;
+
+        let program = Parser::new(
+            ": quit ( Int -- ) exit ;\n\
+             : inc ( Int -- Int ) 1 + ;\n\
+             : twice ( Int -- Int ) inc inc ;",
+        )
+        .parse()
+        .unwrap();
+        let ir = CodeGen::new().compile_program(&program).unwrap();
+
+        // exit_op returns void, so its prototype can't match the caller's
+        assert!(ir.contains("declare void @exit_op(ptr)"));
+        assert!(
+            ir.contains("= tail call ptr @exit_op(ptr %stack)"),
+            "{}",
+            ir
+        );
+        assert!(!ir.contains("musttail call ptr @exit_op"));
+        // Runtime functions and program words keep their guaranteed tail calls
+        assert!(ir.contains("musttail call ptr @add(ptr"));
+        assert!(ir.contains("musttail call ptr @inc(ptr"));
+    }
+
+    #[test]
+    fn test_llvm_global_quotes_only_when_needed() {
+        assert_eq!(llvm_global("add_one"), "@add_one");
+        assert_eq!(llvm_global("c.name-2$"), "@c.name-2$");
+        assert_eq!(llvm_global("2dup"), "@\"2dup\"");
+        assert_eq!(llvm_global("a\"b\\c"), "@\"a\\22b\\5Cc\"");
+    }
 
+    #[test]
+    fn test_word_comment_shows_effect_signature() {
+        // square: ( Int -- Int ) dup *
         let program = Program {
             type_defs: vec![],
-            word_defs: vec![word],
+            word_defs: vec![WordDef {
+                name: "square".to_string(),
+                effect: Effect {
+                    inputs: StackType::from_vec(vec![Type::Int]),
+                    outputs: StackType::from_vec(vec![Type::Int]),
+                },
+                body: vec![
+                    Expr::WordCall("dup".to_string(), SourceLoc::unknown()),
+                    Expr::WordCall("*".to_string(), SourceLoc::unknown()),
+                ],
+                loc: SourceLoc::unknown(),
+                feature: None,
+                inline: None,
+                slot_names: Default::default(),
+                doc: None,
+            }],
         };
 
-        let ir = codegen.compile_program(&program).unwrap();
-
-        assert!(ir.contains("@double"));
-        assert!(ir.contains("call ptr @dup"));
-        assert!(ir.contains("call ptr @add"));
+        let ir = CodeGen::new().compile_program(&program).unwrap();
+        let lines: Vec<&str> = ir.lines().collect();
+        let define = lines
+            .iter()
+            .position(|l| l.starts_with("define ptr @square("))
+            .expect("square should be defined");
+        assert_eq!(lines[define - 1], "; square ( Int -- Int )");
     }
 
     #[test]
-    fn test_no_target_triple_in_generated_ir() {
-        let mut codegen = CodeGen::new();
+    fn test_string_globals_are_byte_aligned() {
+        // greet: ( -- String ) "hi"
+        let program = Program {
+            type_defs: vec![],
+            word_defs: vec![WordDef {
+                name: "greet".to_string(),
+                effect: Effect {
+                    inputs: StackType::Empty,
+                    outputs: StackType::Empty.push(Type::String),
+                },
+                body: vec![Expr::StringLit("hi".to_string(), SourceLoc::unknown())],
+                loc: SourceLoc::unknown(),
+                feature: None,
+                inline: None,
+                slot_names: Default::default(),
+                doc: None,
+            }],
+        };
 
-        let word = WordDef {
-            name: "test".to_string(),
+        let ir = CodeGen::new().compile_program(&program).unwrap();
+        assert!(
+            ir.contains("@.str.0 = private unnamed_addr constant [3 x i8] c\"hi\\00\", align 1"),
+            "String global should be declared with align 1:\n{}",
+            ir
+        );
+        assert!(ir.contains("getelementptr inbounds [3 x i8], ptr @.str.0, i64 0, i64 0"));
+    }
+
+    #[test]
+    fn test_quotations_become_top_level_functions() {
+        // one: ( -- Quotation ) [ 1 ]   two: ( -- Quotation ) [ 2 ]
+        let quoting = |name: &str, n: i64| WordDef {
+            name: name.to_string(),
             effect: Effect {
                 inputs: StackType::Empty,
-                outputs: StackType::Empty,
+                outputs: StackType::Empty.push(Type::Quotation(Box::new(Effect {
+                    inputs: StackType::Empty,
+                    outputs: StackType::Empty.push(Type::Int),
+                }))),
             },
-            body: vec![],
+            body: vec![Expr::Quotation(
+                vec![Expr::IntLit(n, SourceLoc::unknown())],
+                SourceLoc::unknown(),
+            )],
             loc: SourceLoc::unknown(),
+            feature: None,
+            inline: None,
+            slot_names: Default::default(),
+            doc: None,
+        };
+        let program = Program {
+            type_defs: vec![],
+            word_defs: vec![quoting("one", 1), quoting("two", 2)],
         };
 
+        let ir = CodeGen::new().compile_program(&program).unwrap();
+
+        // No function definition starts inside another one
+        let mut open = false;
+        for line in ir.lines() {
+            if line.starts_with("define ") {
+                assert!(!open, "Nested function definition: {}", line);
+                open = true;
+            } else if line == "}" {
+                open = false;
+            }
+        }
+
+        // Each quotation gets its own program-wide name, local to the unit
+        assert!(ir.contains("define internal ptr @quot_0(ptr %stack)"));
+        assert!(ir.contains("define internal ptr @quot_1(ptr %stack)"));
+        assert!(ir.contains("call ptr @push_quotation(ptr %stack, ptr @quot_1)"));
+    }
+
+    #[test]
+    fn test_compose_lowers_to_runtime() {
+        // both: ( -- Quotation ) [ 1 + ] [ 2 * ] compose
+        let quot = |n: i64, op: &str| {
+            Expr::Quotation(
+                vec![
+                    Expr::IntLit(n, SourceLoc::unknown()),
+                    Expr::WordCall(op.to_string(), SourceLoc::unknown()),
+                ],
+                SourceLoc::unknown(),
+            )
+        };
         let program = Program {
             type_defs: vec![],
-            word_defs: vec![word],
+            word_defs: vec![WordDef {
+                name: "both".to_string(),
+                effect: Effect {
+                    inputs: StackType::Empty,
+                    outputs: StackType::Empty.push(Type::Quotation(Box::new(Effect {
+                        inputs: StackType::Empty.push(Type::Int),
+                        outputs: StackType::Empty.push(Type::Int),
+                    }))),
+                },
+                body: vec![
+                    quot(1, "+"),
+                    quot(2, "*"),
+                    Expr::WordCall("compose".to_string(), SourceLoc::unknown()),
+                ],
+                loc: SourceLoc::unknown(),
+                feature: None,
+                inline: None,
+                slot_names: Default::default(),
+                doc: None,
+            }],
         };
 
-        let ir = codegen.compile_program(&program).unwrap();
+        let ir = CodeGen::new().compile_program(&program).unwrap();
+        assert!(ir.contains("declare ptr @compose_quotations(ptr)"));
+        assert!(ir.contains("@compose_quotations(ptr %"));
+    }
 
-        // Verify that target triple is NOT present in the IR
-        // We intentionally omit it to let clang use its default and avoid warnings
-        assert!(
-            !ir.contains("target triple"),
-            "IR should not contain target triple declaration"
+    #[test]
+    fn test_inspect_lowers_to_print_top() {
+        // debug: ( Int -- Int ) inspect
+        let program = Program {
+            type_defs: vec![],
+            word_defs: vec![WordDef {
+                name: "debug".to_string(),
+                effect: Effect {
+                    inputs: StackType::Empty.push(Type::Int),
+                    outputs: StackType::Empty.push(Type::Int),
+                },
+                body: vec![Expr::WordCall("inspect".to_string(), SourceLoc::unknown())],
+                loc: SourceLoc::unknown(),
+                feature: None,
+                inline: None,
+                slot_names: Default::default(),
+                doc: None,
+            }],
+        };
+
+        let ir = CodeGen::new().compile_program(&program).unwrap();
+        assert!(ir.contains("declare ptr @print_top(ptr)"));
+        assert!(ir.contains("call ptr @print_top(ptr %stack)"));
+    }
+
+    #[test]
+    fn test_variant_tags_stable_across_units() {
+        // Two units that both define Option must agree on Some's tag, even if one
+        // declares the variants in a different order
+        let some_unit = |typedef: TypeDef| Program {
+            type_defs: vec![typedef],
+            word_defs: vec![WordDef {
+                name: "wrap".to_string(),
+                effect: Effect {
+                    inputs: StackType::Empty.push(Type::Int),
+                    outputs: StackType::Empty.push(Type::Named {
+                        name: "Option".to_string(),
+                        args: vec![Type::Int],
+                    }),
+                },
+                body: vec![Expr::WordCall("Some".to_string(), SourceLoc::unknown())],
+                loc: SourceLoc::unknown(),
+                feature: None,
+                inline: None,
+                slot_names: Default::default(),
+                doc: None,
+            }],
+        };
+        let mut reordered = option_typedef();
+        reordered.variants.reverse();
+
+        let first = CodeGen::new()
+            .compile_program(&some_unit(option_typedef()))
+            .unwrap();
+        let second = CodeGen::new()
+            .compile_program(&some_unit(reordered))
+            .unwrap();
+
+        let construct = |ir: &str| {
+            ir.lines()
+                .find(|l| l.contains("call ptr @make_variant("))
+                .map(|l| l.trim().to_string())
+                .unwrap()
+        };
+        assert_eq!(construct(&first), construct(&second));
+        assert!(construct(&first).contains(&format!(
+            "i32 {}, i32 1)",
+            variant_tag_for("Option", "Some")
+        )));
+        assert_ne!(
+            variant_tag_for("Option", "Some"),
+            variant_tag_for("Option", "None")
         );
     }
 
     #[test]
-    fn test_codegen_quotation() {
-        let mut codegen = CodeGen::new();
+    fn test_construction_and_match_agree_on_variant_tags() {
+        use crate::parser::Parser;
+
+        let source = "type Option (T) | Some(T) | None\n\
+            : wrap ( Int -- Option(Int) ) Some ;\n\
+            : nothing ( -- Option(Int) ) None ;\n\
+            : get ( Option(Int) -- Int ) match Some => [ ] None => [ 0 ] end ;";
+        let compile = || {
+            let program = Parser::new(source).parse().unwrap();
+            CodeGen::new().compile_program(&program).unwrap()
+        };
+        let ir = compile();
+
+        // Each variant gets one tag, used both to build it and to select its match arm
+        let mut case_tags = Vec::new();
+        for (idx, (variant, fields)) in [("Some", 1), ("None", 0)].into_iter().enumerate() {
+            let tag = variant_tag_for("Option", variant);
+            assert!(
+                ir.contains(&format!("i32 {}, i32 {})", tag, fields)),
+                "{} should be constructed with tag {}:\n{}",
+                variant,
+                tag,
+                ir
+            );
+            assert!(
+                ir.contains(&format!("i32 {}, label %match_case_0_{}", tag, idx)),
+                "the {} arm should be selected by tag {}:\n{}",
+                variant,
+                tag,
+                ir
+            );
+            case_tags.push(tag);
+        }
+        assert_ne!(case_tags[0], case_tags[1]);
 
-        // : test ( -- Int ) [ 5 10 add ] call_quotation ;
-        let word = WordDef {
-            name: "test".to_string(),
+        // Nothing about the assignment varies between compilations
+        assert_eq!(ir, compile());
+    }
+
+    #[test]
+    fn test_runtime_list_tags_match_codegen() {
+        // string_split builds List values in C, so its tags must be the ones codegen derives
+        let header = include_str!("../../runtime/stack.h");
+        for (variant, define) in [("Cons", "LIST_CONS_TAG"), ("Nil", "LIST_NIL_TAG")] {
+            let expected = format!("#define {} {}u", define, variant_tag_for("List", variant));
+            assert!(
+                header.contains(&expected),
+                "stack.h should have `{}`",
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn test_if_with_both_branches_tail_calling_is_terminated() {
+        // step: ( Int Bool -- Int ) if [ inc ] [ dec ]
+        let call = |name: &str| Expr::WordCall(name.to_string(), SourceLoc::unknown());
+        let int_to_int = |name: &str, body: Vec<Expr>| WordDef {
+            name: name.to_string(),
             effect: Effect {
-                inputs: StackType::Empty,
+                inputs: StackType::Empty.push(Type::Int),
                 outputs: StackType::Empty.push(Type::Int),
             },
-            body: vec![
-                Expr::Quotation(
-                    vec![
-                        Expr::IntLit(5, SourceLoc::unknown()),
-                        Expr::IntLit(10, SourceLoc::unknown()),
-                        Expr::WordCall("add".to_string(), SourceLoc::unknown()),
-                    ],
-                    SourceLoc::unknown(),
-                ),
-                Expr::WordCall("call_quotation".to_string(), SourceLoc::unknown()),
-            ],
+            body,
             loc: SourceLoc::unknown(),
+            feature: None,
+            inline: None,
+            slot_names: Default::default(),
+            doc: None,
+        };
+        let step = WordDef {
+            name: "step".to_string(),
+            effect: Effect {
+                inputs: StackType::Empty.push(Type::Int).push(Type::Bool),
+                outputs: StackType::Empty.push(Type::Int),
+            },
+            body: vec![Expr::If {
+                condition: None,
+                then_branch: Box::new(Expr::Quotation(vec![call("inc")], SourceLoc::unknown())),
+                else_branch: Box::new(Expr::Quotation(vec![call("dec")], SourceLoc::unknown())),
+                loc: SourceLoc::unknown(),
+            }],
+            loc: SourceLoc::unknown(),
+            feature: None,
+            inline: None,
+            slot_names: Default::default(),
+            doc: None,
         };
-
         let program = Program {
             type_defs: vec![],
-            word_defs: vec![word],
+            word_defs: vec![
+                int_to_int(
+                    "inc",
+                    vec![Expr::IntLit(1, SourceLoc::unknown()), call("+")],
+                ),
+                int_to_int(
+                    "dec",
+                    vec![Expr::IntLit(1, SourceLoc::unknown()), call("-")],
+                ),
+                step,
+            ],
         };
 
-        let ir = codegen.compile_program(&program).unwrap();
+        let ir = CodeGen::new().compile_program(&program).unwrap();
+        let body: Vec<&str> = ir
+            .lines()
+            .skip_while(|l| !l.starts_with("define ptr @step("))
+            .take_while(|l| *l != "}")
+            .collect();
 
-        // Verify quotation function is generated
-        assert!(
-            ir.contains("define ptr @quot_"),
-            "Should generate quotation function"
-        );
-        // Verify quotation is pushed
-        assert!(
-            ir.contains("call ptr @push_quotation"),
-            "Should push quotation"
-        );
-        // Verify quotation contains the body
+        // Both branches return; the block after the if is explicitly unreachable
+        let after = body
+            .iter()
+            .position(|l| l.starts_with("if_after_"))
+            .expect("Expected an if_after block");
+        assert_eq!(body[after + 1].trim(), "unreachable");
+        assert_eq!(after + 2, body.len(), "Nothing may follow the unreachable");
         assert!(
-            ir.contains("call ptr @push_int"),
-            "Quotation should push integers"
-        );
-        assert!(ir.contains("call ptr @add"), "Quotation should call add");
-        // Verify call_quotation is called
-        assert!(
-            ir.contains("call ptr @call_quotation"),
-            "Should call call_quotation"
+            !body.iter().any(|l| l.contains("phi")),
+            "No merge phi when both branches return"
         );
     }
 }
@@ -0,0 +1,70 @@
+/**
+Code generation options
+
+Everything that configures a `CodeGen` lives in one `CodeGenOptions` value, so a new
+setting is a new field here rather than another constructor or parameter.
+*/
+use super::target::Target;
+
+/// Configuration of a code generator
+#[derive(Debug, Clone, PartialEq)]
+pub struct CodeGenOptions {
+    /// Compilation target (affects the generated entry point)
+    pub target: Target,
+
+    /// Active features; `when(feature)` words outside this set are skipped
+    pub features: Vec<String>,
+
+    /// Verify that each word body leaves the declared number of outputs
+    /// (`cem compile --check-arity`)
+    pub check_output_arity: bool,
+
+    /// Emit DWARF debug metadata: `!dbg` attachments, DISubprograms, and the compile unit
+    pub debug_info: bool,
+
+    /// Compile non-tail `swap` and `rot` by relinking the cells' `next` pointers in
+    /// place instead of calling the runtime. The runtime's underflow checks are skipped,
+    /// so only use this for type-checked programs.
+    pub inline_shuffles: bool,
+
+    /// Compile a non-tail `dup` of a top cell statically known to be an Int or Bool by
+    /// copying its value instead of calling `stack_dup`. The runtime's underflow and tag
+    /// checks are skipped, so only use this for type-checked programs.
+    pub typed_dup: bool,
+
+    /// Values the generated `main` pushes, bottom first, before calling the entry word,
+    /// so a word with inputs can be run directly (e.g. a `( Int -- Int )` word in a test)
+    pub entry_stack: Vec<StackSeed>,
+
+    /// Make `main` fail with a runtime error unless the entry word leaves exactly the
+    /// values its effect declares, catching imbalances the static checks can't see
+    /// (such as a quotation that pushes more than it should)
+    pub strict_stack: bool,
+
+    /// Print each word's name on stdout as it is compiled (`cem compile -v`)
+    pub verbose: bool,
+}
+
+/// A literal pushed onto the entry word's initial stack
+#[derive(Debug, Clone, PartialEq)]
+pub enum StackSeed {
+    Int(i64),
+    Bool(bool),
+    String(String),
+}
+
+impl Default for CodeGenOptions {
+    fn default() -> Self {
+        CodeGenOptions {
+            target: Target::Native,
+            features: Vec::new(),
+            check_output_arity: false,
+            debug_info: true,
+            inline_shuffles: false,
+            typed_dup: false,
+            entry_stack: Vec::new(),
+            strict_stack: false,
+            verbose: false,
+        }
+    }
+}
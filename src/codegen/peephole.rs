@@ -0,0 +1,207 @@
+/**
+Peephole simplification of word bodies
+
+Removes short sequences that leave the stack exactly as they found it before any IR
+is emitted for them:
+
+- `dup drop` and `swap swap`
+- a literal immediately dropped (`5 drop`, `"s" drop`)
+
+and joins two string literals followed by `++` into one literal, so `"ab" "cd" ++`
+compiles to a single constant instead of a runtime `string_concat`.
+
+It also inlines the condition of an `ifte [ cond ] [ then ] [ else ]` ahead of a plain
+`if`; the parser keeps it on the `If` so the type checker can check its effect.
+
+Only values the sequence itself created are discarded, so a `drop` of a value that
+came from elsewhere (and may be the required consumption of a linear value) is always
+kept. Rewriting the AST rather than the emitted IR keeps every later decision (tail
+calls, terminated branches) consistent with what is actually compiled.
+*/
+use crate::ast::{Expr, MatchBranch, WordDef};
+use crate::parser::MAX_STRING_LENGTH;
+
+/// A copy of `word` with its body (and every nested body) simplified
+pub fn simplify_word(word: &WordDef) -> WordDef {
+    WordDef {
+        body: simplify(&word.body),
+        ..word.clone()
+    }
+}
+
+/// Simplify an expression sequence, including the bodies nested inside it
+pub fn simplify(exprs: &[Expr]) -> Vec<Expr> {
+    let mut out: Vec<Expr> = Vec::with_capacity(exprs.len());
+    for expr in exprs {
+        if let Expr::If {
+            condition: Some(condition),
+            then_branch,
+            else_branch,
+            loc,
+        } = expr
+            && let Expr::Quotation(condition, _) = &**condition
+        {
+            out.extend(simplify(condition));
+            out.push(simplify_nested(&Expr::If {
+                condition: None,
+                then_branch: then_branch.clone(),
+                else_branch: else_branch.clone(),
+                loc: loc.clone(),
+            }));
+        } else {
+            out.push(simplify_nested(expr));
+        }
+        // Removing a pair can expose another (`5 dup drop drop`), so keep going
+        loop {
+            if cancels(&out) {
+                out.truncate(out.len() - 2);
+            } else if !fold_concat(&mut out) {
+                break;
+            }
+        }
+    }
+    out
+}
+
+/// Whether the last two expressions of `exprs` leave the stack unchanged
+fn cancels(exprs: &[Expr]) -> bool {
+    let [.., first, Expr::WordCall(second, _)] = exprs else {
+        return false;
+    };
+    match (first, second.as_str()) {
+        (Expr::WordCall(first, _), "drop") => first == "dup",
+        (Expr::WordCall(first, _), "swap") => first == "swap",
+        (Expr::IntLit(..) | Expr::BoolLit(..) | Expr::StringLit(..), "drop") => true,
+        _ => false,
+    }
+}
+
+/// Replace a trailing `"a" "b" ++` with `"ab"`, returning whether it did
+///
+/// A pair whose joined length would exceed the lexer's limit on a literal is left for
+/// the runtime `string_concat`, so folding never makes a literal the source couldn't.
+fn fold_concat(exprs: &mut Vec<Expr>) -> bool {
+    let [
+        ..,
+        Expr::StringLit(first, loc),
+        Expr::StringLit(second, _),
+        Expr::WordCall(op, _),
+    ] = exprs.as_slice()
+    else {
+        return false;
+    };
+    if op != "++" {
+        return false;
+    }
+    let len = first.len() + second.len();
+    if len > MAX_STRING_LENGTH {
+        return false;
+    }
+    let mut joined = String::with_capacity(len);
+    joined.push_str(first);
+    joined.push_str(second);
+    let folded = Expr::StringLit(joined, loc.clone());
+
+    exprs.truncate(exprs.len() - 3);
+    exprs.push(folded);
+    true
+}
+
+fn simplify_nested(expr: &Expr) -> Expr {
+    let boxed = |e: &Expr| Box::new(simplify_nested(e));
+    match expr {
+        Expr::Quotation(body, loc) => Expr::Quotation(simplify(body), loc.clone()),
+        Expr::If {
+            condition,
+            then_branch,
+            else_branch,
+            loc,
+        } => Expr::If {
+            condition: condition.as_deref().map(boxed),
+            then_branch: boxed(then_branch),
+            else_branch: boxed(else_branch),
+            loc: loc.clone(),
+        },
+        Expr::While {
+            condition,
+            body,
+            loc,
+        } => Expr::While {
+            condition: boxed(condition),
+            body: boxed(body),
+            loc: loc.clone(),
+        },
+        Expr::Match { branches, loc } => Expr::Match {
+            branches: branches
+                .iter()
+                .map(|branch| MatchBranch {
+                    pattern: branch.pattern.clone(),
+                    body: simplify(&branch.body),
+                })
+                .collect(),
+            loc: loc.clone(),
+        },
+        Expr::IntLit(..) | Expr::BoolLit(..) | Expr::StringLit(..) | Expr::WordCall(..) => {
+            expr.clone()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::Parser;
+
+    fn simplified(source: &str) -> Vec<String> {
+        let program = Parser::new(source).parse().unwrap();
+        simplify(&program.word_defs[0].body)
+            .iter()
+            .map(|e| e.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_cancelling_pairs_are_removed() {
+        assert!(simplified(": f ( Int -- Int ) dup drop swap swap ;").is_empty());
+        assert!(simplified(": f ( -- ) 5 dup drop drop ;").is_empty());
+        assert_eq!(
+            simplified(": f ( Int -- Int ) [ 1 drop dup drop ] call_quotation ;")[0],
+            "[ ]"
+        );
+    }
+
+    #[test]
+    fn test_adjacent_string_literals_are_concatenated() {
+        assert_eq!(
+            simplified(r#": f ( -- String ) "a" "b" ++ "c" ++ ;"#),
+            vec![r#""abc""#]
+        );
+        assert!(simplified(r#": f ( -- ) "a" "b" ++ drop ;"#).is_empty());
+        assert_eq!(
+            simplified(r#": f ( String -- String ) "a" ++ ;"#),
+            vec![r#""a""#, "++"]
+        );
+
+        // Two literals that would join past the lexer's limit are left to the runtime
+        let half = "x".repeat(MAX_STRING_LENGTH / 2 + 1);
+        let source = format!(r#": f ( -- String ) "{}" "{}" ++ ;"#, half, half);
+        assert_eq!(simplified(&source).len(), 3);
+    }
+
+    #[test]
+    fn test_ifte_condition_is_inlined() {
+        assert_eq!(
+            simplified(": f ( Int -- Int ) ifte [ dup 0 < ] [ 0 swap - ] [ ] ;"),
+            vec!["dup", "0", "<", "if"]
+        );
+    }
+
+    #[test]
+    fn test_drop_of_an_existing_value_is_kept() {
+        assert_eq!(simplified(": f ( Int -- ) drop ;"), vec!["drop"]);
+        assert_eq!(
+            simplified(": f ( Int -- Int ) dup 1 + drop ;"),
+            vec!["dup", "1", "+", "drop"]
+        );
+    }
+}
@@ -0,0 +1,97 @@
+/**
+Compilation targets
+
+Selects the clang target triple and the shape of the generated entry point.
+The IR itself is target-neutral (we omit the target triple from the module),
+so switching targets only changes how `main()` is emitted and how clang is invoked.
+*/
+use std::fmt;
+use std::str::FromStr;
+
+/// A compilation target
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Target {
+    /// The host platform (whatever clang targets by default)
+    #[default]
+    Native,
+
+    /// WebAssembly with the WASI system interface
+    Wasm32Wasi,
+}
+
+impl Target {
+    /// Extra clang arguments needed to compile for this target
+    pub fn clang_args(&self) -> &'static [&'static str] {
+        match self {
+            Target::Native => &[],
+            Target::Wasm32Wasi => &["--target=wasm32-wasi"],
+        }
+    }
+
+    /// Default location of the runtime library built for this target
+    pub fn runtime_lib(&self) -> &'static str {
+        match self {
+            Target::Native => "runtime/libcem_runtime.a",
+            Target::Wasm32Wasi => "runtime/libcem_runtime_wasm32.a",
+        }
+    }
+
+    /// Whether the entry word runs as a strand under the green-thread scheduler
+    ///
+    /// The scheduler relies on native context-switching assembly, so on wasm the
+    /// generated main() calls the entry word directly instead.
+    pub fn uses_scheduler(&self) -> bool {
+        match self {
+            Target::Native => true,
+            Target::Wasm32Wasi => false,
+        }
+    }
+
+    /// Byte offset of the `next` field in a StackCell
+    ///
+    /// The cell is a 4-byte tag, a union aligned to 8 bytes holding two pointers or an
+    /// int64, then `next`; runtime/stack.h asserts the same layout.
+    pub fn cell_next_offset(&self) -> u64 {
+        match self {
+            Target::Native => 24,
+            Target::Wasm32Wasi => 16,
+        }
+    }
+}
+
+impl fmt::Display for Target {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Target::Native => write!(f, "native"),
+            Target::Wasm32Wasi => write!(f, "wasm32"),
+        }
+    }
+}
+
+impl FromStr for Target {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "native" => Ok(Target::Native),
+            "wasm32" | "wasm32-wasi" => Ok(Target::Wasm32Wasi),
+            _ => Err(format!(
+                "Unknown target '{}' (expected 'native' or 'wasm32')",
+                s
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_targets() {
+        assert_eq!("native".parse::<Target>(), Ok(Target::Native));
+        assert_eq!("wasm32".parse::<Target>(), Ok(Target::Wasm32Wasi));
+        assert_eq!("wasm32-wasi".parse::<Target>(), Ok(Target::Wasm32Wasi));
+        assert!("sparc".parse::<Target>().is_err());
+    }
+}
@@ -0,0 +1,322 @@
+/**
+Library interface files (`.cemi`)
+
+A library build writes, next to its `.a`, an interface listing the type definitions
+and, for each word, its compiled symbol and stack effect. Programs built against the
+library can read this instead of the library's source.
+
+The format is line based. The first line is `cem-interface 1`; after it, blank lines
+and `#` comments are ignored and every other line is one entry:
+
+```text
+type Option(T) | Some(T) | None
+word square square ( Int -- Int )
+word add-one add_one ( Int -- Int )
+```
+
+A `type` line is a Cem type definition. A `word` line gives the word's name, its
+symbol, and its effect signature in source syntax. A name or symbol containing
+whitespace is written between backticks, as in source:
+
+```text
+word `open file` `open file` ( String -- Int )
+```
+
+Hand-written `word` lines are how a program binds to functions written in C: the
+symbol is called exactly as given, however unlike a Cem identifier it looks.
+*/
+use crate::ast::types::Effect;
+use crate::ast::{Program, TypeDef};
+use crate::parser::{Parser, parse_effect};
+use crate::symbol::symbol_for_word;
+use std::fmt;
+
+/// First line of every interface file, naming the format version
+pub const INTERFACE_HEADER: &str = "cem-interface 1";
+
+/// The types and words a precompiled library provides
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Interface {
+    pub type_defs: Vec<TypeDef>,
+    pub words: Vec<InterfaceWord>,
+}
+
+/// A word provided by a library
+#[derive(Debug, Clone, PartialEq)]
+pub struct InterfaceWord {
+    pub name: String,
+    /// The symbol the word was compiled to (see `symbol_for_word`)
+    pub symbol: String,
+    pub effect: Effect,
+}
+
+impl Interface {
+    /// The interface of a library built from `program` under `features`
+    pub fn from_program(program: &Program, features: &[String]) -> Interface {
+        Interface {
+            type_defs: program.type_defs.clone(),
+            words: program
+                .enabled_words(features)
+                .map(|word| InterfaceWord {
+                    name: word.name.clone(),
+                    symbol: symbol_for_word(&word.name),
+                    effect: word.effect.clone(),
+                })
+                .collect(),
+        }
+    }
+
+    /// Parse an interface file
+    ///
+    /// Type definitions and effect signatures are read with the Cem parser, so they
+    /// accept exactly what source files do.
+    pub fn parse(source: &str) -> Result<Interface, String> {
+        let mut lines = source.lines().enumerate();
+        match lines.next() {
+            Some((_, header)) if header.trim() == INTERFACE_HEADER => {}
+            _ => return Err(format!("Expected '{}' on the first line", INTERFACE_HEADER)),
+        }
+
+        let mut interface = Interface::default();
+        for (index, line) in lines {
+            let line = line.trim();
+            let error = |message: String| format!("line {}: {}", index + 1, message);
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            if line.starts_with("type ") {
+                let program = Parser::new(line)
+                    .parse()
+                    .map_err(|e| error(e.to_string()))?;
+                match <[TypeDef; 1]>::try_from(program.type_defs) {
+                    Ok([typedef]) if program.word_defs.is_empty() => {
+                        interface.type_defs.push(typedef)
+                    }
+                    _ => return Err(error("Expected one type definition".to_string())),
+                }
+            } else if let Some(rest) = line.strip_prefix("word ") {
+                let fields = split_field(rest).and_then(|(name, rest)| {
+                    split_field(rest).map(|(symbol, effect)| (name, symbol, effect))
+                });
+                let Some((name, symbol, effect)) = fields.filter(|(_, _, e)| !e.is_empty()) else {
+                    return Err(error(
+                        "Expected 'word <name> <symbol> <effect>'".to_string(),
+                    ));
+                };
+
+                let effect = parse_effect(effect)
+                    .map_err(|e| error(format!("Malformed effect for '{}': {}", name, e)))?;
+                interface.words.push(InterfaceWord {
+                    name: name.to_string(),
+                    symbol: symbol.to_string(),
+                    effect,
+                });
+            } else {
+                return Err(error(format!("Unexpected entry '{}'", line)));
+            }
+        }
+
+        Ok(interface)
+    }
+
+    /// Combine the interfaces of several libraries
+    ///
+    /// Fails when two interfaces provide the same type or word, including when one
+    /// interface is given twice. Interfaces can't refer to one another, so there are
+    /// no cycles to resolve beyond that.
+    pub fn merge(interfaces: impl IntoIterator<Item = Interface>) -> Result<Interface, String> {
+        let mut merged = Interface::default();
+        for interface in interfaces {
+            for typedef in interface.type_defs {
+                if merged.type_defs.iter().any(|t| t.name == typedef.name) {
+                    return Err(format!(
+                        "Type '{}' is provided by more than one library interface",
+                        typedef.name
+                    ));
+                }
+                merged.type_defs.push(typedef);
+            }
+            for word in interface.words {
+                if merged.words.iter().any(|w| w.name == word.name) {
+                    return Err(format!(
+                        "Word '{}' is provided by more than one library interface",
+                        word.name
+                    ));
+                }
+                merged.words.push(word);
+            }
+        }
+        Ok(merged)
+    }
+
+    /// Reject a program that defines a type or word this interface already provides
+    pub fn check_against(&self, program: &Program) -> Result<(), String> {
+        if let Some(typedef) = program
+            .type_defs
+            .iter()
+            .find(|t| self.type_defs.iter().any(|lib| lib.name == t.name))
+        {
+            return Err(format!(
+                "Type '{}' is defined by the program and by a library interface",
+                typedef.name
+            ));
+        }
+        if let Some(word) = program
+            .word_defs
+            .iter()
+            .find(|w| self.words.iter().any(|lib| lib.name == w.name))
+        {
+            return Err(format!(
+                "Word '{}' at {} is defined by the program and by a library interface",
+                word.name, word.loc
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Renders the interface file
+impl fmt::Display for Interface {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}", INTERFACE_HEADER)?;
+        for typedef in &self.type_defs {
+            writeln!(f, "{}", render_type_def(typedef))?;
+        }
+        for word in &self.words {
+            writeln!(
+                f,
+                "word {} {} {}",
+                render_field(&word.name),
+                render_field(&word.symbol),
+                word.effect
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Split the leading name or symbol off a `word` line, returning it and the rest of
+/// the line; a field with whitespace in it is quoted with backticks
+fn split_field(line: &str) -> Option<(&str, &str)> {
+    let line = line.trim_start();
+    let (field, rest) = match line.strip_prefix('`') {
+        Some(quoted) => quoted.split_once('`')?,
+        None => line.split_once(char::is_whitespace)?,
+    };
+    (!field.is_empty()).then_some((field, rest.trim_start()))
+}
+
+/// A name or symbol as `split_field` reads it back
+fn render_field(field: &str) -> String {
+    if field.contains(char::is_whitespace) {
+        format!("`{}`", field)
+    } else {
+        field.to_string()
+    }
+}
+
+/// Render a type definition in the syntax the parser accepts
+fn render_type_def(typedef: &TypeDef) -> String {
+    let mut out = format!("type {}", typedef.name);
+    if !typedef.type_params.is_empty() {
+        out.push_str(&format!("({})", typedef.type_params.join(" ")));
+    }
+    for variant in &typedef.variants {
+        out.push_str(&format!(" | {}", variant.name));
+        if !variant.fields.is_empty() {
+            let fields: Vec<String> = variant.fields.iter().map(|f| f.to_string()).collect();
+            out.push_str(&format!("({})", fields.join(" ")));
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::Parser;
+
+    #[test]
+    fn test_interface_lists_types_and_words() {
+        let program = Parser::new(
+            r#"
+            type Shape | Circle(Int) | Rect(Int Int)
+            : area ( Shape -- Int ) drop 0 ;
+            : add-one ( Int -- Int ) 1 + ;
+            "#,
+        )
+        .parse()
+        .unwrap();
+
+        assert_eq!(
+            Interface::from_program(&program, &[]).to_string(),
+            "cem-interface 1\n\
+             type Shape | Circle(Int) | Rect(Int Int)\n\
+             word area area ( Shape -- Int )\n\
+             word add-one add_one ( Int -- Int )\n"
+        );
+    }
+
+    #[test]
+    fn test_rendered_type_defs_parse_back() {
+        let source = "type Pair(A B) | Pair(A B) | Nothing";
+        let program = Parser::new(source).parse().unwrap();
+
+        let rendered = render_type_def(&program.type_defs[0]);
+        assert_eq!(rendered, source);
+        assert_eq!(Parser::new(&rendered).parse().unwrap(), program);
+    }
+
+    #[test]
+    fn test_interface_round_trips() {
+        let program = Parser::new(
+            r#"
+            type Option(T) | Some(T) | None
+            : unwrap-or ( Option(Int) Int -- Int ) swap drop ;
+            : main ( -- Int ) 0 ;
+            "#,
+        )
+        .parse()
+        .unwrap();
+
+        let interface = Interface::from_program(&program, &[]);
+        assert_eq!(interface.words[1].symbol, "cem_main");
+        assert_eq!(Interface::parse(&interface.to_string()), Ok(interface));
+    }
+
+    #[test]
+    fn test_backticked_names_and_symbols_round_trip() {
+        let source = "cem-interface 1\n\
+                      word `open file` `open file` ( String -- Int )\n\
+                      word c::close c::close ( Int -- )\n";
+        let interface = Interface::parse(source).unwrap();
+        assert_eq!(interface.words[0].name, "open file");
+        assert_eq!(interface.words[0].symbol, "open file");
+        assert_eq!(interface.words[1].name, "c::close");
+        assert_eq!(interface.to_string(), source);
+    }
+
+    #[test]
+    fn test_malformed_interfaces_are_rejected() {
+        assert!(Interface::parse("word square square ( Int -- Int )").is_err());
+
+        let err = Interface::parse("cem-interface 1\nword square square ( Int --").unwrap_err();
+        assert!(err.starts_with("line 2:"), "{}", err);
+    }
+
+    #[test]
+    fn test_conflicting_interfaces_are_rejected() {
+        let lib = Interface::parse("cem-interface 1\nword square square ( Int -- Int )").unwrap();
+
+        // The same library twice
+        let err = Interface::merge([lib.clone(), lib.clone()]).unwrap_err();
+        assert!(err.contains("Word 'square' is provided by more than one"));
+
+        // A program redefining a library word
+        let program = Parser::new(": square ( Int -- Int ) dup * ;")
+            .parse()
+            .unwrap();
+        assert!(lib.check_against(&program).is_err());
+    }
+}
@@ -8,7 +8,10 @@
 /// - LLVM code generation
 pub mod ast;
 pub mod codegen;
+pub mod interface;
 pub mod parser;
+pub mod repl;
+pub mod symbol;
 pub mod typechecker;
 
 pub use ast::types::{Effect, StackType, Type};
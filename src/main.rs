@@ -1,7 +1,19 @@
-use cemc::codegen::{CodeGen, link_program};
-use cemc::parser::Parser;
+use cemc::ast::Program;
+use cemc::ast::types::StackType;
+use cemc::codegen::{
+    CodeGen, CodeGenOptions, ControlFlowGraph, LinkOptions, Target, archive_objects,
+    compile_to_assembly_for_target, compile_to_object_with_options, link_program_with_libraries,
+    link_shared_library,
+};
+use cemc::interface::Interface;
+use cemc::parser::{DEFAULT_MAX_ERRORS, Parser};
+use cemc::repl::{Repl, ReplOutcome};
+use cemc::symbol::symbol_for_word;
+use cemc::typechecker::TypeChecker;
+use cemc::typechecker::environment::Environment;
 use clap::{CommandFactory, Parser as ClapParser, Subcommand};
 use std::fs;
+use std::io::{self, BufRead, Write};
 use std::path::Path;
 use std::process::Command;
 
@@ -17,21 +29,87 @@ struct Cli {
 
 #[derive(Subcommand)]
 enum Commands {
-    /// Compile a Cem source file to an executable
+    /// Compile Cem source files to an executable
     Compile {
-        /// Input Cem source file
-        #[arg(value_name = "INPUT")]
-        input: String,
+        /// Input Cem source files, compiled together as one program
+        #[arg(value_name = "INPUT", required = true)]
+        inputs: Vec<String>,
 
-        /// Output executable name (default: input filename without extension)
+        /// Output executable name (default: first input filename without extension)
         #[arg(short, long, value_name = "OUTPUT")]
         output: Option<String>,
 
         /// Keep intermediate LLVM IR file
         #[arg(long)]
         keep_ir: bool,
+
+        /// Keep every intermediate artifact next to the output: the IR (OUTPUT.ll), the
+        /// object file (OUTPUT.o), and the assembly (OUTPUT.s). Implies --keep-ir.
+        #[arg(long)]
+        save_temps: bool,
+
+        /// Write native assembly to OUTPUT.s instead of linking an executable
+        #[arg(long)]
+        emit_asm: bool,
+
+        /// Build a static library OUTPUT.a and its interface OUTPUT.cemi instead of an
+        /// executable
+        #[arg(long, conflicts_with = "emit_asm")]
+        lib: bool,
+
+        /// Build a shared library OUTPUT.so (OUTPUT.dylib on macOS) exporting every word,
+        /// and its interface OUTPUT.cemi, instead of an executable
+        #[arg(long, conflicts_with_all = ["emit_asm", "lib"])]
+        shared: bool,
+
+        /// Write a Graphviz graph of WORD's basic blocks to OUTPUT.dot instead of
+        /// linking an executable
+        #[arg(long, value_name = "WORD", conflicts_with_all = ["emit_asm", "lib", "shared"])]
+        dump_cfg: Option<String>,
+
+        /// Only check that each word's body leaves as many values as its effect
+        /// declares, counting pushes and pops without type checking or building
+        #[arg(long, conflicts_with_all = ["emit_asm", "lib", "shared", "dump_cfg"])]
+        check_stack_balance: bool,
+
+        /// Fail the build if a word's body leaves a different number of values than its
+        /// effect declares
+        #[arg(long)]
+        check_arity: bool,
+
+        /// Fail at exit with a runtime error unless the entry word leaves exactly the
+        /// values its effect declares
+        #[arg(long)]
+        strict_stack: bool,
+
+        /// Compilation target: native or wasm32 (wasm32-wasi)
+        #[arg(long, value_name = "TARGET", default_value = "native")]
+        target: Target,
+
+        /// Comma-separated features enabling `when(feature)` words
+        #[arg(long, value_name = "FEATURES", value_delimiter = ',')]
+        features: Vec<String>,
+
+        /// Interface of a precompiled library to build against; the program is linked
+        /// with the library archive next to it (FILE with a .a extension). Repeatable.
+        #[arg(long = "lib-interface", value_name = "FILE")]
+        lib_interfaces: Vec<String>,
+
+        /// Report at most N parse errors per file, then only count the rest
+        #[arg(long, value_name = "N", default_value_t = DEFAULT_MAX_ERRORS)]
+        max_errors: usize,
+
+        /// Log each compilation phase, the words compiled, and the clang command
+        #[arg(short, long)]
+        verbose: bool,
     },
 
+    /// Start an interactive session that prints the stack type after each input
+    Repl,
+
+    /// List every built-in word with its stack effect, sorted by name
+    Primitives,
+
     /// Generate shell completions for bash, zsh, fish, or powershell
     Completions {
         /// Shell to generate completions for
@@ -45,10 +123,55 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     match cli.command {
         Commands::Compile {
-            input,
+            inputs,
             output,
             keep_ir,
-        } => compile_command(&input, output.as_deref(), keep_ir),
+            save_temps,
+            emit_asm,
+            lib,
+            shared,
+            dump_cfg,
+            check_stack_balance,
+            check_arity,
+            strict_stack,
+            target,
+            features,
+            lib_interfaces,
+            max_errors,
+            verbose,
+        } => compile_command(
+            &inputs,
+            output.as_deref(),
+            CompileOptions {
+                keep_ir: keep_ir || save_temps,
+                save_temps,
+                kind: if check_stack_balance {
+                    OutputKind::StackBalanceReport
+                } else if let Some(word) = dump_cfg {
+                    OutputKind::ControlFlowGraph(word)
+                } else if lib {
+                    OutputKind::Library
+                } else if shared {
+                    OutputKind::SharedLibrary
+                } else if emit_asm {
+                    OutputKind::Assembly
+                } else {
+                    OutputKind::Executable
+                },
+                check_arity,
+                strict_stack,
+                target,
+                features,
+                lib_interfaces,
+                max_errors,
+                verbose,
+            },
+        ),
+        Commands::Repl => repl_command(),
+        Commands::Primitives => {
+            print_primitives();
+            Ok(())
+        }
         Commands::Completions { shell } => {
             generate_completions(shell);
             Ok(())
@@ -56,59 +179,231 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     }
 }
 
+/// What `compile` produces
+#[derive(Clone, PartialEq)]
+enum OutputKind {
+    /// An executable linked with the runtime
+    Executable,
+    /// Native assembly (OUTPUT.s)
+    Assembly,
+    /// A static library (OUTPUT.a) and its interface file (OUTPUT.cemi)
+    Library,
+    /// A shared library (OUTPUT.so or OUTPUT.dylib) and its interface file (OUTPUT.cemi)
+    SharedLibrary,
+    /// A Graphviz graph of one word's basic blocks (OUTPUT.dot)
+    ControlFlowGraph(String),
+    /// Only a report, on stdout, of words whose bodies leave the wrong number of values
+    StackBalanceReport,
+}
+
+/// Flags of the `compile` command
+struct CompileOptions {
+    keep_ir: bool,
+    save_temps: bool,
+    kind: OutputKind,
+    check_arity: bool,
+    strict_stack: bool,
+    target: Target,
+    features: Vec<String>,
+    lib_interfaces: Vec<String>,
+    max_errors: usize,
+    verbose: bool,
+}
+
+/// Print each word whose body leaves a different number of values than it declares,
+/// failing if there are any
+fn report_stack_balance(
+    program: &Program,
+    library: &Interface,
+    target: Target,
+    features: Vec<String>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut codegen = CodeGen::with_options(CodeGenOptions {
+        target,
+        features,
+        ..CodeGenOptions::default()
+    });
+    for word in &library.words {
+        codegen.add_external_word(&word.name, &word.symbol, word.effect.clone());
+    }
+
+    let unbalanced = codegen.check_stack_balance(program)?;
+    for (word, error) in &unbalanced {
+        println!("{}: {}", word.loc, error);
+    }
+    if !unbalanced.is_empty() {
+        return Err(format!(
+            "{} word(s) leave the wrong number of values",
+            unbalanced.len()
+        )
+        .into());
+    }
+    println!("All words are stack balanced");
+    Ok(())
+}
+
 fn compile_command(
-    input_file: &str,
+    input_files: &[String],
     output_name: Option<&str>,
-    keep_ir: bool,
+    options: CompileOptions,
 ) -> Result<(), Box<dyn std::error::Error>> {
+    let CompileOptions {
+        keep_ir,
+        save_temps,
+        kind,
+        check_arity,
+        strict_stack,
+        target,
+        features,
+        lib_interfaces,
+        max_errors,
+        verbose,
+    } = options;
+
     // Determine output name
     let output_name = output_name.map(String::from).unwrap_or_else(|| {
-        // Default: strip .cem extension and use as output name
-        Path::new(input_file)
+        // Default: strip .cem extension of the first input and use as output name
+        Path::new(&input_files[0])
             .file_stem()
             .and_then(|s| s.to_str())
             .unwrap_or("output")
             .to_string()
     });
 
-    // Read source file
-    let source = fs::read_to_string(input_file)
-        .map_err(|e| format!("Failed to read {}: {}", input_file, e))?;
+    // Read and parse each source file, then merge them into one program
+    let mut programs = Vec::new();
+    for input_file in input_files {
+        let source = fs::read_to_string(input_file)
+            .map_err(|e| format!("Failed to read {}: {}", input_file, e))?;
+
+        if verbose {
+            println!("Parsing {}...", input_file);
+        }
+        let mut parser = Parser::new_with_filename(&source, input_file);
+        programs.push(parser.parse_all(max_errors).map_err(|e| e.to_string())?);
+    }
+    let program = Program::merge(programs)?;
+    if verbose {
+        println!(
+            "Parsed {} word(s) and {} type(s)",
+            program.word_defs.len(),
+            program.type_defs.len()
+        );
+    }
+
+    // Load the interfaces of precompiled libraries; each library's archive sits next to
+    // its interface
+    let mut interfaces = Vec::new();
+    for path in &lib_interfaces {
+        let source =
+            fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+        interfaces.push(Interface::parse(&source).map_err(|e| format!("{}: {}", path, e))?);
+    }
+    let library = Interface::merge(interfaces)?;
+    library.check_against(&program)?;
+    let archives: Vec<String> = lib_interfaces
+        .iter()
+        .map(|path| Path::new(path).with_extension("a").display().to_string())
+        .collect();
+
+    if kind == OutputKind::StackBalanceReport {
+        return report_stack_balance(&program, &library, target, features);
+    }
 
-    // Parse
-    println!("Parsing {}...", input_file);
-    let mut parser = Parser::new_with_filename(&source, input_file);
-    let program = parser.parse().map_err(|e| format!("Parse error: {}", e))?;
+    // Typecheck the whole program against the builtins and the libraries' interfaces
+    if verbose {
+        println!("Type checking...");
+    }
+    let mut checker = TypeChecker::new();
+    checker.set_features(features.clone());
+    checker.add_interface(&library);
+    checker.check_program(&program).map_err(|e| e.to_string())?;
+    for warning in checker.warnings() {
+        eprintln!("warning: {}", warning);
+    }
 
-    // Build runtime first
-    println!("Building runtime...");
-    let status = Command::new("just").arg("build-runtime").status()?;
+    // Build runtime first (non-native runtimes must be built ahead of time); assembly
+    // and static libraries are never linked here, so they don't need one
+    if matches!(kind, OutputKind::Executable | OutputKind::SharedLibrary) {
+        if target == Target::Native {
+            if verbose {
+                println!("Building runtime...");
+            }
+            let status = Command::new("just").arg("build-runtime").status()?;
 
-    if !status.success() {
-        return Err("Failed to build runtime".into());
+            if !status.success() {
+                return Err("Failed to build runtime".into());
+            }
+        } else if !Path::new(target.runtime_lib()).exists() {
+            return Err(format!(
+                "Runtime for target '{}' not found at {}",
+                target,
+                target.runtime_lib()
+            )
+            .into());
+        }
     }
 
     // Generate LLVM IR
-    println!("Generating LLVM IR...");
-    let mut codegen = CodeGen::new();
+    if verbose {
+        println!("Generating LLVM IR...");
+    }
+    let mut codegen = CodeGen::with_options(CodeGenOptions {
+        target,
+        features: features.clone(),
+        check_output_arity: check_arity,
+        strict_stack,
+        verbose,
+        // The program passed the type checker above
+        typed_dup: true,
+        ..CodeGenOptions::default()
+    });
+    for word in &library.words {
+        codegen.add_external_word(&word.name, &word.symbol, word.effect.clone());
+    }
+    let words: Vec<_> = program.enabled_words(&features).collect();
+
+    // An executable needs an entry word; an empty program can only be a library
+    if words.is_empty() && !matches!(kind, OutputKind::Library | OutputKind::SharedLibrary) {
+        return Err(format!(
+            "{} defines no words; an executable needs a 'main' word",
+            input_files.join(", ")
+        )
+        .into());
+    }
 
     // Find entry point (look for "main" word, or use first word if only one)
-    let has_main = program.word_defs.iter().any(|w| w.name == "main");
-    let entry_word = if has_main {
+    let has_main = words.iter().any(|w| w.name == "main");
+    let entry_word = if matches!(
+        kind,
+        OutputKind::Library | OutputKind::SharedLibrary | OutputKind::ControlFlowGraph(_)
+    ) {
+        None
+    } else if has_main {
         Some("main")
-    } else if program.word_defs.len() == 1 {
-        println!(
-            "Note: Using '{}' as entry point (no 'main' word found)",
-            program.word_defs[0].name
-        );
-        Some(program.word_defs[0].name.as_str())
+    } else if words.len() == 1 {
+        if verbose {
+            println!(
+                "Note: Using '{}' as entry point (no 'main' word found)",
+                words[0].name
+            );
+        }
+        Some(words[0].name.as_str())
     } else {
         eprintln!("Error: No 'main' word found and multiple words defined");
         eprintln!("Either define a 'main' word or compile a file with only one word");
         std::process::exit(1);
     };
 
-    let ir = codegen.compile_program_with_main(&program, entry_word)?;
+    // Library types take part in constructors and matches like the program's own
+    let mut with_library_types = program.clone();
+    with_library_types
+        .type_defs
+        .extend(library.type_defs.iter().cloned());
+    let ir = codegen.compile_program_with_main(&with_library_types, entry_word)?;
+    if verbose {
+        println!("Generated {} bytes of LLVM IR", ir.len());
+    }
 
     // Write IR to file
     let ir_file = format!("{}.ll", output_name);
@@ -117,21 +412,158 @@ fn compile_command(
         println!("Wrote LLVM IR to {}", ir_file);
     }
 
+    let link_options = LinkOptions {
+        target,
+        verbose,
+        save_temps,
+    };
+
+    if kind == OutputKind::Library {
+        if verbose {
+            println!("Compiling library...");
+        }
+        compile_to_object_with_options(&ir, &output_name, &link_options)?;
+        let object = format!("{}.o", output_name);
+        let archive = format!("{}.a", output_name);
+        archive_objects(std::slice::from_ref(&object), &archive)?;
+        if !save_temps {
+            fs::remove_file(&object).ok();
+        }
+        if !keep_ir {
+            fs::remove_file(&ir_file).ok();
+        }
+
+        let interface = format!("{}.cemi", output_name);
+        fs::write(
+            &interface,
+            Interface::from_program(&program, &features).to_string(),
+        )?;
+        println!(
+            "✅ Built library ./{} with interface ./{}",
+            archive, interface
+        );
+        return Ok(());
+    }
+
+    if kind == OutputKind::SharedLibrary {
+        if verbose {
+            println!("Linking shared library...");
+        }
+        let library_file = link_shared_library(
+            &ir,
+            target.runtime_lib(),
+            &archives,
+            &output_name,
+            &link_options,
+        )?;
+        if !keep_ir {
+            fs::remove_file(&ir_file).ok();
+        }
+
+        let interface = format!("{}.cemi", output_name);
+        fs::write(
+            &interface,
+            Interface::from_program(&program, &features).to_string(),
+        )?;
+        println!(
+            "✅ Built shared library ./{} with interface ./{}",
+            library_file, interface
+        );
+        return Ok(());
+    }
+
+    if let OutputKind::ControlFlowGraph(word) = &kind {
+        let graph = ControlFlowGraph::from_ir(&ir, &symbol_for_word(word))
+            .ok_or_else(|| format!("Word '{}' is not defined", word))?;
+        let dot_file = format!("{}.dot", output_name);
+        fs::write(&dot_file, graph.to_dot())?;
+        if !keep_ir {
+            fs::remove_file(&ir_file).ok();
+        }
+        println!(
+            "✅ Wrote control-flow graph of '{}' to ./{}",
+            word, dot_file
+        );
+        return Ok(());
+    }
+
+    if kind == OutputKind::Assembly {
+        if verbose {
+            println!("Generating assembly...");
+        }
+        compile_to_assembly_for_target(&ir, &output_name, target)?;
+        if !keep_ir {
+            fs::remove_file(&ir_file).ok();
+        }
+        println!("✅ Wrote assembly to ./{}.s", output_name);
+        return Ok(());
+    }
+
     // Link with runtime
-    println!("Linking...");
-    link_program(&ir, "runtime/libcem_runtime.a", &output_name)?;
+    if verbose {
+        println!("Linking...");
+    }
+    link_program_with_libraries(
+        &ir,
+        target.runtime_lib(),
+        &archives,
+        &output_name,
+        &link_options,
+    )?;
 
     // Clean up IR file unless --keep-ir was specified
     if !keep_ir {
         fs::remove_file(&ir_file).ok();
     }
 
-    println!("\n✅ Successfully compiled to ./{}", output_name);
-    println!("Run it with: ./{}", output_name);
+    println!("✅ Successfully compiled to ./{}", output_name);
 
     Ok(())
 }
 
+fn repl_command() -> Result<(), Box<dyn std::error::Error>> {
+    let mut repl = Repl::new();
+    let stdin = io::stdin();
+    let mut lines = stdin.lock().lines();
+
+    loop {
+        print!(
+            "{}",
+            if repl.is_continuing() {
+                "...> "
+            } else {
+                "cem> "
+            }
+        );
+        io::stdout().flush()?;
+
+        let Some(line) = lines.next() else {
+            println!();
+            return Ok(());
+        };
+        match repl.feed(&line?) {
+            ReplOutcome::Continue => {}
+            ReplOutcome::Stack(StackType::Empty) => println!("Stack: (empty)"),
+            ReplOutcome::Stack(stack) => println!("Stack: {}", stack),
+            ReplOutcome::Error(message) => eprintln!("Error: {}", message),
+        }
+    }
+}
+
+/// Print the words the type checker knows before reading any program
+///
+/// That environment holds only words a program can call, so nothing the compiler uses
+/// internally is listed, and every builtin the checker accepts (`some_if` included) is.
+fn print_primitives() {
+    let env = Environment::new();
+    let mut words: Vec<_> = env.words().collect();
+    words.sort_by_key(|(name, _)| *name);
+    let width = words.iter().map(|(name, _)| name.len()).max().unwrap_or(0);
+    for (name, effect) in words {
+        println!("{:width$}  {}", name, effect, width = width);
+    }
+}
+
 fn generate_completions(shell: clap_complete::Shell) {
     let mut cmd = Cli::command();
     let bin_name = cmd.get_name().to_string();
@@ -9,6 +9,10 @@ pub struct Token {
     pub lexeme: String,
     pub line: usize,
     pub column: usize,
+    /// Byte offset of the token's first character in the source
+    pub start: usize,
+    /// Byte offset just past the token's last character
+    pub end: usize,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -19,13 +23,18 @@ pub enum TokenKind {
     BoolLiteral,
 
     // Keywords
-    Type,  // type
-    Colon, // :
-    Pipe,  // |
-    Match, // match
-    End,   // end
-    If,    // if
-    Arrow, // =>
+    Type,        // type
+    Colon,       // :
+    Pipe,        // |
+    PipeForward, // |>
+    Match,       // match
+    End,         // end
+    If,          // if
+    Ifte,        // ifte
+    Elif,        // elif
+    While,       // while
+    Do,          // do
+    Arrow,       // =>
 
     // Delimiters
     LeftParen,    // (
@@ -37,6 +46,12 @@ pub enum TokenKind {
     // Identifier (word name, type name, variant name)
     Ident,
 
+    // Row variable in a stack effect (`..R`, the rest of the stack)
+    RowVar,
+
+    // Text the lexer rejected; the lexeme is the message saying why
+    Error,
+
     // End of file
     Eof,
 
@@ -44,23 +59,43 @@ pub enum TokenKind {
     Comment,
 }
 
+/// Longest string literal the lexer accepts, in bytes (1MB)
+pub const MAX_STRING_LENGTH: usize = 1_000_000;
+
 pub struct Lexer {
     input: Vec<char>,
     position: usize,
+    /// Byte offset of `position` in the source (chars may be multi-byte)
+    offset: usize,
     line: usize,
     column: usize,
+    /// Text of each `##` documentation comment line, with its line number
+    doc_lines: Vec<(usize, String)>,
 }
 
 impl Lexer {
+    /// Lex `input`, skipping a leading UTF-8 byte order mark (which some editors
+    /// write) so the first token is still at line 1, column 1
     pub fn new(input: &str) -> Self {
+        let bom = if input.starts_with('\u{FEFF}') { 1 } else { 0 };
         Lexer {
             input: input.chars().collect(),
-            position: 0,
+            position: bom,
+            offset: bom * '\u{FEFF}'.len_utf8(),
             line: 1,
             column: 1,
+            doc_lines: Vec::new(),
         }
     }
 
+    /// The `##` documentation comments seen so far, as `(line, text)` in source order
+    ///
+    /// Only a `##` that starts its line is documentation; the text is what follows the
+    /// marker, less one leading space.
+    pub fn doc_lines(&self) -> &[(usize, String)] {
+        &self.doc_lines
+    }
+
     pub fn next_token(&mut self) -> Token {
         self.skip_whitespace_and_comments();
 
@@ -70,6 +105,7 @@ impl Lexer {
 
         let start_line = self.line;
         let start_column = self.column;
+        let start_offset = self.offset;
         let c = self.peek();
 
         // Single-character tokens
@@ -81,6 +117,8 @@ impl Lexer {
                     lexeme: "(".to_string(),
                     line: start_line,
                     column: start_column,
+                    start: start_offset,
+                    end: self.offset,
                 };
             }
             ')' => {
@@ -90,6 +128,8 @@ impl Lexer {
                     lexeme: ")".to_string(),
                     line: start_line,
                     column: start_column,
+                    start: start_offset,
+                    end: self.offset,
                 };
             }
             '[' => {
@@ -99,6 +139,8 @@ impl Lexer {
                     lexeme: "[".to_string(),
                     line: start_line,
                     column: start_column,
+                    start: start_offset,
+                    end: self.offset,
                 };
             }
             ']' => {
@@ -108,6 +150,8 @@ impl Lexer {
                     lexeme: "]".to_string(),
                     line: start_line,
                     column: start_column,
+                    start: start_offset,
+                    end: self.offset,
                 };
             }
             ':' => {
@@ -117,6 +161,20 @@ impl Lexer {
                     lexeme: ":".to_string(),
                     line: start_line,
                     column: start_column,
+                    start: start_offset,
+                    end: self.offset,
+                };
+            }
+            '|' if self.peek_next() == Some('>') => {
+                self.advance();
+                self.advance();
+                return Token {
+                    kind: TokenKind::PipeForward,
+                    lexeme: "|>".to_string(),
+                    line: start_line,
+                    column: start_column,
+                    start: start_offset,
+                    end: self.offset,
                 };
             }
             '|' => {
@@ -126,6 +184,8 @@ impl Lexer {
                     lexeme: "|".to_string(),
                     line: start_line,
                     column: start_column,
+                    start: start_offset,
+                    end: self.offset,
                 };
             }
             '-' => {
@@ -139,6 +199,8 @@ impl Lexer {
                         lexeme: "--".to_string(),
                         line: start_line,
                         column: start_column,
+                        start: start_offset,
+                        end: self.offset,
                     };
                 } else if self.peek_next().is_some_and(|c| c.is_ascii_digit()) {
                     // It's a negative number
@@ -149,25 +211,24 @@ impl Lexer {
                 }
             }
             '=' => {
-                self.advance();
-                if self.peek() == '>' {
+                if self.peek_next() == Some('>') {
+                    self.advance();
                     self.advance();
                     return Token {
                         kind: TokenKind::Arrow,
                         lexeme: "=>".to_string(),
                         line: start_line,
                         column: start_column,
+                        start: start_offset,
+                        end: self.offset,
                     };
                 }
-                // Just '=' is an identifier (the equals word)
-                return Token {
-                    kind: TokenKind::Ident,
-                    lexeme: "=".to_string(),
-                    line: start_line,
-                    column: start_column,
-                };
+                // Otherwise '=' starts an operator word, lexed like every other operator
+                return self.identifier_or_keyword();
             }
+            '.' if self.peek_next() == Some('.') => return self.row_variable(),
             '"' => return self.string_literal(),
+            '`' => return self.quoted_identifier(),
             _ => {
                 if c.is_ascii_digit()
                     || (c == '-' && self.peek_next().is_some_and(|n| n.is_ascii_digit()))
@@ -186,9 +247,12 @@ impl Lexer {
             lexeme: c.to_string(),
             line: start_line,
             column: start_column,
+            start: start_offset,
+            end: self.offset,
         }
     }
 
+    /// Tokenize the whole input; the result always ends with exactly one `Eof` token
     pub fn tokenize(&mut self) -> Vec<Token> {
         let mut tokens = Vec::new();
         loop {
@@ -202,6 +266,14 @@ impl Lexer {
         tokens
     }
 
+    /// Tokenize the whole input for tooling such as syntax highlighters or editors
+    ///
+    /// Every token carries its `start..end` byte range in the source alongside its
+    /// line and column, so it can be mapped back to the exact source text.
+    pub fn tokenize_with_spans(&mut self) -> Vec<Token> {
+        self.tokenize()
+    }
+
     fn skip_whitespace_and_comments(&mut self) {
         loop {
             if self.is_at_end() {
@@ -209,18 +281,22 @@ impl Lexer {
             }
 
             match self.peek() {
-                ' ' | '\t' | '\r' => {
+                ' ' | '\t' => {
                     self.advance();
                 }
-                '\n' => {
-                    self.advance();
-                    self.line += 1;
-                    self.column = 0; // Will be incremented to 1 by next advance
-                }
+                '\n' | '\r' => self.line_break(),
                 '#' => {
+                    let is_doc = self.peek_next() == Some('#') && self.starts_line();
+                    let mut text = String::new();
                     // Comment until end of line
-                    while !self.is_at_end() && self.peek() != '\n' {
-                        self.advance();
+                    while !self.is_at_end() && !is_line_break(self.peek()) {
+                        text.push(self.advance());
+                    }
+                    if is_doc {
+                        let text = &text[2..];
+                        let text = text.strip_prefix(' ').unwrap_or(text);
+                        self.doc_lines
+                            .push((self.line, text.trim_end().to_string()));
                     }
                 }
                 _ => return,
@@ -229,10 +305,9 @@ impl Lexer {
     }
 
     fn string_literal(&mut self) -> Token {
-        const MAX_STRING_LENGTH: usize = 1_000_000; // 1MB limit
-
         let start_line = self.line;
         let start_column = self.column;
+        let start_offset = self.offset;
         self.advance(); // consume opening "
 
         let mut value = String::new();
@@ -241,23 +316,27 @@ impl Lexer {
             if value.len() >= MAX_STRING_LENGTH {
                 // Return error token
                 return Token {
-                    kind: TokenKind::Ident, // Use Ident for errors
+                    kind: TokenKind::Error,
                     lexeme: format!(
-                        "ERROR: String exceeds maximum length of {} bytes",
+                        "String exceeds maximum length of {} bytes",
                         MAX_STRING_LENGTH
                     ),
                     line: start_line,
                     column: start_column,
+                    start: start_offset,
+                    end: self.offset,
                 };
             }
 
-            if self.peek() == '\n' {
+            if is_line_break(self.peek()) {
                 // Unterminated string (newline before closing quote)
                 return Token {
-                    kind: TokenKind::Ident,
-                    lexeme: "ERROR: Unterminated string literal (newline)".to_string(),
+                    kind: TokenKind::Error,
+                    lexeme: "Unterminated string literal (newline)".to_string(),
                     line: start_line,
                     column: start_column,
+                    start: start_offset,
+                    end: self.offset,
                 };
             }
 
@@ -284,10 +363,12 @@ impl Lexer {
         if self.is_at_end() {
             // Unterminated string (EOF before closing quote)
             return Token {
-                kind: TokenKind::Ident,
-                lexeme: "ERROR: Unterminated string literal (EOF)".to_string(),
+                kind: TokenKind::Error,
+                lexeme: "Unterminated string literal (EOF)".to_string(),
                 line: start_line,
                 column: start_column,
+                start: start_offset,
+                end: self.offset,
             };
         }
 
@@ -298,12 +379,15 @@ impl Lexer {
             lexeme: value,
             line: start_line,
             column: start_column,
+            start: start_offset,
+            end: self.offset,
         }
     }
 
     fn number_literal(&mut self) -> Token {
         let start_line = self.line;
         let start_column = self.column;
+        let start_offset = self.offset;
         let mut value = String::new();
 
         // Handle negative sign
@@ -312,9 +396,36 @@ impl Lexer {
             self.advance();
         }
 
-        while !self.is_at_end() && self.peek().is_ascii_digit() {
-            value.push(self.peek());
-            self.advance();
+        // Radix prefix: 0x (hex) or 0b (binary). Digits are validated by the parser.
+        if self.peek() == '0' && matches!(self.peek_next(), Some('x' | 'X' | 'b' | 'B')) {
+            value.push(self.advance());
+            value.push(self.advance());
+            while !self.is_at_end() && (self.peek().is_ascii_alphanumeric() || self.peek() == '_') {
+                value.push(self.peek());
+                self.advance();
+            }
+        } else {
+            // Decimal digits, with optional '_' separators (1_000_000)
+            while !self.is_at_end() && (self.peek().is_ascii_digit() || self.peek() == '_') {
+                value.push(self.peek());
+                self.advance();
+            }
+
+            // Digits running into letters name a word instead (2dup, 2drop)
+            if !value.starts_with('-') && self.peek().is_alphabetic() {
+                while !self.is_at_end() && is_identifier_char(self.peek()) {
+                    value.push(self.peek());
+                    self.advance();
+                }
+                return Token {
+                    kind: TokenKind::Ident,
+                    lexeme: value,
+                    line: start_line,
+                    column: start_column,
+                    start: start_offset,
+                    end: self.offset,
+                };
+            }
         }
 
         Token {
@@ -322,17 +433,20 @@ impl Lexer {
             lexeme: value,
             line: start_line,
             column: start_column,
+            start: start_offset,
+            end: self.offset,
         }
     }
 
     fn identifier_or_keyword(&mut self) -> Token {
         let start_line = self.line;
         let start_column = self.column;
+        let start_offset = self.offset;
         let mut value = String::new();
 
         while !self.is_at_end() {
             let c = self.peek();
-            if c.is_alphanumeric() || c == '_' || c == '-' || is_operator_char(c) {
+            if is_identifier_char(c) {
                 value.push(c);
                 self.advance();
             } else {
@@ -345,6 +459,10 @@ impl Lexer {
             "match" => TokenKind::Match,
             "end" => TokenKind::End,
             "if" => TokenKind::If,
+            "ifte" => TokenKind::Ifte,
+            "elif" => TokenKind::Elif,
+            "while" => TokenKind::While,
+            "do" => TokenKind::Do,
             "true" | "false" => TokenKind::BoolLiteral,
             _ => TokenKind::Ident,
         };
@@ -354,6 +472,73 @@ impl Lexer {
             lexeme: value,
             line: start_line,
             column: start_column,
+            start: start_offset,
+            end: self.offset,
+        }
+    }
+
+    /// Lex `` `name` ``, an identifier spelled verbatim; the token is an `Ident` whose
+    /// lexeme is the text between the backticks
+    ///
+    /// This names words that aren't valid Cem identifiers, such as C functions bound by
+    /// a library interface. The name can't be empty or span lines; either is an `Error`
+    /// token.
+    fn quoted_identifier(&mut self) -> Token {
+        let start_line = self.line;
+        let start_column = self.column;
+        let start_offset = self.offset;
+        self.advance(); // consume opening `
+
+        let mut name = String::new();
+        while !self.is_at_end() && self.peek() != '`' && !is_line_break(self.peek()) {
+            name.push(self.advance());
+        }
+
+        let (kind, lexeme) = if self.is_at_end() || is_line_break(self.peek()) {
+            (
+                TokenKind::Error,
+                "Unterminated quoted identifier".to_string(),
+            )
+        } else {
+            self.advance(); // consume closing `
+            if name.is_empty() {
+                (TokenKind::Error, "Empty quoted identifier".to_string())
+            } else {
+                (TokenKind::Ident, name)
+            }
+        };
+
+        Token {
+            kind,
+            lexeme,
+            line: start_line,
+            column: start_column,
+            start: start_offset,
+            end: self.offset,
+        }
+    }
+
+    /// Lex `..Name`; the token's lexeme is the name without the dots
+    fn row_variable(&mut self) -> Token {
+        let start_line = self.line;
+        let start_column = self.column;
+        let start_offset = self.offset;
+        self.advance();
+        self.advance();
+
+        let mut name = String::new();
+        while !self.is_at_end() && is_identifier_char(self.peek()) {
+            name.push(self.peek());
+            self.advance();
+        }
+
+        Token {
+            kind: TokenKind::RowVar,
+            lexeme: name,
+            line: start_line,
+            column: start_column,
+            start: start_offset,
+            end: self.offset,
         }
     }
 
@@ -363,6 +548,8 @@ impl Lexer {
             lexeme: lexeme.to_string(),
             line: self.line,
             column: self.column,
+            start: self.offset,
+            end: self.offset,
         }
     }
 
@@ -374,6 +561,15 @@ impl Lexer {
         }
     }
 
+    /// Whether only whitespace precedes the current character on its line
+    fn starts_line(&self) -> bool {
+        self.input[..self.position]
+            .iter()
+            .rev()
+            .take_while(|&&c| !is_line_break(c))
+            .all(|c| c.is_whitespace())
+    }
+
     fn peek_next(&self) -> Option<char> {
         if self.position + 1 < self.input.len() {
             Some(self.input[self.position + 1])
@@ -384,20 +580,40 @@ impl Lexer {
 
     fn advance(&mut self) -> char {
         let c = self.peek();
+        if !self.is_at_end() {
+            self.offset += c.len_utf8();
+        }
         self.position += 1;
         self.column += 1;
         c
     }
 
+    /// Consume one line break (`\n`, `\r\n`, or a lone `\r`) and start the next line
+    fn line_break(&mut self) {
+        if self.advance() == '\r' && self.peek() == '\n' {
+            self.advance();
+        }
+        self.line += 1;
+        self.column = 1;
+    }
+
     fn is_at_end(&self) -> bool {
         self.position >= self.input.len()
     }
 }
 
+fn is_line_break(c: char) -> bool {
+    c == '\n' || c == '\r'
+}
+
 fn is_operator_char(c: char) -> bool {
     matches!(c, '+' | '-' | '*' | '/' | '<' | '>' | '=' | '!')
 }
 
+fn is_identifier_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '-' || is_operator_char(c)
+}
+
 impl fmt::Display for TokenKind {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -407,9 +623,14 @@ impl fmt::Display for TokenKind {
             TokenKind::Type => write!(f, "type"),
             TokenKind::Colon => write!(f, ":"),
             TokenKind::Pipe => write!(f, "|"),
+            TokenKind::PipeForward => write!(f, "|>"),
             TokenKind::Match => write!(f, "match"),
             TokenKind::End => write!(f, "end"),
             TokenKind::If => write!(f, "if"),
+            TokenKind::Ifte => write!(f, "ifte"),
+            TokenKind::Elif => write!(f, "elif"),
+            TokenKind::While => write!(f, "while"),
+            TokenKind::Do => write!(f, "do"),
             TokenKind::Arrow => write!(f, "=>"),
             TokenKind::LeftParen => write!(f, "("),
             TokenKind::RightParen => write!(f, ")"),
@@ -417,6 +638,8 @@ impl fmt::Display for TokenKind {
             TokenKind::RightBracket => write!(f, "]"),
             TokenKind::Dash => write!(f, "--"),
             TokenKind::Ident => write!(f, "IDENT"),
+            TokenKind::RowVar => write!(f, "ROWVAR"),
+            TokenKind::Error => write!(f, "ERROR"),
             TokenKind::Eof => write!(f, "EOF"),
             TokenKind::Comment => write!(f, "COMMENT"),
         }
@@ -453,6 +676,19 @@ mod tests {
         assert_eq!(tokens[2].lexeme, "0");
     }
 
+    #[test]
+    fn test_radix_and_separated_numbers() {
+        let mut lexer = Lexer::new("0xFF -0b101 1_000");
+        let tokens = lexer.tokenize();
+
+        assert_eq!(tokens[0].kind, TokenKind::IntLiteral);
+        assert_eq!(tokens[0].lexeme, "0xFF");
+        assert_eq!(tokens[1].kind, TokenKind::IntLiteral);
+        assert_eq!(tokens[1].lexeme, "-0b101");
+        assert_eq!(tokens[2].kind, TokenKind::IntLiteral);
+        assert_eq!(tokens[2].lexeme, "1_000");
+    }
+
     #[test]
     fn test_strings() {
         let mut lexer = Lexer::new(r#""hello" "world\n""#);
@@ -475,6 +711,46 @@ mod tests {
         assert_eq!(tokens[7].lexeme, "dup");
     }
 
+    #[test]
+    fn test_operators_are_idents() {
+        let mut lexer = Lexer::new("+ - * / < > = <= >= != =>");
+        let tokens = lexer.tokenize();
+
+        // Every operator word is an Ident carrying its own text; only => is special
+        let expected = ["+", "-", "*", "/", "<", ">", "=", "<=", ">=", "!="];
+        for (token, op) in tokens.iter().zip(expected.iter()) {
+            assert_eq!(token.kind, TokenKind::Ident);
+            assert_eq!(token.lexeme, *op);
+        }
+        assert_eq!(tokens[10].kind, TokenKind::Arrow);
+    }
+
+    #[test]
+    fn test_words_starting_with_digits() {
+        let mut lexer = Lexer::new("2dup 2drop 2 1_000");
+        let tokens = lexer.tokenize();
+
+        assert_eq!(tokens[0].kind, TokenKind::Ident);
+        assert_eq!(tokens[0].lexeme, "2dup");
+        assert_eq!(tokens[1].kind, TokenKind::Ident);
+        assert_eq!(tokens[1].lexeme, "2drop");
+        assert_eq!(tokens[2].kind, TokenKind::IntLiteral);
+        assert_eq!(tokens[3].kind, TokenKind::IntLiteral);
+        assert_eq!(tokens[3].lexeme, "1_000");
+    }
+
+    #[test]
+    fn test_row_variables() {
+        let mut lexer = Lexer::new("( ..R X -- ..S )");
+        let tokens = lexer.tokenize();
+
+        assert_eq!(tokens[1].kind, TokenKind::RowVar);
+        assert_eq!(tokens[1].lexeme, "R");
+        assert_eq!((tokens[1].start, tokens[1].end), (2, 5));
+        assert_eq!(tokens[4].kind, TokenKind::RowVar);
+        assert_eq!(tokens[4].lexeme, "S");
+    }
+
     #[test]
     fn test_comments() {
         let mut lexer = Lexer::new("# comment\n42");
@@ -490,20 +766,63 @@ mod tests {
         let tokens = lexer.tokenize();
 
         // Should get an error token
-        assert!(tokens[0].lexeme.starts_with("ERROR"));
+        assert_eq!(tokens[0].kind, TokenKind::Error);
         assert!(tokens[0].lexeme.contains("Unterminated"));
     }
 
+    #[test]
+    fn test_backticks_quote_an_identifier_verbatim() {
+        let tokens = Lexer::new("`some-c-name` `ns::open file` `match`").tokenize();
+        assert_eq!(tokens[0].kind, TokenKind::Ident);
+        assert_eq!(tokens[0].lexeme, "some-c-name");
+        assert_eq!((tokens[0].start, tokens[0].end), (0, 13));
+        assert_eq!(tokens[1].kind, TokenKind::Ident);
+        assert_eq!(tokens[1].lexeme, "ns::open file");
+        // A quoted keyword is an ordinary identifier
+        assert_eq!(tokens[2].kind, TokenKind::Ident);
+        assert_eq!(tokens[2].lexeme, "match");
+
+        for source in ["`open\n`", "`open", "``"] {
+            let tokens = Lexer::new(source).tokenize();
+            assert_eq!(tokens[0].kind, TokenKind::Error, "{:?}", source);
+        }
+    }
+
     #[test]
     fn test_unterminated_string_eof() {
         let mut lexer = Lexer::new("\"hello");
         let tokens = lexer.tokenize();
 
         // Should get an error token
-        assert!(tokens[0].lexeme.starts_with("ERROR"));
+        assert_eq!(tokens[0].kind, TokenKind::Error);
         assert!(tokens[0].lexeme.contains("Unterminated"));
     }
 
+    #[test]
+    fn test_crlf_and_lone_cr_end_lines() {
+        let lf = ": square ( Int -- Int ) # squares\n  dup *\n;\n\n: s ( -- ) \"a\\r\" drop ;";
+        for source in [lf.replace('\n', "\r\n"), lf.replace('\n', "\r")] {
+            let tokens = Lexer::new(&source).tokenize();
+            let lines: Vec<_> = tokens.iter().map(|t| (t.line, t.column)).collect();
+            let expected: Vec<_> = Lexer::new(lf)
+                .tokenize()
+                .iter()
+                .map(|t| (t.line, t.column))
+                .collect();
+            assert_eq!(lines, expected);
+            assert_eq!(tokens[7].line, 2);
+            // An escaped \r in a string is kept
+            assert_eq!(tokens[15].lexeme, "a\r");
+            assert!(tokens.iter().all(|t| !t.lexeme.contains('\n')));
+        }
+
+        let crlf = lf.replace('\n', "\r\n");
+        assert_eq!(
+            crate::parser::Parser::new(&crlf).parse().unwrap(),
+            crate::parser::Parser::new(lf).parse().unwrap()
+        );
+    }
+
     #[test]
     fn test_valid_string() {
         let mut lexer = Lexer::new("\"hello world\"");
@@ -540,7 +859,35 @@ mod tests {
         let tokens = lexer.tokenize();
 
         // Should get an error token
-        assert!(tokens[0].lexeme.starts_with("ERROR"));
+        assert_eq!(tokens[0].kind, TokenKind::Error);
         assert!(tokens[0].lexeme.contains("maximum length"));
     }
+
+    #[test]
+    fn test_token_byte_offsets() {
+        let source = ": x ( -- ) ;";
+        let tokens = Lexer::new(source).tokenize_with_spans();
+
+        let colon = &tokens[0];
+        assert_eq!(colon.kind, TokenKind::Colon);
+        assert_eq!((colon.start, colon.end), (0, 1));
+
+        let semicolon = &tokens[5];
+        assert_eq!(semicolon.lexeme, ";");
+        assert_eq!((semicolon.start, semicolon.end), (11, 12));
+
+        // Spans slice back to each token's source text
+        for token in &tokens[..tokens.len() - 1] {
+            assert_eq!(&source[token.start..token.end], token.lexeme);
+        }
+    }
+
+    #[test]
+    fn test_token_byte_offsets_after_multibyte_chars() {
+        // "é" is two bytes, so `x` starts at byte 5 though it is the 5th char
+        let tokens = Lexer::new("\"é\" x").tokenize_with_spans();
+
+        assert_eq!((tokens[0].start, tokens[0].end), (0, 4));
+        assert_eq!((tokens[1].start, tokens[1].end), (5, 6));
+    }
 }
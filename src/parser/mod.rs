@@ -4,8 +4,8 @@
 mod lexer;
 mod parse;
 
-pub use lexer::{Lexer, Token, TokenKind};
-pub use parse::{ParseError, Parser};
+pub use lexer::{Lexer, MAX_STRING_LENGTH, Token, TokenKind};
+pub use parse::{DEFAULT_MAX_ERRORS, ParseError, ParseErrors, Parser, parse_effect, parse_str};
 
 #[cfg(test)]
 mod tests;
@@ -1,6 +1,8 @@
 /// Recursive descent parser for Cem
-use crate::ast::types::{Effect, Type};
-use crate::ast::{Expr, MatchBranch, Pattern, Program, TypeDef, Variant, WordDef};
+use crate::ast::types::{Effect, StackType, Type};
+use crate::ast::{
+    Expr, InlineHint, MatchBranch, Pattern, Program, SlotNames, TypeDef, Variant, WordDef,
+};
 use crate::parser::lexer::{Lexer, Token, TokenKind};
 use std::fmt;
 use std::sync::Arc;
@@ -24,14 +26,78 @@ impl fmt::Display for ParseError {
 
 impl std::error::Error for ParseError {}
 
-const MAX_NESTING_DEPTH: usize = 100;
+/// Default number of errors `parse_all` reports before only counting the rest
+pub const DEFAULT_MAX_ERRORS: usize = 20;
+
+/// Every error found by `parse_all`, up to its limit
+#[derive(Debug, Clone)]
+pub struct ParseErrors {
+    /// The reported errors, in source order
+    pub errors: Vec<ParseError>,
+    /// How many further errors were found but not kept
+    pub omitted: usize,
+}
+
+impl fmt::Display for ParseErrors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, error) in self.errors.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{}", error)?;
+        }
+        if self.omitted > 0 {
+            let plural = if self.omitted == 1 { "" } else { "s" };
+            write!(f, "\n... and {} more error{}", self.omitted, plural)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ParseErrors {}
+
+/// Parse a complete program from source text
+///
+/// Never panics, whatever the input: malformed text (including invalid escapes, huge
+/// literals, and deep nesting) is reported as a `ParseError`, which makes this the
+/// entry point for fuzzing.
+pub fn parse_str(input: &str) -> Result<Program, ParseError> {
+    Parser::new(input).parse()
+}
+
+/// Parse a stack effect signature on its own, e.g. `( ..A Int -- ..A Bool )`
+///
+/// This is the inverse of `Effect`'s `Display`, the one rendering used wherever an
+/// effect is written out (interface files, messages): parsing a displayed effect gives
+/// back the same effect, row variables and quotation types included. Slot names are
+/// accepted and dropped.
+pub fn parse_effect(input: &str) -> Result<Effect, ParseError> {
+    let mut parser = Parser::new(input);
+    parser.consume(&TokenKind::LeftParen, "Expected '(' for effect signature")?;
+    let (effect, _) = parser.parse_effect()?;
+    parser.consume(
+        &TokenKind::RightParen,
+        "Expected ')' after effect signature",
+    )?;
+    if !parser.is_at_end() {
+        return Err(parser.error("Unexpected input after effect signature"));
+    }
+    Ok(effect)
+}
+
+/// Default limit on nested quotations, matches, and ifs (see `set_max_nesting_depth`)
+pub const DEFAULT_MAX_NESTING_DEPTH: usize = 256;
 
 pub struct Parser {
     tokens: Vec<Token>,
     current: usize,
     nesting_depth: usize,
+    /// Deepest nesting accepted before parsing fails, bounding recursion on hostile input
+    max_nesting_depth: usize,
     /// Arc-wrapped filename to avoid duplication across all SourceLocs
     filename: Arc<str>,
+    /// `##` documentation comment lines from the lexer, as `(line, text)`
+    doc_lines: Vec<(usize, String)>,
 }
 
 impl Parser {
@@ -46,10 +112,17 @@ impl Parser {
             tokens,
             current: 0,
             nesting_depth: 0,
+            max_nesting_depth: DEFAULT_MAX_NESTING_DEPTH,
             filename: Arc::from(filename),
+            doc_lines: lexer.doc_lines().to_vec(),
         }
     }
 
+    /// Set how deeply quotations, matches, and ifs may nest before parsing fails
+    pub fn set_max_nesting_depth(&mut self, depth: usize) {
+        self.max_nesting_depth = depth;
+    }
+
     /// Helper: Create SourceLoc from current token
     fn current_loc(&self) -> crate::ast::SourceLoc {
         let token = self.peek();
@@ -62,26 +135,90 @@ impl Parser {
     }
 
     pub fn parse(&mut self) -> Result<Program, ParseError> {
-        let mut type_defs = Vec::new();
-        let mut word_defs = Vec::new();
+        let mut program = Program {
+            type_defs: Vec::new(),
+            word_defs: Vec::new(),
+        };
+        while !self.is_at_end() {
+            self.parse_definition(&mut program)?;
+        }
+        Ok(program)
+    }
 
+    /// Parse a program, recovering at the next definition after each error
+    ///
+    /// The first `max_errors` errors are kept; later ones are only counted.
+    pub fn parse_all(&mut self, max_errors: usize) -> Result<Program, ParseErrors> {
+        let mut program = Program {
+            type_defs: Vec::new(),
+            word_defs: Vec::new(),
+        };
+        let mut errors = ParseErrors {
+            errors: Vec::new(),
+            omitted: 0,
+        };
         while !self.is_at_end() {
-            if self.check(&TokenKind::Type) {
-                type_defs.push(self.parse_type_def()?);
-            } else if self.check(&TokenKind::Colon) {
-                word_defs.push(self.parse_word_def()?);
-            } else {
-                return Err(self.error("Expected 'type' or ':'"));
+            let start = self.current;
+            if let Err(error) = self.parse_definition(&mut program) {
+                if errors.errors.len() < max_errors {
+                    errors.errors.push(error);
+                } else {
+                    errors.omitted += 1;
+                }
+                self.synchronize(start);
             }
         }
 
-        Ok(Program {
-            type_defs,
-            word_defs,
-        })
+        if errors.errors.is_empty() && errors.omitted == 0 {
+            Ok(program)
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Parse one top-level definition into `program`
+    fn parse_definition(&mut self, program: &mut Program) -> Result<(), ParseError> {
+        if self.check(&TokenKind::Type) {
+            program.type_defs.push(self.parse_type_def()?);
+        } else if self.check(&TokenKind::Colon) {
+            program.word_defs.push(self.parse_word_def()?);
+        } else if self.check_ident("when") {
+            program.word_defs.push(self.parse_gated_word_def()?);
+        } else if self.check_ident("inline") || self.check_ident("noinline") {
+            program.word_defs.push(self.parse_attributed_word_def()?);
+        } else {
+            return Err(self.error("Expected 'type', 'when', 'inline', 'noinline', or ':'"));
+        }
+        Ok(())
+    }
+
+    /// Skip to the start of the next definition after an error in the one at `start`
+    fn synchronize(&mut self, start: usize) {
+        if self.current == start {
+            self.advance();
+        }
+        while !self.is_at_end()
+            && !self.check(&TokenKind::Type)
+            && !self.check(&TokenKind::Colon)
+            && !self.check_ident("when")
+            && !self.check_ident("inline")
+            && !self.check_ident("noinline")
+        {
+            self.advance();
+        }
+    }
+
+    /// Parse a bare sequence of expressions with no surrounding definition (REPL input)
+    pub fn parse_exprs(&mut self) -> Result<Vec<Expr>, ParseError> {
+        let mut exprs = Vec::new();
+        while !self.is_at_end() {
+            self.parse_expr(&mut exprs)?;
+        }
+        Ok(exprs)
     }
 
     fn parse_type_def(&mut self) -> Result<TypeDef, ParseError> {
+        let doc = self.doc_before(self.peek().line);
         self.consume(&TokenKind::Type, "Expected 'type'")?;
 
         let name = self.consume_ident("Expected type name")?;
@@ -99,7 +236,14 @@ impl Parser {
             self.consume(&TokenKind::RightParen, "Expected ')'")?;
         }
 
-        self.consume(&TokenKind::Pipe, "Expected '|' before first variant")?;
+        // Optional `=` and leading `|` before the first variant:
+        //   type Color = Red | Green    type Color | Red | Green    type Color Red | Green
+        if self.check_ident("=") {
+            self.advance();
+        }
+        if self.check(&TokenKind::Pipe) {
+            self.advance();
+        }
 
         // Parse variants
         let mut variants = Vec::new();
@@ -112,9 +256,7 @@ impl Parser {
                 self.advance();
                 while !self.check(&TokenKind::RightParen) && !self.is_at_end() {
                     fields.push(self.parse_type()?);
-                    if self.check(&TokenKind::RightParen) {
-                        break;
-                    }
+                    self.skip_comma();
                 }
                 self.consume(&TokenKind::RightParen, "Expected ')'")?;
             }
@@ -136,6 +278,7 @@ impl Parser {
             name,
             type_params,
             variants,
+            doc,
         })
     }
 
@@ -147,7 +290,7 @@ impl Parser {
 
         // Parse effect signature
         self.consume(&TokenKind::LeftParen, "Expected '(' for effect signature")?;
-        let effect = self.parse_effect()?;
+        let (effect, slot_names) = self.parse_effect()?;
         self.consume(
             &TokenKind::RightParen,
             "Expected ')' after effect signature",
@@ -156,7 +299,7 @@ impl Parser {
         // Parse body until ';'
         let mut body = Vec::new();
         while !self.check_ident(";") && !self.is_at_end() {
-            body.push(self.parse_expr()?);
+            self.parse_expr(&mut body)?;
         }
 
         self.consume_ident_value(";", "Expected ';' at end of word definition")?;
@@ -166,25 +309,142 @@ impl Parser {
             effect,
             body,
             loc: self.loc_from_token(&colon_token),
+            feature: None,
+            inline: None,
+            slot_names,
+            doc: self.doc_before(colon_token.line),
         })
     }
 
-    fn parse_effect(&mut self) -> Result<Effect, ParseError> {
-        // Parse input stack types
-        let mut inputs = Vec::new();
-        while !self.check(&TokenKind::Dash) && !self.is_at_end() {
-            inputs.push(self.parse_type()?);
+    /// Parse `when(feature) : word ( ... ) ... ;`
+    fn parse_gated_word_def(&mut self) -> Result<WordDef, ParseError> {
+        let doc = self.doc_before(self.peek().line);
+        self.consume_ident_value("when", "Expected 'when'")?;
+        self.consume(&TokenKind::LeftParen, "Expected '(' after 'when'")?;
+        let feature = self.consume_ident("Expected feature name")?;
+        self.consume(&TokenKind::RightParen, "Expected ')' after feature name")?;
+
+        let mut word = self.parse_word_def()?;
+        word.feature = Some(feature);
+        word.doc = doc.or(word.doc);
+        Ok(word)
+    }
+
+    /// Parse `inline : word ( ... ) ... ;` or `noinline : ...`, optionally gated as
+    /// `inline when(feature) : ...`
+    fn parse_attributed_word_def(&mut self) -> Result<WordDef, ParseError> {
+        let doc = self.doc_before(self.peek().line);
+        let hint = if self.check_ident("inline") {
+            InlineHint::Always
+        } else {
+            InlineHint::Never
+        };
+        self.advance();
+
+        let mut word = if self.check_ident("when") {
+            self.parse_gated_word_def()?
+        } else {
+            self.parse_word_def()?
+        };
+        word.inline = Some(hint);
+        word.doc = doc.or(word.doc);
+        Ok(word)
+    }
+
+    /// The documentation of a definition starting on `line`: the `##` comment lines
+    /// directly above it, joined with newlines
+    fn doc_before(&self, line: usize) -> Option<String> {
+        let mut lines = Vec::new();
+        let mut expected = line.saturating_sub(1);
+        for (doc_line, text) in self.doc_lines.iter().rev() {
+            if *doc_line > expected {
+                continue;
+            }
+            if *doc_line < expected {
+                break;
+            }
+            lines.push(text.as_str());
+            expected = expected.saturating_sub(1);
+        }
+        if lines.is_empty() {
+            return None;
         }
+        lines.reverse();
+        Some(lines.join("\n"))
+    }
+
+    fn parse_effect(&mut self) -> Result<(Effect, SlotNames), ParseError> {
+        self.parse_effect_until(&TokenKind::RightParen)
+    }
+
+    /// Parse `inputs -- outputs` up to (not including) `close`
+    fn parse_effect_until(&mut self, close: &TokenKind) -> Result<(Effect, SlotNames), ParseError> {
+        let mut names = SlotNames::default();
+
+        // Parse input stack types
+        let inputs = self.parse_effect_side(&TokenKind::Dash, &mut names.inputs)?;
 
         self.consume(&TokenKind::Dash, "Expected '--' in effect signature")?;
 
         // Parse output stack types
-        let mut outputs = Vec::new();
-        while !self.check(&TokenKind::RightParen) && !self.is_at_end() {
-            outputs.push(self.parse_type()?);
+        let outputs = self.parse_effect_side(close, &mut names.outputs)?;
+
+        Ok((Effect::new(inputs, outputs), names))
+    }
+
+    /// Parse one side of an effect: an optional leading row variable, then slots
+    /// (bottom to top) up to `end`
+    fn parse_effect_side(
+        &mut self,
+        end: &TokenKind,
+        names: &mut Vec<Option<String>>,
+    ) -> Result<StackType, ParseError> {
+        let mut stack = StackType::Empty;
+        if self.check(&TokenKind::RowVar) {
+            stack = StackType::RowVar(self.consume_row_var()?);
+        }
+
+        while !self.check(end) && !self.is_at_end() {
+            if self.check(&TokenKind::RowVar) {
+                return Err(self.error(&format!(
+                    "Row variable '..{}' must come first: it stands for the rest of the stack",
+                    self.peek().lexeme
+                )));
+            }
+            let (name, ty) = self.parse_effect_slot()?;
+            names.push(name);
+            stack = stack.push(ty);
         }
 
-        Ok(Effect::from_vecs(inputs, outputs))
+        Ok(stack)
+    }
+
+    fn consume_row_var(&mut self) -> Result<String, ParseError> {
+        let name = self.peek().lexeme.clone();
+        if name.is_empty() {
+            return Err(self.error("Expected row variable name after '..'"));
+        }
+        self.advance();
+        Ok(name)
+    }
+
+    /// Parse one effect slot: a type with an optional `name:` prefix
+    fn parse_effect_slot(&mut self) -> Result<(Option<String>, Type), ParseError> {
+        let named = self.peek().kind == TokenKind::Ident
+            && self
+                .tokens
+                .get(self.current + 1)
+                .is_some_and(|t| t.kind == TokenKind::Colon);
+
+        let name = if named {
+            let name = self.consume_ident("Expected slot name")?;
+            self.consume(&TokenKind::Colon, "Expected ':' after slot name")?;
+            Some(name)
+        } else {
+            None
+        };
+
+        Ok((name, self.parse_type()?))
     }
 
     fn parse_type(&mut self) -> Result<Type, ParseError> {
@@ -195,6 +455,17 @@ impl Parser {
     }
 
     fn parse_type_inner(&mut self) -> Result<Type, ParseError> {
+        // Quotation type: [ inputs -- outputs ]
+        if self.check(&TokenKind::LeftBracket) {
+            self.advance();
+            let (effect, _) = self.parse_effect_until(&TokenKind::RightBracket)?;
+            self.consume(
+                &TokenKind::RightBracket,
+                "Expected ']' after quotation type",
+            )?;
+            return Ok(Type::Quotation(Box::new(effect)));
+        }
+
         let name = self.consume_ident("Expected type name")?;
 
         match name.as_str() {
@@ -217,9 +488,7 @@ impl Parser {
                         let mut args = Vec::new();
                         while !self.check(&TokenKind::RightParen) && !self.is_at_end() {
                             args.push(self.parse_type()?);
-                            if self.check(&TokenKind::RightParen) {
-                                break;
-                            }
+                            self.skip_comma();
                         }
                         self.consume(&TokenKind::RightParen, "Expected ')'")?;
                         args
@@ -233,20 +502,50 @@ impl Parser {
         }
     }
 
-    fn parse_expr(&mut self) -> Result<Expr, ParseError> {
+    /// Parse one expression onto the end of `out`
+    ///
+    /// A pipe `x |> f` is only sugar for `x f`: the `|>` is dropped here, after
+    /// checking that it joins an expression to a word.
+    fn parse_expr(&mut self, out: &mut Vec<Expr>) -> Result<(), ParseError> {
+        if self.check(&TokenKind::PipeForward) {
+            if out.is_empty() {
+                return Err(self.error("Expected an expression before '|>'"));
+            }
+            self.advance();
+            if !self.check(&TokenKind::Ident) {
+                return Err(self.error("Expected a word after '|>'"));
+            }
+        }
+
+        // Only constructs that contain further expressions count toward the nesting depth
+        let nests = matches!(
+            self.peek().kind,
+            TokenKind::LeftBracket
+                | TokenKind::Match
+                | TokenKind::If
+                | TokenKind::Ifte
+                | TokenKind::While
+        );
+        if !nests {
+            let expr = self.parse_expr_inner()?;
+            out.push(expr);
+            return Ok(());
+        }
+
         self.enter_nesting()?;
         let result = self.parse_expr_inner();
         self.exit_nesting();
-        result
+        out.push(result?);
+        Ok(())
     }
 
     fn parse_expr_inner(&mut self) -> Result<Expr, ParseError> {
         match &self.peek().kind {
             TokenKind::IntLiteral => {
-                let value = self.peek().lexeme.parse::<i64>().map_err(|_| {
+                let value = parse_int_literal(&self.peek().lexeme).map_err(|message| {
                     let token = self.peek();
                     ParseError {
-                        message: format!("Invalid integer: {}", token.lexeme),
+                        message,
                         line: token.line,
                         column: token.column,
                     }
@@ -275,7 +574,7 @@ impl Parser {
                 self.advance(); // consume '['
                 let mut exprs = Vec::new();
                 while !self.check(&TokenKind::RightBracket) && !self.is_at_end() {
-                    exprs.push(self.parse_expr()?);
+                    self.parse_expr(&mut exprs)?;
                 }
                 self.consume(&TokenKind::RightBracket, "Expected ']'")?;
                 Ok(Expr::Quotation(exprs, loc))
@@ -287,21 +586,24 @@ impl Parser {
                 let mut branches = Vec::new();
 
                 while !self.check(&TokenKind::End) && !self.is_at_end() {
-                    let variant_name = self.consume_ident("Expected variant name")?;
+                    let pattern = self.parse_pattern()?;
                     self.consume(&TokenKind::Arrow, "Expected '=>'")?;
 
-                    // Parse branch body (quotation)
-                    self.consume(&TokenKind::LeftBracket, "Expected '[' for branch body")?;
+                    // Parse branch body: either `[ ... ]` or bare expressions up to the
+                    // next arm (`Variant =>`) or `end`
                     let mut body = Vec::new();
-                    while !self.check(&TokenKind::RightBracket) && !self.is_at_end() {
-                        body.push(self.parse_expr()?);
+                    while !self.check_match_arm_boundary() && !self.is_at_end() {
+                        self.parse_expr(&mut body)?;
                     }
-                    self.consume(&TokenKind::RightBracket, "Expected ']'")?;
 
-                    branches.push(MatchBranch {
-                        pattern: Pattern::Variant { name: variant_name },
-                        body,
-                    });
+                    // A lone quotation is the bracketed form; unwrap it into the arm body
+                    if let [Expr::Quotation(..)] = body.as_slice()
+                        && let Some(Expr::Quotation(exprs, _)) = body.pop()
+                    {
+                        body = exprs;
+                    }
+
+                    branches.push(MatchBranch { pattern, body });
                 }
 
                 self.consume(&TokenKind::End, "Expected 'end'")?;
@@ -311,29 +613,26 @@ impl Parser {
             TokenKind::If => {
                 let loc = self.current_loc();
                 self.advance(); // consume 'if'
+                self.parse_if_branches(loc)
+            }
 
-                // Expect two quotations: then-branch and else-branch
-                let then_loc = self.current_loc();
-                self.consume(&TokenKind::LeftBracket, "Expected '[' for then branch")?;
-                let mut then_exprs = Vec::new();
-                while !self.check(&TokenKind::RightBracket) && !self.is_at_end() {
-                    then_exprs.push(self.parse_expr()?);
-                }
-                self.consume(&TokenKind::RightBracket, "Expected ']'")?;
-
-                let else_loc = self.current_loc();
-                self.consume(&TokenKind::LeftBracket, "Expected '[' for else branch")?;
-                let mut else_exprs = Vec::new();
-                while !self.check(&TokenKind::RightBracket) && !self.is_at_end() {
-                    else_exprs.push(self.parse_expr()?);
+            TokenKind::Ifte => {
+                // `ifte [ cond ] [ then ] [ else ]`: an if that computes its own condition
+                let loc = self.current_loc();
+                self.advance(); // consume 'ifte'
+                let cond_loc = self.current_loc();
+                let cond = self.parse_branch_body("ifte condition")?;
+                let mut expr = self.parse_if_branches(loc)?;
+                if let Expr::If { condition, .. } = &mut expr {
+                    *condition = Some(Box::new(Expr::Quotation(cond, cond_loc)));
                 }
-                self.consume(&TokenKind::RightBracket, "Expected ']'")?;
+                Ok(expr)
+            }
 
-                Ok(Expr::If {
-                    then_branch: Box::new(Expr::Quotation(then_exprs, then_loc)),
-                    else_branch: Box::new(Expr::Quotation(else_exprs, else_loc)),
-                    loc,
-                })
+            TokenKind::While => {
+                let loc = self.current_loc();
+                self.advance(); // consume 'while'
+                self.parse_while(loc)
             }
 
             TokenKind::Ident => {
@@ -345,29 +644,111 @@ impl Parser {
 
             _ => {
                 let token = self.peek();
-                Err(ParseError {
-                    message: format!("Unexpected token: {:?}", token.kind),
-                    line: token.line,
-                    column: token.column,
-                })
+                let found = if token.kind == TokenKind::Eof {
+                    "end of input".to_string()
+                } else {
+                    format!("token '{}'", token.lexeme)
+                };
+                Err(self.error(&format!(
+                    "Unexpected {} where an expression was expected",
+                    found
+                )))
             }
         }
     }
 
     // Helper methods
 
+    /// Parse the branches of an `if`: `[ then ]` followed by either `[ else ]` or
+    /// `elif [ cond ] [ then ] ...`
+    ///
+    /// An `elif` desugars into an else branch that computes its condition and then
+    /// runs a nested `if` on it, so later stages only ever see plain `If` nodes.
+    fn parse_if_branches(&mut self, loc: crate::ast::SourceLoc) -> Result<Expr, ParseError> {
+        let then_loc = self.current_loc();
+        let then_exprs = self.parse_branch_body("then branch")?;
+
+        let else_branch = if self.check(&TokenKind::Elif) {
+            let elif_loc = self.current_loc();
+            self.advance(); // consume 'elif'
+            let mut else_exprs = self.parse_branch_body("elif condition")?;
+            // Each elif nests another if inside the else branch
+            self.enter_nesting()?;
+            let nested = self.parse_if_branches(elif_loc.clone());
+            self.exit_nesting();
+            else_exprs.push(nested?);
+            Expr::Quotation(else_exprs, elif_loc)
+        } else {
+            let else_loc = self.current_loc();
+            Expr::Quotation(self.parse_branch_body("else branch")?, else_loc)
+        };
+
+        Ok(Expr::If {
+            condition: None,
+            then_branch: Box::new(Expr::Quotation(then_exprs, then_loc)),
+            else_branch: Box::new(else_branch),
+            loc,
+        })
+    }
+
+    /// Parse the rest of a `while`: `[ cond ] [ body ]`, or `[ cond ] do body end`
+    fn parse_while(&mut self, loc: crate::ast::SourceLoc) -> Result<Expr, ParseError> {
+        let condition_loc = self.current_loc();
+        let condition = self.parse_branch_body("while condition")?;
+
+        let body_loc = self.current_loc();
+        let body = if self.check(&TokenKind::Do) {
+            self.advance(); // consume 'do'
+            let mut exprs = Vec::new();
+            while !self.check(&TokenKind::End) && !self.is_at_end() {
+                self.parse_expr(&mut exprs)?;
+            }
+            self.consume(&TokenKind::End, "Expected 'end' after while body")?;
+            exprs
+        } else {
+            self.parse_branch_body("while body (or 'do')")?
+        };
+
+        Ok(Expr::While {
+            condition: Box::new(Expr::Quotation(condition, condition_loc)),
+            body: Box::new(Expr::Quotation(body, body_loc)),
+            loc,
+        })
+    }
+
+    /// Parse a bracketed `[ ... ]` body belonging to an `if` or `while`
+    fn parse_branch_body(&mut self, what: &str) -> Result<Vec<Expr>, ParseError> {
+        self.consume(
+            &TokenKind::LeftBracket,
+            &format!("Expected '[' for {}", what),
+        )?;
+        let mut exprs = Vec::new();
+        while !self.check(&TokenKind::RightBracket) && !self.is_at_end() {
+            self.parse_expr(&mut exprs)?;
+        }
+        self.consume(&TokenKind::RightBracket, "Expected ']'")?;
+        Ok(exprs)
+    }
+
+    /// The current token; in bounds because the lexer always ends the stream with `Eof`
+    /// and `advance` never moves past it
     fn peek(&self) -> &Token {
         &self.tokens[self.current]
     }
 
-    fn is_at_end(&self) -> bool {
+    /// Whether all input has been consumed; after an error, whether parsing ran out of
+    /// input (so more text could complete it)
+    pub fn is_at_end(&self) -> bool {
         self.peek().kind == TokenKind::Eof
     }
 
+    /// Consume the current token and return it; at the end this is the `Eof` token,
+    /// which is never consumed
     fn advance(&mut self) -> &Token {
-        if !self.is_at_end() {
-            self.current += 1;
+        if self.is_at_end() {
+            return self.peek();
         }
+        self.current += 1;
         &self.tokens[self.current - 1]
     }
 
@@ -386,6 +767,70 @@ impl Parser {
         token.kind == TokenKind::Ident && token.lexeme == value
     }
 
+    /// Skip an optional `,` separating the types in a parenthesized list
+    ///
+    /// `Node(Tree, Tree)` and `Node(Tree Tree)` mean the same thing. The lexer has no
+    /// comma token, so a lone `,` arrives as an identifier.
+    fn skip_comma(&mut self) {
+        if self.check_ident(",") {
+            self.advance();
+        }
+    }
+
+    /// Parse the pattern of a match arm: a variant name, optionally naming its fields
+    /// (`Cons(head, rest)`), an integer literal, or `_`
+    fn parse_pattern(&mut self) -> Result<Pattern, ParseError> {
+        if self.check(&TokenKind::IntLiteral) {
+            let value =
+                parse_int_literal(&self.peek().lexeme).map_err(|message| self.error(&message))?;
+            self.advance();
+            return Ok(Pattern::Int(value));
+        }
+        let name = self.consume_ident("Expected variant name, integer, or '_'")?;
+        if name == "_" {
+            return Ok(Pattern::Wildcard);
+        }
+
+        let mut bindings = Vec::new();
+        if self.check(&TokenKind::LeftParen) {
+            self.advance();
+            while !self.check(&TokenKind::RightParen) && !self.is_at_end() {
+                bindings.push(self.consume_ident("Expected field name")?);
+                self.skip_comma();
+            }
+            self.consume(&TokenKind::RightParen, "Expected ')' after field names")?;
+        }
+        Ok(Pattern::Variant { name, bindings })
+    }
+
+    /// Check whether the next tokens end a match arm: `end` or the next `Variant =>`
+    /// (or `Variant(fields...) =>`)
+    fn check_match_arm_boundary(&self) -> bool {
+        if self.check(&TokenKind::End) {
+            return true;
+        }
+        if !matches!(self.peek().kind, TokenKind::Ident | TokenKind::IntLiteral) {
+            return false;
+        }
+        let mut next = self.current + 1;
+        if self
+            .tokens
+            .get(next)
+            .is_some_and(|t| t.kind == TokenKind::LeftParen)
+        {
+            match self.tokens[next..]
+                .iter()
+                .position(|t| t.kind == TokenKind::RightParen)
+            {
+                Some(close) => next += close + 1,
+                None => return false,
+            }
+        }
+        self.tokens
+            .get(next)
+            .is_some_and(|t| t.kind == TokenKind::Arrow)
+    }
+
     fn consume(&mut self, kind: &TokenKind, message: &str) -> Result<&Token, ParseError> {
         if self.check(kind) {
             Ok(self.advance())
@@ -413,8 +858,17 @@ impl Parser {
         }
     }
 
+    /// An error at the current token
+    ///
+    /// No rule accepts a token the lexer rejected, so parsing always fails on one; the
+    /// lexer's message replaces `message` there, since it says what is actually wrong.
     fn error(&self, message: &str) -> ParseError {
         let token = self.peek();
+        let message = if token.kind == TokenKind::Error {
+            &token.lexeme
+        } else {
+            message
+        };
         ParseError {
             message: message.to_string(),
             line: token.line,
@@ -424,9 +878,12 @@ impl Parser {
 
     fn enter_nesting(&mut self) -> Result<(), ParseError> {
         self.nesting_depth += 1;
-        if self.nesting_depth > MAX_NESTING_DEPTH {
+        if self.nesting_depth > self.max_nesting_depth {
             Err(ParseError {
-                message: format!("Maximum nesting depth of {} exceeded", MAX_NESTING_DEPTH),
+                message: format!(
+                    "Maximum nesting depth of {} exceeded",
+                    self.max_nesting_depth
+                ),
                 line: self.peek().line,
                 column: self.peek().column,
             })
@@ -440,6 +897,45 @@ impl Parser {
     }
 }
 
+/// Parse an integer literal lexeme: decimal, 0x hex, or 0b binary, with '_' separators
+///
+/// Out-of-range values get their own message so users know the value is the problem,
+/// not the syntax.
+fn parse_int_literal(lexeme: &str) -> Result<i64, String> {
+    let (negative, unsigned) = match lexeme.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, lexeme),
+    };
+
+    let (radix, digits) = if let Some(hex) = unsigned
+        .strip_prefix("0x")
+        .or_else(|| unsigned.strip_prefix("0X"))
+    {
+        (16, hex)
+    } else if let Some(bin) = unsigned
+        .strip_prefix("0b")
+        .or_else(|| unsigned.strip_prefix("0B"))
+    {
+        (2, bin)
+    } else {
+        (10, unsigned)
+    };
+
+    let digits: String = digits.chars().filter(|&c| c != '_').collect();
+    let signed = if negative {
+        format!("-{}", digits)
+    } else {
+        digits
+    };
+
+    i64::from_str_radix(&signed, radix).map_err(|e| match e.kind() {
+        std::num::IntErrorKind::PosOverflow | std::num::IntErrorKind::NegOverflow => {
+            format!("Integer literal out of range for i64: {}", lexeme)
+        }
+        _ => format!("Invalid integer: {}", lexeme),
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -467,6 +963,39 @@ mod tests {
         assert_eq!(program.type_defs[0].variants.len(), 2);
     }
 
+    #[test]
+    fn test_parse_feature_gated_word() {
+        let input = "when(linux) : platform ( -- Int ) 1 ;\n: portable ( -- Int ) 2 ;";
+        let mut parser = Parser::new(input);
+        let program = parser.parse().unwrap();
+
+        assert_eq!(program.word_defs.len(), 2);
+        assert_eq!(program.word_defs[0].name, "platform");
+        assert_eq!(program.word_defs[0].feature.as_deref(), Some("linux"));
+        assert_eq!(program.word_defs[1].feature, None);
+
+        let active: Vec<&str> = program
+            .enabled_words(&[])
+            .map(|w| w.name.as_str())
+            .collect();
+        assert_eq!(active, vec!["portable"]);
+    }
+
+    #[test]
+    fn test_parse_inline_attributes() {
+        let input = "inline : fast ( -- Int ) 1 ;\n\
+                     noinline when(linux) : slow ( -- Int ) 2 ;\n\
+                     : plain ( -- Int ) 3 ;";
+        let program = Parser::new(input).parse().unwrap();
+
+        assert_eq!(program.word_defs.len(), 3);
+        assert_eq!(program.word_defs[0].name, "fast");
+        assert_eq!(program.word_defs[0].inline, Some(InlineHint::Always));
+        assert_eq!(program.word_defs[1].inline, Some(InlineHint::Never));
+        assert_eq!(program.word_defs[1].feature.as_deref(), Some("linux"));
+        assert_eq!(program.word_defs[2].inline, None);
+    }
+
     #[test]
     fn test_parse_literals() {
         let input = ": test ( -- Int ) 42 ;";
@@ -480,6 +1009,72 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_parse_type_def_leading_pipe_optional() {
+        let forms = [
+            "type Color | Red | Green | Blue",
+            "type Color Red | Green | Blue",
+            "type Color = Red | Green | Blue",
+            "type Color = | Red | Green | Blue",
+        ];
+
+        let typedefs: Vec<TypeDef> = forms
+            .iter()
+            .map(|input| Parser::new(input).parse().unwrap().type_defs.remove(0))
+            .collect();
+
+        assert_eq!(typedefs[0].variants.len(), 3);
+        for typedef in &typedefs[1..] {
+            assert_eq!(typedef, &typedefs[0]);
+        }
+    }
+
+    #[test]
+    fn test_parse_radix_literals() {
+        let input = ": test ( -- ) 0xFF 0b1010 -0x10 1_000 ;";
+        let mut parser = Parser::new(input);
+        let program = parser.parse().unwrap();
+
+        let values: Vec<i64> = program.word_defs[0]
+            .body
+            .iter()
+            .map(|e| match e {
+                Expr::IntLit(n, _) => *n,
+                _ => panic!("Expected IntLit"),
+            })
+            .collect();
+        assert_eq!(values, vec![255, 10, -16, 1000]);
+    }
+
+    #[test]
+    fn test_decimal_literal_out_of_range() {
+        let mut parser = Parser::new(": test ( -- Int ) 99999999999999999999 ;");
+        let err = parser.parse().unwrap_err();
+        assert!(
+            err.message.contains("out of range for i64"),
+            "Unexpected message: {}",
+            err.message
+        );
+    }
+
+    #[test]
+    fn test_hex_literal_out_of_range() {
+        let mut parser = Parser::new(": test ( -- Int ) 0x1FFFFFFFFFFFFFFFF ;");
+        let err = parser.parse().unwrap_err();
+        assert!(
+            err.message.contains("out of range for i64"),
+            "Unexpected message: {}",
+            err.message
+        );
+    }
+
+    #[test]
+    fn test_malformed_hex_literal_is_invalid_not_out_of_range() {
+        let mut parser = Parser::new(": test ( -- Int ) 0xZZ ;");
+        let err = parser.parse().unwrap_err();
+        assert!(err.message.starts_with("Invalid integer"));
+    }
+
     #[test]
     fn test_parse_quotation() {
         let input = ": test ( -- ) [ 1 2 + ] ;";
@@ -493,15 +1088,37 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_parse_operator_in_quotation() {
+        let input = ": test ( -- ) [ + ] [ = ] ;";
+        let mut parser = Parser::new(input);
+        let program = parser.parse().unwrap();
+
+        match &program.word_defs[0].body[0] {
+            Expr::Quotation(exprs, _) => {
+                assert_eq!(exprs.len(), 1);
+                assert!(matches!(&exprs[0], Expr::WordCall(name, _) if name == "+"));
+            }
+            _ => panic!("Expected Quotation"),
+        }
+        match &program.word_defs[0].body[1] {
+            Expr::Quotation(exprs, _) => {
+                assert!(matches!(&exprs[0], Expr::WordCall(name, _) if name == "="));
+            }
+            _ => panic!("Expected Quotation"),
+        }
+    }
+
     #[test]
     fn test_recursion_depth_limit() {
-        // Create deeply nested quotations that exceed MAX_NESTING_DEPTH
+        // Create deeply nested quotations that exceed DEFAULT_MAX_NESTING_DEPTH
+        let depth = DEFAULT_MAX_NESTING_DEPTH + 5;
         let mut input = String::from(": test ( -- ) ");
-        for _ in 0..105 {
+        for _ in 0..depth {
             input.push_str("[ ");
         }
         input.push_str("42 ");
-        for _ in 0..105 {
+        for _ in 0..depth {
             input.push_str("] ");
         }
         input.push(';');
@@ -515,6 +1132,32 @@ mod tests {
         assert!(err.message.contains("nesting depth"));
     }
 
+    #[test]
+    fn test_configured_nesting_depth_limit() {
+        let nested = |depth: usize| {
+            format!(
+                ": test ( -- ) {}42{} ;",
+                "[ ".repeat(depth),
+                " ]".repeat(depth)
+            )
+        };
+
+        let mut parser = Parser::new(&nested(3));
+        parser.set_max_nesting_depth(3);
+        assert!(parser.parse().is_ok());
+
+        let mut parser = Parser::new(&nested(4));
+        parser.set_max_nesting_depth(3);
+        let err = parser.parse().unwrap_err();
+        assert_eq!(err.message, "Maximum nesting depth of 3 exceeded");
+
+        // Each elif nests one level deeper
+        let mut parser =
+            Parser::new(": test ( -- ) true if [ ] elif [ true ] [ ] elif [ true ] [ ] [ ] ;");
+        parser.set_max_nesting_depth(2);
+        assert!(parser.parse().is_err());
+    }
+
     #[test]
     fn test_source_location_tracking() {
         // Test that line/column numbers are captured correctly
@@ -528,12 +1171,12 @@ mod tests {
         assert_eq!(word_loc.column, 1);
         assert_eq!(word_loc.file.as_ref(), "test.cem");
 
-        // Check integer literal location (line 2, column 2 for '42')
-        // The lexer uses 0-based columns internally but reports 1-based
+        // Check integer literal location (line 2, column 3 for '42')
+        // Columns are 1-based on every line, not just the first
         match &program.word_defs[0].body[0] {
             Expr::IntLit(42, loc) => {
                 assert_eq!(loc.line, 2);
-                assert_eq!(loc.column, 2); // Column for '4' in '42' after two spaces
+                assert_eq!(loc.column, 3); // Column for '4' in '42' after two spaces
                 assert_eq!(loc.file.as_ref(), "test.cem");
             }
             _ => panic!("Expected IntLit"),
@@ -1,6 +1,7 @@
 /// Integration tests for the parser
 use super::*;
-use crate::ast::Expr;
+use crate::ast::types::{Effect, StackType, Type};
+use crate::ast::{Expr, Pattern, Program};
 
 #[test]
 fn test_parse_complete_program() {
@@ -61,6 +62,55 @@ fn test_parse_pattern_match() {
     }
 }
 
+#[test]
+fn test_parse_match_bracketless_arms() {
+    let bracketed = r#"
+        : handle ( Option(Int) -- Int )
+          match
+            Some => [ dup + ]
+            None => [ 0 ]
+          end ;
+    "#;
+    let bracketless = r#"
+        : handle ( Option(Int) -- Int )
+          match
+            Some => dup +
+            None => 0
+          end ;
+    "#;
+
+    let bracketed = Parser::new(bracketed).parse().unwrap();
+    let bracketless = Parser::new(bracketless).parse().unwrap();
+
+    let branches = |program: &crate::ast::Program| match &program.word_defs[0].body[0] {
+        Expr::Match { branches, .. } => branches.iter().map(|b| b.body.len()).collect::<Vec<_>>(),
+        _ => panic!("Expected Match expression"),
+    };
+    assert_eq!(branches(&bracketed), vec![2, 1]);
+    assert_eq!(branches(&bracketless), vec![2, 1]);
+}
+
+#[test]
+fn test_parse_match_bracketless_arm_starting_with_quotation() {
+    // A quotation followed by more expressions is part of a bare arm body
+    let input = r#"
+        : handle ( Option(Int) -- Int )
+          match
+            Some => [ 1 ] drop
+            None => 0
+          end ;
+    "#;
+
+    let program = Parser::new(input).parse().unwrap();
+    match &program.word_defs[0].body[0] {
+        Expr::Match { branches, .. } => {
+            assert_eq!(branches[0].body.len(), 2);
+            assert!(matches!(branches[0].body[0], Expr::Quotation(..)));
+        }
+        _ => panic!("Expected Match expression"),
+    }
+}
+
 #[test]
 fn test_parse_if_expression() {
     let input = r#"
@@ -136,3 +186,441 @@ fn test_parse_polymorphic_effect() {
     assert_eq!(effect.inputs.depth(), Some(1));
     assert_eq!(effect.outputs.depth(), Some(2));
 }
+
+#[test]
+fn test_parse_named_effect_slots() {
+    let named = Parser::new(": add ( n:Int m:Int -- sum:Int ) + ;")
+        .parse()
+        .unwrap();
+    let plain = Parser::new(": add ( Int Int -- Int ) + ;").parse().unwrap();
+
+    let word = &named.word_defs[0];
+    assert_eq!(word.effect, plain.word_defs[0].effect);
+    assert_eq!(
+        word.slot_names.inputs,
+        vec![Some("n".to_string()), Some("m".to_string())]
+    );
+    assert_eq!(word.slot_names.outputs, vec![Some("sum".to_string())]);
+
+    // Names are optional per slot
+    let mixed = Parser::new(": f ( Int x:Bool -- Int ) drop ;")
+        .parse()
+        .unwrap();
+    assert_eq!(
+        mixed.word_defs[0].slot_names.inputs,
+        vec![None, Some("x".to_string())]
+    );
+}
+
+#[test]
+fn test_parse_multiple_row_variables() {
+    let program = Parser::new(": bi ( ..R X [ ..R X -- ..S ] [ ..S X -- ..T ] -- ..T ) ;")
+        .parse()
+        .unwrap();
+
+    let row = |name: &str| StackType::RowVar(name.to_string());
+    let x = || Type::Var("X".to_string());
+    let quotation = |inputs: StackType, outputs: StackType| {
+        Type::Quotation(Box::new(Effect::new(inputs, outputs)))
+    };
+    let expected = Effect::new(
+        row("R")
+            .push(x())
+            .push(quotation(row("R").push(x()), row("S")))
+            .push(quotation(row("S").push(x()), row("T"))),
+        row("T"),
+    );
+    assert_eq!(program.word_defs[0].effect, expected);
+    assert_eq!(
+        program.word_defs[0].effect.to_string(),
+        "( ..R X [ ..R X -- ..S ] [ ..S X -- ..T ] -- ..T )"
+    );
+}
+
+#[test]
+fn test_row_variable_must_come_first() {
+    let err = Parser::new(": bi ( ..R X -- ..S ..T ) ;")
+        .parse()
+        .unwrap_err();
+    assert!(
+        err.message.contains("'..T' must come first"),
+        "{}",
+        err.message
+    );
+    assert_eq!((err.line, err.column), (1, 21));
+}
+
+#[test]
+fn test_parse_both_while_forms() {
+    let bracketed = Parser::new(": count ( Int -- Int ) while [ dup 10 < ] [ 1 + ] ;")
+        .parse()
+        .unwrap();
+    let block = Parser::new(": count ( Int -- Int ) while [ dup 10 < ] do 1 + end ;")
+        .parse()
+        .unwrap();
+
+    // Same loop apart from source locations: compare the quotations' rendering
+    let parts = |program: &Program| match &program.word_defs[0].body[..] {
+        [
+            Expr::While {
+                condition, body, ..
+            },
+        ] => (condition.to_string(), body.to_string()),
+        other => panic!("Expected a single While, got {:?}", other),
+    };
+    assert_eq!(parts(&bracketed), parts(&block));
+    assert_eq!(
+        parts(&block),
+        ("[ dup 10 < ]".to_string(), "[ 1 + ]".to_string())
+    );
+
+    let err = Parser::new(": f ( Int -- Int ) while [ dup 0 > ] do 1 - ;")
+        .parse()
+        .unwrap_err();
+    assert!(
+        err.message.contains("Expected 'end' after while body"),
+        "{}",
+        err.message
+    );
+}
+
+#[test]
+fn test_parse_elif_chain_desugars_to_nested_if() {
+    let input = r#"
+        : sign ( Int -- Int )
+          dup 0 < if [ drop -1 ] elif [ dup 0 = ] [ drop 0 ] elif [ dup 100 > ] [ drop 2 ] [ drop 1 ] ;
+    "#;
+
+    let program = Parser::new(input).parse().unwrap();
+    let body = &program.word_defs[0].body;
+    assert_eq!(body.len(), 4);
+
+    // Each `elif` becomes an else branch: its condition, then a nested if
+    let branches = |expr: &Expr| match expr {
+        Expr::If {
+            then_branch,
+            else_branch,
+            ..
+        } => match (&**then_branch, &**else_branch) {
+            (Expr::Quotation(then_body, _), Expr::Quotation(else_body, _)) => {
+                (then_body.clone(), else_body.clone())
+            }
+            _ => panic!("Expected quotation branches"),
+        },
+        other => panic!("Expected If, got {:?}", other),
+    };
+
+    let (then_body, else_body) = branches(&body[3]);
+    assert_eq!(then_body.len(), 2); // drop -1
+    assert_eq!(else_body.len(), 4); // dup 0 = <if>
+
+    let (then_body, else_body) = branches(&else_body[3]);
+    assert!(matches!(&then_body[1], Expr::IntLit(0, _)));
+    assert_eq!(else_body.len(), 4); // dup 100 > <if>
+
+    let (then_body, else_body) = branches(&else_body[3]);
+    assert!(matches!(&then_body[1], Expr::IntLit(2, _)));
+    assert!(matches!(&else_body[1], Expr::IntLit(1, _)));
+}
+
+#[test]
+fn test_unexpected_token_names_its_text() {
+    let err = Parser::new(": f ( -- ) 1 ) ;").parse().unwrap_err();
+    assert!(
+        err.message.contains("Unexpected token ')'"),
+        "got: {}",
+        err.message
+    );
+    assert_eq!((err.line, err.column), (1, 14));
+}
+
+#[test]
+fn test_variant_pattern_keeps_its_field_names() {
+    let program = Parser::new(": f ( List(Int) -- Int ) match Cons(h, t) => drop Nil => 0 end ;")
+        .parse()
+        .unwrap();
+    let Expr::Match { branches, .. } = &program.word_defs[0].body[0] else {
+        panic!("Expected a match");
+    };
+    assert!(
+        matches!(&branches[0].pattern, Pattern::Variant { bindings, .. } if bindings == &["h", "t"])
+    );
+}
+
+#[test]
+fn test_lexer_errors_are_reported_as_parse_errors() {
+    let err = Parser::new(": f ( -- ) `` ;").parse().unwrap_err();
+    assert_eq!(err.message, "Empty quoted identifier");
+    assert_eq!((err.line, err.column), (1, 12));
+
+    let err = Parser::new(": `f ( -- ) ;").parse().unwrap_err();
+    assert_eq!(err.message, "Unterminated quoted identifier");
+
+    let err = Parser::new(": f ( -- String ) \"abc\n ;")
+        .parse()
+        .unwrap_err();
+    assert_eq!(err.message, "Unterminated string literal (newline)");
+}
+
+#[test]
+fn test_parse_guarded_if() {
+    let program = Parser::new(": abs ( Int -- Int ) ifte [ dup 0 < ] [ 0 swap - ] [ ] ;")
+        .parse()
+        .unwrap();
+    let body = &program.word_defs[0].body;
+
+    // The condition quotation is kept on the if itself
+    assert_eq!(body.len(), 1);
+    let Expr::If {
+        condition: Some(condition),
+        then_branch,
+        else_branch,
+        ..
+    } = &body[0]
+    else {
+        panic!("Expected ifte, got {:?}", body[0]);
+    };
+    assert!(matches!(&**condition, Expr::Quotation(cond, _) if cond.len() == 3));
+    assert!(matches!(&**then_branch, Expr::Quotation(then_body, _) if then_body.len() == 3));
+    assert!(matches!(&**else_branch, Expr::Quotation(else_body, _) if else_body.is_empty()));
+
+    // A plain if followed by a quotation keeps its meaning
+    let program = Parser::new(": f ( Bool -- Int ) if [ 1 ] [ 2 ] [ 3 ] call_quotation + ;")
+        .parse()
+        .unwrap();
+    let body = &program.word_defs[0].body;
+    assert_eq!(body.len(), 4);
+    assert!(matches!(
+        &body[0],
+        Expr::If {
+            condition: None,
+            ..
+        }
+    ));
+    assert!(matches!(&body[1], Expr::Quotation(..)));
+}
+
+#[test]
+fn test_variant_fields_may_be_comma_separated() {
+    let program = Parser::new(
+        "type Tree | Leaf(Int) | Node(Tree, Tree)\ntype Pair | P(Result(Int, String) Int)",
+    )
+    .parse()
+    .unwrap();
+
+    let tree = &program.type_defs[0];
+    let tree_type = Type::Named {
+        name: "Tree".to_string(),
+        args: vec![],
+    };
+    assert_eq!(tree.variants[1].fields, vec![tree_type.clone(), tree_type]);
+
+    let pair = &program.type_defs[1];
+    assert_eq!(
+        pair.variants[0].fields,
+        vec![
+            Type::Named {
+                name: "Result".to_string(),
+                args: vec![Type::Int, Type::String],
+            },
+            Type::Int
+        ]
+    );
+}
+
+#[test]
+fn test_parse_all_caps_reported_errors() {
+    let source = (0..30)
+        .map(|i| format!(": bad{} ( -- Int ) ] ;\n", i))
+        .collect::<String>()
+        + ": good ( -- Int ) 1 ;";
+
+    let errors = Parser::new(&source).parse_all(5).unwrap_err();
+    assert_eq!(errors.errors.len(), 5);
+    assert_eq!(errors.omitted, 25);
+    assert_eq!(errors.errors[1].line, 2);
+    assert!(errors.to_string().ends_with("\n... and 25 more errors"));
+
+    let program = Parser::new(": a ( -- ) ; : b ( -- ) ;")
+        .parse_all(5)
+        .unwrap();
+    assert_eq!(program.word_defs.len(), 2);
+}
+
+#[test]
+fn test_pipe_desugars_to_word_calls() {
+    let piped = Parser::new(": f ( -- Int ) 5 |> double |> negate ;")
+        .parse()
+        .unwrap();
+    let plain = Parser::new(": f ( -- Int ) 5 double negate ;")
+        .parse()
+        .unwrap();
+    // Identical apart from source columns
+    let text = |program: &Program| -> Vec<String> {
+        program.word_defs[0]
+            .body
+            .iter()
+            .map(|e| e.to_string())
+            .collect()
+    };
+    assert_eq!(text(&piped), text(&plain));
+
+    let error = Parser::new(": f ( -- Int ) |> double ;")
+        .parse()
+        .unwrap_err();
+    assert_eq!(error.message, "Expected an expression before '|>'");
+    let error = Parser::new(": f ( -- Int ) 5 |> 6 ;").parse().unwrap_err();
+    assert_eq!(error.message, "Expected a word after '|>'");
+}
+
+#[test]
+fn test_parse_str_accepts_empty_input() {
+    let program = parse_str("").unwrap();
+    assert!(program.type_defs.is_empty() && program.word_defs.is_empty());
+    assert!(
+        parse_str("  # only a comment")
+            .unwrap()
+            .word_defs
+            .is_empty()
+    );
+}
+
+#[test]
+fn test_parse_str_never_panics_on_random_input() {
+    // Random bytes rarely get past the lexer, so half the inputs are shuffled tokens
+    let vocab = [
+        ":", ";", "type", "|", "|>", "(", ")", "[", "]", "--", "..R", "match", "end", "if", "elif",
+        "while", "do", "=>", "when", "Int", "x", "5", "\"s", ",", "-", "#\n", "\\",
+    ];
+    let mut seed: u64 = 0x2545_f491_4f6c_dd1d;
+    let mut next = move || {
+        seed ^= seed << 13;
+        seed ^= seed >> 7;
+        seed ^= seed << 17;
+        seed
+    };
+
+    for i in 0..5000 {
+        let len = next() % 40;
+        let input = if i % 2 == 0 {
+            let bytes: Vec<u8> = (0..len).map(|_| next() as u8).collect();
+            String::from_utf8_lossy(&bytes).into_owned()
+        } else {
+            (0..len)
+                .map(|_| vocab[(next() % vocab.len() as u64) as usize])
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+        let result = std::panic::catch_unwind(|| parse_str(&input));
+        assert!(result.is_ok(), "parser panicked on {:?}", input);
+    }
+}
+
+#[test]
+fn test_parse_integer_and_wildcard_patterns() {
+    let program = Parser::new(": f ( Int -- Int ) match 0 => 1 -3 => [ 2 ] _ => 3 end ;")
+        .parse()
+        .unwrap();
+
+    let Expr::Match { branches, .. } = &program.word_defs[0].body[0] else {
+        panic!("expected a match");
+    };
+    let patterns: Vec<_> = branches.iter().map(|b| b.pattern.clone()).collect();
+    assert_eq!(
+        patterns,
+        vec![Pattern::Int(0), Pattern::Int(-3), Pattern::Wildcard]
+    );
+    assert_eq!(branches[1].body.len(), 1);
+}
+
+#[test]
+fn test_doc_comments_attach_to_the_next_definition() {
+    let program = Parser::new(
+        r#"
+## An optional value
+type Maybe | Just(Int) | Nothing
+
+# An ordinary comment is not documentation
+## Square a number.
+##
+##   3 square  # => 9
+: square ( Int -- Int ) dup * ;
+
+## Separated by a blank line, so it documents nothing
+
+: plain ( -- ) ; ## trailing, also nothing
+: after ( -- ) ;
+"#,
+    )
+    .parse()
+    .unwrap();
+
+    assert_eq!(
+        program.type_defs[0].doc.as_deref(),
+        Some("An optional value")
+    );
+    assert_eq!(
+        program.word_defs[0].doc.as_deref(),
+        Some("Square a number.\n\n  3 square  # => 9")
+    );
+    assert_eq!(program.word_defs[1].doc, None);
+    assert_eq!(program.word_defs[2].doc, None);
+}
+
+#[test]
+fn test_displayed_effects_parse_back_to_the_same_effect() {
+    let row =
+        |name: &str, types: Vec<Type>| StackType::from_vec_with_row(types, Some(name.to_string()));
+    let named = |name: &str, args: Vec<Type>| Type::Named {
+        name: name.to_string(),
+        args,
+    };
+    let quotation = |inputs, outputs| Type::Quotation(Box::new(Effect::new(inputs, outputs)));
+
+    let effects = vec![
+        Effect::new(StackType::Empty, StackType::Empty),
+        Effect::from_vecs(vec![Type::Int, Type::Bool], vec![Type::String]),
+        Effect::from_vecs(vec![Type::Var("A".to_string())], vec![]),
+        Effect::new(
+            row("R", vec![Type::Var("A".to_string())]),
+            row(
+                "R",
+                vec![Type::Var("A".to_string()), Type::Var("A".to_string())],
+            ),
+        ),
+        Effect::from_vecs(
+            vec![named(
+                "Result",
+                vec![Type::Int, named("List", vec![Type::String])],
+            )],
+            vec![named("Option", vec![Type::Var("t".to_string())])],
+        ),
+        Effect::new(
+            row(
+                "A",
+                vec![quotation(row("A", vec![]), row("B", vec![Type::Int]))],
+            ),
+            row("B", vec![]),
+        ),
+        Effect::from_vecs(
+            vec![quotation(
+                StackType::Empty,
+                StackType::from_vec(vec![quotation(StackType::Empty, StackType::Empty)]),
+            )],
+            vec![named("Tree", vec![])],
+        ),
+    ];
+
+    for effect in effects {
+        let displayed = effect.to_string();
+        assert_eq!(
+            parse_effect(&displayed).unwrap(),
+            effect,
+            "{} did not round-trip",
+            displayed
+        );
+    }
+
+    assert!(parse_effect("( Int -- Int ) extra").is_err());
+    assert!(parse_effect("Int -- Int").is_err());
+}
@@ -0,0 +1,178 @@
+/**
+Type-level REPL for Cem
+
+Reads one word definition or expression sequence at a time, type checks it against
+the definitions entered so far, and tracks the type of the stack the expressions
+would leave. Nothing is compiled or run.
+*/
+use crate::ast::types::StackType;
+use crate::parser::{Lexer, Parser, TokenKind};
+use crate::typechecker::TypeChecker;
+
+/// Result of feeding one line to the REPL
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReplOutcome {
+    /// The input so far is incomplete (e.g. an unclosed `[`); more lines are needed
+    Continue,
+    /// The input was accepted; the stack type after it
+    Stack(StackType),
+    /// The input was rejected; the stack is unchanged
+    Error(String),
+}
+
+/// An interactive session: known words and types, plus the current stack type
+pub struct Repl {
+    checker: TypeChecker,
+    stack: StackType,
+    /// Lines of an input that hasn't parsed completely yet
+    pending: String,
+}
+
+impl Repl {
+    pub fn new() -> Self {
+        Repl {
+            checker: TypeChecker::new(),
+            stack: StackType::Empty,
+            pending: String::new(),
+        }
+    }
+
+    /// The stack type left by everything entered so far
+    pub fn stack(&self) -> &StackType {
+        &self.stack
+    }
+
+    /// Whether earlier lines are waiting for the rest of their input
+    pub fn is_continuing(&self) -> bool {
+        !self.pending.is_empty()
+    }
+
+    /// Feed one line of input
+    ///
+    /// Input starting with `:`, `type`, `when`, `inline`, or `noinline` is a definition
+    /// and extends the environment only if it type checks; anything else is a sequence
+    /// of expressions applied to the stack.
+    pub fn feed(&mut self, line: &str) -> ReplOutcome {
+        self.pending.push_str(line);
+        self.pending.push('\n');
+        let source = std::mem::take(&mut self.pending);
+
+        let first = Lexer::new(&source).next_token();
+        let is_definition = matches!(first.kind, TokenKind::Colon | TokenKind::Type)
+            || (first.kind == TokenKind::Ident
+                && matches!(first.lexeme.as_str(), "when" | "inline" | "noinline"));
+
+        let mut parser = Parser::new_with_filename(&source, "<repl>");
+        let result = if is_definition {
+            // Check against a copy so a rejected definition leaves no words behind
+            parser.parse().map(|program| {
+                let mut checker = self.checker.clone();
+                checker.check_program(&program).map(|_| {
+                    self.checker = checker;
+                    self.stack.clone()
+                })
+            })
+        } else {
+            parser
+                .parse_exprs()
+                .map(|exprs| self.checker.check_exprs(&exprs, self.stack.clone()))
+        };
+
+        match result {
+            Err(_) if parser.is_at_end() => {
+                self.pending = source;
+                ReplOutcome::Continue
+            }
+            Err(e) => ReplOutcome::Error(e.to_string()),
+            Ok(Err(e)) => ReplOutcome::Error(e.to_string()),
+            Ok(Ok(stack)) => {
+                self.stack = stack.clone();
+                ReplOutcome::Stack(stack)
+            }
+        }
+    }
+}
+
+impl Default for Repl {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::types::Type;
+
+    #[test]
+    fn test_definition_then_expression() {
+        let mut repl = Repl::new();
+
+        assert_eq!(
+            repl.feed(": square ( Int -- Int ) dup * ;"),
+            ReplOutcome::Stack(StackType::Empty)
+        );
+        assert_eq!(
+            repl.feed("3 square true"),
+            ReplOutcome::Stack(StackType::from_vec(vec![Type::Int, Type::Bool]))
+        );
+    }
+
+    #[test]
+    fn test_unclosed_quotation_continues() {
+        let mut repl = Repl::new();
+        repl.feed("1");
+
+        assert_eq!(repl.feed("[ 2"), ReplOutcome::Continue);
+        assert!(repl.is_continuing());
+
+        let ReplOutcome::Stack(stack) = repl.feed("+ ]") else {
+            panic!("Expected the completed input to be accepted");
+        };
+        let (types, _) = stack.to_vec();
+        assert_eq!(types.len(), 2);
+        assert!(matches!(types[1], Type::Quotation(_)));
+    }
+
+    #[test]
+    fn test_errors_leave_the_stack_unchanged() {
+        let mut repl = Repl::new();
+        repl.feed("1");
+
+        assert!(matches!(repl.feed("\"a\" +"), ReplOutcome::Error(_)));
+        assert_eq!(repl.stack(), &StackType::from_vec(vec![Type::Int]));
+    }
+
+    #[test]
+    fn test_rejected_definition_stays_undefined() {
+        let mut repl = Repl::new();
+
+        assert!(matches!(
+            repl.feed(": bad ( -- Int ) \"x\" ;"),
+            ReplOutcome::Error(_)
+        ));
+        let ReplOutcome::Error(message) = repl.feed("bad") else {
+            panic!("Expected the rejected word to be undefined");
+        };
+        assert!(message.contains("bad"));
+        assert_eq!(repl.stack(), &StackType::Empty);
+    }
+
+    #[test]
+    fn test_inline_hinted_definitions() {
+        let mut repl = Repl::new();
+
+        assert_eq!(
+            repl.feed("inline : double ( Int -- Int ) 2 * ;"),
+            ReplOutcome::Stack(StackType::Empty)
+        );
+        assert_eq!(
+            repl.feed("noinline : triple ( Int -- Int ) 3 * ;"),
+            ReplOutcome::Stack(StackType::Empty)
+        );
+        assert_eq!(
+            repl.feed("1 double triple"),
+            ReplOutcome::Stack(StackType::from_vec(vec![Type::Int]))
+        );
+    }
+}
@@ -0,0 +1,52 @@
+//! Symbol names of compiled words
+//!
+//! Shared by code generation, which defines and calls the symbols, and library
+//! interfaces, which record the symbol each word was compiled to.
+
+/// The symbol a word is compiled to
+///
+/// Operators and hyphenated names are mapped to valid symbol names, and `main` becomes
+/// `cem_main` so it can't collide with the C entry point.
+pub fn symbol_for_word(name: &str) -> String {
+    if name == "main" {
+        "cem_main".to_string()
+    } else {
+        runtime_symbol(name)
+    }
+}
+
+/// Map operator symbols to valid LLVM function names
+/// LLVM doesn't allow symbols like +, -, <, > as function names
+/// Also maps hyphenated Cem names to underscore C names
+pub(crate) fn runtime_symbol(name: &str) -> String {
+    match name {
+        // Arithmetic operators
+        "+" => "add".to_string(),
+        "-" => "subtract".to_string(),
+        "*" => "multiply".to_string(),
+        "/" | "quot" => "divide_op".to_string(),
+        "mod" | "rem" => "mod_op".to_string(),
+        // `abs` would resolve to libc's abs(3)
+        "abs" => "int_abs".to_string(),
+        // Comparison operators
+        "<" => "int_less".to_string(),
+        ">" => "int_greater".to_string(),
+        "<=" => "int_less_equal".to_string(),
+        ">=" => "int_greater_equal".to_string(),
+        "=" => "int_equal".to_string(),
+        "!=" => "int_not_equal".to_string(),
+        "++" => "string_concat".to_string(),
+        // `dup` would resolve to libc's dup(2)
+        "dup" => "stack_dup".to_string(),
+        // Words starting with a digit aren't valid symbol names
+        "2dup" => "two_dup".to_string(),
+        "2drop" => "two_drop".to_string(),
+        // Special functions
+        "exit" => "exit_op".to_string(), // Avoid conflict with stdlib exit()
+        "write" => "write_string".to_string(), // Avoid conflict with POSIX write()
+        "inspect" => "print_top".to_string(), // Debug print that leaves the value in place
+        "compose" => "compose_quotations".to_string(),
+        // For hyphenated names, replace hyphens with underscores
+        _ => name.replace('-', "_"),
+    }
+}
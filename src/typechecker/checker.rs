@@ -1,19 +1,32 @@
-#[cfg(test)]
-use crate::ast::SourceLoc;
 /**
 Core type checker for Cem
 
 Implements bidirectional type checking with stack effect inference.
 */
 use crate::ast::types::{Effect, StackType, Type};
-use crate::ast::{Expr, MatchBranch, Pattern, Program, WordDef};
+use crate::ast::visit::{fold_exprs, visit_exprs};
+use crate::ast::{Expr, MatchBranch, Pattern, Program, SlotNames, SourceLoc, WordDef};
+use crate::interface::Interface;
 use crate::typechecker::environment::Environment;
-use crate::typechecker::errors::{TypeError, TypeResult};
-use crate::typechecker::unification::{unify_stack_types, unify_types};
+use crate::typechecker::errors::{InputMismatchKind, TypeError, TypeResult, TypeWarning};
+use crate::typechecker::unification::{
+    StackSubstitution, Substitution, unify_stack_types, unify_types,
+};
+use std::cell::Cell;
+use std::collections::{HashMap, HashSet};
 
 /// The main type checker
+#[derive(Clone)]
 pub struct TypeChecker {
     env: Environment,
+    /// Active features; words gated on other features are skipped
+    features: Vec<String>,
+    /// Non-fatal diagnostics collected by `check_program`
+    warnings: Vec<TypeWarning>,
+    /// Effect slot names of the program's words, used to name arguments in errors
+    slot_names: HashMap<String, SlotNames>,
+    /// Counter for fresh type and row variable names
+    fresh: Cell<usize>,
 }
 
 impl TypeChecker {
@@ -21,24 +34,102 @@ impl TypeChecker {
     pub fn new() -> Self {
         TypeChecker {
             env: Environment::new(),
+            features: Vec::new(),
+            warnings: Vec::new(),
+            slot_names: HashMap::new(),
+            fresh: Cell::new(0),
+        }
+    }
+
+    /// Warnings produced by the last `check_program`
+    pub fn warnings(&self) -> &[TypeWarning] {
+        &self.warnings
+    }
+
+    /// The environment the checker resolves words and types in
+    ///
+    /// After `check_program` it holds the builtins, any library interfaces, and every
+    /// checked word and type of the program.
+    pub fn environment(&self) -> &Environment {
+        &self.env
+    }
+
+    /// Make a precompiled library's types and words available without its source
+    pub fn add_interface(&mut self, interface: &Interface) {
+        for typedef in &interface.type_defs {
+            self.env.add_type(typedef.clone());
+        }
+        for word in &interface.words {
+            self.env.add_word(word.name.clone(), word.effect.clone());
         }
     }
 
+    /// Set the active features used to select `when(feature)` words
+    pub fn set_features(&mut self, features: Vec<String>) {
+        self.features = features;
+    }
+
     /// Type check a complete program
     pub fn check_program(&mut self, program: &Program) -> TypeResult<()> {
+        self.warnings.clear();
+
         // First pass: add all type definitions
         for typedef in &program.type_defs {
             self.env.add_type(typedef.clone());
         }
+        for typedef in &program.type_defs {
+            for variant in &typedef.variants {
+                variant
+                    .fields
+                    .iter()
+                    .try_for_each(|ty| self.check_type_arity(ty))?;
+            }
+        }
 
-        // Second pass: check all word definitions
-        for word_def in &program.word_defs {
+        // Second pass: declare every enabled word, so a body can call its own word
+        // (recursion) or one defined later
+        let features = self.features.clone();
+        for word_def in program.enabled_words(&features) {
+            self.check_effect_types(word_def)?;
+            self.env
+                .add_word(word_def.name.clone(), word_def.effect.clone());
+        }
+        self.slot_names = program
+            .enabled_words(&features)
+            .map(|w| (w.name.clone(), w.slot_names.clone()))
+            .collect();
+        for word_def in program.enabled_words(&features) {
             self.check_word_def(word_def)?;
+            if Self::has_unguarded_self_call(word_def) {
+                self.warnings.push(TypeWarning::UnguardedRecursion {
+                    word: word_def.name.clone(),
+                });
+            }
+            if let Some(warning) = self.over_general_effect(word_def) {
+                self.warnings.push(warning);
+            }
+            let mut loops = Vec::new();
+            Self::collect_infinite_loops(&word_def.body, &mut loops);
+            self.warnings
+                .extend(loops.into_iter().map(|loc| TypeWarning::InfiniteLoop {
+                    word: word_def.name.clone(),
+                    loc,
+                }));
         }
 
         Ok(())
     }
 
+    /// Type check a sequence of expressions starting from `stack`, returning the result
+    ///
+    /// Words and types from earlier `check_program` calls stay in scope, which lets
+    /// the REPL check one input at a time.
+    pub fn check_exprs(&self, exprs: &[Expr], stack: StackType) -> TypeResult<StackType> {
+        exprs
+            .iter()
+            .try_fold(stack, |stack, expr| self.check_expr(expr, stack))
+    }
+
     /// Type check a word definition
     fn check_word_def(&mut self, word: &WordDef) -> TypeResult<()> {
         // Start with the input stack from the declared effect
@@ -57,6 +148,7 @@ impl TypeChecker {
                 word: word.name.clone(),
             }
         })?;
+        self.check_declared_variables(word)?;
 
         // Add word to environment for future lookups
         self.env.add_word(word.name.clone(), word.effect.clone());
@@ -64,8 +156,224 @@ impl TypeChecker {
         Ok(())
     }
 
+    /// Reject a body whose outputs fix a declared type variable
+    ///
+    /// The declared inputs are matched with the body's principal effect first, so a
+    /// variable the body only consumes as a concrete type (`( A -- A ) 1 +`) is left to
+    /// `over_general_effect`. A variable that the outputs then resolve differently, to a
+    /// concrete type (`( A -- A ) drop 1`) or to another declared variable
+    /// (`( A -- B )` with an empty body), would hand callers a value of the wrong type,
+    /// so it is an error reported with the declared names. Bodies whose effect can't be
+    /// inferred in isolation are skipped.
+    fn check_declared_variables(&self, word: &WordDef) -> TypeResult<()> {
+        let Ok(inferred) = self.infer_quotation_effect(&word.body) else {
+            return Ok(());
+        };
+        let Ok((input_subst, input_rows)) =
+            unify_stack_types(&word.effect.inputs, &inferred.inputs)
+        else {
+            return Ok(());
+        };
+        let Ok(subst) = unify_types(
+            &Type::Quotation(Box::new(word.effect.clone())),
+            &Type::Quotation(Box::new(inferred)),
+        ) else {
+            return Ok(());
+        };
+
+        let mut declared_vars = Vec::new();
+        Self::collect_type_vars(&word.effect.inputs, &mut declared_vars);
+        Self::collect_type_vars(&word.effect.outputs, &mut declared_vars);
+
+        // The body's own variables stand for the declared ones its inputs matched
+        let no_rows = StackSubstitution::new();
+        let before: Vec<Type> = declared_vars
+            .iter()
+            .map(|name| {
+                Self::apply_type_subst_to_type(&Type::Var(name.clone()), &input_subst, &input_rows)
+            })
+            .collect();
+        let declared_names: Substitution = declared_vars
+            .iter()
+            .zip(&before)
+            .rev()
+            .filter_map(|(name, resolved)| match resolved {
+                Type::Var(var) => Some((var.clone(), Type::Var(name.clone()))),
+                _ => None,
+            })
+            .collect();
+
+        for (name, before) in declared_vars.iter().zip(&before) {
+            let after = Self::apply_type_subst_to_type(&Type::Var(name.clone()), &subst, &no_rows);
+            if after != *before {
+                return Err(Box::new(TypeError::DeclaredVariableMismatch {
+                    word: word.name.clone(),
+                    variable: name.clone(),
+                    actual: Self::apply_type_subst_to_type(&after, &declared_names, &no_rows),
+                    declared: word.effect.clone(),
+                }));
+            }
+        }
+        Ok(())
+    }
+
+    /// Compare a word's declaration with the principal effect of its body
+    ///
+    /// Checking against the declaration lets a body silently specialize a declared
+    /// type variable (`( A -- A ) 1 +` binds `A` to `Int`). Inferring the body on its
+    /// own and unifying that with the declaration exposes it: a declared variable that
+    /// resolves to a concrete type, or to the same variable as another one, promises
+    /// more than the body delivers. Bodies whose effect can't be inferred in isolation
+    /// are skipped.
+    fn over_general_effect(&self, word: &WordDef) -> Option<TypeWarning> {
+        let inferred = self.infer_quotation_effect(&word.body).ok()?;
+        let subst = unify_types(
+            &Type::Quotation(Box::new(word.effect.clone())),
+            &Type::Quotation(Box::new(inferred)),
+        )
+        .ok()?;
+
+        let mut declared_vars = Vec::new();
+        Self::collect_type_vars(&word.effect.inputs, &mut declared_vars);
+        Self::collect_type_vars(&word.effect.outputs, &mut declared_vars);
+
+        let no_rows = StackSubstitution::new();
+        let mut resolved_vars = Vec::new();
+        for name in &declared_vars {
+            match Self::apply_type_subst_to_type(&Type::Var(name.clone()), &subst, &no_rows) {
+                Type::Var(resolved) if !resolved_vars.contains(&resolved) => {
+                    resolved_vars.push(resolved)
+                }
+                _ => {
+                    return Some(TypeWarning::OverGeneralEffect {
+                        word: word.name.clone(),
+                        declared: Box::new(word.effect.clone()),
+                        required: Box::new(Effect::new(
+                            Self::apply_type_substitution(&word.effect.inputs, &subst, &no_rows),
+                            Self::apply_type_substitution(&word.effect.outputs, &subst, &no_rows),
+                        )),
+                    });
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Collect the distinct type variables of a stack, in order of appearance
+    fn collect_type_vars(stack: &StackType, vars: &mut Vec<String>) {
+        fn visit(ty: &Type, vars: &mut Vec<String>) {
+            match ty {
+                Type::Var(name) if !vars.contains(name) => vars.push(name.clone()),
+                Type::Named { args, .. } => args.iter().for_each(|arg| visit(arg, vars)),
+                Type::Quotation(effect) | Type::Closure { effect, .. } => {
+                    TypeChecker::collect_type_vars(&effect.inputs, vars);
+                    TypeChecker::collect_type_vars(&effect.outputs, vars);
+                }
+                _ => {}
+            }
+        }
+        stack.iter().for_each(|ty| visit(ty, vars));
+    }
+
+    /// Verify that every named type in a word's effect signature is defined and given
+    /// as many type arguments as it has parameters
+    fn check_effect_types(&self, word: &WordDef) -> TypeResult<()> {
+        let types = || word.effect.inputs.iter().chain(&word.effect.outputs);
+        if let Some(name) = types().find_map(|ty| self.find_undefined_type(ty)) {
+            return Err(Box::new(TypeError::UndefinedType {
+                name,
+                word: Some(word.name.clone()),
+                loc: Some(word.loc.clone()),
+            }));
+        }
+        types().try_for_each(|ty| self.check_type_arity(ty))
+    }
+
+    /// Verify that each application of a defined named type within `ty` supplies one
+    /// argument per type parameter
+    fn check_type_arity(&self, ty: &Type) -> TypeResult<()> {
+        match ty {
+            Type::Int | Type::Bool | Type::String | Type::Var(_) => Ok(()),
+            Type::Named { name, args } => {
+                if let Some(typedef) = self.env.lookup_type(name)
+                    && typedef.type_params.len() != args.len()
+                {
+                    return Err(Box::new(TypeError::WrongTypeArity {
+                        name: name.clone(),
+                        expected: typedef.type_params.len(),
+                        found: args.len(),
+                    }));
+                }
+                args.iter().try_for_each(|arg| self.check_type_arity(arg))
+            }
+            Type::Quotation(effect) | Type::Closure { effect, .. } => effect
+                .inputs
+                .iter()
+                .chain(&effect.outputs)
+                .try_for_each(|ty| self.check_type_arity(ty)),
+        }
+    }
+
+    /// Find the first named type within `ty` (including type arguments and quotation
+    /// effects) that the environment doesn't define
+    fn find_undefined_type(&self, ty: &Type) -> Option<String> {
+        match ty {
+            Type::Int | Type::Bool | Type::String | Type::Var(_) => None,
+            Type::Named { name, args } => {
+                if self.env.lookup_type(name).is_none() {
+                    return Some(name.clone());
+                }
+                args.iter().find_map(|arg| self.find_undefined_type(arg))
+            }
+            Type::Quotation(effect) | Type::Closure { effect, .. } => effect
+                .inputs
+                .iter()
+                .chain(&effect.outputs)
+                .find_map(|ty| self.find_undefined_type(ty)),
+        }
+    }
+
+    /// Whether a word calls itself outside any `if` or `match`
+    ///
+    /// Such a call runs every time the word does, so the recursion has no base case.
+    fn has_unguarded_self_call(word: &WordDef) -> bool {
+        word.body
+            .iter()
+            .any(|expr| matches!(expr, Expr::WordCall(name, _) if *name == word.name))
+    }
+
+    /// Collect the locations of `while` loops in `exprs` (at any depth) whose condition
+    /// is the constant `[ true ]` and whose body never calls `exit`
+    fn collect_infinite_loops(exprs: &[Expr], loops: &mut Vec<SourceLoc>) {
+        visit_exprs(exprs, &mut |expr| {
+            if let Expr::While {
+                condition,
+                body,
+                loc,
+            } = expr
+                && let (Expr::Quotation(cond, _), Expr::Quotation(body, _)) =
+                    (&**condition, &**body)
+                && matches!(cond.as_slice(), [Expr::BoolLit(true, _)])
+                && !Self::calls_exit(body)
+            {
+                loops.push(loc.clone());
+            }
+        });
+    }
+
+    /// Whether `exprs` call `exit` anywhere, including inside branches
+    fn calls_exit(exprs: &[Expr]) -> bool {
+        fold_exprs(exprs, false, &mut |found, expr| {
+            found || matches!(expr, Expr::WordCall(name, _) if name == "exit")
+        })
+    }
+
     /// Type check an expression, returning the resulting stack type
-    fn check_expr(&self, expr: &Expr, stack: StackType) -> TypeResult<StackType> {
+    ///
+    /// Like `check_exprs`, this sees the words and types of earlier `check_program`
+    /// calls, so tools can check one expression at a time against a stack they track.
+    pub fn check_expr(&self, expr: &Expr, stack: StackType) -> TypeResult<StackType> {
         match expr {
             Expr::IntLit(_, _) => {
                 // Push Int onto stack
@@ -82,35 +390,60 @@ impl TypeChecker {
                 Ok(stack.push(Type::String))
             }
 
-            Expr::WordCall(name, _) => {
+            Expr::WordCall(name, loc) => {
                 // Look up word effect
                 let effect = self
                     .env
                     .lookup_word(name)
                     .ok_or_else(|| TypeError::UndefinedWord { name: name.clone() })?;
+                Self::check_closures_not_duplicated(effect, &stack, name, loc)?;
 
                 // Apply effect to current stack
-                self.apply_effect(effect, stack, name)
+                let result = self
+                    .apply_effect(effect, stack.clone(), name)
+                    .map_err(|e| {
+                        self.named_argument_mismatch(name, effect, &stack)
+                            .unwrap_or(e)
+                    })?;
+                if name == "curry" {
+                    return Ok(Self::curried(&stack, result));
+                }
+                Ok(result)
             }
 
-            Expr::Quotation(_exprs, _) => {
-                // For now, treat quotations as opaque
-                // In future: infer the quotation's effect
-                // For now: push a generic quotation type
-                let quotation_effect = Effect::new(StackType::empty(), StackType::empty());
-                Ok(stack.push(Type::Quotation(Box::new(quotation_effect))))
+            Expr::Quotation(body, _) => {
+                let effect = self.infer_quotation_effect(body)?;
+                Ok(stack.push(Type::Quotation(Box::new(effect))))
             }
 
-            Expr::Match { branches, loc: _ } => {
+            Expr::Match { branches, loc } => {
                 // Pattern matching
-                self.check_match(branches, stack)
+                self.check_match(branches, stack, loc)
             }
 
             Expr::If {
+                condition,
                 then_branch,
                 else_branch,
-                loc: _,
+                loc,
             } => {
+                // An `ifte` condition runs first and may only push the Bool
+                let stack = match condition {
+                    Some(condition) => {
+                        let after_condition = self.check_if_branch(condition, stack.clone())?;
+                        let expected = stack.clone().push(Type::Bool);
+                        unify_stack_types(&after_condition, &expected).map_err(|_| {
+                            TypeError::GuardConditionMismatch {
+                                expected: Effect::new(stack.clone(), expected.clone()),
+                                actual: Effect::new(stack, after_condition.clone()),
+                                loc: loc.clone(),
+                            }
+                        })?;
+                        expected
+                    }
+                    None => stack,
+                };
+
                 // Pop Bool from stack
                 let (stack_after_cond, cond_type) =
                     stack.pop().ok_or_else(|| TypeError::StackUnderflow {
@@ -127,17 +460,286 @@ impl TypeChecker {
                 })?;
 
                 // Check both branches produce same stack
-                let then_stack = self.check_expr(then_branch, stack_after_cond.clone())?;
-                let else_stack = self.check_expr(else_branch, stack_after_cond)?;
+                let then_stack = self.check_if_branch(then_branch, stack_after_cond.clone())?;
+                let else_stack = self.check_if_branch(else_branch, stack_after_cond.clone())?;
 
                 // Unify branch results
-                let (_, _) =
-                    unify_stack_types(&then_stack, &else_stack).map_err(|_| TypeError::Other {
-                        message: "if branches produce incompatible stack effects".to_string(),
-                    })?;
+                let (_, _) = unify_stack_types(&then_stack, &else_stack).map_err(|_| {
+                    TypeError::IfBranchMismatch {
+                        then_effect: Effect::new(stack_after_cond.clone(), then_stack.clone()),
+                        else_effect: Effect::new(stack_after_cond, else_stack.clone()),
+                        loc: loc.clone(),
+                    }
+                })?;
 
                 Ok(then_stack)
             }
+
+            Expr::While {
+                condition,
+                body,
+                loc,
+            } => {
+                let mismatch = |part: &str, expected: &StackType, actual: StackType| {
+                    TypeError::WhileStackMismatch {
+                        part: part.to_string(),
+                        expected: Effect::new(stack.clone(), expected.clone()),
+                        actual: Effect::new(stack.clone(), actual),
+                        loc: loc.clone(),
+                    }
+                };
+
+                // The condition pushes a Bool on top of the loop's stack...
+                let after_condition = self.check_if_branch(condition, stack.clone())?;
+                let expected = stack.clone().push(Type::Bool);
+                unify_stack_types(&after_condition, &expected)
+                    .map_err(|_| mismatch("condition", &expected, after_condition.clone()))?;
+
+                // ...and the body leaves that stack unchanged for the next iteration
+                let after_body = self.check_if_branch(body, stack.clone())?;
+                unify_stack_types(&after_body, &stack)
+                    .map_err(|_| mismatch("body", &stack, after_body.clone()))?;
+
+                Ok(stack)
+            }
+        }
+    }
+
+    /// Reject a word that would duplicate a closure, running the linear value moved
+    /// into it twice
+    ///
+    /// A word duplicates a value when an input's type variable appears more often in
+    /// its outputs than in its inputs, as in `dup` ( ..A T -- ..A T T ).
+    fn check_closures_not_duplicated(
+        effect: &Effect,
+        stack: &StackType,
+        word: &str,
+        loc: &SourceLoc,
+    ) -> TypeResult<()> {
+        let count = |side: &StackType, var: &Type| side.iter().filter(|ty| *ty == var).count();
+        for (input, actual) in effect.inputs.iter().zip(stack.iter()) {
+            if let Type::Closure { captured, .. } = actual
+                && matches!(input, Type::Var(_))
+                && count(&effect.outputs, input) > count(&effect.inputs, input)
+            {
+                return Err(Box::new(TypeError::UseAfterMove {
+                    captured: (**captured).clone(),
+                    word: word.to_string(),
+                    loc: loc.clone(),
+                }));
+            }
+        }
+        Ok(())
+    }
+
+    /// The stack after `curry`, given the stack before it and the plain result
+    ///
+    /// Moving a linear value into the quotation, or currying a closure, makes the new
+    /// quotation a closure that owns that value. A user word named `curry` that doesn't
+    /// leave a quotation on top keeps its declared result.
+    fn curried(before: &StackType, after: StackType) -> StackType {
+        let mut inputs = before.iter();
+        let captured = match (inputs.next(), inputs.next()) {
+            (Some(Type::Closure { captured, .. }), _) => (**captured).clone(),
+            (_, Some(value)) if value.is_linear() => value.clone(),
+            _ => return after,
+        };
+        match after.clone().pop() {
+            Some((rest, Type::Quotation(effect))) => rest.push(Type::Closure {
+                effect,
+                captured: Box::new(captured),
+            }),
+            _ => after,
+        }
+    }
+
+    /// Type check an `if` branch
+    ///
+    /// Branches are written as quotations but run inline, so their bodies act on the
+    /// stack directly rather than pushing a quotation value.
+    fn check_if_branch(&self, branch: &Expr, stack: StackType) -> TypeResult<StackType> {
+        match branch {
+            Expr::Quotation(body, _) => body
+                .iter()
+                .try_fold(stack, |stack, expr| self.check_expr(expr, stack)),
+            _ => self.check_expr(branch, stack),
+        }
+    }
+
+    /// Pinpoint which named input of `word_name` the stack fails to satisfy
+    ///
+    /// Returns `None` when the word has no slot names or no single named slot mismatches,
+    /// in which case the general error from `apply_effect` stands.
+    fn named_argument_mismatch(
+        &self,
+        word_name: &str,
+        effect: &Effect,
+        stack: &StackType,
+    ) -> Option<Box<TypeError>> {
+        let names = self.slot_names.get(word_name)?;
+        let (inputs, _) = effect.inputs.to_vec();
+        let (available, _) = stack.to_vec();
+        let offset = available.len().checked_sub(inputs.len())?;
+
+        inputs.iter().enumerate().find_map(|(i, expected)| {
+            let name = names.inputs.get(i)?.as_ref()?;
+            let actual = &available[offset + i];
+            unify_types(actual, expected).err().map(|_| {
+                Box::new(TypeError::TypeMismatch {
+                    expected: expected.clone(),
+                    actual: actual.clone(),
+                    context: format!("argument `{}` of '{}'", name, word_name),
+                })
+            })
+        })
+    }
+
+    /// Infer the stack effect of a quotation body
+    ///
+    /// The body starts on an unknown stack (a fresh row variable). Each word call is
+    /// applied row-polymorphically, so values it needs from below the quotation's own
+    /// pushes bind that row variable; the bindings are replayed onto the input to
+    /// recover what the quotation consumes.
+    fn infer_quotation_effect(&self, body: &[Expr]) -> TypeResult<Effect> {
+        let mut inputs = StackType::RowVar(self.fresh_name("q"));
+        let mut stack = inputs.clone();
+
+        for expr in body {
+            let Expr::WordCall(name, _) = expr else {
+                stack = self.check_expr(expr, stack)?;
+                continue;
+            };
+
+            let effect = self
+                .env
+                .lookup_word(name)
+                .ok_or_else(|| TypeError::UndefinedWord { name: name.clone() })?;
+            let effect = self.instantiate(&Self::row_polymorphic(effect));
+
+            let (type_subst, stack_subst) = unify_stack_types(&effect.inputs, &stack)
+                .map_err(|_| Self::input_mismatch(name, &effect.inputs, &stack))?;
+            stack = Self::apply_type_substitution(&effect.outputs, &type_subst, &stack_subst);
+            inputs = Self::apply_type_substitution(&inputs, &type_subst, &stack_subst);
+        }
+
+        Ok(Effect::new(inputs, stack))
+    }
+
+    /// Generalize a fixed-depth effect over the rest of the stack: ( A -- B ) becomes
+    /// ( ..R A -- ..R B ). Effects that already name their rest are returned unchanged.
+    fn row_polymorphic(effect: &Effect) -> Effect {
+        if effect.inputs.row_var().is_some() {
+            return effect.clone();
+        }
+        let (inputs, _) = effect.inputs.to_vec();
+        let (outputs, _) = effect.outputs.to_vec();
+        let row = Some("R".to_string());
+        Effect::new(
+            StackType::from_vec_with_row(inputs, row.clone()),
+            StackType::from_vec_with_row(outputs, row),
+        )
+    }
+
+    /// Generate a variable name that can't clash with user-written or earlier ones
+    fn fresh_name(&self, base: &str) -> String {
+        let n = self.fresh.get();
+        self.fresh.set(n + 1);
+        format!("{}'{}", base, n)
+    }
+
+    /// Rename every type and row variable in an effect to fresh names
+    ///
+    /// Builtin effects reuse names like `A` and `..R`; each use gets its own copies so
+    /// bindings from one application can't leak into another.
+    fn instantiate(&self, effect: &Effect) -> Effect {
+        let mut renamed = HashMap::new();
+        let mut rename = |name: &str| {
+            renamed
+                .entry(name.to_string())
+                .or_insert_with(|| self.fresh_name(name))
+                .clone()
+        };
+        Self::rename_effect(effect, &mut rename)
+    }
+
+    /// Give each quotation on `stack` its own copy of the variables only it mentions
+    ///
+    /// A quotation's effect is polymorphic in the variables the checker invented for it
+    /// (the `..q'1` its body starts on, say), so each call may bind them differently.
+    /// Without this, calling one of two `dup`'d copies would bind the shared row
+    /// variable to a stack holding the other copy. Variables that also type a plain
+    /// value on the stack, or come from a written signature, stay shared.
+    fn generalize_quotations(&self, stack: &StackType) -> StackType {
+        fn pin(ty: &Type, pinned: &mut HashSet<String>) {
+            match ty {
+                Type::Var(name) => {
+                    pinned.insert(name.clone());
+                }
+                Type::Named { args, .. } => args.iter().for_each(|arg| pin(arg, pinned)),
+                Type::Quotation(_) | Type::Closure { .. } => {}
+                Type::Int | Type::Bool | Type::String => {}
+            }
+        }
+
+        let (types, row) = stack.to_vec();
+        let mut pinned: HashSet<String> = row.iter().cloned().collect();
+        types.iter().for_each(|ty| pin(ty, &mut pinned));
+
+        let types = types
+            .iter()
+            .map(|ty| match ty {
+                Type::Quotation(_) | Type::Closure { .. } => {
+                    let mut renamed = HashMap::new();
+                    Self::rename_type(ty, &mut |name: &str| {
+                        if !name.contains('\'') || pinned.contains(name) {
+                            return name.to_string();
+                        }
+                        renamed
+                            .entry(name.to_string())
+                            .or_insert_with(|| self.fresh_name(name))
+                            .clone()
+                    })
+                }
+                _ => ty.clone(),
+            })
+            .collect();
+        StackType::from_vec_with_row(types, row)
+    }
+
+    fn rename_effect(effect: &Effect, rename: &mut impl FnMut(&str) -> String) -> Effect {
+        Effect::new(
+            Self::rename_stack(&effect.inputs, rename),
+            Self::rename_stack(&effect.outputs, rename),
+        )
+    }
+
+    fn rename_stack(stack: &StackType, rename: &mut impl FnMut(&str) -> String) -> StackType {
+        let (types, row) = stack.to_vec();
+        let types = types
+            .iter()
+            .map(|ty| Self::rename_type(ty, rename))
+            .collect();
+        StackType::from_vec_with_row(types, row.map(|name| rename(&name)))
+    }
+
+    fn rename_type(ty: &Type, rename: &mut impl FnMut(&str) -> String) -> Type {
+        match ty {
+            Type::Var(name) => Type::Var(rename(name)),
+            Type::Named { name, args } => Type::Named {
+                name: name.clone(),
+                args: args
+                    .iter()
+                    .map(|arg| Self::rename_type(arg, rename))
+                    .collect(),
+            },
+            Type::Quotation(effect) => {
+                Type::Quotation(Box::new(Self::rename_effect(effect, rename)))
+            }
+            Type::Closure { effect, captured } => Type::Closure {
+                effect: Box::new(Self::rename_effect(effect, rename)),
+                captured: Box::new(Self::rename_type(captured, rename)),
+            },
+            Type::Int | Type::Bool | Type::String => ty.clone(),
         }
     }
 
@@ -148,9 +750,37 @@ impl TypeChecker {
         stack: StackType,
         word_name: &str,
     ) -> TypeResult<StackType> {
+        let effect = &self.instantiate(effect);
+        let stack = self.generalize_quotations(&stack);
+
         // Try to unify the effect's input with the current stack
         // This handles polymorphic effects like dup: (A -- A A)
 
+        // Row-polymorphic effects like ( ..R A -- ..R A A ) name the rest of the stack
+        // explicitly: unify the whole stack so ..R captures the tail, then rebuild the
+        // outputs with the captured tail in place of ..R.
+        if effect.inputs.row_var().is_some() {
+            let required = effect.inputs.concrete_depth();
+            let available = stack.concrete_depth();
+            if stack.row_var().is_none() && available < required {
+                return Err(Box::new(TypeError::StackUnderflow {
+                    word: word_name.to_string(),
+                    required,
+                    available,
+                }));
+            }
+
+            // Effect first, so its row variable binds to our tail (not the other way round)
+            let (type_subst, stack_subst) = unify_stack_types(&effect.inputs, &stack)
+                .map_err(|_| Self::input_mismatch(word_name, &effect.inputs, &stack))?;
+
+            return Ok(Self::apply_type_substitution(
+                &effect.outputs,
+                &type_subst,
+                &stack_subst,
+            ));
+        }
+
         let input_depth = effect.inputs.depth().unwrap_or(0);
         let stack_depth = stack.depth().unwrap_or(0);
 
@@ -163,96 +793,205 @@ impl TypeChecker {
         }
 
         // For simple case: try unification
-        // Split the stack into "will be consumed" and "will remain"
+        // Split the stack into "will be consumed" (top first) and "will remain"
+        let mut consumed: Vec<Type> = stack.iter().take(input_depth).cloned().collect();
+        if consumed.len() < input_depth {
+            return Err(Box::new(TypeError::StackUnderflow {
+                word: word_name.to_string(),
+                required: input_depth,
+                available: consumed.len(),
+            }));
+        }
         let mut remaining_stack = stack.clone();
-        let mut consumed = Vec::new();
-
-        // Pop the elements that will be consumed
         for _ in 0..input_depth {
-            if let Some((rest, top)) = remaining_stack.pop() {
-                consumed.push(top);
-                remaining_stack = rest;
-            } else {
-                return Err(Box::new(TypeError::StackUnderflow {
-                    word: word_name.to_string(),
-                    required: input_depth,
-                    available: consumed.len(),
-                }));
-            }
+            let Some((rest, _)) = remaining_stack.pop() else {
+                unreachable!("the consumed values were just counted");
+            };
+            remaining_stack = rest;
         }
 
-        // Reverse to get bottom-to-top order
+        // Reverse to get bottom-to-top order, then unify with effect.inputs
         consumed.reverse();
-
-        // Now unify consumed types with effect.inputs
         let consumed_stack = StackType::from_vec(consumed);
-        let (type_subst, _stack_subst) = unify_stack_types(&consumed_stack, &effect.inputs)
-            .map_err(|e| TypeError::Other {
-                message: format!("Cannot apply '{}': input type mismatch: {}", word_name, e),
-            })?;
+        let (type_subst, stack_subst) = unify_stack_types(&consumed_stack, &effect.inputs)
+            .map_err(|_| Self::input_mismatch(word_name, &effect.inputs, &consumed_stack))?;
 
         // Apply substitution to outputs
-        let output_stack = Self::apply_type_substitution(&effect.outputs, &type_subst);
+        let output_stack =
+            Self::apply_type_substitution(&effect.outputs, &type_subst, &stack_subst);
 
         // Rebuild stack: remaining + outputs
-        let mut result = remaining_stack;
-        let mut outputs_vec = Vec::new();
-        let mut temp = output_stack;
-        while let Some((rest, top)) = temp.pop() {
-            outputs_vec.push(top);
-            temp = rest;
+        let (outputs, _) = output_stack.to_vec();
+        Ok(outputs
+            .into_iter()
+            .fold(remaining_stack, |stack, ty| stack.push(ty)))
+    }
+
+    /// The error for `word`'s `inputs` failing to unify with `stack`
+    ///
+    /// A quotation where a value is expected (or a value where a quotation is) is a
+    /// plain type mismatch rather than an unsatisfiable effect, so it is reported as
+    /// one, with the quotation's inferred effect in the message.
+    fn input_mismatch(word: &str, inputs: &StackType, stack: &StackType) -> TypeError {
+        let kind = Self::classify_input_mismatch(inputs, stack);
+        if let InputMismatchKind::Type {
+            position,
+            expected,
+            actual,
+        } = &kind
+        {
+            let is_quotation = |ty: &Type| matches!(ty, Type::Quotation(_) | Type::Closure { .. });
+            let is_var = |ty: &Type| matches!(ty, Type::Var(_));
+            if is_quotation(expected) != is_quotation(actual)
+                && !is_var(expected)
+                && !is_var(actual)
+            {
+                return TypeError::TypeMismatch {
+                    expected: expected.clone(),
+                    actual: actual.clone(),
+                    context: format!("input {} from the top of '{}'", position, word),
+                };
+            }
+        }
+        TypeError::InputMismatch {
+            word: word.to_string(),
+            kind,
+        }
+    }
+
+    /// Work out why `inputs` failed to unify with `stack`
+    ///
+    /// A shallow stack is a depth problem; otherwise the values are unified pairwise
+    /// from the top, so the first position that can't agree (given the bindings made
+    /// above it) is the type problem. If every value agrees, the rest of the stack
+    /// is at fault.
+    fn classify_input_mismatch(inputs: &StackType, stack: &StackType) -> InputMismatchKind {
+        let (wanted, _) = inputs.to_vec();
+        let (have, have_row) = stack.to_vec();
+
+        if have_row.is_none() && have.len() < wanted.len() {
+            return InputMismatchKind::Depth {
+                required: wanted.len(),
+                available: have.len(),
+            };
         }
-        outputs_vec.reverse();
-        for ty in outputs_vec {
-            result = result.push(ty);
+
+        // Identical values can't be the problem
+        if !inputs.is_prefix_of(stack) {
+            let top = |types: &[Type], k: usize| {
+                StackType::from_vec_with_row(types[types.len() - k..].to_vec(), Some("..".into()))
+            };
+            for k in 1..=wanted.len().min(have.len()) {
+                if unify_stack_types(&top(&wanted, k), &top(&have, k)).is_err() {
+                    return InputMismatchKind::Type {
+                        position: k - 1,
+                        expected: wanted[wanted.len() - k].clone(),
+                        actual: have[have.len() - k].clone(),
+                    };
+                }
+            }
         }
 
-        Ok(result)
+        InputMismatchKind::Row {
+            expected: inputs.clone(),
+            actual: stack.clone(),
+        }
     }
 
-    /// Apply type substitution to a stack type
+    /// Apply type and stack substitutions to a stack type
+    ///
+    /// Type variables are replaced using `subst`; a row variable bound in `stack_subst`
+    /// is replaced by the stack tail it captured during unification. Bindings are
+    /// followed transitively, including inside quotation effects.
     fn apply_type_substitution(
         stack: &StackType,
-        subst: &crate::typechecker::unification::Substitution,
+        subst: &Substitution,
+        stack_subst: &StackSubstitution,
     ) -> StackType {
-        match stack {
-            StackType::Empty => StackType::Empty,
-            StackType::Cons { rest, top } => {
-                let new_rest = Self::apply_type_substitution(rest, subst);
-                let new_top = Self::apply_type_subst_to_type(top, subst);
-                new_rest.push(new_top)
-            }
-            StackType::RowVar(name) => {
-                // Row variables don't get substituted here (would need stack substitution)
-                StackType::RowVar(name.clone())
-            }
-        }
+        let base = match stack.row_var() {
+            Some(name) => match stack_subst.get(name) {
+                Some(bound) if *bound != StackType::RowVar(name.to_string()) => {
+                    Self::apply_type_substitution(bound, subst, stack_subst)
+                }
+                _ => StackType::RowVar(name.to_string()),
+            },
+            None => StackType::Empty,
+        };
+        let types: Vec<Type> = stack
+            .iter()
+            .map(|ty| Self::apply_type_subst_to_type(ty, subst, stack_subst))
+            .collect();
+        types
+            .into_iter()
+            .rev()
+            .fold(base, |stack, ty| stack.push(ty))
     }
 
     /// Apply type substitution to a type
     fn apply_type_subst_to_type(
         ty: &Type,
-        subst: &crate::typechecker::unification::Substitution,
+        subst: &Substitution,
+        stack_subst: &StackSubstitution,
     ) -> Type {
         match ty {
-            Type::Var(name) => subst.get(name).cloned().unwrap_or_else(|| ty.clone()),
+            Type::Var(name) => match subst.get(name) {
+                Some(bound) if bound != ty => {
+                    Self::apply_type_subst_to_type(bound, subst, stack_subst)
+                }
+                _ => ty.clone(),
+            },
             Type::Named { name, args } => Type::Named {
                 name: name.clone(),
                 args: args
                     .iter()
-                    .map(|arg| Self::apply_type_subst_to_type(arg, subst))
+                    .map(|arg| Self::apply_type_subst_to_type(arg, subst, stack_subst))
                     .collect(),
             },
-            Type::Quotation(eff) => {
-                // Would need to substitute in effect too
-                Type::Quotation(eff.clone())
-            }
+            Type::Quotation(eff) => Type::Quotation(Box::new(Effect::new(
+                Self::apply_type_substitution(&eff.inputs, subst, stack_subst),
+                Self::apply_type_substitution(&eff.outputs, subst, stack_subst),
+            ))),
+            Type::Closure { effect, captured } => Type::Closure {
+                effect: Box::new(Effect::new(
+                    Self::apply_type_substitution(&effect.inputs, subst, stack_subst),
+                    Self::apply_type_substitution(&effect.outputs, subst, stack_subst),
+                )),
+                captured: Box::new(Self::apply_type_subst_to_type(captured, subst, stack_subst)),
+            },
             _ => ty.clone(),
         }
     }
 
+    /// Explain an undefined word that is one of a match arm's field names
+    ///
+    /// The names in `Cons(head, rest)` only document the fields the arm starts with on
+    /// the stack. An arm calling `head` calls the word `head`, so this only applies when
+    /// no such word is defined.
+    fn explain_field_name_call(error: Box<TypeError>, pattern: &Pattern) -> Box<TypeError> {
+        match (*error, pattern) {
+            (
+                TypeError::UndefinedWord { name },
+                Pattern::Variant {
+                    name: variant,
+                    bindings,
+                },
+            ) if bindings.contains(&name) => Box::new(TypeError::Other {
+                message: format!(
+                    "'{}' only documents a field of {}; the field is on the stack, not a word",
+                    name, variant
+                ),
+            }),
+            (error, _) => Box::new(error),
+        }
+    }
+
     /// Type check a pattern match
-    fn check_match(&self, branches: &[MatchBranch], stack: StackType) -> TypeResult<StackType> {
+    fn check_match(
+        &self,
+        branches: &[MatchBranch],
+        stack: StackType,
+        loc: &SourceLoc,
+    ) -> TypeResult<StackType> {
         if branches.is_empty() {
             return Err(Box::new(TypeError::Other {
                 message: "Empty pattern match".to_string(),
@@ -268,8 +1007,9 @@ impl TypeChecker {
             })?;
 
         // Get the type name from scrutinee
-        let type_name = match &scrutinee_type {
-            Type::Named { name, .. } => name.clone(),
+        let (type_name, type_args) = match &scrutinee_type {
+            Type::Int => return self.check_int_match(branches, stack_after_pop),
+            Type::Named { name, args } => (name.clone(), args.as_slice()),
             _ => {
                 return Err(Box::new(TypeError::Other {
                     message: format!("Cannot pattern match on non-ADT type: {}", scrutinee_type),
@@ -283,14 +1023,46 @@ impl TypeChecker {
                 .get_variants(&type_name)
                 .ok_or_else(|| TypeError::UndefinedType {
                     name: type_name.clone(),
+                    word: None,
+                    loc: None,
                 })?;
 
-        let covered_variants: Vec<_> = branches
-            .iter()
-            .map(|b| match &b.pattern {
-                Pattern::Variant { name } => name.as_str(),
+        // The scrutinee's type arguments, by the parameter they fill (`T` is Int when
+        // matching a List(Int)), so fields are typed for this application of the type
+        let params: HashMap<&str, &Type> = self
+            .env
+            .lookup_type(&type_name)
+            .filter(|typedef| typedef.type_params.len() == type_args.len())
+            .map(|typedef| {
+                typedef
+                    .type_params
+                    .iter()
+                    .map(String::as_str)
+                    .zip(type_args)
+                    .collect()
             })
-            .collect();
+            .unwrap_or_default();
+
+        let mut covered_variants: Vec<&str> = Vec::new();
+        for branch in branches {
+            match &branch.pattern {
+                Pattern::Variant { name, .. } if covered_variants.contains(&name.as_str()) => {
+                    return Err(Box::new(TypeError::DuplicateMatchArm {
+                        variant: name.clone(),
+                        loc: loc.clone(),
+                    }));
+                }
+                Pattern::Variant { name, .. } => covered_variants.push(name),
+                Pattern::Int(_) | Pattern::Wildcard => {
+                    return Err(Box::new(TypeError::Other {
+                        message: format!(
+                            "Pattern {} can only match an Int, not type '{}'",
+                            branch.pattern, type_name
+                        ),
+                    }));
+                }
+            }
+        }
 
         let missing: Vec<_> = variants
             .iter()
@@ -312,28 +1084,70 @@ impl TypeChecker {
             // Get the variant definition
             let variant = variants
                 .iter()
-                .find(|v| match &branch.pattern {
-                    Pattern::Variant { name } => v.name == *name,
-                })
+                .find(
+                    |v| matches!(&branch.pattern, Pattern::Variant { name, .. } if v.name == *name),
+                )
                 .ok_or_else(|| TypeError::Other {
                     message: "Unknown variant in pattern".to_string(),
                 })?;
 
+            if let Pattern::Variant { bindings, .. } = &branch.pattern
+                && !bindings.is_empty()
+                && bindings.len() != variant.fields.len()
+            {
+                return Err(Box::new(TypeError::Other {
+                    message: format!(
+                        "Pattern {} names {} field(s), but '{}' has {}",
+                        branch.pattern,
+                        bindings.len(),
+                        variant.name,
+                        variant.fields.len()
+                    ),
+                }));
+            }
+
             // Pattern destructures: push variant fields onto stack
             let mut branch_stack = stack_after_pop.clone();
             for field_type in &variant.fields {
-                branch_stack = branch_stack.push(field_type.clone());
+                branch_stack = branch_stack.push(Self::fill_type_params(field_type, &params));
             }
 
             // Type check branch body
             for expr in &branch.body {
-                branch_stack = self.check_expr(expr, branch_stack)?;
+                branch_stack = self
+                    .check_expr(expr, branch_stack)
+                    .map_err(|e| Self::explain_field_name_call(e, &branch.pattern))?;
             }
 
-            branch_results.push(branch_stack);
+            branch_results.push((variant, branch_stack));
+        }
+
+        // A branch that ends deeper than the others by no more than its own field count
+        // most likely left destructured fields unconsumed; report that before the more
+        // general "branches disagree" error
+        let base_depth = stack_after_pop.concrete_depth() as isize;
+        let net = |result: &StackType| result.concrete_depth() as isize - base_depth;
+        let min_net = branch_results
+            .iter()
+            .map(|(_, result)| net(result))
+            .min()
+            .unwrap_or(0);
+        for (variant, result) in &branch_results {
+            let excess = net(result) - min_net;
+            if excess > 0 && excess as usize <= variant.fields.len() {
+                return Err(Box::new(TypeError::MatchBranchLeakedField {
+                    type_name: type_name.clone(),
+                    variant: variant.name.clone(),
+                    leaked: excess as usize,
+                }));
+            }
         }
 
         // All branches must produce the same stack effect
+        let branch_results: Vec<StackType> = branch_results
+            .into_iter()
+            .map(|(_, result)| result)
+            .collect();
         let first_result = &branch_results[0];
         for (i, result) in branch_results.iter().enumerate().skip(1) {
             let (_, _) = unify_stack_types(first_result, result).map_err(|_| {
@@ -348,17 +1162,116 @@ impl TypeChecker {
 
         Ok(first_result.clone())
     }
-}
 
-impl Default for TypeChecker {
-    fn default() -> Self {
-        Self::new()
+    /// Replace the type parameters in a variant field's type with the arguments they
+    /// were given
+    ///
+    /// Unlike a substitution, the replacement is a single step: an argument that
+    /// happens to name another parameter (matching a `Result(E, T)`) is left alone.
+    fn fill_type_params(ty: &Type, params: &HashMap<&str, &Type>) -> Type {
+        let fill_stack = |stack: &StackType| {
+            let (types, row) = stack.to_vec();
+            StackType::from_vec_with_row(
+                types
+                    .iter()
+                    .map(|ty| Self::fill_type_params(ty, params))
+                    .collect(),
+                row,
+            )
+        };
+        match ty {
+            Type::Var(name) => params
+                .get(name.as_str())
+                .map_or(ty.clone(), |&arg| arg.clone()),
+            Type::Named { name, args } => Type::Named {
+                name: name.clone(),
+                args: args
+                    .iter()
+                    .map(|arg| Self::fill_type_params(arg, params))
+                    .collect(),
+            },
+            Type::Quotation(effect) => Type::Quotation(Box::new(Effect::new(
+                fill_stack(&effect.inputs),
+                fill_stack(&effect.outputs),
+            ))),
+            Type::Closure { effect, captured } => Type::Closure {
+                effect: Box::new(Effect::new(
+                    fill_stack(&effect.inputs),
+                    fill_stack(&effect.outputs),
+                )),
+                captured: Box::new(Self::fill_type_params(captured, params)),
+            },
+            Type::Int | Type::Bool | Type::String => ty.clone(),
+        }
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    /// Type check a match on the Int popped off `stack`
+    ///
+    /// Every arm is an integer literal or the wildcard `_`, which must come last and
+    /// is required: no finite set of literals covers every Int. The scrutinee is
+    /// consumed, so each arm starts from `stack`.
+    fn check_int_match(&self, branches: &[MatchBranch], stack: StackType) -> TypeResult<StackType> {
+        let mut seen = Vec::new();
+        let mut after_wildcard = false;
+        for branch in branches {
+            let unreachable = after_wildcard
+                || match &branch.pattern {
+                    Pattern::Int(value) if seen.contains(value) => true,
+                    Pattern::Int(value) => {
+                        seen.push(*value);
+                        false
+                    }
+                    Pattern::Wildcard => {
+                        after_wildcard = true;
+                        false
+                    }
+                    Pattern::Variant { name, .. } => {
+                        return Err(Box::new(TypeError::Other {
+                            message: format!("Cannot match variant '{}' against an Int", name),
+                        }));
+                    }
+                };
+            if unreachable {
+                return Err(Box::new(TypeError::Other {
+                    message: format!("Unreachable match arm '{}'", branch.pattern),
+                }));
+            }
+        }
+        if !matches!(branches.last().map(|b| &b.pattern), Some(Pattern::Wildcard)) {
+            return Err(Box::new(TypeError::NonExhaustiveMatch {
+                type_name: "Int".to_string(),
+                missing_variants: vec!["_".to_string()],
+            }));
+        }
+
+        let mut results = Vec::new();
+        for branch in branches {
+            results.push(self.check_exprs(&branch.body, stack.clone())?);
+        }
+        let first_result = &results[0];
+        for (i, result) in results.iter().enumerate().skip(1) {
+            unify_stack_types(first_result, result).map_err(|_| {
+                TypeError::InconsistentBranchEffects {
+                    type_name: "Int".to_string(),
+                    expected: Effect::new(stack.clone(), first_result.clone()),
+                    actual: Effect::new(stack.clone(), result.clone()),
+                    branch: format!("branch {}", i),
+                }
+            })?;
+        }
+        Ok(first_result.clone())
+    }
+}
+
+impl Default for TypeChecker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
     // Test imports (currently unused)
 
     #[test]
@@ -429,4 +1342,961 @@ mod tests {
             e => panic!("Expected StackUnderflow, got {:?}", e),
         }
     }
+
+    #[test]
+    fn test_row_polymorphic_word_keeps_tail() {
+        let mut checker = TypeChecker::new();
+
+        // dupd-style word that names the rest of the stack: ( ..R A B -- ..R A A B )
+        let row = || StackType::RowVar("R".to_string());
+        let var = |n: &str| Type::Var(n.to_string());
+        checker.env.add_word(
+            "dupd".to_string(),
+            Effect::new(
+                row().push(var("A")).push(var("B")),
+                row().push(var("A")).push(var("A")).push(var("B")),
+            ),
+        );
+
+        // String Bool Int dupd => String Bool Bool Int
+        let stack = StackType::from_vec(vec![Type::String, Type::Bool, Type::Int]);
+        let result = checker
+            .check_expr(
+                &Expr::WordCall("dupd".to_string(), SourceLoc::unknown()),
+                stack,
+            )
+            .unwrap();
+
+        assert_eq!(
+            result,
+            StackType::from_vec(vec![Type::String, Type::Bool, Type::Bool, Type::Int])
+        );
+    }
+
+    #[test]
+    fn test_distinct_row_variables_stay_separate() {
+        use crate::parser::Parser;
+
+        let mut checker = TypeChecker::new();
+        let bi = Parser::new(": bi ( ..R X [ ..R X -- ..S ] [ ..S X -- ..T ] -- ..T ) ;")
+            .parse()
+            .unwrap();
+        checker
+            .env
+            .add_word("bi".to_string(), bi.word_defs[0].effect.clone());
+
+        // Bool 5 [ 1 + ] [ + ] bi => Bool Int: ..S binds to ..R Int, ..T to ..R Int
+        let exprs = Parser::new("5 [ 1 + ] [ + ] bi").parse_exprs().unwrap();
+        let result = checker
+            .check_exprs(&exprs, StackType::from_vec(vec![Type::Bool]))
+            .unwrap();
+        assert_eq!(result, StackType::from_vec(vec![Type::Bool, Type::Int]));
+
+        // The second quotation sees what the first left: ..S is not ..R
+        let exprs = Parser::new("5 [ drop ] [ + ] bi").parse_exprs().unwrap();
+        assert!(
+            checker
+                .check_exprs(&exprs, StackType::from_vec(vec![Type::Bool]))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_while_keeps_the_stack_shape() {
+        use crate::parser::Parser;
+
+        let check = |source: &str| {
+            let program = Parser::new(source).parse().unwrap();
+            TypeChecker::new().check_program(&program)
+        };
+
+        assert!(check(": count ( Int -- Int ) while [ dup 10 < ] do 1 + end ;").is_ok());
+
+        let err = check(": grow ( Int -- Int ) while [ dup 10 < ] do dup end ;").unwrap_err();
+        match *err {
+            TypeError::WhileStackMismatch { ref part, .. } => assert_eq!(part, "body"),
+            ref e => panic!("Expected WhileStackMismatch, got {:?}", e),
+        }
+        assert!(
+            err.to_string()
+                .contains("must have effect ( Int -- Int ), but has ( Int -- Int Int )")
+        );
+
+        let err = check(": f ( Int -- Int ) while [ 1 ] do end ;").unwrap_err();
+        assert!(
+            matches!(*err, TypeError::WhileStackMismatch { ref part, .. } if part == "condition")
+        );
+    }
+
+    #[test]
+    fn test_variant_constructor_word() {
+        let checker = TypeChecker::new();
+
+        // 5 Some => Option(Int)
+        let stack = checker
+            .check_expr(&Expr::IntLit(5, SourceLoc::unknown()), StackType::empty())
+            .unwrap();
+        let result = checker
+            .check_expr(
+                &Expr::WordCall("Some".to_string(), SourceLoc::unknown()),
+                stack,
+            )
+            .unwrap();
+
+        assert_eq!(
+            result,
+            StackType::empty().push(Type::Named {
+                name: "Option".to_string(),
+                args: vec![Type::Int],
+            })
+        );
+    }
+
+    #[test]
+    fn test_check_program_registers_constructors() {
+        use crate::ast::{TypeDef, Variant, WordDef};
+
+        let color = Type::Named {
+            name: "Color".to_string(),
+            args: vec![],
+        };
+        let program = Program {
+            type_defs: vec![TypeDef {
+                name: "Color".to_string(),
+                type_params: vec![],
+                variants: vec![Variant {
+                    name: "Red".to_string(),
+                    fields: vec![],
+                }],
+                doc: None,
+            }],
+            word_defs: vec![WordDef {
+                name: "red".to_string(),
+                effect: Effect::from_vecs(vec![], vec![color]),
+                body: vec![Expr::WordCall("Red".to_string(), SourceLoc::unknown())],
+                loc: SourceLoc::unknown(),
+                feature: None,
+                inline: None,
+                slot_names: Default::default(),
+                doc: None,
+            }],
+        };
+
+        let mut checker = TypeChecker::new();
+        assert!(checker.check_program(&program).is_ok());
+    }
+
+    #[test]
+    fn test_match_branch_leaking_field() {
+        use crate::ast::{MatchBranch, Pattern};
+
+        let checker = TypeChecker::new();
+        let branch = |name: &str, body: Vec<Expr>| MatchBranch {
+            pattern: Pattern::Variant {
+                name: name.to_string(),
+                bindings: Vec::new(),
+            },
+            body,
+        };
+
+        // Option(Int) match Some => [ 1 ] None => [ 1 ] end
+        // The Some branch pushes 1 but never consumes the unwrapped Int
+        let stack = StackType::empty().push(Type::Named {
+            name: "Option".to_string(),
+            args: vec![Type::Int],
+        });
+        let expr = Expr::Match {
+            branches: vec![
+                branch("Some", vec![Expr::IntLit(1, SourceLoc::unknown())]),
+                branch("None", vec![Expr::IntLit(1, SourceLoc::unknown())]),
+            ],
+            loc: SourceLoc::unknown(),
+        };
+
+        match *checker.check_expr(&expr, stack).unwrap_err() {
+            TypeError::MatchBranchLeakedField {
+                variant, leaked, ..
+            } => {
+                assert_eq!(variant, "Some");
+                assert_eq!(leaked, 1);
+            }
+            e => panic!("Expected MatchBranchLeakedField, got {:?}", e),
+        }
+    }
+
+    #[test]
+    fn test_two_dup_and_two_drop() {
+        let checker = TypeChecker::new();
+        let stack = StackType::from_vec(vec![Type::Int, Type::Bool]);
+        let call = |name: &str| Expr::WordCall(name.to_string(), SourceLoc::unknown());
+
+        let result = checker.check_expr(&call("2dup"), stack.clone()).unwrap();
+        assert_eq!(
+            result,
+            StackType::from_vec(vec![Type::Int, Type::Bool, Type::Int, Type::Bool])
+        );
+
+        let result = checker.check_expr(&call("2drop"), stack).unwrap();
+        assert_eq!(result, StackType::Empty);
+    }
+
+    #[test]
+    fn test_assert_consumes_a_bool() {
+        let checker = TypeChecker::new();
+        let assert = Expr::WordCall("assert".to_string(), SourceLoc::unknown());
+
+        let result = checker
+            .check_expr(&assert, StackType::from_vec(vec![Type::Int, Type::Bool]))
+            .unwrap();
+        assert_eq!(result, StackType::from_vec(vec![Type::Int]));
+
+        assert!(
+            checker
+                .check_expr(&assert, StackType::from_vec(vec![Type::Int]))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_conditional_some_wraps_the_value_in_an_option() {
+        let checker = TypeChecker::new();
+        let option_int = Type::Named {
+            name: "Option".to_string(),
+            args: vec![Type::Int],
+        };
+
+        for word in ["some_if", "some_unless"] {
+            let call = Expr::WordCall(word.to_string(), SourceLoc::unknown());
+            let stack = checker
+                .check_expr(&call, StackType::from_vec(vec![Type::Int, Type::Bool]))
+                .unwrap();
+            assert_eq!(stack, StackType::from_vec(vec![option_int.clone()]));
+
+            // The flag must be a Bool
+            let err = checker
+                .check_expr(&call, StackType::from_vec(vec![Type::Int, Type::Int]))
+                .unwrap_err();
+            assert!(matches!(*err, TypeError::InputMismatch { .. }));
+        }
+    }
+
+    #[test]
+    fn test_string_split_produces_a_list_of_strings() {
+        let checker = TypeChecker::new();
+        let exprs = [
+            Expr::StringLit("a,b,c".to_string(), SourceLoc::unknown()),
+            Expr::StringLit(",".to_string(), SourceLoc::unknown()),
+            Expr::WordCall("string_split".to_string(), SourceLoc::unknown()),
+        ];
+
+        let result = checker.check_exprs(&exprs, StackType::Empty).unwrap();
+        assert_eq!(
+            result,
+            StackType::from_vec(vec![Type::Named {
+                name: "List".to_string(),
+                args: vec![Type::String],
+            }])
+        );
+
+        // The delimiter must be a String too
+        assert!(
+            checker
+                .check_exprs(&exprs[..1], StackType::from_vec(vec![Type::Int]))
+                .and_then(|stack| checker.check_exprs(&exprs[2..], stack))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_write_and_write_line_consume_a_string() {
+        let checker = TypeChecker::new();
+        for word in ["write", "write_line"] {
+            let call = Expr::WordCall(word.to_string(), SourceLoc::unknown());
+            let result = checker
+                .check_expr(&call, StackType::from_vec(vec![Type::Int, Type::String]))
+                .unwrap();
+            assert_eq!(result, StackType::from_vec(vec![Type::Int]), "{}", word);
+
+            assert!(
+                checker
+                    .check_expr(&call, StackType::from_vec(vec![Type::Int]))
+                    .is_err(),
+                "{} should reject an Int",
+                word
+            );
+        }
+    }
+
+    #[test]
+    fn test_guarded_if_condition_must_only_push_a_bool() {
+        use crate::parser::Parser;
+
+        let check = |source: &str| {
+            let program = Parser::new(source).parse().unwrap();
+            TypeChecker::new().check_program(&program)
+        };
+
+        assert!(check(": abs ( Int -- Int ) ifte [ dup 0 < ] [ 0 swap - ] [ ] ;").is_ok());
+
+        // Consuming the value under test is not a condition
+        let err = check(": f ( Int -- Int ) ifte [ 0 < ] [ 1 ] [ 2 ] ;").unwrap_err();
+        assert!(
+            matches!(*err, TypeError::GuardConditionMismatch { .. }),
+            "unexpected error: {}",
+            err
+        );
+        // Nor is leaving something other than a Bool
+        assert!(check(": f ( Int -- Int ) ifte [ dup ] [ ] [ ] ;").is_err());
+    }
+
+    #[test]
+    fn test_int_match_requires_a_final_wildcard() {
+        use crate::parser::Parser;
+
+        let check = |source: &str| {
+            let program = Parser::new(source).parse().unwrap();
+            TypeChecker::new().check_program(&program)
+        };
+
+        assert!(
+            check(": name ( Int -- String ) match 0 => \"zero\" 1 => \"one\" _ => \"many\" end ;")
+                .is_ok()
+        );
+
+        let err = check(": f ( Int -- Int ) match 0 => 1 1 => 0 end ;").unwrap_err();
+        assert_eq!(
+            *err,
+            TypeError::NonExhaustiveMatch {
+                type_name: "Int".to_string(),
+                missing_variants: vec!["_".to_string()],
+            }
+        );
+
+        // Arms that can never match
+        let err = check(": f ( Int -- Int ) match _ => 1 0 => 0 end ;").unwrap_err();
+        assert!(
+            err.to_string().contains("Unreachable match arm '0'"),
+            "{}",
+            err
+        );
+        assert!(check(": f ( Int -- Int ) match 0 => 1 0 => 2 _ => 3 end ;").is_err());
+    }
+
+    #[test]
+    fn test_variant_matched_twice_is_a_duplicate_arm() {
+        use crate::parser::Parser;
+
+        let program = Parser::new(
+            "type Flag | On | Off\n\
+             : f ( Flag -- Int ) match On => 1 On => 2 Off => 0 end ;",
+        )
+        .parse()
+        .unwrap();
+        let err = TypeChecker::new().check_program(&program).unwrap_err();
+        match *err {
+            TypeError::DuplicateMatchArm { variant, loc } => {
+                assert_eq!(variant, "On");
+                assert_eq!(loc.line, 2);
+            }
+            e => panic!("Expected DuplicateMatchArm, got {:?}", e),
+        }
+    }
+
+    #[test]
+    fn test_environment_reports_checked_words() {
+        use crate::parser::Parser;
+
+        let program = Parser::new(
+            "type Flag | On | Off\n\
+             : square ( Int -- Int ) dup * ;\n\
+             : quad ( Int -- Int ) square square ;",
+        )
+        .parse()
+        .unwrap();
+        let mut checker = TypeChecker::new();
+        checker.check_program(&program).unwrap();
+
+        let env = checker.environment();
+        let int_to_int = Effect::from_vecs(vec![Type::Int], vec![Type::Int]);
+        assert_eq!(env.lookup_word("square"), Some(&int_to_int));
+        assert_eq!(env.lookup_word("quad"), Some(&int_to_int));
+        assert!(env.words().any(|(name, _)| name == "quad"));
+
+        let flag = env.types().find(|t| t.name == "Flag").unwrap();
+        assert_eq!(flag.variants.len(), 2);
+    }
+
+    #[test]
+    fn test_inspect_preserves_stack() {
+        let checker = TypeChecker::new();
+        let stack = StackType::from_vec(vec![Type::Bool, Type::String]);
+
+        let result = checker
+            .check_expr(
+                &Expr::WordCall("inspect".to_string(), SourceLoc::unknown()),
+                stack.clone(),
+            )
+            .unwrap();
+
+        assert_eq!(result, stack);
+    }
+
+    #[test]
+    fn test_unguarded_recursion_warns() {
+        use crate::parser::Parser;
+
+        let program = Parser::new(
+            ": spin ( Int -- Int ) 1 + spin ;
+             : countdown ( Int -- Int ) dup 0 > if [ 1 - countdown ] [ ] ;",
+        )
+        .parse()
+        .unwrap();
+
+        let mut checker = TypeChecker::new();
+        checker.check_program(&program).unwrap();
+
+        assert_eq!(
+            checker.warnings(),
+            &[TypeWarning::UnguardedRecursion {
+                word: "spin".to_string()
+            }]
+        );
+    }
+
+    #[test]
+    fn test_constant_true_while_warns() {
+        use crate::parser::Parser;
+
+        let program = Parser::new(
+            ": spin ( -- ) while [ true ] [ ] ;\n\
+             : quit ( -- ) while [ true ] [ 0 exit ] ;\n\
+             : count ( Int -- Int ) while [ dup 10 < ] [ 1 + ] ;",
+        )
+        .parse()
+        .unwrap();
+
+        let mut checker = TypeChecker::new();
+        checker.check_program(&program).unwrap();
+
+        assert_eq!(checker.warnings().len(), 1, "{:?}", checker.warnings());
+        let TypeWarning::InfiniteLoop { word, loc } = &checker.warnings()[0] else {
+            panic!(
+                "Expected an infinite-loop warning, got {:?}",
+                checker.warnings()
+            );
+        };
+        assert_eq!(word, "spin");
+        assert_eq!((loc.line, loc.column), (1, 15));
+        assert!(
+            checker.warnings()[0]
+                .to_string()
+                .contains("never terminates")
+        );
+    }
+
+    #[test]
+    fn test_over_general_declaration_warns() {
+        use crate::parser::Parser;
+
+        let program = Parser::new(
+            r#"
+            : f ( A -- A ) 1 + ;
+            : twice ( A -- A A ) dup ;
+            : first ( A B -- A ) drop ;
+            "#,
+        )
+        .parse()
+        .unwrap();
+
+        let mut checker = TypeChecker::new();
+        checker.check_program(&program).unwrap();
+
+        // Only `f` promises a polymorphism its body doesn't have
+        assert_eq!(
+            checker.warnings(),
+            &[TypeWarning::OverGeneralEffect {
+                word: "f".to_string(),
+                declared: Box::new(Effect::from_vecs(
+                    vec![Type::Var("A".to_string())],
+                    vec![Type::Var("A".to_string())]
+                )),
+                required: Box::new(Effect::from_vecs(vec![Type::Int], vec![Type::Int])),
+            }]
+        );
+        assert_eq!(
+            checker.warnings()[0].to_string(),
+            "Word 'f' declares ( A -- A ) but its body requires ( Int -- Int )"
+        );
+    }
+
+    #[test]
+    fn test_body_fixing_a_declared_variable_is_reported_by_name() {
+        use crate::parser::Parser;
+
+        let check = |source: &str| {
+            let program = Parser::new(source).parse().unwrap();
+            TypeChecker::new().check_program(&program)
+        };
+
+        let err = check(": f ( A -- A ) drop 1 ;").unwrap_err();
+        assert_eq!(
+            *err,
+            TypeError::DeclaredVariableMismatch {
+                word: "f".to_string(),
+                variable: "A".to_string(),
+                actual: Type::Int,
+                declared: Effect::from_vecs(
+                    vec![Type::Var("A".to_string())],
+                    vec![Type::Var("A".to_string())]
+                ),
+            }
+        );
+        assert_eq!(
+            err.to_string(),
+            "Word 'f' declares ( A -- A ), but its body turns A into Int"
+        );
+
+        let err = check(": g ( A -- B ) ;").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Word 'g' declares ( A -- B ), but its body makes A the same type as B"
+        );
+    }
+
+    #[test]
+    fn test_if_branch_mismatch_reports_both_branches() {
+        use crate::parser::Parser;
+
+        let program = Parser::new_with_filename(
+            ": pick ( Int Bool -- Int ) if [ 1 + ] [ true ] ;",
+            "pick.cem",
+        )
+        .parse()
+        .unwrap();
+
+        let err = TypeChecker::new().check_program(&program).unwrap_err();
+        let TypeError::IfBranchMismatch {
+            then_effect,
+            else_effect,
+            loc,
+        } = *err.clone()
+        else {
+            panic!("Expected IfBranchMismatch, got {:?}", err);
+        };
+        assert_eq!((loc.line, loc.column), (1, 28));
+        assert_eq!(then_effect.to_string(), "( Int -- Int )");
+        assert_eq!(else_effect.to_string(), "( Int -- Int Bool )");
+        assert!(err.to_string().contains("at pick.cem:1:28"));
+    }
+
+    #[test]
+    fn test_library_words_need_their_interface() {
+        use crate::parser::Parser;
+
+        let program = Parser::new(": main ( -- Int ) 3 square ;").parse().unwrap();
+
+        let err = TypeChecker::new().check_program(&program).unwrap_err();
+        assert!(matches!(*err, TypeError::UndefinedWord { ref name } if name == "square"));
+
+        let interface =
+            Interface::parse("cem-interface 1\nword square square ( Int -- Int )").unwrap();
+        let mut checker = TypeChecker::new();
+        checker.add_interface(&interface);
+        checker.check_program(&program).unwrap();
+    }
+
+    #[test]
+    fn test_mismatch_names_the_argument() {
+        use crate::parser::Parser;
+
+        let program = Parser::new(
+            r#"
+            : scale ( n:Int factor:Int -- scaled:Int ) * ;
+            : main ( -- Int ) 2 true scale ;
+            "#,
+        )
+        .parse()
+        .unwrap();
+
+        let err = TypeChecker::new().check_program(&program).unwrap_err();
+        match err.as_ref() {
+            TypeError::TypeMismatch {
+                expected,
+                actual,
+                context,
+            } => {
+                assert_eq!(*expected, Type::Int);
+                assert_eq!(*actual, Type::Bool);
+                assert_eq!(context, "argument `factor` of 'scale'");
+            }
+            other => panic!("Expected TypeMismatch, got {:?}", other),
+        }
+        assert!(err.to_string().contains("argument `factor` of 'scale'"));
+    }
+
+    #[test]
+    fn test_effect_with_undefined_type_is_rejected() {
+        use crate::parser::Parser;
+
+        let program = Parser::new(": f ( Option(Widget) -- ) drop ;")
+            .parse()
+            .unwrap();
+
+        let err = TypeChecker::new().check_program(&program).unwrap_err();
+        match err.as_ref() {
+            TypeError::UndefinedType { name, word, loc } => {
+                assert_eq!(name, "Widget");
+                assert_eq!(word.as_deref(), Some("f"));
+                assert_eq!(loc.as_ref().map(|l| l.line), Some(1));
+            }
+            other => panic!("Expected UndefinedType, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_cons_pattern_binds_the_element_and_the_tail() {
+        use crate::parser::Parser;
+
+        let check = |source: &str| {
+            let program = Parser::new(source).parse().unwrap();
+            TypeChecker::new().check_program(&program)
+        };
+
+        // `h` is the element type and `t` the rest of the list, so recursing on `t` and
+        // adding `h` sums the list
+        check(
+            ": sum ( List(Int) -- Int )
+               match
+                 Cons(h, t) => sum +
+                 Nil => 0
+               end ;",
+        )
+        .unwrap();
+
+        // The element of a List(String) is a String, not just any type
+        check(
+            ": count ( List(String) -- Int )
+               match
+                 Cons(h, t) => count swap drop 1 +
+                 Nil => 0
+               end ;",
+        )
+        .unwrap();
+        let err = check(
+            ": bad ( List(String) -- Int )
+               match
+                 Cons(h, t) => drop 1 +
+                 Nil => 0
+               end ;",
+        )
+        .unwrap_err();
+        assert!(matches!(*err, TypeError::InputMismatch { ref word, .. } if word == "+"));
+
+        // One name per field
+        let err = check(
+            ": f ( List(Int) -- Int )
+               match
+                 Cons(h) => drop drop 0
+                 Nil => 0
+               end ;",
+        )
+        .unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("Pattern Cons(h) names 1 field(s), but 'Cons' has 2")
+        );
+
+        // Field names aren't words: calling one is an error unless a word has that name
+        let err = check(
+            ": f ( List(Int) -- Int )
+               match
+                 Cons(h, t) => [ t ] drop drop
+                 Nil => 0
+               end ;",
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "'t' only documents a field of Cons; the field is on the stack, not a word"
+        );
+        check(
+            ": head ( List(Int) -- Int )
+               match
+                 Cons(head, tail) => drop
+                 Nil => 0
+               end ;
+             : second ( List(Int) -- Int )
+               match
+                 Cons(head, tail) => swap drop head
+                 Nil => 0
+               end ;",
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_tuple_pattern_binds_each_component() {
+        use crate::parser::Parser;
+
+        let check = |source: &str| {
+            let program = Parser::new(source).parse().unwrap();
+            TypeChecker::new().check_program(&program)
+        };
+
+        // `n` is the Int and `flag` the Bool, on top
+        let source = ": bump ( Tuple(Int Bool) -- Int )
+                        match
+                          Tuple(n, flag) => if [ 1 + ] [ 1 - ]
+                        end ;";
+        check(source).unwrap();
+        let err = check(&source.replace("Tuple(Int Bool) --", "Tuple(Bool Int) --")).unwrap_err();
+        assert!(matches!(*err, TypeError::TypeMismatch { .. }), "{:?}", err);
+
+        // Constructing one pairs the top two values, bottom first
+        check(": pair ( -- Tuple(Int Bool) ) 1 true Tuple ;").unwrap();
+    }
+
+    #[test]
+    fn test_named_type_needs_one_argument_per_parameter() {
+        use crate::parser::Parser;
+
+        let check = |source: &str| {
+            let program = Parser::new(source).parse().unwrap();
+            TypeChecker::new().check_program(&program)
+        };
+
+        let err = check(": f ( Option(Int, Bool) -- ) drop ;").unwrap_err();
+        assert_eq!(
+            *err,
+            TypeError::WrongTypeArity {
+                name: "Option".to_string(),
+                expected: 1,
+                found: 2,
+            }
+        );
+        let err = check(": f ( Option -- ) drop ;").unwrap_err();
+        assert_eq!(
+            *err,
+            TypeError::WrongTypeArity {
+                name: "Option".to_string(),
+                expected: 1,
+                found: 0,
+            }
+        );
+        assert!(check(": f ( Option(Int) -- ) drop ;").is_ok());
+    }
+
+    #[test]
+    fn test_compose_quotations() {
+        use crate::parser::Parser;
+
+        let program = Parser::new(": main ( -- Int ) 5 [ 1 + ] [ 2 * ] compose call_quotation ;")
+            .parse()
+            .unwrap();
+        let checker = TypeChecker::new();
+
+        // [ 1 + ] [ 2 * ] compose : a quotation from one Int to one Int, over any stack
+        let stack = program.word_defs[0].body[1..4]
+            .iter()
+            .try_fold(StackType::Empty, |stack, expr| {
+                checker.check_expr(expr, stack)
+            })
+            .unwrap();
+        let (types, _) = stack.to_vec();
+        let [Type::Quotation(effect)] = types.as_slice() else {
+            panic!("Expected a single quotation, got {}", stack);
+        };
+        let (inputs, input_row) = effect.inputs.to_vec();
+        let (outputs, output_row) = effect.outputs.to_vec();
+        assert_eq!(inputs, vec![Type::Int]);
+        assert_eq!(outputs, vec![Type::Int]);
+        assert!(input_row.is_some());
+        assert_eq!(input_row, output_row);
+
+        // Calling the composition on 5 leaves the declared Int
+        TypeChecker::new().check_program(&program).unwrap();
+    }
+
+    #[test]
+    fn test_curry_captures_the_first_input() {
+        use crate::parser::Parser;
+
+        let program = Parser::new(": main ( -- Int ) 41 1 [ + ] curry call_quotation ;")
+            .parse()
+            .unwrap();
+        let checker = TypeChecker::new();
+
+        // 1 [ + ] curry : a quotation from one Int to one Int, over any stack
+        let stack = program.word_defs[0].body[1..4]
+            .iter()
+            .try_fold(StackType::Empty, |stack, expr| {
+                checker.check_expr(expr, stack)
+            })
+            .unwrap();
+        let (types, _) = stack.to_vec();
+        let [Type::Quotation(effect)] = types.as_slice() else {
+            panic!("Expected a single quotation, got {}", stack);
+        };
+        let (inputs, input_row) = effect.inputs.to_vec();
+        let (outputs, output_row) = effect.outputs.to_vec();
+        assert_eq!(inputs, vec![Type::Int]);
+        assert_eq!(outputs, vec![Type::Int]);
+        assert!(input_row.is_some());
+        assert_eq!(input_row, output_row);
+
+        TypeChecker::new().check_program(&program).unwrap();
+
+        // The captured value must be what the quotation takes
+        let program = Parser::new(r#": f ( -- Int ) 41 "one" [ + ] curry call_quotation ;"#)
+            .parse()
+            .unwrap();
+        assert!(TypeChecker::new().check_program(&program).is_err());
+    }
+
+    #[test]
+    fn test_a_user_defined_curry_keeps_its_own_effect() {
+        use crate::parser::Parser;
+
+        let program = Parser::new(
+            r#": curry ( String Int -- Int ) swap drop ;
+               : main ( -- Int ) "hi" 1 curry ;"#,
+        )
+        .parse()
+        .unwrap();
+        TypeChecker::new().check_program(&program).unwrap();
+    }
+
+    #[test]
+    fn test_copying_a_quotation_that_owns_a_string_is_a_linear_violation() {
+        use crate::parser::Parser;
+
+        let check = |source: &str| {
+            let program = Parser::new(source).parse().unwrap();
+            TypeChecker::new().check_program(&program)
+        };
+
+        // The String moves into the quotation; calling it once uses it once
+        check(r#": greet ( -- ) "hi" [ write_line ] curry call_quotation ;"#).unwrap();
+
+        // Copying the quotation would write the moved String a second time
+        let err = check(
+            r#": greet ( -- ) "hi" [ write_line ] curry dup call_quotation call_quotation ;"#,
+        )
+        .unwrap_err();
+        let TypeError::UseAfterMove { captured, word, .. } = *err else {
+            panic!("Expected a linear violation, got {}", err);
+        };
+        assert_eq!(captured, Type::String);
+        assert_eq!(word, "dup");
+
+        // Currying more onto the quotation keeps the String in it
+        assert!(matches!(
+            *check(r#": f ( -- ) "hi" [ write_line drop ] curry 1 swap curry dup drop drop ;"#)
+                .unwrap_err(),
+            TypeError::UseAfterMove { .. }
+        ));
+
+        // A quotation holding only Copy values may be copied, and each copy called
+        check(": f ( -- ) 1 [ + ] curry dup drop drop ;").unwrap();
+        check(": f ( -- Int ) 20 [ 1 + ] curry dup call_quotation swap call_quotation + ;")
+            .unwrap();
+
+        // A quotation whose stack is the caller's own can't be called with its copy on
+        // that stack; this is an error, not an infinite type
+        assert!(
+            check(": f ( ..A [..A -- ..A] -- ..A ) dup call_quotation call_quotation ;").is_err()
+        );
+    }
+
+    #[test]
+    fn test_seq_of_statements_is_stack_neutral() {
+        use crate::parser::Parser;
+
+        let check = |source: &str| {
+            let program = Parser::new(source).parse().unwrap();
+            TypeChecker::new().check_program(&program)
+        };
+
+        let program = Parser::new(r#": greet ( -- ) [ "a" write_line ] [ "b" write_line ] seq ;"#)
+            .parse()
+            .unwrap();
+        let stack = program.word_defs[0]
+            .body
+            .iter()
+            .try_fold(StackType::Empty, |stack, expr| {
+                TypeChecker::new().check_expr(expr, stack)
+            })
+            .unwrap();
+        assert_eq!(stack, StackType::Empty);
+
+        // Statements run on whatever is below them
+        assert!(check(": f ( Int -- Int ) [ ] [ \"b\" write_line ] seq ;").is_ok());
+        // A quotation that leaves a value isn't a statement
+        assert!(check(": f ( -- Int ) [ 1 ] [ ] seq ;").is_err());
+    }
+
+    #[test]
+    fn test_input_type_mismatch_is_categorized() {
+        use crate::parser::Parser;
+
+        // Deep enough for `+`, but its top value is a String
+        let program = Parser::new(r#": f ( -- Int ) 1 "x" + ;"#).parse().unwrap();
+
+        let err = TypeChecker::new().check_program(&program).unwrap_err();
+        assert_eq!(
+            *err,
+            TypeError::InputMismatch {
+                word: "+".to_string(),
+                kind: InputMismatchKind::Type {
+                    position: 0,
+                    expected: Type::Int,
+                    actual: Type::String,
+                },
+            }
+        );
+    }
+
+    #[test]
+    fn test_quotation_passed_as_a_value_is_a_type_mismatch() {
+        use crate::parser::Parser;
+
+        let program = Parser::new(": f ( Int -- Int ) [ 1 + ] + ;")
+            .parse()
+            .unwrap();
+
+        let err = TypeChecker::new().check_program(&program).unwrap_err();
+        match err.as_ref() {
+            TypeError::TypeMismatch {
+                expected,
+                actual: Type::Quotation(effect),
+                context,
+            } => {
+                assert_eq!(*expected, Type::Int);
+                assert_eq!(effect.inputs.to_vec().0, vec![Type::Int]);
+                assert_eq!(effect.outputs.to_vec().0, vec![Type::Int]);
+                assert_eq!(context, "input 0 from the top of '+'");
+            }
+            other => panic!("Expected TypeMismatch, got {:?}", other),
+        }
+        assert!(
+            err.to_string()
+                .contains("expected Int, but got quotation [ ..R")
+        );
+    }
+
+    #[test]
+    fn test_conflicting_bindings_are_a_type_mismatch() {
+        // ( A A -- ) applied to Int Bool: each value fits alone, the second conflicts
+        let inputs = StackType::from_vec(vec![Type::Var("A".into()), Type::Var("A".into())]);
+        let stack = StackType::from_vec(vec![Type::Int, Type::Bool]);
+
+        assert_eq!(
+            TypeChecker::classify_input_mismatch(&inputs, &stack),
+            InputMismatchKind::Type {
+                position: 1,
+                expected: Type::Var("A".into()),
+                actual: Type::Int,
+            }
+        );
+    }
 }
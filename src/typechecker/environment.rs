@@ -47,6 +47,18 @@ impl Environment {
         self.words.get(name)
     }
 
+    /// All known words and their effects, in no particular order
+    pub fn words(&self) -> impl Iterator<Item = (&str, &Effect)> {
+        self.words
+            .iter()
+            .map(|(name, effect)| (name.as_str(), effect))
+    }
+
+    /// All known type definitions, in no particular order
+    pub fn types(&self) -> impl Iterator<Item = &TypeDef> {
+        self.types.values()
+    }
+
     /// Add a type definition and automatically create variant constructor words
     pub fn add_type(&mut self, typedef: TypeDef) {
         // Note: Validation of variant features (multi-field, nested) happens at codegen time
@@ -79,17 +91,14 @@ impl Environment {
             };
 
             // Build the effect signature
-            // Input stack: variant fields (if any)
+            // Input stack: variant fields (if any), the first field deepest, as a
+            //   match on the variant pushes them back: Cons(T List(T)) is
+            //   ( T List(T) -- List(T) )
             // Output stack: the ADT type
-            //
-            // Note: .rev() is used because stack types are built right-to-left
-            // For Some(T), we want: ( T -- Option(T) )
-            // Without .rev(), we'd get the fields in wrong order for multi-field variants
             let effect = Effect {
                 inputs: variant
                     .fields
                     .iter()
-                    .rev()
                     .fold(StackType::Empty, |stack, field| stack.push(field.clone())),
                 outputs: StackType::Empty.push(result_type),
             };
@@ -203,6 +212,32 @@ impl Environment {
             },
         );
 
+        // 2dup: ( A B -- A B A B )
+        self.add_word(
+            "2dup".to_string(),
+            Effect {
+                inputs: StackType::empty()
+                    .push(Type::Var("A".to_string()))
+                    .push(Type::Var("B".to_string())),
+                outputs: StackType::empty()
+                    .push(Type::Var("A".to_string()))
+                    .push(Type::Var("B".to_string()))
+                    .push(Type::Var("A".to_string()))
+                    .push(Type::Var("B".to_string())),
+            },
+        );
+
+        // 2drop: ( A B -- )
+        self.add_word(
+            "2drop".to_string(),
+            Effect {
+                inputs: StackType::empty()
+                    .push(Type::Var("A".to_string()))
+                    .push(Type::Var("B".to_string())),
+                outputs: StackType::empty(),
+            },
+        );
+
         // Arithmetic operations
         // +: ( Int Int -- Int )
         self.add_word(
@@ -216,18 +251,42 @@ impl Environment {
             Effect::from_vecs(vec![Type::Int, Type::Int], vec![Type::Int]),
         );
 
-        // *: ( Int Int -- Int )
+        // negate: ( Int -- Int )
         self.add_word(
-            "*".to_string(),
+            "negate".to_string(),
+            Effect::from_vecs(vec![Type::Int], vec![Type::Int]),
+        );
+
+        // abs, signum: ( Int -- Int )
+        for name in ["abs", "signum"] {
+            self.add_word(
+                name.to_string(),
+                Effect::from_vecs(vec![Type::Int], vec![Type::Int]),
+            );
+        }
+
+        // gcd: ( Int Int -- Int ), never negative except that 2^63 (the gcd of INT64_MIN
+        // with 0 or itself) wraps to INT64_MIN
+        self.add_word(
+            "gcd".to_string(),
             Effect::from_vecs(vec![Type::Int, Type::Int], vec![Type::Int]),
         );
 
-        // /: ( Int Int -- Int )
+        // *: ( Int Int -- Int )
         self.add_word(
-            "/".to_string(),
+            "*".to_string(),
             Effect::from_vecs(vec![Type::Int, Type::Int], vec![Type::Int]),
         );
 
+        // /, quot: ( Int Int -- Int ) truncating division
+        // mod, rem: ( Int Int -- Int ) remainder with the sign of the dividend
+        for name in ["/", "quot", "mod", "rem"] {
+            self.add_word(
+                name.to_string(),
+                Effect::from_vecs(vec![Type::Int, Type::Int], vec![Type::Int]),
+            );
+        }
+
         // Comparison operations
         // =: ( Int Int -- Bool )
         self.add_word(
@@ -265,6 +324,13 @@ impl Environment {
             Effect::from_vecs(vec![Type::Int, Type::Int], vec![Type::Bool]),
         );
 
+        // Boolean operations
+        // not: ( Bool -- Bool )
+        self.add_word(
+            "not".to_string(),
+            Effect::from_vecs(vec![Type::Bool], vec![Type::Bool]),
+        );
+
         // clone: ( A -- A A ) for explicit cloning
         self.add_word(
             "clone".to_string(),
@@ -276,6 +342,72 @@ impl Environment {
             },
         );
 
+        // inspect: ( A -- A ) prints the top value without consuming it (debugging)
+        self.add_word(
+            "inspect".to_string(),
+            Effect {
+                inputs: StackType::empty().push(Type::Var("A".to_string())),
+                outputs: StackType::empty().push(Type::Var("A".to_string())),
+            },
+        );
+
+        // breakpoint: ( -- ) stops in an attached debugger
+        self.add_word("breakpoint".to_string(), Effect::from_vecs(vec![], vec![]));
+
+        // assert: ( Bool -- ) aborts with the assert's source location when false
+        self.add_word(
+            "assert".to_string(),
+            Effect::from_vecs(vec![Type::Bool], vec![]),
+        );
+
+        // some_if: ( A Bool -- Option(A) ) gives Some of the value when the Bool is true
+        // and None otherwise; some_unless is the reverse
+        for word in ["some_if", "some_unless"] {
+            self.add_word(
+                word.to_string(),
+                Effect::from_vecs(
+                    vec![Type::Var("A".to_string()), Type::Bool],
+                    vec![Type::Named {
+                        name: "Option".to_string(),
+                        args: vec![Type::Var("A".to_string())],
+                    }],
+                ),
+            );
+        }
+
+        // string_split: ( String String -- List(String) ) splits the second string at
+        // each occurrence of the delimiter on top
+        self.add_word(
+            "string_split".to_string(),
+            Effect::from_vecs(
+                vec![Type::String, Type::String],
+                vec![Type::Named {
+                    name: "List".to_string(),
+                    args: vec![Type::String],
+                }],
+            ),
+        );
+
+        // ++: ( String String -- String ) appends the top string to the one below it
+        self.add_word(
+            "++".to_string(),
+            Effect::from_vecs(vec![Type::String, Type::String], vec![Type::String]),
+        );
+
+        // write: ( String -- ) prints the string as is; write_line appends a newline
+        for word in ["write", "write_line"] {
+            self.add_word(
+                word.to_string(),
+                Effect::from_vecs(vec![Type::String], vec![]),
+            );
+        }
+
+        // read_line: ( -- String ) reads one line from stdin
+        self.add_word(
+            "read_line".to_string(),
+            Effect::from_vecs(vec![], vec![Type::String]),
+        );
+
         // Type conversions
         // int-to-string: ( Int -- String )
         self.add_word(
@@ -289,12 +421,69 @@ impl Environment {
             Effect::from_vecs(vec![Type::Bool], vec![Type::String]),
         );
 
+        // bool_to_int: ( Bool -- Int ), false -> 0 and true -> 1
+        self.add_word(
+            "bool_to_int".to_string(),
+            Effect::from_vecs(vec![Type::Bool], vec![Type::Int]),
+        );
+
+        // int_to_bool: ( Int -- Bool ), nonzero -> true
+        self.add_word(
+            "int_to_bool".to_string(),
+            Effect::from_vecs(vec![Type::Int], vec![Type::Bool]),
+        );
+
         // exit: ( Int -- )
         // Note: This function never returns, but we model it as consuming Int and producing empty stack
         self.add_word(
             "exit".to_string(),
             Effect::from_vecs(vec![Type::Int], vec![]),
         );
+
+        // Quotation combinators are typed over row variables, so they work at any depth
+        let row = |name: &str| StackType::RowVar(name.to_string());
+        let quotation = |inputs: &str, outputs: &str| {
+            Type::Quotation(Box::new(Effect::new(row(inputs), row(outputs))))
+        };
+
+        // call_quotation: ( ..A [..A -- ..B] -- ..B )
+        self.add_word(
+            "call_quotation".to_string(),
+            Effect::new(row("A").push(quotation("A", "B")), row("B")),
+        );
+
+        // compose: ( ..S [..A -- ..B] [..B -- ..C] -- ..S [..A -- ..C] )
+        self.add_word(
+            "compose".to_string(),
+            Effect::new(
+                row("S").push(quotation("A", "B")).push(quotation("B", "C")),
+                row("S").push(quotation("A", "C")),
+            ),
+        );
+
+        // seq: ( ..A [..A -- ..A] [..A -- ..A] -- ..A ) runs two statements in order; both
+        // must leave the stack as they found it
+        self.add_word(
+            "seq".to_string(),
+            Effect::new(
+                row("A").push(quotation("A", "A")).push(quotation("A", "A")),
+                row("A"),
+            ),
+        );
+
+        // curry: ( ..S A [..R A -- ..T] -- ..S [..R -- ..T] ) captures A in the quotation
+        self.add_word(
+            "curry".to_string(),
+            Effect::new(
+                row("S")
+                    .push(Type::Var("A".to_string()))
+                    .push(Type::Quotation(Box::new(Effect::new(
+                        row("R").push(Type::Var("A".to_string())),
+                        row("T"),
+                    )))),
+                row("S").push(quotation("R", "T")),
+            ),
+        );
     }
 
     /// Add built-in type definitions
@@ -313,6 +502,7 @@ impl Environment {
                     fields: vec![],
                 },
             ],
+            doc: None,
         });
 
         // Result<T, E>
@@ -329,6 +519,18 @@ impl Environment {
                     fields: vec![Type::Var("E".to_string())],
                 },
             ],
+            doc: None,
+        });
+
+        // Tuple<A, B>: a single variant, so `match Tuple(a, b) => ...` destructures it
+        self.add_type(TypeDef {
+            name: "Tuple".to_string(),
+            type_params: vec!["A".to_string(), "B".to_string()],
+            variants: vec![Variant {
+                name: "Tuple".to_string(),
+                fields: vec![Type::Var("A".to_string()), Type::Var("B".to_string())],
+            }],
+            doc: None,
         });
 
         // List<T>
@@ -351,6 +553,7 @@ impl Environment {
                     fields: vec![],
                 },
             ],
+            doc: None,
         });
     }
 }
@@ -382,6 +585,56 @@ mod tests {
         assert!(env.lookup_word("unknown").is_none());
     }
 
+    #[test]
+    fn test_not_negates_a_bool() {
+        let env = Environment::new();
+
+        assert_eq!(
+            env.lookup_word("not"),
+            Some(&Effect::from_vecs(vec![Type::Bool], vec![Type::Bool]))
+        );
+    }
+
+    #[test]
+    fn test_negate_flips_an_int() {
+        let env = Environment::new();
+
+        assert_eq!(
+            env.lookup_word("negate"),
+            Some(&Effect::from_vecs(vec![Type::Int], vec![Type::Int]))
+        );
+    }
+
+    #[test]
+    fn test_abs_signum_and_gcd() {
+        let env = Environment::new();
+
+        let unary = Effect::from_vecs(vec![Type::Int], vec![Type::Int]);
+        assert_eq!(env.lookup_word("abs"), Some(&unary));
+        assert_eq!(env.lookup_word("signum"), Some(&unary));
+        assert_eq!(
+            env.lookup_word("gcd"),
+            Some(&Effect::from_vecs(
+                vec![Type::Int, Type::Int],
+                vec![Type::Int]
+            ))
+        );
+    }
+
+    #[test]
+    fn test_bool_int_conversions() {
+        let env = Environment::new();
+
+        assert_eq!(
+            env.lookup_word("bool_to_int"),
+            Some(&Effect::from_vecs(vec![Type::Bool], vec![Type::Int]))
+        );
+        assert_eq!(
+            env.lookup_word("int_to_bool"),
+            Some(&Effect::from_vecs(vec![Type::Int], vec![Type::Bool]))
+        );
+    }
+
     #[test]
     fn test_builtin_types() {
         let env = Environment::new();
@@ -402,6 +655,23 @@ mod tests {
         assert_eq!(list_def.unwrap().variants.len(), 2);
     }
 
+    #[test]
+    fn test_constructor_takes_its_first_field_deepest() {
+        let env = Environment::new();
+
+        // Cons(T List(T)) is ( T List(T) -- List(T) ), the order make_variant packs
+        // the fields in and a match unpacks them
+        let t = Type::Var("T".to_string());
+        let list = Type::Named {
+            name: "List".to_string(),
+            args: vec![t.clone()],
+        };
+        assert_eq!(
+            env.lookup_word("Cons"),
+            Some(&Effect::from_vecs(vec![t, list.clone()], vec![list]))
+        );
+    }
+
     #[test]
     fn test_add_word() {
         let mut env = Environment::new();
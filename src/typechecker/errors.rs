@@ -1,6 +1,7 @@
 /**
 Type checking errors for Cem
 */
+use crate::ast::SourceLoc;
 use crate::ast::types::{Effect, StackType, Type};
 use std::fmt;
 
@@ -30,11 +31,35 @@ pub enum TypeError {
         word: String,
     },
 
+    /// A word's body resolves a type variable of its declared effect to a concrete
+    /// type or to another declared variable (`( A -- A ) drop 1`)
+    DeclaredVariableMismatch {
+        word: String,
+        variable: String,
+        actual: Type,
+        declared: Effect,
+    },
+
     /// Undefined word reference
     UndefinedWord { name: String },
 
     /// Undefined type reference
-    UndefinedType { name: String },
+    ///
+    /// `word` and `loc` identify the word whose effect signature names the type, when
+    /// the reference comes from a signature.
+    UndefinedType {
+        name: String,
+        word: Option<String>,
+        loc: Option<SourceLoc>,
+    },
+
+    /// A named type applied to the wrong number of type arguments (`Option(Int, Bool)`,
+    /// or a bare `Option`)
+    WrongTypeArity {
+        name: String,
+        expected: usize,
+        found: usize,
+    },
 
     /// Non-exhaustive pattern match
     NonExhaustiveMatch {
@@ -42,6 +67,9 @@ pub enum TypeError {
         missing_variants: Vec<String>,
     },
 
+    /// A variant matched by more than one arm; every arm after the first is dead
+    DuplicateMatchArm { variant: String, loc: SourceLoc },
+
     /// Inconsistent effects across pattern match branches
     InconsistentBranchEffects {
         type_name: String,
@@ -50,11 +78,50 @@ pub enum TypeError {
         branch: String,
     },
 
+    /// The branches of an `if` leave stacks that don't unify
+    ///
+    /// Both effects start from the stack below the condition.
+    IfBranchMismatch {
+        then_effect: Effect,
+        else_effect: Effect,
+        loc: SourceLoc,
+    },
+
+    /// A while loop's condition or body doesn't keep the stack shape the loop needs
+    ///
+    /// The condition must push just a Bool and the body must leave the stack as it
+    /// found it, so every iteration starts from the same stack.
+    WhileStackMismatch {
+        part: String,
+        expected: Effect,
+        actual: Effect,
+        loc: SourceLoc,
+    },
+
+    /// An `ifte` condition did more than push a Bool onto the stack it was given
+    GuardConditionMismatch {
+        expected: Effect,
+        actual: Effect,
+        loc: SourceLoc,
+    },
+
+    /// A match branch left some of its destructured fields on the stack
+    MatchBranchLeakedField {
+        type_name: String,
+        variant: String,
+        leaked: usize,
+    },
+
     /// Attempt to duplicate non-Copy type
     CannotDuplicate { ty: Type, operation: String },
 
-    /// Use of value after move (linear type violation)
-    UseAfterMove { var: String },
+    /// A word would duplicate a closure, using the linear value moved into it twice
+    /// (linear type violation)
+    UseAfterMove {
+        captured: Type,
+        word: String,
+        loc: SourceLoc,
+    },
 
     /// Cannot unify types (for polymorphism)
     UnificationError {
@@ -63,6 +130,12 @@ pub enum TypeError {
         reason: String,
     },
 
+    /// A word's declared inputs don't fit the stack it is applied to
+    InputMismatch {
+        word: String,
+        kind: InputMismatchKind,
+    },
+
     /// Cannot unify stack types
     StackUnificationError {
         stack1: StackType,
@@ -97,7 +170,9 @@ impl fmt::Display for TypeError {
                 write!(
                     f,
                     "Type mismatch in {}: expected {}, but got {}",
-                    context, expected, actual
+                    context,
+                    describe_type(expected),
+                    describe_type(actual)
                 )
             }
 
@@ -113,12 +188,57 @@ impl fmt::Display for TypeError {
                 )
             }
 
+            TypeError::DeclaredVariableMismatch {
+                word,
+                variable,
+                actual: actual @ Type::Var(_),
+                declared,
+            } => {
+                write!(
+                    f,
+                    "Word '{}' declares {}, but its body makes {} the same type as {}",
+                    word, declared, variable, actual
+                )
+            }
+
+            TypeError::DeclaredVariableMismatch {
+                word,
+                variable,
+                actual,
+                declared,
+            } => {
+                write!(
+                    f,
+                    "Word '{}' declares {}, but its body turns {} into {}",
+                    word, declared, variable, actual
+                )
+            }
+
             TypeError::UndefinedWord { name } => {
                 write!(f, "Undefined word: '{}'", name)
             }
 
-            TypeError::UndefinedType { name } => {
-                write!(f, "Undefined type: '{}'", name)
+            TypeError::UndefinedType { name, word, loc } => {
+                write!(f, "Undefined type: '{}'", name)?;
+                if let Some(word) = word {
+                    write!(f, " in effect of '{}'", word)?;
+                }
+                if let Some(loc) = loc {
+                    write!(f, " at {}", loc)?;
+                }
+                Ok(())
+            }
+
+            TypeError::WrongTypeArity {
+                name,
+                expected,
+                found,
+            } => {
+                write!(
+                    f,
+                    "Type '{}' takes {} type argument(s), but {} were given",
+                    name, expected, found
+                )
             }
 
             TypeError::NonExhaustiveMatch {
@@ -133,6 +253,14 @@ impl fmt::Display for TypeError {
                 )
             }
 
+            TypeError::DuplicateMatchArm { variant, loc } => {
+                write!(
+                    f,
+                    "Variant '{}' is matched by more than one arm of the match at {}; only the first can run",
+                    variant, loc
+                )
+            }
+
             TypeError::InconsistentBranchEffects {
                 type_name,
                 expected,
@@ -146,6 +274,55 @@ impl fmt::Display for TypeError {
                 )
             }
 
+            TypeError::IfBranchMismatch {
+                then_effect,
+                else_effect,
+                loc,
+            } => {
+                write!(
+                    f,
+                    "if branches produce incompatible stack effects at {}:\n  then: {}\n  else: {}",
+                    loc, then_effect, else_effect
+                )
+            }
+
+            TypeError::WhileStackMismatch {
+                part,
+                expected,
+                actual,
+                loc,
+            } => {
+                write!(
+                    f,
+                    "while {} at {} must have effect {}, but has {}",
+                    part, loc, expected, actual
+                )
+            }
+
+            TypeError::GuardConditionMismatch {
+                expected,
+                actual,
+                loc,
+            } => {
+                write!(
+                    f,
+                    "ifte condition at {} must have effect {}, but has {}",
+                    loc, expected, actual
+                )
+            }
+
+            TypeError::MatchBranchLeakedField {
+                type_name,
+                variant,
+                leaked,
+            } => {
+                write!(
+                    f,
+                    "Match branch '{}' on '{}' leaves {} of its field(s) on the stack unconsumed",
+                    variant, type_name, leaked
+                )
+            }
+
             TypeError::CannotDuplicate { ty, operation } => {
                 write!(
                     f,
@@ -155,14 +332,48 @@ impl fmt::Display for TypeError {
                 )
             }
 
-            TypeError::UseAfterMove { var } => {
-                write!(f, "Use of '{}' after move (linear type violation)", var)
+            TypeError::UseAfterMove {
+                captured,
+                word,
+                loc,
+            } => {
+                write!(
+                    f,
+                    "'{}' at {} would copy a quotation that owns the {} moved into it by \
+                     'curry', using that value twice (linear type violation)",
+                    word, loc, captured
+                )
             }
 
             TypeError::UnificationError { ty1, ty2, reason } => {
                 write!(f, "Cannot unify types {} and {}: {}", ty1, ty2, reason)
             }
 
+            TypeError::InputMismatch { word, kind } => match kind {
+                InputMismatchKind::Depth {
+                    required,
+                    available,
+                } => write!(
+                    f,
+                    "Cannot apply '{}': it needs {} value(s) but the stack holds {}",
+                    word, required, available
+                ),
+                InputMismatchKind::Type {
+                    position,
+                    expected,
+                    actual,
+                } => write!(
+                    f,
+                    "Cannot apply '{}': expected {} at position {} from the top, but got {}",
+                    word, expected, position, actual
+                ),
+                InputMismatchKind::Row { expected, actual } => write!(
+                    f,
+                    "Cannot apply '{}': the stack ({}) has the right values but doesn't fit ({}) below them",
+                    word, actual, expected
+                ),
+            },
+
             TypeError::StackUnificationError {
                 stack1,
                 stack2,
@@ -183,3 +394,80 @@ impl fmt::Display for TypeError {
 }
 
 impl std::error::Error for TypeError {}
+
+/// A type as it reads in a message: quotations are labelled, since their effect alone
+/// (`[ Int -- Int ]`) is easy to mistake for a stack
+fn describe_type(ty: &Type) -> String {
+    match ty {
+        Type::Quotation(_) | Type::Closure { .. } => format!("quotation {}", ty),
+        _ => ty.to_string(),
+    }
+}
+
+/// Why a word's inputs failed to unify with the stack
+#[derive(Debug, Clone, PartialEq)]
+pub enum InputMismatchKind {
+    /// The stack holds fewer values than the word consumes
+    Depth { required: usize, available: usize },
+
+    /// A value has the wrong type; `position` counts from the top of the stack (0 = top)
+    Type {
+        position: usize,
+        expected: Type,
+        actual: Type,
+    },
+
+    /// The values agree but the rest of the stack (its row variable or bottom) does not
+    Row {
+        expected: StackType,
+        actual: StackType,
+    },
+}
+
+/// Non-fatal diagnostics: the program type checks but likely misbehaves
+#[derive(Debug, Clone, PartialEq)]
+pub enum TypeWarning {
+    /// A word calls itself with no `if`/`match` guarding the call (no base case)
+    UnguardedRecursion { word: String },
+
+    /// A word's declared effect is more general than its body allows, e.g. `( A -- A )`
+    /// for a body that only works on `Int`; `required` is the declaration specialized
+    /// to what the body actually needs
+    OverGeneralEffect {
+        word: String,
+        declared: Box<Effect>,
+        required: Box<Effect>,
+    },
+
+    /// A `while` whose condition is literally `[ true ]` and whose body never calls
+    /// `exit`, so the loop can't end
+    InfiniteLoop { word: String, loc: SourceLoc },
+}
+
+impl fmt::Display for TypeWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TypeWarning::UnguardedRecursion { word } => {
+                write!(
+                    f,
+                    "Word '{}' calls itself unconditionally; this recursion never terminates",
+                    word
+                )
+            }
+            TypeWarning::OverGeneralEffect {
+                word,
+                declared,
+                required,
+            } => write!(
+                f,
+                "Word '{}' declares {} but its body requires {}",
+                word, declared, required
+            ),
+            TypeWarning::InfiniteLoop { word, loc } => write!(
+                f,
+                "while loop in '{}' at {} has condition [ true ] and a body that never exits; it never terminates",
+                word, loc
+            ),
+        }
+    }
+}
@@ -13,4 +13,4 @@ pub mod errors;
 pub mod unification;
 
 pub use checker::TypeChecker;
-pub use errors::{TypeError, TypeResult};
+pub use errors::{InputMismatchKind, TypeError, TypeResult, TypeWarning};
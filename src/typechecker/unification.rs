@@ -16,22 +16,36 @@ pub type StackSubstitution = HashMap<String, StackType>;
 /// Unify two types, returning a substitution or error
 pub fn unify_types(ty1: &Type, ty2: &Type) -> TypeResult<Substitution> {
     let mut subst = HashMap::new();
-    unify_types_with_subst(ty1, ty2, &mut subst)?;
+    unify_types_with_subst(ty1, ty2, &mut subst, &mut HashMap::new())?;
     Ok(subst)
 }
 
-fn unify_types_with_subst(ty1: &Type, ty2: &Type, subst: &mut Substitution) -> TypeResult<()> {
+fn unify_types_with_subst(
+    ty1: &Type,
+    ty2: &Type,
+    subst: &mut Substitution,
+    stack_subst: &mut StackSubstitution,
+) -> TypeResult<()> {
     match (ty1, ty2) {
         // Same primitive types unify
         (Type::Int, Type::Int) => Ok(()),
         (Type::Bool, Type::Bool) => Ok(()),
         (Type::String, Type::String) => Ok(()),
 
+        // A variable unifies with itself without binding
+        (Type::Var(a), Type::Var(b)) if a == b => Ok(()),
+
         // Type variables
         (Type::Var(name), ty) | (ty, Type::Var(name)) => {
             if let Some(existing) = subst.get(name).cloned() {
                 // Variable already bound, check consistency
-                unify_types_with_subst(&existing, ty, subst)
+                unify_types_with_subst(&existing, ty, subst, stack_subst)
+            } else if occurs_in_type(Var::Type(name), ty, subst, stack_subst) {
+                Err(Box::new(TypeError::UnificationError {
+                    ty1: ty1.clone(),
+                    ty2: ty2.clone(),
+                    reason: format!("{} would have to contain itself", name),
+                }))
             } else {
                 // Bind variable
                 subst.insert(name.clone(), ty.clone());
@@ -59,17 +73,21 @@ fn unify_types_with_subst(ty1: &Type, ty2: &Type, subst: &mut Substitution) -> T
 
             // Unify all type arguments
             for (arg1, arg2) in a1.iter().zip(a2.iter()) {
-                unify_types_with_subst(arg1, arg2, subst)?;
+                unify_types_with_subst(arg1, arg2, subst, stack_subst)?;
             }
 
             Ok(())
         }
 
-        // Quotations: unify their effects (would need effect unification)
-        (Type::Quotation(_eff1), Type::Quotation(_eff2)) => {
-            // TODO: Implement effect unification
-            // For now, just succeed
-            Ok(())
+        // Quotations: unify their effects, sharing row variables with the enclosing stacks
+        // A closure is a quotation that owns its captured value, so it goes wherever a
+        // quotation of the same effect does
+        (
+            Type::Quotation(eff1) | Type::Closure { effect: eff1, .. },
+            Type::Quotation(eff2) | Type::Closure { effect: eff2, .. },
+        ) => {
+            unify_stack_types_with_subst(&eff1.inputs, &eff2.inputs, subst, stack_subst)?;
+            unify_stack_types_with_subst(&eff1.outputs, &eff2.outputs, subst, stack_subst)
         }
 
         // Mismatched types
@@ -107,7 +125,7 @@ fn unify_stack_types_with_subst(
         // Cons cells: unify tops and rests
         (StackType::Cons { rest: r1, top: t1 }, StackType::Cons { rest: r2, top: t2 }) => {
             // Unify the top types
-            unify_types_with_subst(t1, t2, type_subst)?;
+            unify_types_with_subst(t1, t2, type_subst, stack_subst)?;
 
             // Unify the rest stacks
             unify_stack_types_with_subst(r1, r2, type_subst, stack_subst)?;
@@ -115,11 +133,20 @@ fn unify_stack_types_with_subst(
             Ok(())
         }
 
+        // A row variable unifies with itself without binding
+        (StackType::RowVar(a), StackType::RowVar(b)) if a == b => Ok(()),
+
         // Row variable can unify with anything
         (StackType::RowVar(name), stack) | (stack, StackType::RowVar(name)) => {
             if let Some(existing) = stack_subst.get(name).cloned() {
                 // Variable already bound, check consistency
                 unify_stack_types_with_subst(&existing, stack, type_subst, stack_subst)
+            } else if occurs_in_stack(Var::Row(name), stack, type_subst, stack_subst) {
+                Err(Box::new(TypeError::StackUnificationError {
+                    stack1: stack1.clone(),
+                    stack2: stack2.clone(),
+                    reason: format!("..{} would have to contain itself", name),
+                }))
             } else {
                 // Bind variable
                 stack_subst.insert(name.clone(), stack.clone());
@@ -136,9 +163,73 @@ fn unify_stack_types_with_subst(
     }
 }
 
+/// A type variable or a stack row variable
+#[derive(Clone, Copy)]
+enum Var<'a> {
+    Type(&'a str),
+    Row(&'a str),
+}
+
+/// Whether `var` appears in `ty`, following the bindings made so far
+///
+/// Binding a variable to something that contains it would make an infinite type (a
+/// quotation whose input stack holds that same quotation, say), so unification
+/// refuses it rather than building a substitution that never bottoms out.
+fn occurs_in_type(
+    var: Var,
+    ty: &Type,
+    subst: &Substitution,
+    stack_subst: &StackSubstitution,
+) -> bool {
+    match ty {
+        Type::Var(name) => {
+            matches!(var, Var::Type(v) if v == name)
+                || subst.get(name).is_some_and(|bound| {
+                    bound != ty && occurs_in_type(var, bound, subst, stack_subst)
+                })
+        }
+        Type::Named { args, .. } => args
+            .iter()
+            .any(|arg| occurs_in_type(var, arg, subst, stack_subst)),
+        Type::Quotation(effect) => {
+            occurs_in_stack(var, &effect.inputs, subst, stack_subst)
+                || occurs_in_stack(var, &effect.outputs, subst, stack_subst)
+        }
+        Type::Closure { effect, captured } => {
+            occurs_in_stack(var, &effect.inputs, subst, stack_subst)
+                || occurs_in_stack(var, &effect.outputs, subst, stack_subst)
+                || occurs_in_type(var, captured, subst, stack_subst)
+        }
+        Type::Int | Type::Bool | Type::String => false,
+    }
+}
+
+/// Whether `var` appears in `stack`, following the bindings made so far
+fn occurs_in_stack(
+    var: Var,
+    stack: &StackType,
+    subst: &Substitution,
+    stack_subst: &StackSubstitution,
+) -> bool {
+    match stack {
+        StackType::Empty => false,
+        StackType::Cons { rest, top } => {
+            occurs_in_type(var, top, subst, stack_subst)
+                || occurs_in_stack(var, rest, subst, stack_subst)
+        }
+        StackType::RowVar(name) => {
+            matches!(var, Var::Row(v) if v == name)
+                || stack_subst.get(name).is_some_and(|bound| {
+                    bound != stack && occurs_in_stack(var, bound, subst, stack_subst)
+                })
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::ast::types::Effect;
 
     #[test]
     fn test_unify_primitives() {
@@ -188,4 +279,46 @@ mod tests {
         let stack3 = StackType::empty().push(Type::Bool);
         assert!(unify_stack_types(&stack1, &stack3).is_err());
     }
+
+    #[test]
+    fn test_occurs_check_rejects_infinite_types() {
+        let a = Type::Var("A".to_string());
+        let list_a = Type::Named {
+            name: "List".to_string(),
+            args: vec![a.clone()],
+        };
+
+        let err = unify_types(&a, &list_a).unwrap_err();
+        assert!(err.to_string().contains("A would have to contain itself"));
+        assert!(unify_types(&list_a, &a).is_err());
+
+        // A variable still unifies with itself
+        assert!(unify_types(&a, &a).is_ok());
+    }
+
+    #[test]
+    fn test_occurs_check_rejects_infinite_stacks() {
+        let row = StackType::RowVar("R".to_string());
+
+        // ..R against a stack built on ..R
+        let grown = row.clone().push(Type::Int);
+        let err = unify_stack_types(&row, &grown).unwrap_err();
+        assert!(err.to_string().contains("..R would have to contain itself"));
+
+        // ..R against a stack holding a quotation that takes ..R
+        let quot = Type::Quotation(Box::new(Effect::new(row.clone(), row.clone())));
+        let holding = StackType::empty().push(quot);
+        assert!(unify_stack_types(&holding, &row).is_err());
+
+        // A binding made earlier is followed: A := List(B), then B against A
+        let a = Type::Var("A".to_string());
+        let b = Type::Var("B".to_string());
+        let list_b = Type::Named {
+            name: "List".to_string(),
+            args: vec![b.clone()],
+        };
+        let stack1 = StackType::empty().push(a.clone()).push(b);
+        let stack2 = StackType::empty().push(list_b).push(a);
+        assert!(unify_stack_types(&stack1, &stack2).is_err());
+    }
 }
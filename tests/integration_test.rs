@@ -3,7 +3,10 @@ use cemc::ast::types::{Effect, StackType, Type};
 End-to-end integration test: Cem source → LLVM IR → executable
 */
 use cemc::ast::{Expr, MatchBranch, Pattern, Program, SourceLoc, TypeDef, Variant, WordDef};
-use cemc::codegen::{CodeGen, compile_to_object, link_program};
+use cemc::codegen::{
+    CodeGen, CodeGenOptions, StackSeed, Target, compile_to_object, compile_to_object_for_target,
+    link_program, variant_tag_for,
+};
 use std::process::Command;
 use std::sync::Once;
 
@@ -34,6 +37,10 @@ fn test_end_to_end_compilation() {
         },
         body: vec![Expr::IntLit(42, SourceLoc::unknown())],
         loc: SourceLoc::unknown(),
+        feature: None,
+        inline: None,
+        slot_names: Default::default(),
+        doc: None,
     };
 
     let program = Program {
@@ -78,6 +85,10 @@ fn test_arithmetic_compilation() {
             Expr::WordCall("add".to_string(), SourceLoc::unknown()),
         ],
         loc: SourceLoc::unknown(),
+        feature: None,
+        inline: None,
+        slot_names: Default::default(),
+        doc: None,
     };
 
     let program = Program {
@@ -115,6 +126,10 @@ fn test_executable_with_main() {
         },
         body: vec![Expr::IntLit(42, SourceLoc::unknown())],
         loc: SourceLoc::unknown(),
+        feature: None,
+        inline: None,
+        slot_names: Default::default(),
+        doc: None,
     };
 
     let program = Program {
@@ -166,6 +181,10 @@ fn test_multiply_executable() {
             Expr::WordCall("multiply".to_string(), SourceLoc::unknown()),
         ],
         loc: SourceLoc::unknown(),
+        feature: None,
+        inline: None,
+        slot_names: Default::default(),
+        doc: None,
     };
 
     let program = Program {
@@ -208,6 +227,7 @@ fn test_if_expression() {
         body: vec![
             Expr::BoolLit(true, SourceLoc::unknown()),
             Expr::If {
+                condition: None,
                 then_branch: Box::new(Expr::Quotation(
                     vec![Expr::IntLit(42, SourceLoc::unknown())],
                     SourceLoc::unknown(),
@@ -220,6 +240,10 @@ fn test_if_expression() {
             },
         ],
         loc: SourceLoc::unknown(),
+        feature: None,
+        inline: None,
+        slot_names: Default::default(),
+        doc: None,
     };
 
     let program = Program {
@@ -269,6 +293,10 @@ fn test_tail_call_optimization() {
         },
         body: vec![], // Identity - does nothing, returns stack as-is
         loc: SourceLoc::unknown(),
+        feature: None,
+        inline: None,
+        slot_names: Default::default(),
+        doc: None,
     };
 
     // : call_identity ( -- Int ) 42 identity ;
@@ -283,6 +311,10 @@ fn test_tail_call_optimization() {
             Expr::WordCall("identity".to_string(), SourceLoc::unknown()),
         ],
         loc: SourceLoc::unknown(),
+        feature: None,
+        inline: None,
+        slot_names: Default::default(),
+        doc: None,
     };
 
     let program = Program {
@@ -331,6 +363,7 @@ fn test_if_false_branch() {
         body: vec![
             Expr::BoolLit(false, SourceLoc::unknown()), // Push false
             Expr::If {
+                condition: None,
                 then_branch: Box::new(Expr::Quotation(
                     vec![Expr::IntLit(42, SourceLoc::unknown())],
                     SourceLoc::unknown(),
@@ -343,6 +376,10 @@ fn test_if_false_branch() {
             },
         ],
         loc: SourceLoc::unknown(),
+        feature: None,
+        inline: None,
+        slot_names: Default::default(),
+        doc: None,
     };
 
     let program = Program {
@@ -385,6 +422,10 @@ fn test_tail_call_in_if_branch() {
         },
         body: vec![], // Identity - returns stack as-is
         loc: SourceLoc::unknown(),
+        feature: None,
+        inline: None,
+        slot_names: Default::default(),
+        doc: None,
     };
 
     // Create a word that calls another word in tail position within an if branch
@@ -398,6 +439,7 @@ fn test_tail_call_in_if_branch() {
             outputs: StackType::Empty.push(Type::Int),
         },
         body: vec![Expr::If {
+            condition: None,
             // Both branches call passthrough in tail position
             // Then branch: push 42 then call passthrough
             then_branch: Box::new(Expr::Quotation(
@@ -418,6 +460,10 @@ fn test_tail_call_in_if_branch() {
             loc: SourceLoc::unknown(),
         }],
         loc: SourceLoc::unknown(),
+        feature: None,
+        inline: None,
+        slot_names: Default::default(),
+        doc: None,
     };
 
     // Entry word that sets up the test: push true, call conditional_call
@@ -433,6 +479,10 @@ fn test_tail_call_in_if_branch() {
             Expr::WordCall("conditional_call".to_string(), SourceLoc::unknown()),
         ],
         loc: SourceLoc::unknown(),
+        feature: None,
+        inline: None,
+        slot_names: Default::default(),
+        doc: None,
     };
 
     let program = Program {
@@ -485,11 +535,13 @@ fn test_nested_if_expressions() {
             outputs: StackType::Empty.push(Type::Int),
         },
         body: vec![Expr::If {
+            condition: None,
             // Outer if: first bool
             then_branch: Box::new(Expr::Quotation(
                 vec![
                     // Inner if in then branch
                     Expr::If {
+                        condition: None,
                         then_branch: Box::new(Expr::Quotation(
                             vec![Expr::IntLit(1, SourceLoc::unknown())],
                             SourceLoc::unknown(),
@@ -507,6 +559,7 @@ fn test_nested_if_expressions() {
                 vec![
                     // Inner if in else branch
                     Expr::If {
+                        condition: None,
                         then_branch: Box::new(Expr::Quotation(
                             vec![Expr::IntLit(3, SourceLoc::unknown())],
                             SourceLoc::unknown(),
@@ -523,6 +576,10 @@ fn test_nested_if_expressions() {
             loc: SourceLoc::unknown(),
         }],
         loc: SourceLoc::unknown(),
+        feature: None,
+        inline: None,
+        slot_names: Default::default(),
+        doc: None,
     };
 
     // Test case: true, true => should give 1
@@ -538,6 +595,10 @@ fn test_nested_if_expressions() {
             Expr::WordCall("nested_if".to_string(), SourceLoc::unknown()),
         ],
         loc: SourceLoc::unknown(),
+        feature: None,
+        inline: None,
+        slot_names: Default::default(),
+        doc: None,
     };
 
     let program = Program {
@@ -597,6 +658,10 @@ fn test_scheduler_linkage() {
             Expr::WordCall("add".to_string(), SourceLoc::unknown()),
         ],
         loc: SourceLoc::unknown(),
+        feature: None,
+        inline: None,
+        slot_names: Default::default(),
+        doc: None,
     };
 
     let program = Program {
@@ -644,6 +709,10 @@ fn test_debug_metadata_emission() {
             SourceLoc::new(1, 25, "test.cem".to_string()),
         )],
         loc: SourceLoc::new(1, 1, "test.cem".to_string()),
+        feature: None,
+        inline: None,
+        slot_names: Default::default(),
+        doc: None,
     };
 
     let program = Program {
@@ -703,6 +772,10 @@ fn test_debug_metadata_filename_escaping() {
             SourceLoc::new(1, 1, "test\"file.cem".to_string()),
         )],
         loc: SourceLoc::new(1, 1, "test\"file.cem".to_string()),
+        feature: None,
+        inline: None,
+        slot_names: Default::default(),
+        doc: None,
     };
 
     let program = Program {
@@ -741,6 +814,7 @@ fn test_pattern_match_codegen() {
                 fields: vec![],
             },
         ],
+        doc: None,
     };
 
     // Create a word that pattern matches on Option:
@@ -763,12 +837,14 @@ fn test_pattern_match_codegen() {
                 MatchBranch {
                     pattern: Pattern::Variant {
                         name: "Some".to_string(),
+                        bindings: Vec::new(),
                     },
                     body: vec![], // Just unwraps the Int from Some
                 },
                 MatchBranch {
                     pattern: Pattern::Variant {
                         name: "None".to_string(),
+                        bindings: Vec::new(),
                     },
                     body: vec![Expr::IntLit(0, SourceLoc::unknown())], // Push 0
                 },
@@ -776,6 +852,10 @@ fn test_pattern_match_codegen() {
             loc: SourceLoc::unknown(),
         }],
         loc: SourceLoc::unknown(),
+        feature: None,
+        inline: None,
+        slot_names: Default::default(),
+        doc: None,
     };
 
     let program = Program {
@@ -825,10 +905,10 @@ fn test_pattern_match_codegen() {
         "IR should have merge point or returns"
     );
 
-    // 6. Should extract variant tag from stack cell
+    // 6. Should extract variant tag through the runtime
     assert!(
-        ir.contains("getelementptr inbounds"),
-        "IR should extract variant tag using GEP"
+        ir.contains("call i32 @variant_tag"),
+        "IR should extract variant tag with variant_tag"
     );
 
     // 7. Verify IR compiles to object code
@@ -861,6 +941,7 @@ fn test_variant_construction_with_field() {
                 fields: vec![],
             },
         ],
+        doc: None,
     };
 
     // Create a word that constructs Some(42) and extracts the value:
@@ -884,12 +965,14 @@ fn test_variant_construction_with_field() {
                     MatchBranch {
                         pattern: Pattern::Variant {
                             name: "Some".to_string(),
+                            bindings: Vec::new(),
                         },
                         body: vec![], // Unwraps to Int (42)
                     },
                     MatchBranch {
                         pattern: Pattern::Variant {
                             name: "None".to_string(),
+                            bindings: Vec::new(),
                         },
                         body: vec![Expr::IntLit(0, SourceLoc::unknown())], // Should never execute
                     },
@@ -898,6 +981,10 @@ fn test_variant_construction_with_field() {
             },
         ],
         loc: SourceLoc::unknown(),
+        feature: None,
+        inline: None,
+        slot_names: Default::default(),
+        doc: None,
     };
 
     let program = Program {
@@ -917,25 +1004,21 @@ fn test_variant_construction_with_field() {
 
     // Verify IR contains variant construction:
 
-    // 1. Should allocate cell for variant field data
+    // 1. Should construct the variant in the runtime, taking one field from the stack
+    let some_args = format!("i32 {}, i32 1)", variant_tag_for("Option", "Some"));
     assert!(
-        ir.contains("call ptr @alloc_cell()"),
-        "IR should allocate cell for variant field"
+        ir.lines()
+            .any(|l| l.contains("call ptr @make_variant(") && l.contains(&some_args)),
+        "IR should call make_variant with Some's tag and field count"
     );
 
-    // 2. Should use memcpy to copy field value
+    // 2. Field data is owned by the runtime, not copied inline
     assert!(
-        ir.contains("@llvm.memcpy"),
-        "IR should use memcpy to copy field value"
+        !ir.contains("call void @llvm.memcpy"),
+        "IR should not copy field cells inline"
     );
 
-    // 3. Should call push_variant
-    assert!(
-        ir.contains("call ptr @push_variant"),
-        "IR should call push_variant"
-    );
-
-    // 4. Compile and link to verify it works
+    // 3. Compile and link to verify it works
     link_program(
         &ir,
         "runtime/libcem_runtime.a",
@@ -943,7 +1026,7 @@ fn test_variant_construction_with_field() {
     )
     .expect("Failed to link");
 
-    // 5. Run the program - it should execute without errors
+    // 4. Run the program - it should execute without errors
     let output = Command::new("./test_variant_construction_exe")
         .output()
         .expect("Failed to run executable");
@@ -959,3 +1042,1597 @@ fn test_variant_construction_with_field() {
 
     println!("✅ Variant construction with field test passed!");
 }
+
+#[test]
+fn test_inspect_prints_and_continues() {
+    // Build runtime
+    ensure_runtime_built();
+
+    // : main ( -- Int ) 41 inspect 1 + ;
+    let word = WordDef {
+        name: "main".to_string(),
+        effect: Effect {
+            inputs: StackType::Empty,
+            outputs: StackType::Empty.push(Type::Int),
+        },
+        body: vec![
+            Expr::IntLit(41, SourceLoc::unknown()),
+            Expr::WordCall("inspect".to_string(), SourceLoc::unknown()),
+            Expr::IntLit(1, SourceLoc::unknown()),
+            Expr::WordCall("+".to_string(), SourceLoc::unknown()),
+        ],
+        loc: SourceLoc::unknown(),
+        feature: None,
+        inline: None,
+        slot_names: Default::default(),
+        doc: None,
+    };
+
+    let program = Program {
+        type_defs: vec![],
+        word_defs: vec![word],
+    };
+
+    let mut codegen = CodeGen::new();
+    let ir = codegen
+        .compile_program_with_main(&program, Some("main"))
+        .expect("Failed to generate IR");
+
+    link_program(&ir, "runtime/libcem_runtime.a", "test_inspect_exe").expect("Failed to link");
+
+    let output = Command::new("./test_inspect_exe")
+        .output()
+        .expect("Failed to run executable");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    // inspect printed 41 and the program went on to compute 41 + 1
+    assert!(output.status.success());
+    assert!(
+        stdout.lines().next() == Some("41"),
+        "inspect should print the top value first:\n{}",
+        stdout
+    );
+
+    // Clean up
+    std::fs::remove_file("test_inspect_exe").ok();
+    std::fs::remove_file("test_inspect_exe.ll").ok();
+}
+
+#[test]
+fn test_composed_quotation_runs_both_parts() {
+    // Build runtime
+    ensure_runtime_built();
+
+    // : main ( -- Int ) 5 [ 1 + ] [ 2 * ] compose call_quotation ;
+    let quot = |n: i64, op: &str| {
+        Expr::Quotation(
+            vec![
+                Expr::IntLit(n, SourceLoc::unknown()),
+                Expr::WordCall(op.to_string(), SourceLoc::unknown()),
+            ],
+            SourceLoc::unknown(),
+        )
+    };
+    let word = WordDef {
+        name: "main".to_string(),
+        effect: Effect {
+            inputs: StackType::Empty,
+            outputs: StackType::Empty.push(Type::Int),
+        },
+        body: vec![
+            Expr::IntLit(5, SourceLoc::unknown()),
+            quot(1, "+"),
+            quot(2, "*"),
+            Expr::WordCall("compose".to_string(), SourceLoc::unknown()),
+            Expr::WordCall("call_quotation".to_string(), SourceLoc::unknown()),
+        ],
+        loc: SourceLoc::unknown(),
+        feature: None,
+        inline: None,
+        slot_names: Default::default(),
+        doc: None,
+    };
+
+    let program = Program {
+        type_defs: vec![],
+        word_defs: vec![word],
+    };
+
+    let mut codegen = CodeGen::new();
+    let ir = codegen
+        .compile_program_with_main(&program, Some("main"))
+        .expect("Failed to generate IR");
+
+    link_program(&ir, "runtime/libcem_runtime.a", "test_compose_exe").expect("Failed to link");
+
+    let output = Command::new("./test_compose_exe")
+        .output()
+        .expect("Failed to run executable");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    // (5 + 1) * 2
+    assert!(output.status.success());
+    assert!(
+        stdout.contains("Stack (top to bottom): 12 "),
+        "Composed quotation should add then double:\n{}",
+        stdout
+    );
+
+    // Clean up
+    std::fs::remove_file("test_compose_exe").ok();
+    std::fs::remove_file("test_compose_exe.ll").ok();
+}
+
+#[test]
+fn test_not_negates_true() {
+    // Build runtime
+    ensure_runtime_built();
+
+    // : main ( -- Bool ) true not ;
+    let word = WordDef {
+        name: "main".to_string(),
+        effect: Effect {
+            inputs: StackType::Empty,
+            outputs: StackType::Empty.push(Type::Bool),
+        },
+        body: vec![
+            Expr::BoolLit(true, SourceLoc::unknown()),
+            Expr::WordCall("not".to_string(), SourceLoc::unknown()),
+        ],
+        loc: SourceLoc::unknown(),
+        feature: None,
+        inline: None,
+        slot_names: Default::default(),
+        doc: None,
+    };
+
+    let program = Program {
+        type_defs: vec![],
+        word_defs: vec![word],
+    };
+
+    let mut codegen = CodeGen::new();
+    let ir = codegen
+        .compile_program_with_main(&program, Some("main"))
+        .expect("Failed to generate IR");
+    assert!(ir.contains("declare ptr @not(ptr)"));
+
+    link_program(&ir, "runtime/libcem_runtime.a", "test_not_exe").expect("Failed to link");
+
+    let output = Command::new("./test_not_exe")
+        .output()
+        .expect("Failed to run executable");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(output.status.success());
+    assert!(
+        stdout.contains("Stack (top to bottom): false "),
+        "true not should leave false:\n{}",
+        stdout
+    );
+
+    // Clean up
+    std::fs::remove_file("test_not_exe").ok();
+    std::fs::remove_file("test_not_exe.ll").ok();
+}
+
+#[test]
+fn test_two_dup_copies_top_pair() {
+    // Build runtime
+    ensure_runtime_built();
+
+    // : main ( -- Int Int Int Int ) 1 2 2dup ;
+    let word = WordDef {
+        name: "main".to_string(),
+        effect: Effect {
+            inputs: StackType::Empty,
+            outputs: StackType::from_vec(vec![Type::Int, Type::Int, Type::Int, Type::Int]),
+        },
+        body: vec![
+            Expr::IntLit(1, SourceLoc::unknown()),
+            Expr::IntLit(2, SourceLoc::unknown()),
+            Expr::WordCall("2dup".to_string(), SourceLoc::unknown()),
+        ],
+        loc: SourceLoc::unknown(),
+        feature: None,
+        inline: None,
+        slot_names: Default::default(),
+        doc: None,
+    };
+
+    let program = Program {
+        type_defs: vec![],
+        word_defs: vec![word],
+    };
+
+    let mut codegen = CodeGen::new();
+    let ir = codegen
+        .compile_program_with_main(&program, Some("main"))
+        .expect("Failed to generate IR");
+
+    link_program(&ir, "runtime/libcem_runtime.a", "test_2dup_exe").expect("Failed to link");
+
+    let output = Command::new("./test_2dup_exe")
+        .output()
+        .expect("Failed to run executable");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(output.status.success());
+    assert!(
+        stdout.contains("Stack (top to bottom): 2 1 2 1 "),
+        "1 2 2dup should leave four values:\n{}",
+        stdout
+    );
+
+    // Clean up
+    std::fs::remove_file("test_2dup_exe").ok();
+    std::fs::remove_file("test_2dup_exe.ll").ok();
+}
+
+/// Check whether clang can produce wasm32-wasi objects on this machine
+fn wasm_target_available() -> bool {
+    Command::new("clang")
+        .args([
+            "--target=wasm32-wasi",
+            "-x",
+            "c",
+            "-c",
+            "-o",
+            "/dev/null",
+            "/dev/null",
+        ])
+        .output()
+        .map(|o| o.status.success())
+        .unwrap_or(false)
+}
+
+#[test]
+fn test_wasm_object_generation() {
+    if !wasm_target_available() {
+        eprintln!("Skipping: clang has no wasm32-wasi target available");
+        return;
+    }
+
+    // : main ( -- Int ) 42 ;
+    let word = WordDef {
+        name: "main".to_string(),
+        effect: Effect {
+            inputs: StackType::Empty,
+            outputs: StackType::Empty.push(Type::Int),
+        },
+        body: vec![Expr::IntLit(42, SourceLoc::unknown())],
+        loc: SourceLoc::unknown(),
+        feature: None,
+        inline: None,
+        slot_names: Default::default(),
+        doc: None,
+    };
+
+    let program = Program {
+        type_defs: vec![],
+        word_defs: vec![word],
+    };
+
+    let mut codegen = CodeGen::with_target(Target::Wasm32Wasi);
+    let ir = codegen
+        .compile_program_with_main(&program, Some("main"))
+        .expect("Failed to generate IR");
+
+    compile_to_object_for_target(&ir, "test_wasm_main", Target::Wasm32Wasi)
+        .expect("Failed to compile IR to wasm object");
+
+    // wasm objects start with the "\0asm" magic number
+    let object = std::fs::read("test_wasm_main.o").expect("wasm object should exist");
+    assert!(
+        object.starts_with(b"\0asm"),
+        "Output should be a wasm object"
+    );
+
+    // Clean up
+    std::fs::remove_file("test_wasm_main.o").ok();
+    std::fs::remove_file("test_wasm_main.ll").ok();
+}
+
+#[test]
+fn test_verbose_compile_logs_phases() {
+    std::fs::create_dir_all("target").ok();
+    std::fs::write(
+        "target/verbose_demo.cem",
+        ": answer ( -- Int ) 42 ;\n: main ( -- Int ) answer ;\n",
+    )
+    .expect("Failed to write source");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_cem"))
+        .args([
+            "compile",
+            "target/verbose_demo.cem",
+            "-o",
+            "target/verbose_demo",
+            "-v",
+        ])
+        .output()
+        .expect("Failed to run cem");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(
+        output.status.success(),
+        "cem compile failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(
+        stdout.contains("Compiling word 'answer'"),
+        "Verbose output should name each word:\n{}",
+        stdout
+    );
+    assert!(
+        stdout.contains("Running: clang"),
+        "Verbose output should show the clang invocation:\n{}",
+        stdout
+    );
+
+    // Clean up
+    std::fs::remove_file("target/verbose_demo").ok();
+    std::fs::remove_file("target/verbose_demo.cem").ok();
+}
+
+#[test]
+fn test_ir_file_removed_unless_keep_ir() {
+    std::fs::create_dir_all("target").ok();
+    std::fs::write("target/keep_ir_demo.cem", ": main ( -- Int ) 0 ;\n")
+        .expect("Failed to write source");
+
+    let compile = |extra: &[&str]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_cem"))
+            .args([
+                "compile",
+                "target/keep_ir_demo.cem",
+                "-o",
+                "target/keep_ir_demo",
+            ])
+            .args(extra)
+            .output()
+            .expect("Failed to run cem");
+        assert!(
+            output.status.success(),
+            "cem compile failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    };
+
+    // Default flow cleans up the intermediate IR after linking
+    compile(&[]);
+    assert!(
+        !std::path::Path::new("target/keep_ir_demo.ll").exists(),
+        "IR file should be removed after a successful compile"
+    );
+
+    // --keep-ir retains it for debugging
+    compile(&["--keep-ir"]);
+    assert!(
+        std::path::Path::new("target/keep_ir_demo.ll").exists(),
+        "IR file should be kept with --keep-ir"
+    );
+
+    // Clean up
+    std::fs::remove_file("target/keep_ir_demo.ll").ok();
+    std::fs::remove_file("target/keep_ir_demo").ok();
+    std::fs::remove_file("target/keep_ir_demo.cem").ok();
+}
+
+#[test]
+fn test_strict_stack_traps_on_a_leftover_value() {
+    std::fs::create_dir_all("target").ok();
+    // A library interface is trusted as written, so one that understates a word's
+    // outputs gets past the typechecker
+    std::fs::write("target/two_values.cem", ": two ( -- Int Int ) 1 2 ;\n")
+        .expect("Failed to write source");
+    let output = Command::new(env!("CARGO_BIN_EXE_cem"))
+        .args([
+            "compile",
+            "target/two_values.cem",
+            "-o",
+            "target/two_values",
+            "--lib",
+        ])
+        .output()
+        .expect("Failed to run cem");
+    assert!(
+        output.status.success(),
+        "cem compile --lib failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    std::fs::write(
+        "target/two_values.cemi",
+        std::fs::read_to_string("target/two_values.cemi")
+            .expect("interface should be written")
+            .replace("( -- Int Int )", "( -- Int )"),
+    )
+    .expect("Failed to rewrite interface");
+    std::fs::write("target/strict_stack_demo.cem", ": main ( -- Int ) two ;\n")
+        .expect("Failed to write source");
+
+    let build_and_run = |extra: &[&str]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_cem"))
+            .args([
+                "compile",
+                "target/strict_stack_demo.cem",
+                "-o",
+                "target/strict_stack_demo",
+                "--lib-interface",
+                "target/two_values.cemi",
+            ])
+            .args(extra)
+            .output()
+            .expect("Failed to run cem");
+        assert!(
+            output.status.success(),
+            "cem compile failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        Command::new("target/strict_stack_demo")
+            .output()
+            .expect("Failed to run strict_stack_demo")
+    };
+
+    assert!(build_and_run(&[]).status.success());
+
+    let run = build_and_run(&["--strict-stack"]);
+    assert!(!run.status.success(), "the leftover value should trap");
+    let stderr = String::from_utf8_lossy(&run.stderr);
+    assert!(
+        stderr
+            .contains("program ended with 2 value(s) on the stack, but its entry word declares 1"),
+        "unexpected error: {}",
+        stderr
+    );
+
+    // Clean up
+    std::fs::remove_file("target/strict_stack_demo").ok();
+    std::fs::remove_file("target/strict_stack_demo.cem").ok();
+    for file in ["cem", "cemi", "a"] {
+        std::fs::remove_file(format!("target/two_values.{}", file)).ok();
+    }
+}
+
+#[test]
+fn test_save_temps_keeps_the_object_file() {
+    std::fs::create_dir_all("target").ok();
+    std::fs::write("target/save_temps_demo.cem", ": main ( -- Int ) 0 ;\n")
+        .expect("Failed to write source");
+
+    let compile = |extra: &[&str]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_cem"))
+            .args([
+                "compile",
+                "target/save_temps_demo.cem",
+                "-o",
+                "target/save_temps_demo",
+            ])
+            .args(extra)
+            .output()
+            .expect("Failed to run cem");
+        assert!(
+            output.status.success(),
+            "cem compile failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        String::from_utf8_lossy(&output.stdout).to_string()
+    };
+
+    // The object is removed once it has been linked
+    compile(&[]);
+    assert!(!std::path::Path::new("target/save_temps_demo.o").exists());
+
+    // The kept object is assembled from the kept assembly, and is what gets linked
+    let stdout = compile(&["--save-temps", "--verbose"]);
+    assert!(
+        stdout.contains("-c target/save_temps_demo.s -o target/save_temps_demo.o"),
+        "{}",
+        stdout
+    );
+    assert!(
+        stdout.contains("target/save_temps_demo.o runtime/libcem_runtime.a"),
+        "{}",
+        stdout
+    );
+    for artifact in ["ll", "o", "s"] {
+        let path = format!("target/save_temps_demo.{}", artifact);
+        assert!(
+            std::path::Path::new(&path).exists(),
+            "{} should be kept with --save-temps",
+            path
+        );
+        std::fs::remove_file(&path).ok();
+    }
+
+    // Clean up
+    std::fs::remove_file("target/save_temps_demo").ok();
+    std::fs::remove_file("target/save_temps_demo.cem").ok();
+}
+
+/// Check whether a native clang is installed on this machine
+fn native_clang_available() -> bool {
+    Command::new("clang")
+        .arg("--version")
+        .output()
+        .map(|o| o.status.success())
+        .unwrap_or(false)
+}
+
+#[test]
+fn test_emit_asm_writes_assembly() {
+    if !native_clang_available() {
+        eprintln!("Skipping: clang is not installed");
+        return;
+    }
+
+    std::fs::create_dir_all("target").ok();
+    std::fs::write("target/asm_demo.cem", ": main ( -- Int ) 42 ;\n")
+        .expect("Failed to write source");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_cem"))
+        .args([
+            "compile",
+            "target/asm_demo.cem",
+            "-o",
+            "target/asm_demo",
+            "--emit-asm",
+        ])
+        .output()
+        .expect("Failed to run cem");
+    assert!(
+        output.status.success(),
+        "cem compile --emit-asm failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let asm = std::fs::read_to_string("target/asm_demo.s").expect("Assembly file should exist");
+    assert!(!asm.trim().is_empty(), "Assembly file should not be empty");
+    assert!(
+        !std::path::Path::new("target/asm_demo").exists(),
+        "--emit-asm should not link an executable"
+    );
+
+    // Clean up
+    std::fs::remove_file("target/asm_demo.s").ok();
+    std::fs::remove_file("target/asm_demo.cem").ok();
+}
+
+#[test]
+fn test_lib_build_writes_interface() {
+    if !native_clang_available() {
+        eprintln!("Skipping: clang is not installed");
+        return;
+    }
+
+    std::fs::create_dir_all("target").ok();
+    std::fs::write(
+        "target/mathlib.cem",
+        ": square ( Int -- Int ) dup * ;\n: add-one ( Int -- Int ) 1 + ;\n",
+    )
+    .expect("Failed to write source");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_cem"))
+        .args([
+            "compile",
+            "target/mathlib.cem",
+            "-o",
+            "target/mathlib",
+            "--lib",
+        ])
+        .output()
+        .expect("Failed to run cem");
+    assert!(
+        output.status.success(),
+        "cem compile --lib failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    assert!(std::path::Path::new("target/mathlib.a").exists());
+    let interface =
+        std::fs::read_to_string("target/mathlib.cemi").expect("Interface file should exist");
+    assert!(interface.starts_with("cem-interface 1\n"));
+    assert!(interface.contains("word square square ( Int -- Int )"));
+    assert!(interface.contains("word add-one add_one ( Int -- Int )"));
+
+    // Clean up
+    std::fs::remove_file("target/mathlib.a").ok();
+    std::fs::remove_file("target/mathlib.cemi").ok();
+    std::fs::remove_file("target/mathlib.cem").ok();
+}
+
+#[test]
+fn test_program_and_library_each_with_quotations_link() {
+    if !native_clang_available() {
+        eprintln!("Skipping: clang is not installed");
+        return;
+    }
+    ensure_runtime_built();
+
+    // Both units number their quotations from quot_0
+    std::fs::create_dir_all("target").ok();
+    std::fs::write(
+        "target/quotlib.cem",
+        ": add-two ( Int -- Int ) [ 2 + ] call_quotation ;\n",
+    )
+    .expect("Failed to write source");
+    std::fs::write(
+        "target/quotprog.cem",
+        ": main ( -- Int ) 40 [ add-two ] call_quotation ;\n",
+    )
+    .expect("Failed to write source");
+
+    let compile = |args: &[&str]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_cem"))
+            .arg("compile")
+            .args(args)
+            .output()
+            .expect("Failed to run cem");
+        assert!(
+            output.status.success(),
+            "cem compile failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    };
+    compile(&["target/quotlib.cem", "-o", "target/quotlib", "--lib"]);
+    compile(&[
+        "target/quotprog.cem",
+        "-o",
+        "target/quotprog",
+        "--lib-interface",
+        "target/quotlib.cemi",
+    ]);
+
+    let run = Command::new("target/quotprog")
+        .output()
+        .expect("Failed to run quotprog");
+    assert!(run.status.success());
+    assert!(
+        String::from_utf8_lossy(&run.stdout).contains("42"),
+        "expected 40 + 2, got: {}",
+        String::from_utf8_lossy(&run.stdout)
+    );
+
+    // Clean up
+    std::fs::remove_file("target/quotprog").ok();
+    std::fs::remove_file("target/quotprog.cem").ok();
+    for file in ["cem", "cemi", "a"] {
+        std::fs::remove_file(format!("target/quotlib.{}", file)).ok();
+    }
+}
+
+#[test]
+fn test_shared_build_exports_each_word() {
+    if !native_clang_available() || Command::new("nm").arg("--version").output().is_err() {
+        eprintln!("Skipping: clang or nm is not installed");
+        return;
+    }
+    ensure_runtime_built();
+
+    std::fs::create_dir_all("target").ok();
+    std::fs::write("target/sharedmath.cem", ": square ( Int -- Int ) dup * ;\n")
+        .expect("Failed to write source");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_cem"))
+        .args([
+            "compile",
+            "target/sharedmath.cem",
+            "-o",
+            "target/sharedmath",
+            "--shared",
+        ])
+        .output()
+        .expect("Failed to run cem");
+    assert!(
+        output.status.success(),
+        "cem compile --shared failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let library = format!("target/sharedmath.{}", std::env::consts::DLL_EXTENSION);
+    let symbols = Command::new("nm")
+        .args(["-g", &library])
+        .output()
+        .expect("Failed to run nm");
+    let symbols = String::from_utf8_lossy(&symbols.stdout);
+    assert!(
+        symbols
+            .lines()
+            .any(|line| line.ends_with(" T square") || line.ends_with(" T _square")),
+        "square should be exported:\n{}",
+        symbols
+    );
+    assert!(std::path::Path::new("target/sharedmath.cemi").exists());
+
+    // Clean up
+    std::fs::remove_file(&library).ok();
+    std::fs::remove_file("target/sharedmath.cemi").ok();
+    std::fs::remove_file("target/sharedmath.cem").ok();
+}
+
+#[test]
+fn test_compile_multiple_files() {
+    std::fs::create_dir_all("target").ok();
+    std::fs::write("target/multi_main.cem", ": main ( -- Int ) 20 double ;\n")
+        .expect("Failed to write source");
+    std::fs::write("target/multi_helper.cem", ": double ( Int -- Int ) 2 * ;\n")
+        .expect("Failed to write source");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_cem"))
+        .args([
+            "compile",
+            "target/multi_main.cem",
+            "target/multi_helper.cem",
+            "-o",
+            "target/multi_prog",
+        ])
+        .output()
+        .expect("Failed to run cem");
+    assert!(
+        output.status.success(),
+        "cem compile failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let run = Command::new("target/multi_prog")
+        .output()
+        .expect("Failed to run executable");
+    let stdout = String::from_utf8_lossy(&run.stdout);
+    assert!(
+        stdout.contains("Stack (top to bottom): 40 "),
+        "main should call the helper from the other file:\n{}",
+        stdout
+    );
+
+    // Clean up
+    std::fs::remove_file("target/multi_prog").ok();
+    std::fs::remove_file("target/multi_main.cem").ok();
+    std::fs::remove_file("target/multi_helper.cem").ok();
+}
+
+#[test]
+fn test_duplicate_word_across_files_is_rejected() {
+    std::fs::create_dir_all("target").ok();
+    std::fs::write("target/dup_a.cem", ": main ( -- Int ) 1 ;\n").expect("Failed to write source");
+    std::fs::write("target/dup_b.cem", ": main ( -- Int ) 2 ;\n").expect("Failed to write source");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_cem"))
+        .args([
+            "compile",
+            "target/dup_a.cem",
+            "target/dup_b.cem",
+            "-o",
+            "target/dup_prog",
+        ])
+        .output()
+        .expect("Failed to run cem");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(!output.status.success());
+    assert!(
+        stderr.contains(
+            "Word 'main' at target/dup_b.cem:1:1 is already defined at target/dup_a.cem:1:1"
+        ),
+        "Duplicate definition should name both locations:\n{}",
+        stderr
+    );
+
+    // Clean up
+    std::fs::remove_file("target/dup_a.cem").ok();
+    std::fs::remove_file("target/dup_b.cem").ok();
+}
+
+#[test]
+fn test_type_error_across_files_is_rejected() {
+    std::fs::create_dir_all("target").ok();
+    std::fs::write("target/typed_a.cem", ": double ( Int -- Int ) dup + ;\n")
+        .expect("Failed to write source");
+    std::fs::write("target/typed_b.cem", ": main ( -- Int ) \"two\" double ;\n")
+        .expect("Failed to write source");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_cem"))
+        .args([
+            "compile",
+            "target/typed_a.cem",
+            "target/typed_b.cem",
+            "-o",
+            "target/typed_prog",
+        ])
+        .output()
+        .expect("Failed to run cem");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    // The program is typechecked as a whole before any code is generated
+    assert!(!output.status.success());
+    assert!(
+        stderr.contains(
+            "Cannot apply 'double': expected Int at position 0 from the top, but got String"
+        ),
+        "Type error should be reported:\n{}",
+        stderr
+    );
+    assert!(!std::path::Path::new("target/typed_prog.ll").exists());
+
+    // Clean up
+    std::fs::remove_file("target/typed_a.cem").ok();
+    std::fs::remove_file("target/typed_b.cem").ok();
+}
+
+#[test]
+fn test_dump_cfg_writes_dot_graph() {
+    std::fs::create_dir_all("target").ok();
+    std::fs::write(
+        "target/cfg_demo.cem",
+        ": pick ( Bool -- Int ) if [ 1 ] [ 2 ] ;\n: main ( -- Int ) true pick ;\n",
+    )
+    .expect("Failed to write source");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_cem"))
+        .args([
+            "compile",
+            "target/cfg_demo.cem",
+            "-o",
+            "target/cfg_demo",
+            "--dump-cfg",
+            "pick",
+        ])
+        .output()
+        .expect("Failed to run cem");
+    assert!(
+        output.status.success(),
+        "cem compile --dump-cfg failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let dot = std::fs::read_to_string("target/cfg_demo.dot").expect("Dot file should exist");
+    assert!(dot.starts_with("digraph \"pick\" {"), "Got: {}", dot);
+    for node in ["\"entry\"", "\"then_", "\"else_", "\"merge_"] {
+        assert!(dot.contains(node), "Missing {} node in: {}", node, dot);
+    }
+    assert!(dot.contains("\"entry\" -> \"then_"), "Got: {}", dot);
+
+    // Clean up
+    std::fs::remove_file("target/cfg_demo.dot").ok();
+    std::fs::remove_file("target/cfg_demo.cem").ok();
+}
+
+#[test]
+fn test_assert_aborts_only_on_false() {
+    std::fs::create_dir_all("target").ok();
+    std::fs::write(
+        "target/assert_pass.cem",
+        ": main ( -- Int ) true assert 7 ;\n",
+    )
+    .expect("Failed to write source");
+    std::fs::write(
+        "target/assert_fail.cem",
+        ": main ( -- Int ) false assert 7 ;\n",
+    )
+    .expect("Failed to write source");
+
+    for name in ["assert_pass", "assert_fail"] {
+        let output = Command::new(env!("CARGO_BIN_EXE_cem"))
+            .args([
+                "compile",
+                &format!("target/{}.cem", name),
+                "-o",
+                &format!("target/{}", name),
+            ])
+            .output()
+            .expect("Failed to run cem");
+        assert!(
+            output.status.success(),
+            "Compilation of {} failed: {}",
+            name,
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+
+    let passed = Command::new("target/assert_pass")
+        .output()
+        .expect("Failed to run assert_pass");
+    assert!(passed.status.success(), "true assert should continue");
+    assert!(String::from_utf8_lossy(&passed.stdout).contains('7'));
+
+    let failed = Command::new("target/assert_fail")
+        .output()
+        .expect("Failed to run assert_fail");
+    assert!(!failed.status.success(), "false assert should abort");
+    let stderr = String::from_utf8_lossy(&failed.stderr);
+    assert!(
+        stderr.contains("assertion failed at target/assert_fail.cem:1:25"),
+        "Got: {}",
+        stderr
+    );
+
+    // Clean up
+    for name in ["assert_pass", "assert_fail"] {
+        std::fs::remove_file(format!("target/{}", name)).ok();
+        std::fs::remove_file(format!("target/{}.cem", name)).ok();
+    }
+}
+
+#[test]
+fn test_division_by_zero_reports_runtime_error() {
+    std::fs::create_dir_all("target").ok();
+    std::fs::write("target/div_zero.cem", ": main ( -- Int ) 7 0 / ;\n")
+        .expect("Failed to write source");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_cem"))
+        .args(["compile", "target/div_zero.cem", "-o", "target/div_zero"])
+        .output()
+        .expect("Failed to run cem");
+    assert!(
+        output.status.success(),
+        "Compilation failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let run = Command::new("target/div_zero")
+        .output()
+        .expect("Failed to run div_zero");
+    assert!(!run.status.success(), "Dividing by zero should abort");
+    let stderr = String::from_utf8_lossy(&run.stderr);
+    assert!(
+        stderr.contains("/: division by zero at target/div_zero.cem:1:23"),
+        "Got: {}",
+        stderr
+    );
+
+    // Clean up
+    std::fs::remove_file("target/div_zero").ok();
+    std::fs::remove_file("target/div_zero.cem").ok();
+}
+
+#[test]
+fn test_seeded_entry_stack_runs_a_word_with_inputs() {
+    ensure_runtime_built();
+
+    let program = cemc::parser::Parser::new(": double ( Int -- Int ) dup + ;")
+        .parse()
+        .expect("Failed to parse");
+
+    let mut codegen = CodeGen::with_options(CodeGenOptions {
+        entry_stack: vec![StackSeed::Int(21)],
+        ..CodeGenOptions::default()
+    });
+    let ir = codegen
+        .compile_program_with_main(&program, Some("double"))
+        .expect("Failed to generate IR");
+
+    link_program(&ir, "runtime/libcem_runtime.a", "test_seed_exe").expect("Failed to link");
+
+    let output = Command::new("./test_seed_exe")
+        .output()
+        .expect("Failed to run executable");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(output.status.success());
+    assert!(
+        stdout.contains("Stack (top to bottom): 42 "),
+        "double should run on the seeded 21:\n{}",
+        stdout
+    );
+
+    std::fs::remove_file("test_seed_exe").ok();
+    std::fs::remove_file("test_seed_exe.ll").ok();
+}
+
+#[test]
+fn test_while_loop_counts_up() {
+    ensure_runtime_built();
+
+    let source = ": main ( -- Int ) 0 while [ dup 5 < ] do 1 + end ;";
+    let program = cemc::parser::Parser::new(source)
+        .parse()
+        .expect("Failed to parse");
+
+    let mut codegen = CodeGen::new();
+    let ir = codegen
+        .compile_program_with_main(&program, Some("main"))
+        .expect("Failed to generate IR");
+
+    link_program(&ir, "runtime/libcem_runtime.a", "test_while_exe").expect("Failed to link");
+
+    let output = Command::new("./test_while_exe")
+        .output()
+        .expect("Failed to run executable");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(output.status.success());
+    assert!(
+        stdout.contains("Stack (top to bottom): 5 "),
+        "Expected the loop to stop at 5, got: {}",
+        stdout
+    );
+
+    std::fs::remove_file("test_while_exe").ok();
+}
+
+#[test]
+fn test_some_if_and_some_unless_follow_the_flag() {
+    ensure_runtime_built();
+    std::fs::create_dir_all("target").ok();
+
+    for (word, flag, kept) in [
+        ("some_if", "true", true),
+        ("some_if", "false", false),
+        ("some_unless", "true", false),
+        ("some_unless", "false", true),
+    ] {
+        let name = format!("{}_{}", word, flag);
+        std::fs::write(
+            format!("target/{}.cem", name),
+            format!(
+                ": main ( -- ) 5 {} {} match\n\
+                   Some => [ int-to-string write_line ]\n\
+                   None => [ \"dropped\" write_line ]\n\
+                 end ;\n",
+                flag, word
+            ),
+        )
+        .expect("Failed to write source");
+
+        let output = Command::new(env!("CARGO_BIN_EXE_cem"))
+            .args([
+                "compile",
+                &format!("target/{}.cem", name),
+                "-o",
+                &format!("target/{}", name),
+            ])
+            .output()
+            .expect("Failed to run cem");
+        assert!(
+            output.status.success(),
+            "Compilation of {} failed: {}",
+            name,
+            String::from_utf8_lossy(&output.stderr)
+        );
+
+        let run = Command::new(format!("target/{}", name))
+            .output()
+            .expect("Failed to run program");
+        assert!(run.status.success());
+        let stdout = String::from_utf8_lossy(&run.stdout);
+        assert_eq!(
+            (stdout.contains('5'), stdout.contains("dropped")),
+            (kept, !kept),
+            "{} {} printed: {}",
+            flag,
+            word,
+            stdout
+        );
+    }
+}
+
+#[test]
+fn test_string_split_counts_pieces() {
+    ensure_runtime_built();
+    std::fs::create_dir_all("target").ok();
+    std::fs::write(
+        "target/string_split.cem",
+        r#"type List (T)
+  | Cons(T List(T))
+  | Nil
+
+: count ( List(String) -- Int )
+  match
+    Cons => [ count swap drop 1 + ]
+    Nil => [ 0 ]
+  end ;
+
+: main ( -- Int ) "a,b,c" "," string_split count ;
+"#,
+    )
+    .expect("Failed to write source");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_cem"))
+        .args([
+            "compile",
+            "target/string_split.cem",
+            "-o",
+            "target/string_split",
+        ])
+        .output()
+        .expect("Failed to run cem");
+    assert!(
+        output.status.success(),
+        "Compilation failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let run = Command::new("target/string_split")
+        .output()
+        .expect("Failed to run string_split");
+    assert!(run.status.success());
+    assert!(
+        String::from_utf8_lossy(&run.stdout).contains('3'),
+        "expected a count of 3, got: {}",
+        String::from_utf8_lossy(&run.stdout)
+    );
+}
+
+#[test]
+fn test_cons_pattern_sums_a_list_recursively() {
+    ensure_runtime_built();
+    std::fs::create_dir_all("target").ok();
+    std::fs::write(
+        "target/list_sum.cem",
+        r#"type List (T)
+  | Cons(T List(T))
+  | Nil
+
+: sum ( List(Int) -- Int )
+  match
+    Cons(head, rest) => [ sum + ]
+    Nil => [ 0 ]
+  end ;
+
+: main ( -- Int ) 1 2 3 4 Nil Cons Cons Cons Cons sum ;
+"#,
+    )
+    .expect("Failed to write source");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_cem"))
+        .args(["compile", "target/list_sum.cem", "-o", "target/list_sum"])
+        .output()
+        .expect("Failed to run cem");
+    assert!(
+        output.status.success(),
+        "Compilation failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let run = Command::new("target/list_sum")
+        .output()
+        .expect("Failed to run list_sum");
+    assert!(run.status.success());
+    assert!(
+        String::from_utf8_lossy(&run.stdout).contains("10"),
+        "expected a sum of 10, got: {}",
+        String::from_utf8_lossy(&run.stdout)
+    );
+
+    std::fs::remove_file("target/list_sum").ok();
+    std::fs::remove_file("target/list_sum.cem").ok();
+}
+
+#[test]
+fn test_tuple_pattern_uses_both_components() {
+    ensure_runtime_built();
+
+    let source = ": bump ( Tuple(Int Bool) -- Int )
+                    match
+                      Tuple(n, flag) => if [ 1 + ] [ 1 - ]
+                    end ;
+                  : main ( -- ) 41 true Tuple bump exit ;";
+    let program = cemc::parser::Parser::new(source)
+        .parse()
+        .expect("Failed to parse");
+
+    let ir = CodeGen::new()
+        .compile_program_with_main(&program, Some("main"))
+        .expect("Failed to generate IR");
+
+    link_program(&ir, "runtime/libcem_runtime.a", "test_tuple_exe").expect("Failed to link");
+
+    let output = Command::new("./test_tuple_exe")
+        .output()
+        .expect("Failed to run executable");
+
+    // The Bool chose `1 +` and the Int was its operand
+    assert_eq!(output.status.code(), Some(42));
+
+    std::fs::remove_file("test_tuple_exe").ok();
+    std::fs::remove_file("test_tuple_exe.ll").ok();
+}
+
+#[test]
+fn test_negate_flips_the_sign() {
+    ensure_runtime_built();
+
+    // Exit codes are unsigned, so shift the result back into range: -5 + 47 = 42
+    let source = ": main ( -- ) 5 negate 47 + exit ;";
+    let program = cemc::parser::Parser::new(source)
+        .parse()
+        .expect("Failed to parse");
+
+    let ir = CodeGen::new()
+        .compile_program_with_main(&program, Some("main"))
+        .expect("Failed to generate IR");
+    assert!(ir.contains("call ptr @negate("));
+
+    link_program(&ir, "runtime/libcem_runtime.a", "test_negate_exe").expect("Failed to link");
+
+    let output = Command::new("./test_negate_exe")
+        .output()
+        .expect("Failed to run executable");
+    assert_eq!(output.status.code(), Some(42));
+
+    std::fs::remove_file("test_negate_exe").ok();
+    std::fs::remove_file("test_negate_exe.ll").ok();
+}
+
+#[test]
+fn test_abs_signum_and_gcd() {
+    ensure_runtime_built();
+
+    let exit_code = |source: &str, exe: &str| {
+        let program = cemc::parser::Parser::new(source)
+            .parse()
+            .expect("Failed to parse");
+        let ir = CodeGen::new()
+            .compile_program_with_main(&program, Some("main"))
+            .expect("Failed to generate IR");
+        link_program(&ir, "runtime/libcem_runtime.a", exe).expect("Failed to link");
+
+        let output = Command::new(format!("./{}", exe))
+            .output()
+            .expect("Failed to run executable");
+        std::fs::remove_file(exe).ok();
+        std::fs::remove_file(format!("{}.ll", exe)).ok();
+        output.status.code()
+    };
+
+    assert_eq!(
+        exit_code(": main ( -- ) -5 abs exit ;", "test_abs_exe"),
+        Some(5)
+    );
+    // -1 shifted into the unsigned exit code range
+    assert_eq!(
+        exit_code(": main ( -- ) -3 signum 10 + exit ;", "test_signum_exe"),
+        Some(9)
+    );
+    assert_eq!(
+        exit_code(": main ( -- ) 12 18 gcd exit ;", "test_gcd_exe"),
+        Some(6)
+    );
+}
+
+#[test]
+fn test_write_omits_the_newline() {
+    ensure_runtime_built();
+    std::fs::create_dir_all("target").ok();
+    std::fs::write(
+        "target/write_no_newline.cem",
+        ": main ( -- ) \"a\" write \"b\" write_line ;\n",
+    )
+    .expect("Failed to write source");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_cem"))
+        .args([
+            "compile",
+            "target/write_no_newline.cem",
+            "-o",
+            "target/write_no_newline",
+        ])
+        .output()
+        .expect("Failed to run cem");
+    assert!(
+        output.status.success(),
+        "Compilation failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let run = Command::new("target/write_no_newline")
+        .output()
+        .expect("Failed to run write_no_newline");
+    assert!(run.status.success());
+    let stdout = String::from_utf8_lossy(&run.stdout);
+    assert!(stdout.starts_with("ab\n"), "got: {:?}", stdout);
+}
+
+#[test]
+fn test_recursive_tree_sums_its_leaves() {
+    ensure_runtime_built();
+    std::fs::create_dir_all("target").ok();
+    // Variant fields live in heap cells owned by the variant, so a Node can hold Trees
+    std::fs::write(
+        "target/tree_sum.cem",
+        r#"type Tree
+  | Leaf(Int)
+  | Node(Tree, Tree)
+
+: sum ( Tree -- Int )
+  match
+    Leaf => [ ]
+    Node => [ sum swap sum + ]
+  end ;
+
+: main ( -- Int ) 1 Leaf 2 Leaf Node 3 Leaf Node sum ;
+"#,
+    )
+    .expect("Failed to write source");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_cem"))
+        .args(["compile", "target/tree_sum.cem", "-o", "target/tree_sum"])
+        .output()
+        .expect("Failed to run cem");
+    assert!(
+        output.status.success(),
+        "Compilation failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let run = Command::new("target/tree_sum")
+        .output()
+        .expect("Failed to run tree_sum");
+    assert!(run.status.success());
+    assert!(
+        String::from_utf8_lossy(&run.stdout).contains('6'),
+        "expected 1 + 2 + 3, got: {}",
+        String::from_utf8_lossy(&run.stdout)
+    );
+}
+
+#[test]
+fn test_max_errors_caps_parse_errors() {
+    std::fs::create_dir_all("target").ok();
+    let source: String = (0..50)
+        .map(|i| format!(": bad{} ( -- Int ) ] ;\n", i))
+        .collect();
+    std::fs::write("target/many_errors.cem", source).expect("Failed to write source");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_cem"))
+        .args([
+            "compile",
+            "target/many_errors.cem",
+            "--max-errors",
+            "3",
+            "-o",
+            "target/many_errors",
+        ])
+        .output()
+        .expect("Failed to run cem");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(!output.status.success());
+    assert_eq!(stderr.matches("Parse error at").count(), 3, "{}", stderr);
+    assert!(stderr.contains("... and 47 more errors"), "{}", stderr);
+
+    std::fs::remove_file("target/many_errors.cem").ok();
+}
+
+#[test]
+fn test_int_match_selects_the_matching_arm() {
+    ensure_runtime_built();
+    std::fs::create_dir_all("target").ok();
+    // Dense cases compile to a switch, sparse ones to comparisons; both must agree
+    std::fs::write(
+        "target/int_match.cem",
+        r#": dense ( Int -- Int ) match 1 => 10 2 => 20 3 => 30 _ => 0 end ;
+: sparse ( Int -- Int ) match 5 => 100 5000 => 200 _ => 0 end ;
+
+: main ( -- Int ) 2 dense 5000 sparse + 7 dense + ;
+"#,
+    )
+    .expect("Failed to write source");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_cem"))
+        .args(["compile", "target/int_match.cem", "-o", "target/int_match"])
+        .output()
+        .expect("Failed to run cem");
+    assert!(
+        output.status.success(),
+        "Compilation failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let run = Command::new("target/int_match")
+        .output()
+        .expect("Failed to run int_match");
+    assert!(run.status.success());
+    assert!(
+        String::from_utf8_lossy(&run.stdout).contains("220"),
+        "expected 20 + 200 + 0, got: {}",
+        String::from_utf8_lossy(&run.stdout)
+    );
+}
+
+#[test]
+fn test_bool_int_conversions_round_trip() {
+    ensure_runtime_built();
+    std::fs::create_dir_all("target").ok();
+    // true -> 1, nonzero -> true, 0 -> false
+    std::fs::write(
+        "target/bool_int.cem",
+        ": main ( -- Int ) true bool_to_int 100 * 7 int_to_bool bool_to_int 10 * + 0 int_to_bool bool_to_int + ;\n",
+    )
+    .expect("Failed to write source");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_cem"))
+        .args(["compile", "target/bool_int.cem", "-o", "target/bool_int"])
+        .output()
+        .expect("Failed to run cem");
+    assert!(
+        output.status.success(),
+        "Compilation failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let run = Command::new("target/bool_int")
+        .output()
+        .expect("Failed to run bool_int");
+    assert!(run.status.success());
+    assert!(
+        String::from_utf8_lossy(&run.stdout).contains("110"),
+        "expected 100 + 10 + 0, got: {}",
+        String::from_utf8_lossy(&run.stdout)
+    );
+}
+
+#[test]
+fn test_primitives_lists_builtins_with_their_effects() {
+    let output = Command::new(env!("CARGO_BIN_EXE_cem"))
+        .arg("primitives")
+        .output()
+        .expect("Failed to run cem");
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    let effect_of = |word: &str| {
+        stdout
+            .lines()
+            .find_map(|line| {
+                let (name, effect) = line.split_once(char::is_whitespace)?;
+                (name == word).then(|| effect.trim().to_string())
+            })
+            .unwrap_or_else(|| panic!("'{}' is not listed:\n{}", word, stdout))
+    };
+    assert_eq!(effect_of("dup"), "( A -- A A )");
+    assert_eq!(effect_of("+"), "( Int Int -- Int )");
+    assert_eq!(effect_of("some_if"), "( A Bool -- Option(A) )");
+    assert_eq!(effect_of("some_unless"), "( A Bool -- Option(A) )");
+
+    let names: Vec<_> = stdout
+        .lines()
+        .filter_map(|line| line.split_whitespace().next())
+        .collect();
+    assert!(names.is_sorted(), "not sorted:\n{}", stdout);
+    // Only words a program can call are listed
+    assert!(!names.contains(&"call_condition"), "{}", stdout);
+}
+
+#[test]
+fn test_curried_quotation_adds_its_captured_value() {
+    ensure_runtime_built();
+    std::fs::create_dir_all("target").ok();
+    // The copy made by dup owns its own captured value, so both copies can be called
+    std::fs::write(
+        "target/curry.cem",
+        ": add-one-twice ( Int -- Int ) [ 1 + ] curry dup call_quotation swap call_quotation + ;\n\
+         : main ( -- Int ) 20 add-one-twice ;\n",
+    )
+    .expect("Failed to write source");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_cem"))
+        .args(["compile", "target/curry.cem", "-o", "target/curry"])
+        .output()
+        .expect("Failed to run cem");
+    assert!(
+        output.status.success(),
+        "Compilation failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let run = Command::new("target/curry")
+        .output()
+        .expect("Failed to run curry");
+    assert!(run.status.success());
+    assert!(
+        String::from_utf8_lossy(&run.stdout).contains("42"),
+        "expected (20 + 1) + (20 + 1), got: {}",
+        String::from_utf8_lossy(&run.stdout)
+    );
+}
+
+#[test]
+fn test_seq_runs_its_statements_in_order() {
+    ensure_runtime_built();
+    std::fs::create_dir_all("target").ok();
+    std::fs::write(
+        "target/seq.cem",
+        ": main ( -- ) [ \"first\" write_line ] [ \"second\" write_line ] seq ;\n",
+    )
+    .expect("Failed to write source");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_cem"))
+        .args(["compile", "target/seq.cem", "-o", "target/seq"])
+        .output()
+        .expect("Failed to run cem");
+    assert!(
+        output.status.success(),
+        "Compilation failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let run = Command::new("target/seq")
+        .output()
+        .expect("Failed to run seq");
+    assert!(run.status.success());
+    let stdout = String::from_utf8_lossy(&run.stdout);
+    let first = stdout.find("first").expect("first statement should run");
+    let second = stdout.find("second").expect("second statement should run");
+    assert!(first < second, "statements ran out of order: {}", stdout);
+}
+
+#[test]
+fn test_byte_order_mark_is_ignored() {
+    std::fs::create_dir_all("target").ok();
+    let source = ": main ( -- ) \"hi\" write_line ;\n";
+    let compile = |text: String| {
+        std::fs::write("target/bom_demo.cem", text).expect("Failed to write source");
+        let output = Command::new(env!("CARGO_BIN_EXE_cem"))
+            .args([
+                "compile",
+                "target/bom_demo.cem",
+                "-o",
+                "target/bom_demo",
+                "--dump-cfg",
+                "main",
+                "--keep-ir",
+            ])
+            .output()
+            .expect("Failed to run cem");
+        assert!(
+            output.status.success(),
+            "cem compile failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        std::fs::read_to_string("target/bom_demo.ll").expect("IR should be kept")
+    };
+
+    let plain = compile(source.to_string());
+    let with_bom = compile(format!("\u{FEFF}{}", source));
+    assert_eq!(plain, with_bom);
+
+    for file in ["cem", "ll", "dot"] {
+        std::fs::remove_file(format!("target/bom_demo.{}", file)).ok();
+    }
+}
+
+#[test]
+fn test_check_stack_balance_flags_an_extra_push() {
+    std::fs::create_dir_all("target").ok();
+    std::fs::write(
+        "target/unbalanced.cem",
+        ": double ( Int -- Int ) dup + 0 ;\n: main ( -- Int ) 2 double ;\n",
+    )
+    .expect("Failed to write source");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_cem"))
+        .args(["compile", "target/unbalanced.cem", "--check-stack-balance"])
+        .output()
+        .expect("Failed to run cem");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(!output.status.success());
+    assert!(
+        stdout.contains(
+            "target/unbalanced.cem:1:1: Word 'double' declares 1 output(s) but its body leaves 2"
+        ),
+        "unexpected report: {}",
+        stdout
+    );
+    assert!(!stdout.contains("'main'"));
+    // Only the check runs: nothing is built
+    assert!(!std::path::Path::new("target/unbalanced").exists());
+
+    std::fs::remove_file("target/unbalanced.cem").ok();
+}
+
+#[test]
+fn test_check_expr_is_usable_from_outside_the_crate() {
+    let checker = cemc::typechecker::TypeChecker::new();
+
+    let stack = checker
+        .check_expr(&Expr::IntLit(7, SourceLoc::unknown()), StackType::Empty)
+        .expect("an Int literal should check on any stack");
+    assert_eq!(stack, StackType::Empty.push(Type::Int));
+
+    let add = Expr::WordCall("+".to_string(), SourceLoc::unknown());
+    let stack = checker
+        .check_expr(&add, StackType::from_vec(vec![Type::Int, Type::Int]))
+        .expect("+ should accept two Ints");
+    assert_eq!(stack, StackType::Empty.push(Type::Int));
+}